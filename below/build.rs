@@ -3,7 +3,32 @@ use std::path::Path;
 
 use libbpf_cargo::SkeletonBuilder;
 
-const SRC: &str = "./src/bpf/exitstat.bpf.c";
+const SRCS: &[(&str, &str)] = &[
+    (
+        "./src/bpf/exitstat.bpf.c",
+        "./src/bpf/.output/exitstat.skel.rs",
+    ),
+    (
+        "./src/bpf/execsnoop.bpf.c",
+        "./src/bpf/.output/execsnoop.skel.rs",
+    ),
+    (
+        "./src/bpf/oomkill.bpf.c",
+        "./src/bpf/.output/oomkill.skel.rs",
+    ),
+    (
+        "./src/bpf/runqlat.bpf.c",
+        "./src/bpf/.output/runqlat.skel.rs",
+    ),
+    (
+        "./src/bpf/biolat.bpf.c",
+        "./src/bpf/.output/biolat.skel.rs",
+    ),
+    (
+        "./src/bpf/tcphealth.bpf.c",
+        "./src/bpf/.output/tcphealth.skel.rs",
+    ),
+];
 
 fn main() {
     // It's unfortunate we cannot use `OUT_DIR` to store the generated skeleton.
@@ -15,7 +40,8 @@ fn main() {
     // However, there is hope! When the above feature stabilizes we can clean this
     // all up.
     create_dir_all("./src/bpf/.output").unwrap();
-    let skel = Path::new("./src/bpf/.output/exitstat.skel.rs");
-    SkeletonBuilder::new(SRC).generate(&skel).unwrap();
-    println!("cargo:rerun-if-changed={}", SRC);
+    for (src, skel) in SRCS {
+        SkeletonBuilder::new(src).generate(Path::new(skel)).unwrap();
+        println!("cargo:rerun-if-changed={}", src);
+    }
 }
@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use std::fs;
+use std::net::{IpAddr, Ipv4Addr};
 use std::path::{Path, PathBuf};
 
 use anyhow::{bail, Result};
@@ -37,6 +38,9 @@ pub struct BelowConfig {
     pub store_dir: PathBuf,
     pub cgroup_root: PathBuf,
     pub cgroup_filter_out: String,
+    /// Address the remote viewing server of `below record --port` listens
+    /// on. Loopback by default, since the store is served unauthenticated.
+    pub remote_store_bind_address: IpAddr,
 }
 
 impl Default for BelowConfig {
@@ -46,6 +50,7 @@ impl Default for BelowConfig {
             store_dir: BELOW_DEFAULT_STORE.into(),
             cgroup_root: cgroupfs::DEFAULT_CG_ROOT.into(),
             cgroup_filter_out: String::new(),
+            remote_store_bind_address: Ipv4Addr::LOCALHOST.into(),
         }
     }
 }
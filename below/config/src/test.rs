@@ -31,6 +31,10 @@ fn test_config_default() {
         cgroupfs::DEFAULT_CG_ROOT
     );
     assert_eq!(below_config.cgroup_filter_out, String::new());
+    assert_eq!(
+        below_config.remote_store_bind_address.to_string(),
+        "127.0.0.1"
+    );
 }
 
 #[test]
@@ -72,6 +76,7 @@ fn test_config_load_success() {
         store_dir = '/var/log/below'
         cgroup_root = '/sys/fs/cgroup'
         cgroup_filter_out = 'user.slice'
+        remote_store_bind_address = '::'
         # I'm a comment
         something_else = "demacia"
     "#;
@@ -87,6 +92,7 @@ fn test_config_load_success() {
     assert_eq!(below_config.store_dir.to_string_lossy(), "/var/log/below");
     assert_eq!(below_config.cgroup_root.to_string_lossy(), "/sys/fs/cgroup");
     assert_eq!(below_config.cgroup_filter_out, "user.slice");
+    assert_eq!(below_config.remote_store_bind_address.to_string(), "::");
 }
 
 #[test]
@@ -15,8 +15,9 @@
 use crate::{CommonField, DumpField};
 use model::EnumIter;
 use model::{
-    FieldId, NetworkModelFieldId, SingleCgroupModelFieldId, SingleDiskModelFieldId,
-    SingleNetModelFieldId, SingleProcessModelFieldId, SystemModelFieldId,
+    FieldId, NetworkModelFieldId, SingleCgroupModelFieldId, SingleCifsModelFieldId,
+    SingleDiskModelFieldId, SingleExecModelFieldId, SingleFsModelFieldId, SingleNetModelFieldId,
+    SingleNfsModelFieldId, SingleProcessModelFieldId, SystemModelFieldId,
 };
 
 use anyhow::{bail, Error, Result};
@@ -143,10 +144,14 @@ pub enum SystemAggField {
     Mem,
     Vm,
     Stat,
+    KernelTables,
+    Exitstat,
 }
 
 impl AggField<SystemModelFieldId> for SystemAggField {
     fn expand(&self, detail: bool) -> Vec<SystemModelFieldId> {
+        use model::ExitstatModelFieldId as Exitstat;
+        use model::KernelTablesModelFieldId as KernelTables;
         use model::MemoryModelFieldId as Mem;
         use model::ProcStatModelFieldId as Stat;
         use model::SingleCpuModelFieldId as Cpu;
@@ -163,6 +168,12 @@ impl AggField<SystemModelFieldId> for SystemAggField {
                 Self::Mem => Mem::unit_variant_iter().map(FieldId::Mem).collect(),
                 Self::Vm => Vm::unit_variant_iter().map(FieldId::Vm).collect(),
                 Self::Stat => Stat::unit_variant_iter().map(FieldId::Stat).collect(),
+                Self::KernelTables => KernelTables::unit_variant_iter()
+                    .map(FieldId::KernelTables)
+                    .collect(),
+                Self::Exitstat => Exitstat::unit_variant_iter()
+                    .map(FieldId::Exitstat)
+                    .collect(),
             }
         } else {
             // Default fields for each group
@@ -177,6 +188,18 @@ impl AggField<SystemModelFieldId> for SystemAggField {
                     .collect(),
                 Self::Vm => Vm::unit_variant_iter().map(FieldId::Vm).collect(),
                 Self::Stat => Stat::unit_variant_iter().map(FieldId::Stat).collect(),
+                Self::KernelTables => vec![
+                    KernelTables::FileHandlesPct,
+                    KernelTables::PidUsagePct,
+                    KernelTables::ThreadsUsagePct,
+                ]
+                .into_iter()
+                .map(FieldId::KernelTables)
+                .collect(),
+                Self::Exitstat => vec![Exitstat::Status, Exitstat::Health]
+                    .into_iter()
+                    .map(FieldId::Exitstat)
+                    .collect(),
             }
         }
     }
@@ -193,6 +216,8 @@ pub static DEFAULT_SYSTEM_FIELDS: &[SystemOptionField] = &[
     DumpOptionField::Unit(DumpField::FieldId(SystemModelFieldId::KernelVersion)),
     DumpOptionField::Unit(DumpField::FieldId(SystemModelFieldId::OsRelease)),
     DumpOptionField::Agg(SystemAggField::Stat),
+    DumpOptionField::Agg(SystemAggField::KernelTables),
+    DumpOptionField::Agg(SystemAggField::Exitstat),
     DumpOptionField::Unit(DumpField::Common(CommonField::Timestamp)),
 ];
 
@@ -216,8 +241,15 @@ cpus.N.<cpu_field> for individual CPU data. N is individual CPU index and
 
 {all_vm_fields}
 
+vm.counters.NAME.value and vm.counters.NAME.per_sec for any counter in
+/proc/vmstat. NAME is the counter name, e.g. compact_stall.
+
 {all_stat_fields}
 
+{all_kernel_tables_fields}
+
+{all_exitstat_fields}
+
 ********************** Aggregated fields **********************
 
 * cpu: includes [{agg_cpu_fields}].
@@ -228,6 +260,11 @@ cpus.N.<cpu_field> for individual CPU data. N is individual CPU index and
 
 * stat: includes [{agg_stat_fields}].
 
+* kernel_tables: includes [{agg_kernel_tables_fields}].
+
+* exitstat: includes [{agg_exitstat_fields}]. Exited processes may be missing
+  or incomplete while the health is "degraded".
+
 * --detail: includes [<agg_field>.*] for each given aggregated field.
 
 * --default: includes [{default_fields}].
@@ -238,6 +275,12 @@ cpus.N.<cpu_field> for individual CPU data. N is individual CPU index and
 
 $ below dump system -b "08:30:00" -e "08:30:30" -f datetime vm hostname -O csv
 
+$ below dump system -b "08:30:00" -e "08:30:30" -f datetime vm.counters.compact_stall.per_sec
+
+Output the windows in which exit data of short-lived processes may be incomplete:
+
+$ below dump system -b "08:30:00" -e "08:30:30" -f datetime exitstat --detail
+
 "#,
         about = SYSTEM_ABOUT,
         common_fields = join(CommonField::unit_variant_iter()),
@@ -246,10 +289,14 @@ $ below dump system -b "08:30:00" -e "08:30:30" -f datetime vm hostname -O csv
         all_memory_fields = join(SystemAggField::Mem.expand(true)),
         all_vm_fields = join(SystemAggField::Vm.expand(true)),
         all_stat_fields = join(SystemAggField::Stat.expand(true)),
+        all_kernel_tables_fields = join(SystemAggField::KernelTables.expand(true)),
+        all_exitstat_fields = join(SystemAggField::Exitstat.expand(true)),
         agg_cpu_fields = join(SystemAggField::Cpu.expand(false)),
         agg_memory_fields = join(SystemAggField::Mem.expand(false)),
         agg_vm_fields = join(SystemAggField::Vm.expand(false)),
         agg_stat_fields = join(SystemAggField::Stat.expand(false)),
+        agg_kernel_tables_fields = join(SystemAggField::KernelTables.expand(false)),
+        agg_exitstat_fields = join(SystemAggField::Exitstat.expand(false)),
         default_fields = join(DEFAULT_SYSTEM_FIELDS.to_owned()),
     )
 });
@@ -265,6 +312,9 @@ pub enum DiskAggField {
     Read,
     Write,
     Discard,
+    Flush,
+    Iostat,
+    Topology,
     FsInfo,
 }
 
@@ -294,7 +344,21 @@ impl AggField<SingleDiskModelFieldId> for DiskAggField {
                 DiscardSectors,
                 TimeSpendDiscardMs,
             ],
-            Self::FsInfo => vec![DiskUsage, PartitionSize, FilesystemType],
+            Self::Flush => vec![FlushCompleted, TimeSpendFlushMs],
+            Self::Iostat => vec![
+                UtilPct,
+                AvgQueueSize,
+                ReadAwaitMs,
+                WriteAwaitMs,
+                AvgRequestSize,
+                IosInProgress,
+                ReadLatencyP50Us,
+                ReadLatencyP99Us,
+                WriteLatencyP50Us,
+                WriteLatencyP99Us,
+            ],
+            Self::Topology => vec![DmName, Parent, Slaves],
+            Self::FsInfo => vec![DiskUsage, PartitionSize, FilesystemType, MountPoint],
         }
     }
 }
@@ -309,9 +373,12 @@ pub static DEFAULT_DISK_FIELDS: &[DiskOptionField] = &[
     )),
     DumpOptionField::Unit(DumpField::FieldId(SingleDiskModelFieldId::Major)),
     DumpOptionField::Unit(DumpField::FieldId(SingleDiskModelFieldId::Minor)),
+    DumpOptionField::Agg(DiskAggField::Topology),
     DumpOptionField::Agg(DiskAggField::Read),
     DumpOptionField::Agg(DiskAggField::Write),
     DumpOptionField::Agg(DiskAggField::Discard),
+    DumpOptionField::Agg(DiskAggField::Flush),
+    DumpOptionField::Agg(DiskAggField::Iostat),
     DumpOptionField::Agg(DiskAggField::FsInfo),
     DumpOptionField::Unit(DumpField::Common(CommonField::Timestamp)),
 ];
@@ -335,6 +402,12 @@ static DISK_LONG_ABOUT: Lazy<String> = Lazy::new(|| {
 
 * discard: includes [{agg_discard_fields}].
 
+* flush: includes [{agg_flush_fields}].
+
+* iostat: includes [{agg_iostat_fields}].
+
+* topology: includes [{agg_topology_fields}].
+
 * fs_info: includes [{agg_fsinfo_fields}].
 
 * --detail: no effect.
@@ -357,18 +430,300 @@ Output stats for top 5 read partitions for each time slice from 08:30:00 to 08:3
 
 $ below dump disk -b "08:30:00" -e "08:30:30" -s read_bytes_per_sec --rsort --top 5
 
+Output iostat style utilization and latency for whole disks from 08:30:00 to 08:30:30:
+
+$ below dump disk -b "08:30:00" -e "08:30:30" -s minor -F "^0$" -f name iostat
+
+Output device-mapper names, md members and mount points of all disks at 08:30:00:
+
+$ below dump disk -b "08:30:00" -e "08:30:00" -f name topology fs_info
+
 "#,
         about = DISK_ABOUT,
         common_fields = join(CommonField::unit_variant_iter()),
         agg_read_fields = join(DiskAggField::Read.expand(false)),
         agg_write_fields = join(DiskAggField::Write.expand(false)),
         agg_discard_fields = join(DiskAggField::Discard.expand(false)),
+        agg_flush_fields = join(DiskAggField::Flush.expand(false)),
+        agg_iostat_fields = join(DiskAggField::Iostat.expand(false)),
+        agg_topology_fields = join(DiskAggField::Topology.expand(false)),
         agg_fsinfo_fields = join(DiskAggField::FsInfo.expand(false)),
         default_fields = join(DEFAULT_DISK_FIELDS.to_owned()),
     )
 });
 
-/// Represents the four sub-model of ProcessModel.
+/// Represents the capacity and inode fields of a filesystem.
+#[derive(
+    Clone,
+    Debug,
+    PartialEq,
+    below_derive::EnumFromStr,
+    below_derive::EnumToString
+)]
+pub enum FsAggField {
+    Usage,
+    Inode,
+}
+
+impl AggField<SingleFsModelFieldId> for FsAggField {
+    fn expand(&self, _detail: bool) -> Vec<SingleFsModelFieldId> {
+        use model::SingleFsModelFieldId::*;
+
+        match self {
+            Self::Usage => vec![UsagePct, TotalBytes, UsedBytes, FreeBytes, AvailBytes],
+            Self::Inode => vec![
+                InodeUsagePct,
+                TotalInodes,
+                UsedInodes,
+                FreeInodes,
+                AvailInodes,
+            ],
+        }
+    }
+}
+
+pub type FsOptionField = DumpOptionField<SingleFsModelFieldId, FsAggField>;
+
+pub static DEFAULT_FS_FIELDS: &[FsOptionField] = &[
+    DumpOptionField::Unit(DumpField::Common(CommonField::Datetime)),
+    DumpOptionField::Unit(DumpField::FieldId(SingleFsModelFieldId::MountPoint)),
+    DumpOptionField::Unit(DumpField::FieldId(SingleFsModelFieldId::MountSource)),
+    DumpOptionField::Unit(DumpField::FieldId(SingleFsModelFieldId::FsType)),
+    DumpOptionField::Agg(FsAggField::Usage),
+    DumpOptionField::Agg(FsAggField::Inode),
+    DumpOptionField::Unit(DumpField::Common(CommonField::Timestamp)),
+];
+
+const FS_ABOUT: &str = "Dump filesystem capacity and inode usage";
+
+/// Generated about message for Fs dump so supported fields are up-to-date.
+static FS_LONG_ABOUT: Lazy<String> = Lazy::new(|| {
+    format!(
+        r#"{about}
+
+********************** Available fields **********************
+
+{common_fields}, {fs_fields}.
+
+********************** Aggregated fields **********************
+
+* usage: includes [{agg_usage_fields}].
+
+* inode: includes [{agg_inode_fields}].
+
+* --detail: no effect.
+
+* --default: includes [{default_fields}].
+
+* --everything: includes everything (equivalent to --default --detail).
+
+********************** Example Commands **********************
+
+Simple example:
+
+$ below dump fs -b "08:30:00" -e "08:30:30" -f mount_point usage -O csv
+
+Output usage of /var from 08:30:00 to 08:30:30:
+
+$ below dump fs -b "08:30:00" -e "08:30:30" -s mount_point -F "^/var$" -f mount_point usage inode
+
+Output the 3 fullest filesystems for each time slice from 08:30:00 to 08:30:30:
+
+$ below dump fs -b "08:30:00" -e "08:30:30" -s usage_pct --rsort --top 3
+
+"#,
+        about = FS_ABOUT,
+        common_fields = join(CommonField::unit_variant_iter()),
+        fs_fields = join(SingleFsModelFieldId::unit_variant_iter()),
+        agg_usage_fields = join(FsAggField::Usage.expand(false)),
+        agg_inode_fields = join(FsAggField::Inode.expand(false)),
+        default_fields = join(DEFAULT_FS_FIELDS.to_owned()),
+    )
+});
+
+/// Represents the throughput and per-op fields of an NFS mount.
+#[derive(
+    Clone,
+    Debug,
+    PartialEq,
+    below_derive::EnumFromStr,
+    below_derive::EnumToString
+)]
+pub enum NfsAggField {
+    Rate,
+    Read,
+    Write,
+    Getattr,
+    Lookup,
+}
+
+impl AggField<SingleNfsModelFieldId> for NfsAggField {
+    fn expand(&self, _detail: bool) -> Vec<SingleNfsModelFieldId> {
+        use model::NfsOpModelFieldId as Op;
+        use model::SingleNfsModelFieldId::*;
+
+        match self {
+            Self::Rate => vec![
+                ReadBytesPerSec,
+                WriteBytesPerSec,
+                ServerReadBytesPerSec,
+                ServerWriteBytesPerSec,
+                OpsPerSec,
+                RetransPerSec,
+            ],
+            Self::Read => Op::unit_variant_iter().map(Read).collect(),
+            Self::Write => Op::unit_variant_iter().map(Write).collect(),
+            Self::Getattr => Op::unit_variant_iter().map(Getattr).collect(),
+            Self::Lookup => Op::unit_variant_iter().map(Lookup).collect(),
+        }
+    }
+}
+
+pub type NfsOptionField = DumpOptionField<SingleNfsModelFieldId, NfsAggField>;
+
+pub static DEFAULT_NFS_FIELDS: &[NfsOptionField] = &[
+    DumpOptionField::Unit(DumpField::Common(CommonField::Datetime)),
+    DumpOptionField::Unit(DumpField::FieldId(SingleNfsModelFieldId::MountPoint)),
+    DumpOptionField::Unit(DumpField::FieldId(SingleNfsModelFieldId::Device)),
+    DumpOptionField::Agg(NfsAggField::Rate),
+    DumpOptionField::Agg(NfsAggField::Read),
+    DumpOptionField::Agg(NfsAggField::Write),
+    DumpOptionField::Agg(NfsAggField::Getattr),
+    DumpOptionField::Agg(NfsAggField::Lookup),
+    DumpOptionField::Unit(DumpField::Common(CommonField::Timestamp)),
+];
+
+const NFS_ABOUT: &str = "Dump NFS client stats";
+
+/// Generated about message for Nfs dump so supported fields are up-to-date.
+static NFS_LONG_ABOUT: Lazy<String> = Lazy::new(|| {
+    format!(
+        r#"{about}
+
+********************** Available fields **********************
+
+{common_fields}, and expanded fields below.
+
+********************** Aggregated fields **********************
+
+* rate: includes [{agg_rate_fields}].
+
+* read: includes [{agg_read_fields}].
+
+* write: includes [{agg_write_fields}].
+
+* getattr: includes [{agg_getattr_fields}].
+
+* lookup: includes [{agg_lookup_fields}].
+
+* --detail: no effect.
+
+* --default: includes [{default_fields}].
+
+* --everything: includes everything (equivalent to --default --detail).
+
+********************** Example Commands **********************
+
+Simple example:
+
+$ below dump nfs -b "08:30:00" -e "08:30:30" -f mount_point rate -O csv
+
+Output READ and WRITE latency of mounts under /mnt from 08:30:00 to 08:30:30:
+
+$ below dump nfs -b "08:30:00" -e "08:30:30" -s mount_point -F "^/mnt/" -f mount_point read write
+
+"#,
+        about = NFS_ABOUT,
+        common_fields = join(CommonField::unit_variant_iter()),
+        agg_rate_fields = join(NfsAggField::Rate.expand(false)),
+        agg_read_fields = join(NfsAggField::Read.expand(false)),
+        agg_write_fields = join(NfsAggField::Write.expand(false)),
+        agg_getattr_fields = join(NfsAggField::Getattr.expand(false)),
+        agg_lookup_fields = join(NfsAggField::Lookup.expand(false)),
+        default_fields = join(DEFAULT_NFS_FIELDS.to_owned()),
+    )
+});
+
+/// Represents the throughput fields of a CIFS share.
+#[derive(
+    Clone,
+    Debug,
+    PartialEq,
+    below_derive::EnumFromStr,
+    below_derive::EnumToString
+)]
+pub enum CifsAggField {
+    Rate,
+}
+
+impl AggField<SingleCifsModelFieldId> for CifsAggField {
+    fn expand(&self, _detail: bool) -> Vec<SingleCifsModelFieldId> {
+        use model::SingleCifsModelFieldId::*;
+
+        match self {
+            Self::Rate => vec![
+                SmbsPerSec,
+                ReadBytesPerSec,
+                WriteBytesPerSec,
+                ReadsPerSec,
+                WritesPerSec,
+                FailedOpsPerSec,
+            ],
+        }
+    }
+}
+
+pub type CifsOptionField = DumpOptionField<SingleCifsModelFieldId, CifsAggField>;
+
+pub static DEFAULT_CIFS_FIELDS: &[CifsOptionField] = &[
+    DumpOptionField::Unit(DumpField::Common(CommonField::Datetime)),
+    DumpOptionField::Unit(DumpField::FieldId(SingleCifsModelFieldId::Share)),
+    DumpOptionField::Agg(CifsAggField::Rate),
+    DumpOptionField::Unit(DumpField::FieldId(SingleCifsModelFieldId::OpenFiles)),
+    DumpOptionField::Unit(DumpField::Common(CommonField::Timestamp)),
+];
+
+const CIFS_ABOUT: &str = "Dump CIFS/SMB client stats";
+
+/// Generated about message for Cifs dump so supported fields are up-to-date.
+static CIFS_LONG_ABOUT: Lazy<String> = Lazy::new(|| {
+    format!(
+        r#"{about}
+
+********************** Available fields **********************
+
+{common_fields}, {cifs_fields}.
+
+********************** Aggregated fields **********************
+
+* rate: includes [{agg_rate_fields}].
+
+* --detail: no effect.
+
+* --default: includes [{default_fields}].
+
+* --everything: includes everything (equivalent to --default --detail).
+
+********************** Example Commands **********************
+
+Simple example:
+
+$ below dump cifs -b "08:30:00" -e "08:30:30" -f share rate -O csv
+
+Output the 3 busiest shares for each time slice from 08:30:00 to 08:30:30:
+
+$ below dump cifs -b "08:30:00" -e "08:30:30" -s smbs_per_sec --rsort --top 3
+
+"#,
+        about = CIFS_ABOUT,
+        common_fields = join(CommonField::unit_variant_iter()),
+        cifs_fields = join(SingleCifsModelFieldId::unit_variant_iter()),
+        agg_rate_fields = join(CifsAggField::Rate.expand(false)),
+        default_fields = join(DEFAULT_CIFS_FIELDS.to_owned()),
+    )
+});
+
+/// Represents the sub-models of ProcessModel.
 #[derive(
     Clone,
     Debug,
@@ -380,6 +735,7 @@ pub enum ProcessAggField {
     Cpu,
     Mem,
     Io,
+    Tcp,
 }
 
 impl AggField<SingleProcessModelFieldId> for ProcessAggField {
@@ -388,12 +744,14 @@ impl AggField<SingleProcessModelFieldId> for ProcessAggField {
         use model::ProcessIoModelFieldId as Io;
         use model::ProcessMemoryModelFieldId as Mem;
         use model::SingleProcessModelFieldId as FieldId;
+        use model::TcpHealthModelFieldId as Tcp;
 
         if detail {
             match self {
                 Self::Cpu => Cpu::unit_variant_iter().map(FieldId::Cpu).collect(),
                 Self::Mem => Mem::unit_variant_iter().map(FieldId::Mem).collect(),
                 Self::Io => Io::unit_variant_iter().map(FieldId::Io).collect(),
+                Self::Tcp => Tcp::unit_variant_iter().map(FieldId::Tcp).collect(),
             }
         } else {
             // Default fields for each group
@@ -401,6 +759,7 @@ impl AggField<SingleProcessModelFieldId> for ProcessAggField {
                 Self::Cpu => vec![FieldId::Cpu(Cpu::UsagePct)],
                 Self::Mem => vec![FieldId::Mem(Mem::RssBytes)],
                 Self::Io => vec![FieldId::Io(Io::RbytesPerSec), FieldId::Io(Io::WbytesPerSec)],
+                Self::Tcp => Tcp::unit_variant_iter().map(FieldId::Tcp).collect(),
             }
         }
     }
@@ -414,6 +773,7 @@ pub static DEFAULT_PROCESS_FIELDS: &[ProcessOptionField] = &[
     DumpOptionField::Unit(DumpField::FieldId(SingleProcessModelFieldId::Ppid)),
     DumpOptionField::Unit(DumpField::FieldId(SingleProcessModelFieldId::Comm)),
     DumpOptionField::Unit(DumpField::FieldId(SingleProcessModelFieldId::State)),
+    DumpOptionField::Unit(DumpField::FieldId(SingleProcessModelFieldId::ExitStatus)),
     DumpOptionField::Agg(ProcessAggField::Cpu),
     DumpOptionField::Agg(ProcessAggField::Mem),
     DumpOptionField::Agg(ProcessAggField::Io),
@@ -441,6 +801,8 @@ static PROCESS_LONG_ABOUT: Lazy<String> = Lazy::new(|| {
 
 {all_io_fields}
 
+{all_tcp_fields}
+
 ********************** Aggregated fields **********************
 
 * cpu: includes [{agg_cpu_fields}].
@@ -449,6 +811,8 @@ static PROCESS_LONG_ABOUT: Lazy<String> = Lazy::new(|| {
 
 * io: includes [{agg_io_fields}].
 
+* tcp: includes [{agg_tcp_fields}]. Requires below record --enable-tcp-health.
+
 * --detail: includes [<agg_field>.*] for each given aggregated field.
 
 * --default: includes [{default_fields}].
@@ -469,6 +833,10 @@ Output stats for top 5 CPU intense processes for each time slice from 08:30:00 t
 
 $ below dump process -b "08:30:00" -e "08:30:30" -s cpu.usage_pct --rsort --top 5
 
+Output processes that failed or were OOM killed from 08:30:00 to 08:30:30:
+
+$ below dump process -b "08:30:00" -e "08:30:30" --crashed -f pid comm exit_status
+
 "#,
         about = PROCESS_ABOUT,
         common_fields = join(CommonField::unit_variant_iter()),
@@ -476,14 +844,179 @@ $ below dump process -b "08:30:00" -e "08:30:30" -s cpu.usage_pct --rsort --top
         all_cpu_fields = join(ProcessAggField::Cpu.expand(true)),
         all_memory_fields = join(ProcessAggField::Mem.expand(true)),
         all_io_fields = join(ProcessAggField::Io.expand(true)),
+        all_tcp_fields = join(ProcessAggField::Tcp.expand(true)),
         agg_cpu_fields = join(ProcessAggField::Cpu.expand(false)),
         agg_memory_fields = join(ProcessAggField::Mem.expand(false)),
         agg_io_fields = join(ProcessAggField::Io.expand(false)),
+        agg_tcp_fields = join(ProcessAggField::Tcp.expand(false)),
         default_fields = join(DEFAULT_PROCESS_FIELDS.to_owned()),
     )
 });
 
-/// Represents the four sub-model of SingleCgroupModel.
+/// Represents what was executed.
+#[derive(
+    Clone,
+    Debug,
+    PartialEq,
+    below_derive::EnumFromStr,
+    below_derive::EnumToString
+)]
+pub enum ExecAggField {
+    Cmd,
+}
+
+impl AggField<SingleExecModelFieldId> for ExecAggField {
+    fn expand(&self, _detail: bool) -> Vec<SingleExecModelFieldId> {
+        use model::SingleExecModelFieldId::*;
+
+        match self {
+            Self::Cmd => vec![Filename, Cmdline],
+        }
+    }
+}
+
+pub type ExecOptionField = DumpOptionField<SingleExecModelFieldId, ExecAggField>;
+
+pub static DEFAULT_EXEC_FIELDS: &[ExecOptionField] = &[
+    DumpOptionField::Unit(DumpField::Common(CommonField::Datetime)),
+    DumpOptionField::Unit(DumpField::FieldId(SingleExecModelFieldId::Pid)),
+    DumpOptionField::Unit(DumpField::FieldId(SingleExecModelFieldId::Ppid)),
+    DumpOptionField::Unit(DumpField::FieldId(SingleExecModelFieldId::Comm)),
+    DumpOptionField::Unit(DumpField::FieldId(SingleExecModelFieldId::Cgroup)),
+    DumpOptionField::Unit(DumpField::FieldId(SingleExecModelFieldId::Timestamp)),
+    DumpOptionField::Unit(DumpField::Common(CommonField::Timestamp)),
+    DumpOptionField::Agg(ExecAggField::Cmd),
+];
+
+const EXEC_ABOUT: &str = "Dump programs executed between samples";
+
+/// Generated about message for Exec dump so supported fields are up-to-date.
+static EXEC_LONG_ABOUT: Lazy<String> = Lazy::new(|| {
+    format!(
+        r#"{about}
+
+Each row is one execve() traced by the execsnoop BPF program, so processes
+that started and exited between two samples are included. Nothing is dumped
+if below record ran with --disable-exec-trace.
+
+********************** Available fields **********************
+
+{common_fields}, {exec_fields}.
+
+********************** Aggregated fields **********************
+
+* cmd: includes [{agg_cmd_fields}].
+
+* --detail: no effect.
+
+* --default: includes [{default_fields}].
+
+* --everything: includes everything (equivalent to --default --detail).
+
+********************** Example Commands **********************
+
+Simple example:
+
+$ below dump exec -b "08:30:00" -e "08:30:30" -f pid ppid cmd -O csv
+
+Output everything executed in a cgroup from 08:30:00 to 08:30:30:
+
+$ below dump exec -b "08:30:00" -e "08:30:30" -s cgroup -F "^/system.slice/foo.service"
+
+"#,
+        about = EXEC_ABOUT,
+        common_fields = join(CommonField::unit_variant_iter()),
+        exec_fields = join(SingleExecModelFieldId::unit_variant_iter()),
+        agg_cmd_fields = join(ExecAggField::Cmd.expand(false)),
+        default_fields = join(DEFAULT_EXEC_FIELDS.to_owned()),
+    )
+});
+
+/// Represents who was killed and why.
+#[derive(
+    Clone,
+    Debug,
+    PartialEq,
+    below_derive::EnumFromStr,
+    below_derive::EnumToString
+)]
+pub enum OomAggField {
+    Victim,
+    Trigger,
+}
+
+impl AggField<SingleOomKillModelFieldId> for OomAggField {
+    fn expand(&self, _detail: bool) -> Vec<SingleOomKillModelFieldId> {
+        use model::SingleOomKillModelFieldId::*;
+
+        match self {
+            Self::Victim => vec![Pid, Comm, RssBytes, Cgroup],
+            Self::Trigger => vec![TriggerPid, TriggerComm],
+        }
+    }
+}
+
+pub type OomOptionField = DumpOptionField<SingleOomKillModelFieldId, OomAggField>;
+
+pub static DEFAULT_OOM_FIELDS: &[OomOptionField] = &[
+    DumpOptionField::Unit(DumpField::Common(CommonField::Datetime)),
+    DumpOptionField::Agg(OomAggField::Victim),
+    DumpOptionField::Unit(DumpField::FieldId(SingleOomKillModelFieldId::Kind)),
+    DumpOptionField::Unit(DumpField::FieldId(SingleOomKillModelFieldId::OomCgroup)),
+    DumpOptionField::Unit(DumpField::FieldId(SingleOomKillModelFieldId::TotalBytes)),
+    DumpOptionField::Unit(DumpField::FieldId(SingleOomKillModelFieldId::Timestamp)),
+    DumpOptionField::Unit(DumpField::Common(CommonField::Timestamp)),
+];
+
+const OOM_ABOUT: &str = "Dump OOM kills";
+
+/// Generated about message for OOM dump so supported fields are up-to-date.
+static OOM_LONG_ABOUT: Lazy<String> = Lazy::new(|| {
+    format!(
+        r#"{about}
+
+Each row is one process killed by the kernel OOM killer, as traced by the
+oomkill BPF program. kind is "global" when the whole system ran out of memory
+and "cgroup" when oom_cgroup hit its memory.max. Nothing is dumped if below
+record ran with --disable-oom-trace.
+
+********************** Available fields **********************
+
+{common_fields}, {oom_fields}.
+
+********************** Aggregated fields **********************
+
+* victim: includes [{agg_victim_fields}].
+
+* trigger: includes [{agg_trigger_fields}].
+
+* --detail: no effect.
+
+* --default: includes [{default_fields}].
+
+* --everything: includes everything (equivalent to --default --detail).
+
+********************** Example Commands **********************
+
+Simple example:
+
+$ below dump oom -b "08:30:00" -e "08:30:30" -f victim kind oom_cgroup -O csv
+
+Output OOM kills caused by a cgroup hitting its limit from 08:30:00 to 08:30:30:
+
+$ below dump oom -b "08:30:00" -e "08:30:30" -s kind -F cgroup
+
+"#,
+        about = OOM_ABOUT,
+        common_fields = join(CommonField::unit_variant_iter()),
+        oom_fields = join(SingleOomKillModelFieldId::unit_variant_iter()),
+        agg_victim_fields = join(OomAggField::Victim.expand(false)),
+        agg_trigger_fields = join(OomAggField::Trigger.expand(false)),
+        default_fields = join(DEFAULT_OOM_FIELDS.to_owned()),
+    )
+});
+
+/// Represents the sub-models of SingleCgroupModel.
 #[derive(
     Clone,
     Debug,
@@ -496,6 +1029,7 @@ pub enum CgroupAggField {
     Mem,
     Io,
     Pressure,
+    Tcp,
 }
 
 impl AggField<SingleCgroupModelFieldId> for CgroupAggField {
@@ -505,6 +1039,7 @@ impl AggField<SingleCgroupModelFieldId> for CgroupAggField {
         use model::CgroupMemoryModelFieldId as Mem;
         use model::CgroupPressureModelFieldId as Pressure;
         use model::SingleCgroupModelFieldId as FieldId;
+        use model::TcpHealthModelFieldId as Tcp;
 
         if detail {
             match self {
@@ -514,6 +1049,7 @@ impl AggField<SingleCgroupModelFieldId> for CgroupAggField {
                 Self::Pressure => Pressure::unit_variant_iter()
                     .map(FieldId::Pressure)
                     .collect(),
+                Self::Tcp => Tcp::unit_variant_iter().map(FieldId::Tcp).collect(),
             }
         } else {
             // Default fields for each group
@@ -526,6 +1062,7 @@ impl AggField<SingleCgroupModelFieldId> for CgroupAggField {
                     FieldId::Pressure(Pressure::MemoryFullPct),
                     FieldId::Pressure(Pressure::IoFullPct),
                 ],
+                Self::Tcp => Tcp::unit_variant_iter().map(FieldId::Tcp).collect(),
             }
         }
     }
@@ -563,6 +1100,8 @@ static CGROUP_LONG_ABOUT: Lazy<String> = Lazy::new(|| {
 
 {all_pressure_fields}
 
+{all_tcp_fields}
+
 ********************** Aggregated fields **********************
 
 * cpu: includes [{agg_cpu_fields}].
@@ -573,6 +1112,8 @@ static CGROUP_LONG_ABOUT: Lazy<String> = Lazy::new(|| {
 
 * pressure: includes [{agg_pressure_fields}].
 
+* tcp: includes [{agg_tcp_fields}]. Requires below record --enable-tcp-health.
+
 * --detail: includes [<agg_field>.*] for each given aggregated field.
 
 * --default: includes [{default_fields}].
@@ -603,10 +1144,12 @@ $ below dump cgroup -b "08:30:00" -e "08:30:30" -s cpu.usage_pct --rsort --top 5
         all_memory_fields = join(CgroupAggField::Mem.expand(true)),
         all_io_fields = join(CgroupAggField::Io.expand(true)),
         all_pressure_fields = join(CgroupAggField::Pressure.expand(true)),
+        all_tcp_fields = join(CgroupAggField::Tcp.expand(true)),
         agg_cpu_fields = join(CgroupAggField::Cpu.expand(false)),
         agg_memory_fields = join(CgroupAggField::Mem.expand(false)),
         agg_io_fields = join(CgroupAggField::Io.expand(false)),
         agg_pressure_fields = join(CgroupAggField::Pressure.expand(false)),
+        agg_tcp_fields = join(CgroupAggField::Tcp.expand(false)),
         default_fields = join(DEFAULT_CGROUP_FIELDS.to_owned()),
     )
 });
@@ -983,6 +1526,48 @@ pub enum DumpCommand {
         #[structopt(long, short, conflicts_with("fields"))]
         pattern: Option<String>,
     },
+    #[structopt(about = FS_ABOUT, long_about = FS_LONG_ABOUT.as_str())]
+    Fs {
+        /// Select which fields to display and in what order.
+        #[structopt(short, long)]
+        fields: Option<Vec<FsOptionField>>,
+        #[structopt(flatten)]
+        opts: GeneralOpt,
+        /// Select field for operation, use with --sort, --rsort, --filter, --top
+        #[structopt(long, short)]
+        select: Option<SingleFsModelFieldId>,
+        /// Saved pattern in the dumprc file under [fs] section.
+        #[structopt(long, short, conflicts_with("fields"))]
+        pattern: Option<String>,
+    },
+    #[structopt(about = NFS_ABOUT, long_about = NFS_LONG_ABOUT.as_str())]
+    Nfs {
+        /// Select which fields to display and in what order.
+        #[structopt(short, long)]
+        fields: Option<Vec<NfsOptionField>>,
+        #[structopt(flatten)]
+        opts: GeneralOpt,
+        /// Select field for operation, use with --sort, --rsort, --filter, --top
+        #[structopt(long, short)]
+        select: Option<SingleNfsModelFieldId>,
+        /// Saved pattern in the dumprc file under [nfs] section.
+        #[structopt(long, short, conflicts_with("fields"))]
+        pattern: Option<String>,
+    },
+    #[structopt(about = CIFS_ABOUT, long_about = CIFS_LONG_ABOUT.as_str())]
+    Cifs {
+        /// Select which fields to display and in what order.
+        #[structopt(short, long)]
+        fields: Option<Vec<CifsOptionField>>,
+        #[structopt(flatten)]
+        opts: GeneralOpt,
+        /// Select field for operation, use with --sort, --rsort, --filter, --top
+        #[structopt(long, short)]
+        select: Option<SingleCifsModelFieldId>,
+        /// Saved pattern in the dumprc file under [cifs] section.
+        #[structopt(long, short, conflicts_with("fields"))]
+        pattern: Option<String>,
+    },
     #[structopt(about = PROCESS_ABOUT, long_about = PROCESS_LONG_ABOUT.as_str())]
     Process {
         /// Select which fields to display and in what order.
@@ -996,6 +1581,39 @@ pub enum DumpCommand {
         /// Saved pattern in the dumprc file under [process] section.
         #[structopt(long, short, conflicts_with("fields"))]
         pattern: Option<String>,
+        /// Only dump processes that failed: exited with a nonzero code or were
+        /// killed by a signal other than SIGTERM, SIGINT or SIGHUP, including
+        /// OOM kills
+        #[structopt(long)]
+        crashed: bool,
+    },
+    #[structopt(about = EXEC_ABOUT, long_about = EXEC_LONG_ABOUT.as_str())]
+    Exec {
+        /// Select which fields to display and in what order.
+        #[structopt(short, long)]
+        fields: Option<Vec<ExecOptionField>>,
+        #[structopt(flatten)]
+        opts: GeneralOpt,
+        /// Select field for operation, use with --sort, --rsort, --filter, --top
+        #[structopt(long, short)]
+        select: Option<SingleExecModelFieldId>,
+        /// Saved pattern in the dumprc file under [exec] section.
+        #[structopt(long, short, conflicts_with("fields"))]
+        pattern: Option<String>,
+    },
+    #[structopt(about = OOM_ABOUT, long_about = OOM_LONG_ABOUT.as_str())]
+    Oom {
+        /// Select which fields to display and in what order.
+        #[structopt(short, long)]
+        fields: Option<Vec<OomOptionField>>,
+        #[structopt(flatten)]
+        opts: GeneralOpt,
+        /// Select field for operation, use with --sort, --rsort, --filter, --top
+        #[structopt(long, short)]
+        select: Option<SingleOomKillModelFieldId>,
+        /// Saved pattern in the dumprc file under [oom] section.
+        #[structopt(long, short, conflicts_with("fields"))]
+        pattern: Option<String>,
     },
     #[structopt(about = CGROUP_ABOUT, long_about = CGROUP_LONG_ABOUT.as_str())]
     Cgroup {
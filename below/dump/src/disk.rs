@@ -49,10 +49,10 @@ impl Dumper for Disk {
     ) -> Result<IterExecResult> {
         let mut disks: Vec<_> = model
             .system
-            .disks
-            .iter()
+            .disks_tree()
+            .into_iter()
             .filter_map(
-                |(_, model)| match (self.select.as_ref(), self.opts.filter.as_ref()) {
+                |model| match (self.select.as_ref(), self.opts.filter.as_ref()) {
                     (Some(field_id), Some(filter))
                         if !filter.is_match(
                             &model
@@ -27,15 +27,18 @@ use common::cliutil;
 use common::util::{get_belowrc_dump_section_key, get_belowrc_filename, timestamp_to_datetime};
 use model::{EnumIter, Field, FieldId, Queriable};
 
-use store::advance::{new_advance_local, new_advance_remote};
-use store::Advance;
-use store::Direction;
+use store::{DataFrame, Direction, LocalStore, RemoteStore, Store};
 
 pub mod cgroup;
+pub mod cifs;
 pub mod command;
 pub mod disk;
+pub mod exec;
+pub mod fs;
 pub mod iface;
 pub mod network;
+pub mod nfs;
+pub mod oom;
 pub mod print;
 pub mod process;
 pub mod system;
@@ -93,33 +96,40 @@ pub type CgroupField = DumpField<model::SingleCgroupModelFieldId>;
 pub type ProcessField = DumpField<model::SingleProcessModelFieldId>;
 pub type SystemField = DumpField<model::SystemModelFieldId>;
 pub type DiskField = DumpField<model::SingleDiskModelFieldId>;
+pub type ExecField = DumpField<model::SingleExecModelFieldId>;
+pub type FsField = DumpField<model::SingleFsModelFieldId>;
+pub type NfsField = DumpField<model::SingleNfsModelFieldId>;
+pub type CifsField = DumpField<model::SingleCifsModelFieldId>;
+pub type OomField = DumpField<model::SingleOomKillModelFieldId>;
 pub type NetworkField = DumpField<model::NetworkModelFieldId>;
 pub type IfaceField = DumpField<model::SingleNetModelFieldId>;
 // Essentially the same as NetworkField
 pub type TransportField = DumpField<model::NetworkModelFieldId>;
 
-fn get_advance(
+fn get_store(
     logger: slog::Logger,
     dir: PathBuf,
     host: Option<String>,
     port: Option<u16>,
     opts: &command::GeneralOpt,
-) -> Result<(SystemTime, SystemTime, Advance)> {
+) -> Result<(
+    SystemTime,
+    SystemTime,
+    Box<dyn Store<SampleType = DataFrame>>,
+)> {
     let (time_begin, time_end) = cliutil::system_time_range_from_date_and_adjuster(
         opts.begin.as_str(),
         opts.end.as_deref(),
         opts.yesterdays.as_deref(),
     )?;
 
-    let mut advance = if let Some(host) = host {
-        new_advance_remote(logger.clone(), host, port, time_begin)?
+    let store: Box<dyn Store<SampleType = DataFrame>> = if let Some(host) = host {
+        Box::new(RemoteStore::new(host, port)?)
     } else {
-        new_advance_local(logger.clone(), dir, time_begin)
+        Box::new(LocalStore::new(logger, dir))
     };
 
-    advance.initialize();
-
-    Ok((time_begin, time_end, advance))
+    Ok((time_begin, time_end, store))
 }
 
 /// Try to read $HOME/.config/below/belowrc file and generate a list of keys which will
@@ -190,7 +200,7 @@ pub fn run(
             opts,
             pattern,
         } => {
-            let (time_begin, time_end, advance) = get_advance(logger, dir, host, port, &opts)?;
+            let (time_begin, time_end, mut store) = get_store(logger, dir, host, port, &opts)?;
             let default = opts.everything || opts.default;
             let detail = opts.everything || opts.detail;
             let fields = if let Some(pattern_key) = pattern {
@@ -211,7 +221,7 @@ pub fn run(
                 None => Box::new(io::stdout()),
             };
             dump_timeseries(
-                advance,
+                store.as_mut(),
                 time_begin,
                 time_end,
                 &system,
@@ -227,7 +237,7 @@ pub fn run(
             select,
             pattern,
         } => {
-            let (time_begin, time_end, advance) = get_advance(logger, dir, host, port, &opts)?;
+            let (time_begin, time_end, mut store) = get_store(logger, dir, host, port, &opts)?;
             let default = opts.everything || opts.default;
             let detail = opts.everything || opts.detail;
             let fields = if let Some(pattern_key) = pattern {
@@ -248,7 +258,7 @@ pub fn run(
                 None => Box::new(io::stdout()),
             };
             dump_timeseries(
-                advance,
+                store.as_mut(),
                 time_begin,
                 time_end,
                 &disk,
@@ -258,13 +268,125 @@ pub fn run(
                 errs,
             )
         }
+        DumpCommand::Fs {
+            fields,
+            opts,
+            select,
+            pattern,
+        } => {
+            let (time_begin, time_end, mut store) = get_store(logger, dir, host, port, &opts)?;
+            let default = opts.everything || opts.default;
+            let detail = opts.everything || opts.detail;
+            let fields = if let Some(pattern_key) = pattern {
+                parse_pattern(filename, pattern_key, "fs")
+            } else {
+                fields
+            };
+            let fields = expand_fields(
+                match fields.as_ref() {
+                    Some(fields) if !default => fields,
+                    _ => command::DEFAULT_FS_FIELDS,
+                },
+                detail,
+            );
+            let fs = fs::Fs::new(&opts, select, fields);
+            let mut output: Box<dyn Write> = match opts.output.as_ref() {
+                Some(file_path) => Box::new(File::create(file_path)?),
+                None => Box::new(io::stdout()),
+            };
+            dump_timeseries(
+                store.as_mut(),
+                time_begin,
+                time_end,
+                &fs,
+                output.as_mut(),
+                opts.output_format,
+                opts.br,
+                errs,
+            )
+        }
+        DumpCommand::Nfs {
+            fields,
+            opts,
+            select,
+            pattern,
+        } => {
+            let (time_begin, time_end, mut store) = get_store(logger, dir, host, port, &opts)?;
+            let default = opts.everything || opts.default;
+            let detail = opts.everything || opts.detail;
+            let fields = if let Some(pattern_key) = pattern {
+                parse_pattern(filename, pattern_key, "nfs")
+            } else {
+                fields
+            };
+            let fields = expand_fields(
+                match fields.as_ref() {
+                    Some(fields) if !default => fields,
+                    _ => command::DEFAULT_NFS_FIELDS,
+                },
+                detail,
+            );
+            let nfs = nfs::Nfs::new(&opts, select, fields);
+            let mut output: Box<dyn Write> = match opts.output.as_ref() {
+                Some(file_path) => Box::new(File::create(file_path)?),
+                None => Box::new(io::stdout()),
+            };
+            dump_timeseries(
+                store.as_mut(),
+                time_begin,
+                time_end,
+                &nfs,
+                output.as_mut(),
+                opts.output_format,
+                opts.br,
+                errs,
+            )
+        }
+        DumpCommand::Cifs {
+            fields,
+            opts,
+            select,
+            pattern,
+        } => {
+            let (time_begin, time_end, mut store) = get_store(logger, dir, host, port, &opts)?;
+            let default = opts.everything || opts.default;
+            let detail = opts.everything || opts.detail;
+            let fields = if let Some(pattern_key) = pattern {
+                parse_pattern(filename, pattern_key, "cifs")
+            } else {
+                fields
+            };
+            let fields = expand_fields(
+                match fields.as_ref() {
+                    Some(fields) if !default => fields,
+                    _ => command::DEFAULT_CIFS_FIELDS,
+                },
+                detail,
+            );
+            let cifs = cifs::Cifs::new(&opts, select, fields);
+            let mut output: Box<dyn Write> = match opts.output.as_ref() {
+                Some(file_path) => Box::new(File::create(file_path)?),
+                None => Box::new(io::stdout()),
+            };
+            dump_timeseries(
+                store.as_mut(),
+                time_begin,
+                time_end,
+                &cifs,
+                output.as_mut(),
+                opts.output_format,
+                opts.br,
+                errs,
+            )
+        }
         DumpCommand::Process {
             fields,
             opts,
             select,
             pattern,
+            crashed,
         } => {
-            let (time_begin, time_end, advance) = get_advance(logger, dir, host, port, &opts)?;
+            let (time_begin, time_end, mut store) = get_store(logger, dir, host, port, &opts)?;
             let default = opts.everything || opts.default;
             let detail = opts.everything || opts.detail;
             let fields = if let Some(pattern_key) = pattern {
@@ -279,13 +401,13 @@ pub fn run(
                 },
                 detail,
             );
-            let process = process::Process::new(&opts, select, fields);
+            let process = process::Process::new(&opts, select, crashed, fields);
             let mut output: Box<dyn Write> = match opts.output.as_ref() {
                 Some(file_path) => Box::new(File::create(file_path)?),
                 None => Box::new(io::stdout()),
             };
             dump_timeseries(
-                advance,
+                store.as_mut(),
                 time_begin,
                 time_end,
                 &process,
@@ -295,13 +417,87 @@ pub fn run(
                 errs,
             )
         }
+        DumpCommand::Exec {
+            fields,
+            opts,
+            select,
+            pattern,
+        } => {
+            let (time_begin, time_end, mut store) = get_store(logger, dir, host, port, &opts)?;
+            let default = opts.everything || opts.default;
+            let detail = opts.everything || opts.detail;
+            let fields = if let Some(pattern_key) = pattern {
+                parse_pattern(filename, pattern_key, "exec")
+            } else {
+                fields
+            };
+            let fields = expand_fields(
+                match fields.as_ref() {
+                    Some(fields) if !default => fields,
+                    _ => command::DEFAULT_EXEC_FIELDS,
+                },
+                detail,
+            );
+            let exec = exec::Exec::new(&opts, select, fields);
+            let mut output: Box<dyn Write> = match opts.output.as_ref() {
+                Some(file_path) => Box::new(File::create(file_path)?),
+                None => Box::new(io::stdout()),
+            };
+            dump_timeseries(
+                store.as_mut(),
+                time_begin,
+                time_end,
+                &exec,
+                output.as_mut(),
+                opts.output_format,
+                opts.br,
+                errs,
+            )
+        }
+        DumpCommand::Oom {
+            fields,
+            opts,
+            select,
+            pattern,
+        } => {
+            let (time_begin, time_end, mut store) = get_store(logger, dir, host, port, &opts)?;
+            let default = opts.everything || opts.default;
+            let detail = opts.everything || opts.detail;
+            let fields = if let Some(pattern_key) = pattern {
+                parse_pattern(filename, pattern_key, "oom")
+            } else {
+                fields
+            };
+            let fields = expand_fields(
+                match fields.as_ref() {
+                    Some(fields) if !default => fields,
+                    _ => command::DEFAULT_OOM_FIELDS,
+                },
+                detail,
+            );
+            let oom = oom::Oom::new(&opts, select, fields);
+            let mut output: Box<dyn Write> = match opts.output.as_ref() {
+                Some(file_path) => Box::new(File::create(file_path)?),
+                None => Box::new(io::stdout()),
+            };
+            dump_timeseries(
+                store.as_mut(),
+                time_begin,
+                time_end,
+                &oom,
+                output.as_mut(),
+                opts.output_format,
+                opts.br,
+                errs,
+            )
+        }
         DumpCommand::Cgroup {
             fields,
             opts,
             select,
             pattern,
         } => {
-            let (time_begin, time_end, advance) = get_advance(logger, dir, host, port, &opts)?;
+            let (time_begin, time_end, mut store) = get_store(logger, dir, host, port, &opts)?;
             let default = opts.everything || opts.default;
             let detail = opts.everything || opts.detail;
             let fields = if let Some(pattern_key) = pattern {
@@ -322,7 +518,7 @@ pub fn run(
                 None => Box::new(io::stdout()),
             };
             dump_timeseries(
-                advance,
+                store.as_mut(),
                 time_begin,
                 time_end,
                 &cgroup,
@@ -338,7 +534,7 @@ pub fn run(
             select,
             pattern,
         } => {
-            let (time_begin, time_end, advance) = get_advance(logger, dir, host, port, &opts)?;
+            let (time_begin, time_end, mut store) = get_store(logger, dir, host, port, &opts)?;
             let default = opts.everything || opts.default;
             let detail = opts.everything || opts.detail;
             let fields = if let Some(pattern_key) = pattern {
@@ -359,7 +555,7 @@ pub fn run(
                 None => Box::new(io::stdout()),
             };
             dump_timeseries(
-                advance,
+                store.as_mut(),
                 time_begin,
                 time_end,
                 &iface,
@@ -374,7 +570,7 @@ pub fn run(
             opts,
             pattern,
         } => {
-            let (time_begin, time_end, advance) = get_advance(logger, dir, host, port, &opts)?;
+            let (time_begin, time_end, mut store) = get_store(logger, dir, host, port, &opts)?;
             let default = opts.everything || opts.default;
             let detail = opts.everything || opts.detail;
             let fields = if let Some(pattern_key) = pattern {
@@ -395,7 +591,7 @@ pub fn run(
                 None => Box::new(io::stdout()),
             };
             dump_timeseries(
-                advance,
+                store.as_mut(),
                 time_begin,
                 time_end,
                 &network,
@@ -410,7 +606,7 @@ pub fn run(
             opts,
             pattern,
         } => {
-            let (time_begin, time_end, advance) = get_advance(logger, dir, host, port, &opts)?;
+            let (time_begin, time_end, mut store) = get_store(logger, dir, host, port, &opts)?;
             let default = opts.everything || opts.default;
             let detail = opts.everything || opts.detail;
             let fields = if let Some(pattern_key) = pattern {
@@ -431,7 +627,7 @@ pub fn run(
                 None => Box::new(io::stdout()),
             };
             dump_timeseries(
-                advance,
+                store.as_mut(),
                 time_begin,
                 time_end,
                 &transport,
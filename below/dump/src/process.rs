@@ -38,6 +38,7 @@ impl HasRenderConfigForDump for model::SingleProcessModel {
 pub struct Process {
     opts: GeneralOpt,
     select: Option<SingleProcessModelFieldId>,
+    crashed: bool,
     fields: Vec<ProcessField>,
 }
 
@@ -45,11 +46,13 @@ impl Process {
     pub fn new(
         opts: &GeneralOpt,
         select: Option<SingleProcessModelFieldId>,
+        crashed: bool,
         fields: Vec<ProcessField>,
     ) -> Self {
         Self {
             opts: opts.to_owned(),
             select,
+            crashed,
             fields,
         }
     }
@@ -68,6 +71,7 @@ impl Dumper for Process {
             .process
             .processes
             .iter()
+            .filter(|(_, spm)| !self.crashed || spm.is_crashed())
             .filter_map(
                 |(_, spm)| match (self.select.as_ref(), self.opts.filter.as_ref()) {
                     (Some(field_id), Some(filter))
@@ -116,6 +116,9 @@ fn test_dump_sys_titles() {
         "Stolen",
         "Guest",
         "Guest Nice",
+        "Freq",
+        "Core Throttle",
+        "Package Throttle",
         "Total",
         "Free",
         "Available",
@@ -170,6 +173,25 @@ fn test_dump_sys_titles() {
         "Total Procs",
         "Running Procs",
         "Blocked Procs",
+        "File Handles",
+        "File Max",
+        "File Handles Usage",
+        "Inodes",
+        "Inodes Free",
+        "Dentries",
+        "Dentries Unused",
+        "Threads",
+        "Pid Max",
+        "Pid Usage",
+        "Threads Max",
+        "Threads Usage",
+        "Entropy",
+        "Entropy Pool",
+        "Entropy Avail",
+        "Exitstat",
+        "Exitstat Lost",
+        "Exitstat Dropped",
+        "Exitstat Health",
         "CPU 31 Idx",
         "CPU 31 Usage",
         "CPU 31 User",
@@ -182,10 +204,30 @@ fn test_dump_sys_titles() {
         "CPU 31 Stolen",
         "CPU 31 Guest",
         "CPU 31 Guest Nice",
+        "CPU 31 Freq",
+        "CPU 31 Core Throttle",
+        "CPU 31 Package Throttle",
     ];
     assert_eq!(titles, expected_titles);
 }
 
+#[test]
+fn test_dump_sys_vm_counter_field() {
+    let field = command::SystemOptionField::from_str("vm.counters.compact_stall.per_sec")
+        .expect("Failed to parse vmstat counter field");
+    let titles = expand_fields(&[field], false)
+        .into_iter()
+        .filter_map(|dump_field| match dump_field {
+            DumpField::Common(_) => None,
+            DumpField::FieldId(field_id) => {
+                let rc = model::SystemModel::get_render_config_for_dump(&field_id);
+                Some(rc.render_title(false))
+            }
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(titles, vec!["compact_stall Rate"]);
+}
+
 #[test]
 // Test correctness of process decoration
 // This test will also test JSON correctness.
@@ -199,7 +241,7 @@ fn test_dump_process_content() {
     let mut opts: GeneralOpt = Default::default();
     let fields = command::expand_fields(command::DEFAULT_PROCESS_FIELDS, true);
     opts.output_format = Some(OutputFormat::Json);
-    let process_dumper = process::Process::new(&opts, None, fields.clone());
+    let process_dumper = process::Process::new(&opts, None, false, fields.clone());
 
     // update model again to populate cpu and io data
     let model = collector
@@ -321,6 +363,7 @@ fn test_dump_proc_select() {
     let process_dumper = process::Process::new(
         &opts,
         Some(model::SingleProcessModelFieldId::Pid),
+        false,
         fields.clone(),
     );
 
@@ -343,6 +386,7 @@ fn test_dump_proc_select() {
     let process_dumper = process::Process::new(
         &opts,
         Some(model::SingleProcessModelFieldId::Pid),
+        false,
         fields.clone(),
     );
 
@@ -369,8 +413,12 @@ fn test_dump_proc_select() {
     // test select sort top
     opts.sort = false;
     opts.rsort = true;
-    let process_dumper =
-        process::Process::new(&opts, Some(model::SingleProcessModelFieldId::Pid), fields);
+    let process_dumper = process::Process::new(
+        &opts,
+        Some(model::SingleProcessModelFieldId::Pid),
+        false,
+        fields,
+    );
 
     process_content = Vec::new();
     round = 0;
@@ -474,6 +522,9 @@ fn test_dump_cgroup_titles() {
         "Nr Period",
         "Nr Throttled",
         "Throttled Pct",
+        "Run Delay P50",
+        "Run Delay P90",
+        "Run Delay P99",
         "Mem Total",
         "Mem Swap",
         "Mem Anon",
@@ -520,6 +571,10 @@ fn test_dump_cgroup_titles() {
         "DBytes",
         "D I/O",
         "RW Total",
+        "Read Lat P50",
+        "Read Lat P99",
+        "Write Lat P50",
+        "Write Lat P99",
         "CPU Pressure",
         "I/O Some Pressure",
         "I/O Pressure",
@@ -943,6 +998,9 @@ fn test_dump_disk_titles() {
         "Disk",
         "Major",
         "Minor",
+        "DM Name",
+        "Parent",
+        "Slaves",
         "Read",
         "Read Completed",
         "Read Merged",
@@ -958,9 +1016,199 @@ fn test_dump_disk_titles() {
         "Discard Merged",
         "Discard Sectors",
         "Time Spend Discard",
+        "Flush Completed",
+        "Time Spend Flush",
+        "Util",
+        "Avg Queue Size",
+        "Read Await",
+        "Write Await",
+        "Avg Request Size",
+        "IOs In Progress",
+        "Read Lat P50",
+        "Read Lat P99",
+        "Write Lat P50",
+        "Write Lat P99",
         "Disk Usage",
         "Partition Size",
         "Filesystem Type",
+        "Mount Point",
+    ];
+    assert_eq!(titles, expected_titles);
+}
+
+#[test]
+fn test_dump_fs_titles() {
+    let titles = expand_fields(command::DEFAULT_FS_FIELDS, true)
+        .iter()
+        .filter_map(|dump_field| match dump_field {
+            DumpField::Common(_) => None,
+            DumpField::FieldId(field_id) => {
+                let rc = model::SingleFsModel::get_render_config_for_dump(&field_id);
+                Some(rc.render_title(false))
+            }
+        })
+        .collect::<Vec<_>>();
+    let expected_titles = vec![
+        "Mount Point",
+        "Mount Source",
+        "Fs Type",
+        "Usage",
+        "Total",
+        "Used",
+        "Free",
+        "Avail",
+        "Inode Usage",
+        "Total Inodes",
+        "Used Inodes",
+        "Free Inodes",
+        "Avail Inodes",
+    ];
+    assert_eq!(titles, expected_titles);
+}
+
+#[test]
+fn test_dump_exec_titles() {
+    let titles = expand_fields(command::DEFAULT_EXEC_FIELDS, true)
+        .iter()
+        .filter_map(|dump_field| match dump_field {
+            DumpField::Common(_) => None,
+            DumpField::FieldId(field_id) => {
+                let rc = model::SingleExecModel::get_render_config_for_dump(&field_id);
+                Some(rc.render_title(false))
+            }
+        })
+        .collect::<Vec<_>>();
+    let expected_titles = vec![
+        "Pid",
+        "Ppid",
+        "Comm",
+        "Cgroup",
+        "Exec Time",
+        "Filename",
+        "Cmdline",
+    ];
+    assert_eq!(titles, expected_titles);
+}
+
+#[test]
+fn test_dump_exec_content() {
+    let model = model::get_sample_model();
+    let fields = command::expand_fields(command::DEFAULT_EXEC_FIELDS, true);
+    let mut opts: GeneralOpt = Default::default();
+    opts.output_format = Some(OutputFormat::Json);
+    let exec_dumper = exec::Exec::new(&opts, None, fields);
+
+    let mut exec_content: Vec<u8> = Vec::new();
+    let mut round = 0;
+    let ctx = CommonFieldContext { timestamp: 0 };
+    exec_dumper
+        .dump_model(&ctx, &model, &mut exec_content, &mut round, false)
+        .expect("Failed to dump exec model");
+
+    let jval: Value = serde_json::from_slice(&exec_content).expect("Fail parse json of exec dump");
+    assert_eq!(round, model.process.execs.len());
+    assert_eq!(jval[0]["Pid"], "4001");
+    assert_eq!(jval[0]["Cmdline"], "/bin/sh -c true");
+}
+
+#[test]
+fn test_dump_oom_titles() {
+    let titles = expand_fields(command::DEFAULT_OOM_FIELDS, true)
+        .iter()
+        .filter_map(|dump_field| match dump_field {
+            DumpField::Common(_) => None,
+            DumpField::FieldId(field_id) => {
+                let rc = model::SingleOomKillModel::get_render_config_for_dump(&field_id);
+                Some(rc.render_title(false))
+            }
+        })
+        .collect::<Vec<_>>();
+    let expected_titles = vec![
+        "Pid",
+        "Comm",
+        "RSS",
+        "Cgroup",
+        "Kind",
+        "OOM Cgroup",
+        "OOM Limit",
+        "Kill Time",
+    ];
+    assert_eq!(titles, expected_titles);
+}
+
+#[test]
+fn test_dump_oom_content() {
+    let model = model::get_sample_model();
+    let fields = command::expand_fields(command::DEFAULT_OOM_FIELDS, true);
+    let mut opts: GeneralOpt = Default::default();
+    opts.output_format = Some(OutputFormat::Json);
+    let oom_dumper = oom::Oom::new(&opts, None, fields);
+
+    let mut oom_content: Vec<u8> = Vec::new();
+    let mut round = 0;
+    let ctx = CommonFieldContext { timestamp: 0 };
+    oom_dumper
+        .dump_model(&ctx, &model, &mut oom_content, &mut round, false)
+        .expect("Failed to dump oom model");
+
+    let jval: Value = serde_json::from_slice(&oom_content).expect("Fail parse json of oom dump");
+    assert_eq!(round, model.oom.kills.len());
+    assert_eq!(jval[0]["Pid"], "4100");
+    assert_eq!(jval[0]["Kind"], "cgroup");
+    assert_eq!(jval[0]["OOM Cgroup"], "/workload.slice");
+}
+
+#[test]
+fn test_dump_nfs_titles() {
+    let titles = expand_fields(command::DEFAULT_NFS_FIELDS, true)
+        .iter()
+        .filter_map(|dump_field| match dump_field {
+            DumpField::Common(_) => None,
+            DumpField::FieldId(field_id) => {
+                let rc = model::SingleNfsModel::get_render_config_for_dump(&field_id);
+                Some(rc.render_title(false))
+            }
+        })
+        .collect::<Vec<_>>();
+    let mut expected_titles = vec![
+        "Mount Point".to_owned(),
+        "Device".to_owned(),
+        "Read".to_owned(),
+        "Write".to_owned(),
+        "Server Read".to_owned(),
+        "Server Write".to_owned(),
+        "Ops".to_owned(),
+        "Retrans".to_owned(),
+    ];
+    for op in &["Read", "Write", "Getattr", "Lookup"] {
+        for title in &["Ops", "Retrans", "Timeouts", "RTT", "Exec"] {
+            expected_titles.push(format!("{} {}", op, title));
+        }
+    }
+    assert_eq!(titles, expected_titles);
+}
+
+#[test]
+fn test_dump_cifs_titles() {
+    let titles = expand_fields(command::DEFAULT_CIFS_FIELDS, true)
+        .iter()
+        .filter_map(|dump_field| match dump_field {
+            DumpField::Common(_) => None,
+            DumpField::FieldId(field_id) => {
+                let rc = model::SingleCifsModel::get_render_config_for_dump(&field_id);
+                Some(rc.render_title(false))
+            }
+        })
+        .collect::<Vec<_>>();
+    let expected_titles = vec![
+        "Share",
+        "SMBs",
+        "Read",
+        "Write",
+        "Reads",
+        "Writes",
+        "Failed Ops",
+        "Open Files",
     ];
     assert_eq!(titles, expected_titles);
 }
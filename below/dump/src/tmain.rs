@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::time::Duration;
+
 use super::*;
 
 #[derive(PartialEq)]
@@ -40,7 +42,7 @@ pub trait Dumper {
 /// Dumper implementations. This function is responsible for retrieving Models
 /// and handling formatting between time steps.
 pub fn dump_timeseries(
-    mut advance: Advance,
+    store: &mut dyn Store<SampleType = DataFrame>,
     time_begin: SystemTime,
     time_end: SystemTime,
     dumper: &dyn Dumper,
@@ -49,8 +51,8 @@ pub fn dump_timeseries(
     br: Option<String>,
     errs: Receiver<Error>,
 ) -> Result<()> {
-    let mut model = match advance.jump_sample_to(time_begin) {
-        Some(m) => m,
+    let first_timestamp = match store.get_sample_at_timestamp(time_begin, Direction::Forward)? {
+        Some((timestamp, _)) => timestamp,
         None => bail!(
             "No initial sample could be found!\n\
             You may have provided a time in the future or no data was recorded during the provided time. \
@@ -59,22 +61,51 @@ pub fn dump_timeseries(
         ),
     };
 
-    cliutil::check_initial_sample_time_in_time_range(model.timestamp, time_begin, time_end)?;
+    cliutil::check_initial_sample_time_in_time_range(first_timestamp, time_begin, time_end)?;
+
+    // Models are calculated from a sample and the one before it, so the
+    // first model needs the sample right before the range
+    let mut last = store
+        .get_sample_at_timestamp(first_timestamp - Duration::from_secs(1), Direction::Reverse)?;
 
     let json = output_format == Some(OutputFormat::Json);
     let csv = output_format == Some(OutputFormat::Csv);
 
     let mut round = 0;
+    let mut needs_separator = false;
 
     if json {
         write!(output, "[")?;
     }
 
-    loop {
+    for item in store::iter_range(store, time_begin, time_end, 1) {
         // Received external error, e.g. stop signal
         if let Ok(e) = errs.try_recv() {
             bail!(e);
         }
+        let (timestamp, frame) = item?;
+        let model = model::Model::new(
+            timestamp,
+            &frame.sample,
+            last.as_ref().map(|(last_timestamp, last_frame)| {
+                (
+                    &last_frame.sample,
+                    timestamp
+                        .duration_since(*last_timestamp)
+                        .expect("time went backwards"),
+                )
+            }),
+        );
+        last = Some((timestamp, frame));
+
+        if needs_separator {
+            if json {
+                write!(output, "\n")?;
+            } else if br.is_some() && !csv {
+                write!(output, "{}\n", br.as_ref().unwrap())?;
+            }
+        }
+
         let ctx = CommonFieldContext {
             timestamp: model
                 .timestamp
@@ -97,32 +128,16 @@ pub fn dump_timeseries(
                 }
             }
         };
-
-        if advance.get_next_ts() > time_end {
-            break;
-        }
-
-        model = match advance.advance(Direction::Forward) {
-            Some(m) => m,
-            None => break,
-        };
-
-        if res == IterExecResult::Skip {
-            continue;
-        }
-
-        if json {
-            write!(output, "\n")?;
-        } else if br.is_some() && !csv {
-            write!(output, "{}\n", br.as_ref().unwrap())?;
-        }
+        needs_separator = res != IterExecResult::Skip;
     }
 
     if json {
         write!(output, "]")?;
     }
 
-    cliutil::check_final_sample_time_with_requested_time(model.timestamp, time_end);
+    if let Some((last_timestamp, _)) = last {
+        cliutil::check_final_sample_time_with_requested_time(last_timestamp, time_end);
+    }
 
     Ok(())
 }
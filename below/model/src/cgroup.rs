@@ -36,6 +36,8 @@ pub struct SingleCgroupModel {
     pub io_total: Option<CgroupIoModel>,
     #[queriable(subquery)]
     pub pressure: Option<CgroupPressureModel>,
+    #[queriable(subquery)]
+    pub tcp: Option<TcpHealthModel>,
 }
 
 /// A model that represents a cgroup subtree. Each instance is a node that uses
@@ -184,7 +186,15 @@ impl CgroupModel {
         {
             // We have cumulative data, create cpu, io models
             let cpu = match (last.cpu_stat.as_ref(), sample.cpu_stat.as_ref()) {
-                (Some(begin), Some(end)) => Some(CgroupCpuModel::new(begin, end, delta)),
+                (Some(begin), Some(end)) => {
+                    let mut cpu = CgroupCpuModel::new(begin, end, delta);
+                    if let (Some(begin), Some(end)) =
+                        (last.run_delay_hist.as_ref(), sample.run_delay_hist.as_ref())
+                    {
+                        cpu.set_run_delay(begin, end);
+                    }
+                    Some(cpu)
+                }
                 _ => None,
             };
             let io = match (last.io_stat.as_ref(), sample.io_stat.as_ref()) {
@@ -192,10 +202,20 @@ impl CgroupModel {
                     end.iter()
                         .filter_map(|(device_name, end_io_stat)| {
                             begin.get(device_name).map(|begin_io_stat| {
-                                (
-                                    device_name.clone(),
-                                    CgroupIoModel::new(&begin_io_stat, &end_io_stat, delta),
-                                )
+                                let mut io =
+                                    CgroupIoModel::new(&begin_io_stat, &end_io_stat, delta);
+                                if let (Some(begin), Some(end)) = (
+                                    last.io_latency_hist
+                                        .as_ref()
+                                        .and_then(|hists| hists.get(device_name)),
+                                    sample
+                                        .io_latency_hist
+                                        .as_ref()
+                                        .and_then(|hists| hists.get(device_name)),
+                                ) {
+                                    io.set_latency(begin, end);
+                                }
+                                (device_name.clone(), io)
                             })
                         })
                         .collect::<BTreeMap<String, CgroupIoModel>>(),
@@ -214,6 +234,13 @@ impl CgroupModel {
             (None, None, None, last.is_some())
         };
 
+        let tcp = last_if_inode_matches.and_then(|(last, delta)| {
+            match (last.tcp_health.as_ref(), sample.tcp_health.as_ref()) {
+                (Some(begin), Some(end)) => Some(TcpHealthModel::new(begin, end, delta)),
+                _ => None,
+            }
+        });
+
         let memory = Some(CgroupMemoryModel::new(sample, last));
 
         let pressure = sample
@@ -256,6 +283,7 @@ impl CgroupModel {
                 io,
                 io_total,
                 pressure,
+                tcp,
                 depth,
             },
             children,
@@ -278,6 +306,24 @@ impl Recursive for SingleCgroupModel {
     }
 }
 
+/// Maps cgroup inode numbers, which is what BPF reports as the cgroup ID, to
+/// paths in the same format as /proc/[pid]/cgroup.
+pub(crate) fn collect_cgroup_paths(
+    sample: &CgroupSample,
+    path: &str,
+    paths: &mut BTreeMap<u64, String>,
+) {
+    if let Some(ino) = sample.inode_number {
+        let path = if path.is_empty() { "/" } else { path };
+        paths.insert(ino as u64, path.to_owned());
+    }
+    if let Some(children) = sample.children.as_ref() {
+        for (name, child) in children {
+            collect_cgroup_paths(child, &format!("{}/{}", path, name), paths);
+        }
+    }
+}
+
 #[derive(
     Clone,
     Debug,
@@ -294,6 +340,15 @@ pub struct CgroupCpuModel {
     pub nr_periods_per_sec: Option<f64>,
     pub nr_throttled_per_sec: Option<f64>,
     pub throttled_pct: Option<f64>,
+    /// Percentiles of how long runnable tasks waited for a CPU, rounded up
+    /// to a power of two
+    pub run_delay_p50_us: Option<u64>,
+    pub run_delay_p90_us: Option<u64>,
+    pub run_delay_p99_us: Option<u64>,
+    /// Run delays during the interval, bucketed the same way as
+    /// `CgroupSample::run_delay_hist`
+    #[queriable(ignore)]
+    pub run_delay_hist: Option<Vec<u64>>,
 }
 
 impl CgroupCpuModel {
@@ -309,6 +364,16 @@ impl CgroupCpuModel {
             nr_periods_per_sec: count_per_sec!(begin.nr_periods, end.nr_periods, delta),
             nr_throttled_per_sec: count_per_sec!(begin.nr_throttled, end.nr_throttled, delta),
             throttled_pct: usec_pct!(begin.throttled_usec, end.throttled_usec, delta),
+            ..Default::default()
+        }
+    }
+
+    fn set_run_delay(&mut self, begin: &[u64], end: &[u64]) {
+        if let Some(hist) = log2_hist_delta(begin, end) {
+            self.run_delay_p50_us = log2_hist_percentile(&hist, 50.0);
+            self.run_delay_p90_us = log2_hist_percentile(&hist, 90.0);
+            self.run_delay_p99_us = log2_hist_percentile(&hist, 99.0);
+            self.run_delay_hist = Some(hist);
         }
     }
 }
@@ -330,6 +395,18 @@ pub struct CgroupIoModel {
     pub dbytes_per_sec: Option<f64>,
     pub dios_per_sec: Option<f64>,
     pub rwbytes_per_sec: Option<f64>,
+    /// Percentiles of block IO latency from issue to completion, rounded up
+    /// to a power of two
+    pub read_latency_p50_us: Option<u64>,
+    pub read_latency_p99_us: Option<u64>,
+    pub write_latency_p50_us: Option<u64>,
+    pub write_latency_p99_us: Option<u64>,
+    /// IO latencies during the interval, bucketed the same way as
+    /// `IoLatencyHist`
+    #[queriable(ignore)]
+    pub read_latency_hist: Option<Vec<u64>>,
+    #[queriable(ignore)]
+    pub write_latency_hist: Option<Vec<u64>>,
 }
 
 impl CgroupIoModel {
@@ -345,9 +422,25 @@ impl CgroupIoModel {
             dbytes_per_sec: count_per_sec!(begin.dbytes, end.dbytes, delta),
             dios_per_sec: count_per_sec!(begin.dios, end.dios, delta),
             rwbytes_per_sec,
+            ..Default::default()
         }
     }
 
+    fn set_latency(&mut self, begin: &IoLatencyHist, end: &IoLatencyHist) {
+        self.read_latency_hist = log2_hist_delta(&begin.read, &end.read);
+        self.write_latency_hist = log2_hist_delta(&begin.write, &end.write);
+        self.update_latency_percentiles();
+    }
+
+    fn update_latency_percentiles(&mut self) {
+        let read = self.read_latency_hist.as_deref();
+        let write = self.write_latency_hist.as_deref();
+        self.read_latency_p50_us = read.and_then(|hist| log2_hist_percentile(hist, 50.0));
+        self.read_latency_p99_us = read.and_then(|hist| log2_hist_percentile(hist, 99.0));
+        self.write_latency_p50_us = write.and_then(|hist| log2_hist_percentile(hist, 50.0));
+        self.write_latency_p99_us = write.and_then(|hist| log2_hist_percentile(hist, 99.0));
+    }
+
     pub fn empty() -> CgroupIoModel {
         // If io.stat file is empty, it means cgroup has no I/O at all. In that
         // case we default to zero instead of None.
@@ -359,6 +452,7 @@ impl CgroupIoModel {
             dbytes_per_sec: Some(0.0),
             dios_per_sec: Some(0.0),
             rwbytes_per_sec: Some(0.0),
+            ..Default::default()
         }
     }
 }
@@ -367,7 +461,15 @@ impl std::ops::Add<&CgroupIoModel> for CgroupIoModel {
     type Output = Self;
 
     fn add(self, other: &Self) -> Self {
-        Self {
+        let add_hists = |hist: Option<Vec<u64>>, other: &Option<Vec<u64>>| match (hist, other) {
+            (Some(mut hist), Some(other)) => {
+                log2_hist_add(&mut hist, other);
+                Some(hist)
+            }
+            (hist, None) => hist,
+            (None, other) => other.clone(),
+        };
+        let mut sum = Self {
             rbytes_per_sec: opt_add(self.rbytes_per_sec, other.rbytes_per_sec),
             wbytes_per_sec: opt_add(self.wbytes_per_sec, other.wbytes_per_sec),
             rios_per_sec: opt_add(self.rios_per_sec, other.rios_per_sec),
@@ -375,7 +477,13 @@ impl std::ops::Add<&CgroupIoModel> for CgroupIoModel {
             dbytes_per_sec: opt_add(self.dbytes_per_sec, other.dbytes_per_sec),
             dios_per_sec: opt_add(self.dios_per_sec, other.dios_per_sec),
             rwbytes_per_sec: opt_add(self.rwbytes_per_sec, other.rwbytes_per_sec),
-        }
+            read_latency_hist: add_hists(self.read_latency_hist, &other.read_latency_hist),
+            write_latency_hist: add_hists(self.write_latency_hist, &other.write_latency_hist),
+            ..Default::default()
+        };
+        // Percentiles don't add up, derive them from the combined histograms
+        sum.update_latency_percentiles();
+        sum
     }
 }
 
@@ -691,4 +799,58 @@ mod tests {
             Some(Field::F64(42.0))
         );
     }
+
+    #[test]
+    fn run_delay_percentiles() {
+        let sample = |hist: Vec<u64>| CgroupSample {
+            cpu_stat: Some(Default::default()),
+            inode_number: Some(1),
+            run_delay_hist: Some(hist),
+            ..Default::default()
+        };
+        let begin = sample(vec![10, 10, 10]);
+        // 90 delays under 2us, 9 in [2, 4)us and 1 in [4, 8)us
+        let end = sample(vec![100, 19, 11]);
+        let model = CgroupModel::new(
+            "<root>".to_owned(),
+            String::new(),
+            0,
+            &end,
+            Some((&begin, Duration::from_secs(1))),
+        );
+        let cpu = model.data.cpu.expect("No cpu model");
+        assert_eq!(cpu.run_delay_hist, Some(vec![90, 9, 1]));
+        assert_eq!(cpu.run_delay_p50_us, Some(2));
+        assert_eq!(cpu.run_delay_p90_us, Some(2));
+        assert_eq!(cpu.run_delay_p99_us, Some(4));
+
+        assert_eq!(log2_hist_percentile(&[0, 0], 50.0), None);
+        let mut hist = vec![0; LOG2_HIST_BUCKETS];
+        hist[LOG2_HIST_BUCKETS - 2] = 1;
+        assert_eq!(
+            log2_hist_percentile(&hist, 50.0),
+            Some(1 << (LOG2_HIST_BUCKETS - 1))
+        );
+        // The last bucket has no upper bound
+        hist[LOG2_HIST_BUCKETS - 1] = 1;
+        assert_eq!(log2_hist_percentile(&hist, 99.0), None);
+    }
+
+    #[test]
+    fn io_latency_total() {
+        let sda = CgroupIoModel {
+            read_latency_hist: Some(vec![0, 100]),
+            ..Default::default()
+        };
+        let sdb = CgroupIoModel {
+            read_latency_hist: Some(vec![0, 0, 0, 100]),
+            write_latency_hist: Some(vec![1]),
+            ..Default::default()
+        };
+        let total = CgroupIoModel::empty() + &sda + &sdb;
+        assert_eq!(total.read_latency_hist, Some(vec![0, 100, 0, 100]));
+        assert_eq!(total.read_latency_p50_us, Some(4));
+        assert_eq!(total.read_latency_p99_us, Some(16));
+        assert_eq!(total.write_latency_p50_us, Some(2));
+    }
 }
@@ -12,16 +12,47 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 
 use super::*;
 use regex::Regex;
-use slog::{self, error};
+use slog::{self, error, warn};
 use std::path::{Path, PathBuf};
 
+/// Cumulative run delay histograms keyed by cgroup ID, see
+/// `CgroupSample::run_delay_hist`
+pub type RunDelayHists = BTreeMap<u64, Vec<u64>>;
+
+/// Cumulative block IO latency histograms keyed by cgroup ID, then by
+/// "major:minor" of the disk
+pub type IoLatencyHists = BTreeMap<u64, BTreeMap<String, IoLatencyHist>>;
+
+/// Cumulative TCP retransmits and drops
+#[derive(Default, Debug)]
+pub struct TcpHealthStats {
+    /// Keyed by cgroup ID. Counted separately from the processes so that
+    /// they keep going up after processes exit.
+    pub cgroups: BTreeMap<u64, procfs::TcpHealthStat>,
+    /// Keyed by pid
+    pub processes: BTreeMap<i32, procfs::TcpHealthStat>,
+}
+
 pub struct CollectorOptions {
     pub cgroup_root: PathBuf,
     pub exit_data: Arc<Mutex<procfs::PidMap>>,
+    /// Status of the exitstat BPF program. None if exitstat is off.
+    pub exit_health: Option<Arc<Mutex<procfs::ExitstatHealth>>>,
+    /// Execs reported since the last collection. None if exec tracing is off.
+    pub exec_data: Option<Arc<Mutex<Vec<procfs::ExecEvent>>>>,
+    /// OOM kills reported since the last collection. None if OOM tracing is off.
+    pub oom_data: Option<Arc<Mutex<Vec<procfs::OomKillEvent>>>>,
+    /// Latest run delay histograms. None if run queue latency tracing is off.
+    pub run_delay_receiver: Option<collector_plugin::Consumer<RunDelayHists>>,
+    /// Latest block IO latency histograms. None if IO latency tracing is off.
+    pub io_latency_receiver: Option<collector_plugin::Consumer<IoLatencyHists>>,
+    /// Latest TCP retransmit and drop counts. None if TCP tracing is off.
+    pub tcp_health_receiver: Option<collector_plugin::Consumer<TcpHealthStats>>,
     pub collect_io_stat: bool,
     pub disable_disk_stat: bool,
     pub cgroup_re: Option<Regex>,
@@ -32,6 +63,12 @@ impl Default for CollectorOptions {
         Self {
             cgroup_root: Path::new(cgroupfs::DEFAULT_CG_ROOT).to_path_buf(),
             exit_data: Default::default(),
+            exit_health: None,
+            exec_data: None,
+            oom_data: None,
+            run_delay_receiver: None,
+            io_latency_receiver: None,
+            tcp_health_receiver: None,
             collect_io_stat: true,
             disable_disk_stat: false,
             cgroup_re: None,
@@ -75,6 +112,9 @@ impl Collector {
     }
 }
 
+/// Whether a cpu sysfs file failed to be read yet, see `collect_sample`
+static CPU_SYSFS_ERROR_LOGGED: AtomicBool = AtomicBool::new(false);
+
 pub fn opt_add<T: std::ops::Add<T, Output = T>>(a: Option<T>, b: Option<T>) -> Option<T> {
     match (a, b) {
         (Some(a), Some(b)) => Some(a + b),
@@ -84,6 +124,64 @@ pub fn opt_add<T: std::ops::Add<T, Output = T>>(a: Option<T>, b: Option<T>) -> O
     }
 }
 
+/// Difference between two samples of a cumulative counter. None if either
+/// sample is missing or the counter went backwards (e.g. it was reset).
+pub fn opt_delta(begin: Option<u64>, end: Option<u64>) -> Option<u64> {
+    match (begin, end) {
+        (Some(begin), Some(end)) if begin <= end => Some(end - begin),
+        _ => None,
+    }
+}
+
+/// Number of buckets of the log2 histograms kept by the BPF programs. The
+/// last one also counts every larger value.
+pub const LOG2_HIST_BUCKETS: usize = 26;
+
+/// Upper bound of the bucket the given percentile falls into, where bucket i
+/// of `hist` holds values in [2^i, 2^(i+1)). None if `hist` is empty or the
+/// percentile falls into the open-ended last bucket, which has no upper
+/// bound.
+pub fn log2_hist_percentile(hist: &[u64], pct: f64) -> Option<u64> {
+    let total: u64 = hist.iter().sum();
+    if total == 0 {
+        return None;
+    }
+    let target = (total as f64 * pct / 100.0).ceil() as u64;
+    let mut seen = 0;
+    for (i, count) in hist.iter().enumerate() {
+        seen += count;
+        if seen >= target {
+            if i + 1 >= LOG2_HIST_BUCKETS {
+                return None;
+            }
+            return Some(1 << (i + 1));
+        }
+    }
+    None
+}
+
+/// Per-bucket difference between two samples of a cumulative log2 histogram.
+/// None if any bucket went backwards, e.g. because BPF was restarted.
+pub fn log2_hist_delta(begin: &[u64], end: &[u64]) -> Option<Vec<u64>> {
+    if begin.len() > end.len() {
+        return None;
+    }
+    end.iter()
+        .enumerate()
+        .map(|(i, count)| count.checked_sub(begin.get(i).copied().unwrap_or(0)))
+        .collect()
+}
+
+/// Adds `other` to `hist` bucket by bucket
+pub fn log2_hist_add(hist: &mut Vec<u64>, other: &[u64]) {
+    if hist.len() < other.len() {
+        hist.resize(other.len(), 0);
+    }
+    for (total, count) in hist.iter_mut().zip(other) {
+        *total += count;
+    }
+}
+
 pub fn opt_multiply<S: Sized + std::ops::Mul<T, Output = S>, T: Sized>(
     a: Option<S>,
     b: Option<T>,
@@ -137,6 +235,96 @@ fn merge_procfs_and_exit_data(
     procfs_data
 }
 
+/// Sets the run delay histogram of each cgroup to the sum of its own and its
+/// descendants', since BPF only reports the cgroup a task belongs to.
+fn attach_run_delay_hists(sample: &mut CgroupSample, hists: &RunDelayHists) -> Vec<u64> {
+    let mut hist = sample
+        .inode_number
+        .and_then(|ino| hists.get(&(ino as u64)))
+        .cloned()
+        .unwrap_or_default();
+    if let Some(children) = sample.children.as_mut() {
+        for child in children.values_mut() {
+            log2_hist_add(&mut hist, &attach_run_delay_hists(child, hists));
+        }
+    }
+    sample.run_delay_hist = Some(hist.clone());
+    hist
+}
+
+/// Adds `other` to `total` device by device
+fn add_io_latency_hists(
+    total: &mut BTreeMap<String, IoLatencyHist>,
+    other: &BTreeMap<String, IoLatencyHist>,
+) {
+    for (device, hist) in other {
+        let entry = total.entry(device.clone()).or_default();
+        log2_hist_add(&mut entry.read, &hist.read);
+        log2_hist_add(&mut entry.write, &hist.write);
+    }
+}
+
+/// Same as `attach_run_delay_hists`, for block IO latencies
+fn attach_io_latency_hists(
+    sample: &mut CgroupSample,
+    hists: &IoLatencyHists,
+) -> BTreeMap<String, IoLatencyHist> {
+    let mut total = sample
+        .inode_number
+        .and_then(|ino| hists.get(&(ino as u64)))
+        .cloned()
+        .unwrap_or_default();
+    if let Some(children) = sample.children.as_mut() {
+        for child in children.values_mut() {
+            add_io_latency_hists(&mut total, &attach_io_latency_hists(child, hists));
+        }
+    }
+    sample.io_latency_hist = Some(total.clone());
+    total
+}
+
+/// Same as `attach_run_delay_hists`, for TCP retransmits and drops
+fn attach_tcp_health(sample: &mut CgroupSample, stats: &TcpHealthStats) -> procfs::TcpHealthStat {
+    // Zero rather than None so that the first retransmit of a cgroup shows up
+    // as a rate
+    let mut total = zero_tcp_health();
+    if let Some(stat) = sample
+        .inode_number
+        .and_then(|ino| stats.cgroups.get(&(ino as u64)))
+    {
+        total.add(stat);
+    }
+    if let Some(children) = sample.children.as_mut() {
+        for child in children.values_mut() {
+            total.add(&attach_tcp_health(child, stats));
+        }
+    }
+    sample.tcp_health = Some(total.clone());
+    total
+}
+
+/// Sets the TCP counters of every process. Processes without any are set to
+/// zero, see `attach_tcp_health`.
+fn attach_process_tcp_health(processes: &mut procfs::PidMap, stats: &TcpHealthStats) {
+    for (pid, pidinfo) in processes.iter_mut() {
+        pidinfo.tcp = Some(
+            stats
+                .processes
+                .get(pid)
+                .cloned()
+                .unwrap_or_else(zero_tcp_health),
+        );
+    }
+}
+
+fn zero_tcp_health() -> procfs::TcpHealthStat {
+    procfs::TcpHealthStat {
+        retransmits: Some(0),
+        drops: Some(0),
+        drop_reasons: None,
+    }
+}
+
 /// This function will test if all field of DiskStat are zero, if so we will need to skip
 /// this sample inside collector.
 fn is_all_zero_disk_stats(disk_stats: &procfs::DiskStat) -> bool {
@@ -166,22 +354,75 @@ fn collect_sample(logger: &slog::Logger, options: &CollectorOptions) -> Result<S
             .lock()
             .expect("tried to acquire poisoned lock"),
     );
+    let execs = options.exec_data.as_ref().map(|exec_data| {
+        std::mem::take(&mut *exec_data.lock().expect("tried to acquire poisoned lock"))
+    });
+    let oom_kills = options.oom_data.as_ref().map(|oom_data| {
+        std::mem::take(&mut *oom_data.lock().expect("tried to acquire poisoned lock"))
+    });
+
+    let mut cgroup = collect_cgroup_sample(
+        &cgroupfs::CgroupReader::new(options.cgroup_root.to_owned())?,
+        options.collect_io_stat,
+        logger,
+        &options.cgroup_re,
+    )?;
+    if let Some(run_delay_receiver) = options.run_delay_receiver.as_ref() {
+        // Nothing is received if the plugin hasn't polled since the last
+        // sample. The histograms are cumulative so that only costs us two
+        // intervals: this one and the next, which has nothing to diff
+        // against.
+        match run_delay_receiver.try_take() {
+            Ok(Some(hists)) => {
+                attach_run_delay_hists(&mut cgroup, &hists);
+            }
+            Ok(None) => {}
+            Err(e) => error!(logger, "{:#}", e),
+        }
+    }
+    let mut disk_io_latency_hist = None;
+    if let Some(io_latency_receiver) = options.io_latency_receiver.as_ref() {
+        // Same as above, losing one sample now and then is fine
+        match io_latency_receiver.try_take() {
+            Ok(Some(hists)) => {
+                attach_io_latency_hists(&mut cgroup, &hists);
+                let mut by_disk = BTreeMap::new();
+                for cgroup_hists in hists.values() {
+                    add_io_latency_hists(&mut by_disk, cgroup_hists);
+                }
+                disk_io_latency_hist = Some(by_disk);
+            }
+            Ok(None) => {}
+            Err(e) => error!(logger, "{:#}", e),
+        }
+    }
+
+    let mut processes = merge_procfs_and_exit_data(
+        reader
+            .read_all_pids()?
+            .into_iter()
+            .map(|(k, v)| (k, v.into()))
+            .collect(),
+        exit_pidmap,
+    );
+    if let Some(tcp_health_receiver) = options.tcp_health_receiver.as_ref() {
+        // Counters are cumulative as well
+        match tcp_health_receiver.try_take() {
+            Ok(Some(stats)) => {
+                attach_tcp_health(&mut cgroup, &stats);
+                attach_process_tcp_health(&mut processes, &stats);
+            }
+            Ok(None) => {}
+            Err(e) => error!(logger, "{:#}", e),
+        }
+    }
 
     Ok(Sample {
-        cgroup: collect_cgroup_sample(
-            &cgroupfs::CgroupReader::new(options.cgroup_root.to_owned())?,
-            options.collect_io_stat,
-            logger,
-            &options.cgroup_re,
-        )?,
-        processes: merge_procfs_and_exit_data(
-            reader
-                .read_all_pids()?
-                .into_iter()
-                .map(|(k, v)| (k, v.into()))
-                .collect(),
-            exit_pidmap,
-        ),
+        cgroup,
+        processes,
+        execs,
+        oom_kills,
+        source_host: None,
         netstats: match procfs::NetReader::new().and_then(|v| v.read_netstat()) {
             Ok(ns) => ns.into(),
             Err(e) => {
@@ -193,6 +434,53 @@ fn collect_sample(logger: &slog::Logger, options: &CollectorOptions) -> Result<S
             stat: reader.read_stat()?.into(),
             meminfo: reader.read_meminfo()?.into(),
             vmstat: reader.read_vmstat()?.into(),
+            cpu_sysfs: match reader.read_cpu_sysfs_stats() {
+                Ok((cpu_sysfs, errors)) => {
+                    // The same files tend to fail every time, e.g. counters
+                    // a driver doesn't support, so only log the first one
+                    if let Some(e) = errors.first() {
+                        if !CPU_SYSFS_ERROR_LOGGED.swap(true, Ordering::Relaxed) {
+                            warn!(
+                                logger,
+                                "{:#}. Further cpu sysfs read errors are not logged.", e
+                            );
+                        }
+                    }
+                    Some(cpu_sysfs)
+                }
+                Err(e) => {
+                    error!(logger, "{:#}", e);
+                    None
+                }
+            },
+            swaps: match reader.read_swaps() {
+                Ok(swaps) => Some(swaps),
+                Err(e) => {
+                    error!(logger, "{:#}", e);
+                    None
+                }
+            },
+            zram: match reader.read_zram_stats() {
+                Ok(zram) => Some(zram),
+                Err(e) => {
+                    error!(logger, "{:#}", e);
+                    None
+                }
+            },
+            zswap: match reader.read_zswap_stats() {
+                Ok(zswap) => zswap,
+                Err(e) => {
+                    error!(logger, "{:#}", e);
+                    None
+                }
+            },
+            kernel_tables: match reader.read_kernel_tables() {
+                Ok(kernel_tables) => Some(kernel_tables),
+                Err(e) => {
+                    error!(logger, "{:#}", e);
+                    None
+                }
+            },
             hostname: get_hostname()?,
             kernel_version: match reader.read_kernel_version() {
                 Ok(k) => Some(k),
@@ -227,6 +515,38 @@ fn collect_sample(logger: &slog::Logger, options: &CollectorOptions) -> Result<S
                     }
                 }
             },
+            nfs: match reader.read_mountstats() {
+                Ok(nfs) => Some(nfs),
+                Err(e) => {
+                    error!(logger, "{:#}", e);
+                    None
+                }
+            },
+            cifs: match reader.read_cifs_stats() {
+                Ok(cifs) => cifs,
+                Err(e) => {
+                    error!(logger, "{:#}", e);
+                    None
+                }
+            },
+            disk_io_latency_hist,
+            exitstat: options.exit_health.as_ref().map(|exit_health| {
+                exit_health
+                    .lock()
+                    .expect("tried to acquire poisoned lock")
+                    .clone()
+            }),
+            filesystems: if options.disable_disk_stat {
+                None
+            } else {
+                match reader.read_fs_stats() {
+                    Ok(filesystems) => Some(filesystems),
+                    Err(e) => {
+                        error!(logger, "{:#}", e);
+                        None
+                    }
+                }
+            },
         },
     })
 }
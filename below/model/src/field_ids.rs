@@ -45,6 +45,11 @@ pub const MODEL_FIELD_IDS: &[&'static str] = &[
     "system.cpu.stolen_pct",
     "system.cpu.guest_pct",
     "system.cpu.guest_nice_pct",
+    "system.cpu.freq_mhz",
+    "system.cpu.core_throttle_per_sec",
+    "system.cpu.package_throttle_per_sec",
+    "system.cpu.idle_states.<key>.residency_pct",
+    "system.cpu.idle_states.<key>.usage_per_sec",
     "system.cpus.<idx>.idx",
     "system.cpus.<idx>.usage_pct",
     "system.cpus.<idx>.user_pct",
@@ -57,6 +62,11 @@ pub const MODEL_FIELD_IDS: &[&'static str] = &[
     "system.cpus.<idx>.stolen_pct",
     "system.cpus.<idx>.guest_pct",
     "system.cpus.<idx>.guest_nice_pct",
+    "system.cpus.<idx>.freq_mhz",
+    "system.cpus.<idx>.core_throttle_per_sec",
+    "system.cpus.<idx>.package_throttle_per_sec",
+    "system.cpus.<idx>.idle_states.<key>.residency_pct",
+    "system.cpus.<idx>.idle_states.<key>.usage_per_sec",
     "system.mem.total",
     "system.mem.free",
     "system.mem.available",
@@ -103,14 +113,68 @@ pub const MODEL_FIELD_IDS: &[&'static str] = &[
     "system.vm.pgscan_kswapd",
     "system.vm.pgscan_direct",
     "system.vm.oom_kill",
+    "system.vm.counters.<key>.value",
+    "system.vm.counters.<key>.per_sec",
+    "system.swap.total_bytes",
+    "system.swap.used_bytes",
+    "system.swap.devices.<idx>.name",
+    "system.swap.devices.<idx>.swap_type",
+    "system.swap.devices.<idx>.size_bytes",
+    "system.swap.devices.<idx>.used_bytes",
+    "system.swap.devices.<idx>.usage_pct",
+    "system.swap.devices.<idx>.priority",
+    "system.swap.zram.<key>.orig_data_bytes",
+    "system.swap.zram.<key>.compr_data_bytes",
+    "system.swap.zram.<key>.mem_used_bytes",
+    "system.swap.zram.<key>.mem_limit_bytes",
+    "system.swap.zram.<key>.compression_ratio",
+    "system.swap.zram.<key>.same_pages",
+    "system.swap.zram.<key>.huge_pages",
+    "system.swap.zram.<key>.failed_reads_per_sec",
+    "system.swap.zram.<key>.failed_writes_per_sec",
+    "system.swap.zswap.pool_total_bytes",
+    "system.swap.zswap.stored_bytes",
+    "system.swap.zswap.compression_ratio",
+    "system.swap.zswap.written_back_pages_per_sec",
+    "system.swap.zswap.pool_limit_hit_per_sec",
+    "system.swap.zswap.reject_per_sec",
+    "system.kernel_tables.file_handles_allocated",
+    "system.kernel_tables.file_handles_max",
+    "system.kernel_tables.file_handles_pct",
+    "system.kernel_tables.inodes_allocated",
+    "system.kernel_tables.inodes_free",
+    "system.kernel_tables.dentries",
+    "system.kernel_tables.dentries_unused",
+    "system.kernel_tables.nr_threads",
+    "system.kernel_tables.pid_max",
+    "system.kernel_tables.pid_usage_pct",
+    "system.kernel_tables.threads_max",
+    "system.kernel_tables.threads_usage_pct",
+    "system.kernel_tables.entropy_avail",
+    "system.kernel_tables.entropy_poolsize",
+    "system.kernel_tables.entropy_pct",
+    "system.exitstat.status",
+    "system.exitstat.lost_events",
+    "system.exitstat.dropped_events",
+    "system.exitstat.health",
     "system.disks.<key>.name",
     "system.disks.<key>.disk_usage",
     "system.disks.<key>.partition_size",
     "system.disks.<key>.filesystem_type",
+    "system.disks.<key>.mount_point",
+    "system.disks.<key>.dm_name",
+    "system.disks.<key>.parent",
+    "system.disks.<key>.slaves",
     "system.disks.<key>.read_bytes_per_sec",
     "system.disks.<key>.write_bytes_per_sec",
     "system.disks.<key>.discard_bytes_per_sec",
     "system.disks.<key>.disk_total_bytes_per_sec",
+    "system.disks.<key>.util_pct",
+    "system.disks.<key>.avg_queue_size",
+    "system.disks.<key>.read_await_ms",
+    "system.disks.<key>.write_await_ms",
+    "system.disks.<key>.avg_request_size",
+    "system.disks.<key>.ios_in_progress",
     "system.disks.<key>.read_completed",
     "system.disks.<key>.read_merged",
     "system.disks.<key>.read_sectors",
@@ -123,8 +187,64 @@ pub const MODEL_FIELD_IDS: &[&'static str] = &[
     "system.disks.<key>.discard_merged",
     "system.disks.<key>.discard_sectors",
     "system.disks.<key>.time_spend_discard_ms",
+    "system.disks.<key>.flush_completed",
+    "system.disks.<key>.time_spend_flush_ms",
     "system.disks.<key>.major",
     "system.disks.<key>.minor",
+    "system.disks.<key>.read_latency_p50_us",
+    "system.disks.<key>.read_latency_p99_us",
+    "system.disks.<key>.write_latency_p50_us",
+    "system.disks.<key>.write_latency_p99_us",
+    "system.filesystems.<key>.mount_point",
+    "system.filesystems.<key>.mount_source",
+    "system.filesystems.<key>.fs_type",
+    "system.filesystems.<key>.usage_pct",
+    "system.filesystems.<key>.total_bytes",
+    "system.filesystems.<key>.used_bytes",
+    "system.filesystems.<key>.free_bytes",
+    "system.filesystems.<key>.avail_bytes",
+    "system.filesystems.<key>.inode_usage_pct",
+    "system.filesystems.<key>.total_inodes",
+    "system.filesystems.<key>.used_inodes",
+    "system.filesystems.<key>.free_inodes",
+    "system.filesystems.<key>.avail_inodes",
+    "system.nfs.<key>.mount_point",
+    "system.nfs.<key>.device",
+    "system.nfs.<key>.fs_type",
+    "system.nfs.<key>.read_bytes_per_sec",
+    "system.nfs.<key>.write_bytes_per_sec",
+    "system.nfs.<key>.server_read_bytes_per_sec",
+    "system.nfs.<key>.server_write_bytes_per_sec",
+    "system.nfs.<key>.ops_per_sec",
+    "system.nfs.<key>.retrans_per_sec",
+    "system.nfs.<key>.read.ops_per_sec",
+    "system.nfs.<key>.read.retrans_per_sec",
+    "system.nfs.<key>.read.timeouts_per_sec",
+    "system.nfs.<key>.read.avg_rtt_ms",
+    "system.nfs.<key>.read.avg_exec_ms",
+    "system.nfs.<key>.write.ops_per_sec",
+    "system.nfs.<key>.write.retrans_per_sec",
+    "system.nfs.<key>.write.timeouts_per_sec",
+    "system.nfs.<key>.write.avg_rtt_ms",
+    "system.nfs.<key>.write.avg_exec_ms",
+    "system.nfs.<key>.getattr.ops_per_sec",
+    "system.nfs.<key>.getattr.retrans_per_sec",
+    "system.nfs.<key>.getattr.timeouts_per_sec",
+    "system.nfs.<key>.getattr.avg_rtt_ms",
+    "system.nfs.<key>.getattr.avg_exec_ms",
+    "system.nfs.<key>.lookup.ops_per_sec",
+    "system.nfs.<key>.lookup.retrans_per_sec",
+    "system.nfs.<key>.lookup.timeouts_per_sec",
+    "system.nfs.<key>.lookup.avg_rtt_ms",
+    "system.nfs.<key>.lookup.avg_exec_ms",
+    "system.cifs.<key>.share",
+    "system.cifs.<key>.smbs_per_sec",
+    "system.cifs.<key>.read_bytes_per_sec",
+    "system.cifs.<key>.write_bytes_per_sec",
+    "system.cifs.<key>.reads_per_sec",
+    "system.cifs.<key>.writes_per_sec",
+    "system.cifs.<key>.failed_ops_per_sec",
+    "system.cifs.<key>.open_files",
     "cgroup.[path:/<cgroup_path>/.]name",
     "cgroup.[path:/<cgroup_path>/.]full_path",
     "cgroup.[path:/<cgroup_path>/.]inode_number",
@@ -134,6 +254,9 @@ pub const MODEL_FIELD_IDS: &[&'static str] = &[
     "cgroup.[path:/<cgroup_path>/.]cpu.nr_periods_per_sec",
     "cgroup.[path:/<cgroup_path>/.]cpu.nr_throttled_per_sec",
     "cgroup.[path:/<cgroup_path>/.]cpu.throttled_pct",
+    "cgroup.[path:/<cgroup_path>/.]cpu.run_delay_p50_us",
+    "cgroup.[path:/<cgroup_path>/.]cpu.run_delay_p90_us",
+    "cgroup.[path:/<cgroup_path>/.]cpu.run_delay_p99_us",
     "cgroup.[path:/<cgroup_path>/.]mem.total",
     "cgroup.[path:/<cgroup_path>/.]mem.swap",
     "cgroup.[path:/<cgroup_path>/.]mem.anon",
@@ -180,6 +303,10 @@ pub const MODEL_FIELD_IDS: &[&'static str] = &[
     "cgroup.[path:/<cgroup_path>/.]io_details.<key>.dbytes_per_sec",
     "cgroup.[path:/<cgroup_path>/.]io_details.<key>.dios_per_sec",
     "cgroup.[path:/<cgroup_path>/.]io_details.<key>.rwbytes_per_sec",
+    "cgroup.[path:/<cgroup_path>/.]io_details.<key>.read_latency_p50_us",
+    "cgroup.[path:/<cgroup_path>/.]io_details.<key>.read_latency_p99_us",
+    "cgroup.[path:/<cgroup_path>/.]io_details.<key>.write_latency_p50_us",
+    "cgroup.[path:/<cgroup_path>/.]io_details.<key>.write_latency_p99_us",
     "cgroup.[path:/<cgroup_path>/.]io.rbytes_per_sec",
     "cgroup.[path:/<cgroup_path>/.]io.wbytes_per_sec",
     "cgroup.[path:/<cgroup_path>/.]io.rios_per_sec",
@@ -187,11 +314,18 @@ pub const MODEL_FIELD_IDS: &[&'static str] = &[
     "cgroup.[path:/<cgroup_path>/.]io.dbytes_per_sec",
     "cgroup.[path:/<cgroup_path>/.]io.dios_per_sec",
     "cgroup.[path:/<cgroup_path>/.]io.rwbytes_per_sec",
+    "cgroup.[path:/<cgroup_path>/.]io.read_latency_p50_us",
+    "cgroup.[path:/<cgroup_path>/.]io.read_latency_p99_us",
+    "cgroup.[path:/<cgroup_path>/.]io.write_latency_p50_us",
+    "cgroup.[path:/<cgroup_path>/.]io.write_latency_p99_us",
     "cgroup.[path:/<cgroup_path>/.]pressure.cpu_some_pct",
     "cgroup.[path:/<cgroup_path>/.]pressure.io_some_pct",
     "cgroup.[path:/<cgroup_path>/.]pressure.io_full_pct",
     "cgroup.[path:/<cgroup_path>/.]pressure.memory_some_pct",
     "cgroup.[path:/<cgroup_path>/.]pressure.memory_full_pct",
+    "cgroup.[path:/<cgroup_path>/.]tcp.retransmits_per_sec",
+    "cgroup.[path:/<cgroup_path>/.]tcp.drops_per_sec",
+    "cgroup.[path:/<cgroup_path>/.]tcp.top_drop_reason",
     "process.processes.<key>.pid",
     "process.processes.<key>.ppid",
     "process.processes.<key>.comm",
@@ -219,6 +353,20 @@ pub const MODEL_FIELD_IDS: &[&'static str] = &[
     "process.processes.<key>.cpu.num_threads",
     "process.processes.<key>.cmdline",
     "process.processes.<key>.exe_path",
+    "process.processes.<key>.exit_code",
+    "process.processes.<key>.exit_signal",
+    "process.processes.<key>.exit_status",
+    "process.processes.<key>.tcp.retransmits_per_sec",
+    "process.processes.<key>.tcp.drops_per_sec",
+    "process.processes.<key>.tcp.top_drop_reason",
+    "process.execs.<idx>.timestamp",
+    "process.execs.<idx>.pid",
+    "process.execs.<idx>.ppid",
+    "process.execs.<idx>.comm",
+    "process.execs.<idx>.filename",
+    "process.execs.<idx>.cmdline",
+    "process.execs.<idx>.cgroup_id",
+    "process.execs.<idx>.cgroup",
     "network.interfaces.<key>.interface",
     "network.interfaces.<key>.rx_bytes_per_sec",
     "network.interfaces.<key>.tx_bytes_per_sec",
@@ -324,4 +472,14 @@ pub const MODEL_FIELD_IDS: &[&'static str] = &[
     "network.udp6.sndbuf_errors",
     "network.udp6.in_csum_errors",
     "network.udp6.ignored_multi",
+    "oom.kills.<idx>.timestamp",
+    "oom.kills.<idx>.pid",
+    "oom.kills.<idx>.comm",
+    "oom.kills.<idx>.rss_bytes",
+    "oom.kills.<idx>.cgroup",
+    "oom.kills.<idx>.kind",
+    "oom.kills.<idx>.oom_cgroup",
+    "oom.kills.<idx>.total_bytes",
+    "oom.kills.<idx>.trigger_pid",
+    "oom.kills.<idx>.trigger_comm",
 ];
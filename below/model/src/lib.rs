@@ -27,7 +27,9 @@ pub mod collector_plugin;
 #[cfg(test)]
 mod field_ids;
 pub mod network;
+pub mod oom;
 pub mod process;
+pub mod rollup;
 pub mod sample;
 mod sample_model;
 pub mod system;
@@ -40,6 +42,7 @@ pub use facebook::*;
 pub use cgroup::*;
 pub use collector::*;
 pub use network::*;
+pub use oom::*;
 pub use process::*;
 pub use sample::*;
 pub use system::*;
@@ -399,6 +402,8 @@ pub struct Model {
     pub process: ProcessModel,
     #[queriable(subquery)]
     pub network: NetworkModel,
+    #[queriable(subquery)]
+    pub oom: OomModel,
 }
 
 impl Model {
@@ -406,6 +411,8 @@ impl Model {
     /// `CumulativeSample` as well as the `Duration` since it was
     /// collected.
     pub fn new(timestamp: SystemTime, sample: &Sample, last: Option<(&Sample, Duration)>) -> Self {
+        // Rates between samples of different merged hosts are meaningless
+        let last = last.filter(|(s, _)| s.source_host == sample.source_host);
         Model {
             time_elapsed: last.map(|(_, d)| d).unwrap_or_default(),
             timestamp,
@@ -418,8 +425,14 @@ impl Model {
                 last.map(|(s, d)| (&s.cgroup, d)),
             )
             .aggr_top_level_val(),
-            process: ProcessModel::new(&sample.processes, last.map(|(s, d)| (&s.processes, d))),
+            process: ProcessModel::new(
+                &sample.processes,
+                sample.execs.as_deref(),
+                &sample.cgroup,
+                last.map(|(s, d)| (&s.processes, d)),
+            ),
             network: NetworkModel::new(&sample.netstats, last.map(|(s, d)| (&s.netstats, d))),
+            oom: OomModel::new(sample.oom_kills.as_deref(), &sample.cgroup),
         }
     }
 }
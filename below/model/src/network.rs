@@ -128,6 +128,71 @@ impl TcpModel {
     }
 }
 
+/// TCP retransmits and drops attributed to a process or cgroup, unlike
+/// `TcpModel` which is host-wide
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Serialize,
+    Deserialize,
+    below_derive::Queriable
+)]
+pub struct TcpHealthModel {
+    pub retransmits_per_sec: Option<f64>,
+    pub drops_per_sec: Option<f64>,
+    /// kfree_skb reason most drops had in the interval. Only set for
+    /// cgroups, on kernels that report the reason.
+    pub top_drop_reason: Option<String>,
+}
+
+impl TcpHealthModel {
+    pub fn new(
+        begin: &procfs::TcpHealthStat,
+        end: &procfs::TcpHealthStat,
+        delta: Duration,
+    ) -> TcpHealthModel {
+        TcpHealthModel {
+            retransmits_per_sec: count_per_sec!(begin.retransmits, end.retransmits, delta),
+            drops_per_sec: count_per_sec!(begin.drops, end.drops, delta),
+            top_drop_reason: get_top_drop_reason(begin, end),
+        }
+    }
+
+    pub fn fold(left: &TcpHealthModel, right: &TcpHealthModel) -> TcpHealthModel {
+        TcpHealthModel {
+            retransmits_per_sec: opt_add(left.retransmits_per_sec, right.retransmits_per_sec),
+            drops_per_sec: opt_add(left.drops_per_sec, right.drops_per_sec),
+            // Counts aren't kept in the model, so this can't be redone
+            top_drop_reason: left
+                .top_drop_reason
+                .clone()
+                .or_else(|| right.top_drop_reason.clone()),
+        }
+    }
+}
+
+/// Returns the drop reason whose count went up the most between `begin` and
+/// `end`, if any went up.
+fn get_top_drop_reason(
+    begin: &procfs::TcpHealthStat,
+    end: &procfs::TcpHealthStat,
+) -> Option<String> {
+    let empty = BTreeMap::new();
+    let begin_reasons = begin.drop_reasons.as_ref().unwrap_or(&empty);
+    end.drop_reasons
+        .as_ref()?
+        .iter()
+        .map(|(reason, drops)| {
+            let before = begin_reasons.get(reason).copied().unwrap_or_default();
+            (drops.saturating_sub(before), reason)
+        })
+        .filter(|(drops, _)| *drops > 0)
+        .max()
+        .map(|(_, reason)| reason.clone())
+}
+
 #[derive(Default, Serialize, Deserialize, below_derive::Queriable)]
 pub struct IpModel {
     pub forwarding_pkts_per_sec: Option<u64>,
@@ -480,4 +545,33 @@ mod test {
             Some(Field::F64(42.0))
         );
     }
+
+    #[test]
+    fn top_drop_reason() {
+        let stat = |reasons: &[(&str, u64)]| procfs::TcpHealthStat {
+            retransmits: Some(0),
+            drops: Some(reasons.iter().map(|(_, drops)| drops).sum()),
+            drop_reasons: Some(
+                reasons
+                    .iter()
+                    .map(|(reason, drops)| (reason.to_string(), *drops))
+                    .collect(),
+            ),
+        };
+        let begin = stat(&[("NO_SOCKET", 100), ("TCP_CSUM", 1)]);
+        let end = stat(&[("NO_SOCKET", 101), ("TCP_CSUM", 5), ("SOCKET_FILTER", 2)]);
+        let model = TcpHealthModel::new(&begin, &end, Duration::from_secs(1));
+        assert_eq!(model.drops_per_sec, Some(7.0));
+        // Most drops in the interval, not overall
+        assert_eq!(model.top_drop_reason, Some("TCP_CSUM".to_owned()));
+
+        let model = TcpHealthModel::new(&end, &end, Duration::from_secs(1));
+        assert_eq!(model.top_drop_reason, None);
+        let model = TcpHealthModel::new(
+            &Default::default(),
+            &Default::default(),
+            Duration::from_secs(1),
+        );
+        assert_eq!(model.top_drop_reason, None);
+    }
 }
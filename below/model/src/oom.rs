@@ -0,0 +1,153 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::*;
+
+#[derive(Default, Serialize, Deserialize, below_derive::Queriable)]
+pub struct OomModel {
+    /// OOM kills during the interval, in the order they happened
+    #[queriable(subquery)]
+    pub kills: Vec<SingleOomKillModel>,
+}
+
+impl OomModel {
+    pub fn new(kills: Option<&[procfs::OomKillEvent]>, cgroup: &CgroupSample) -> OomModel {
+        let kills = match kills {
+            Some(kills) if !kills.is_empty() => {
+                let mut cgroup_paths = BTreeMap::new();
+                collect_cgroup_paths(cgroup, "", &mut cgroup_paths);
+                kills
+                    .iter()
+                    .map(|kill| SingleOomKillModel::new(kill, &cgroup_paths))
+                    .collect()
+            }
+            _ => Vec::new(),
+        };
+
+        OomModel { kills }
+    }
+}
+
+#[derive(Default, Clone, Serialize, Deserialize, below_derive::Queriable)]
+pub struct SingleOomKillModel {
+    /// When the kill was reported, in seconds since epoch
+    pub timestamp: Option<u64>,
+    pub pid: Option<i32>,
+    pub comm: Option<String>,
+    pub rss_bytes: Option<u64>,
+    /// cgroup of the victim
+    pub cgroup: Option<String>,
+    /// "global" for system-wide OOMs, "cgroup" if a memory cgroup hit its limit
+    pub kind: Option<String>,
+    /// Memory cgroup that ran out of memory. None for system-wide OOMs.
+    pub oom_cgroup: Option<String>,
+    /// Memory available to the OOMing cgroup or system
+    pub total_bytes: Option<u64>,
+    /// Process whose allocation invoked the OOM killer
+    pub trigger_pid: Option<i32>,
+    pub trigger_comm: Option<String>,
+}
+
+impl SingleOomKillModel {
+    fn new(
+        sample: &procfs::OomKillEvent,
+        cgroup_paths: &BTreeMap<u64, String>,
+    ) -> SingleOomKillModel {
+        SingleOomKillModel {
+            timestamp: sample.timestamp,
+            pid: sample.victim_pid,
+            comm: sample.victim_comm.clone(),
+            rss_bytes: sample.victim_rss_bytes,
+            cgroup: sample
+                .victim_cgroup_id
+                .and_then(|id| cgroup_paths.get(&id))
+                .cloned(),
+            kind: sample.constraint.map(|constraint| {
+                match constraint {
+                    procfs::OomConstraint::None => "global",
+                    procfs::OomConstraint::Cpuset => "cpuset",
+                    procfs::OomConstraint::MemoryPolicy => "mempolicy",
+                    procfs::OomConstraint::Memcg => "cgroup",
+                }
+                .to_owned()
+            }),
+            oom_cgroup: sample
+                .memcg_id
+                .and_then(|id| cgroup_paths.get(&id))
+                .cloned(),
+            total_bytes: sample.total_bytes,
+            trigger_pid: sample.trigger_pid,
+            trigger_comm: sample.trigger_comm.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn oom_kills() {
+        let cgroup = CgroupSample {
+            inode_number: Some(1),
+            children: Some(
+                vec![(
+                    "workload.slice".to_owned(),
+                    CgroupSample {
+                        inode_number: Some(2),
+                        ..Default::default()
+                    },
+                )]
+                .into_iter()
+                .collect(),
+            ),
+            ..Default::default()
+        };
+        let kills = vec![
+            procfs::OomKillEvent {
+                victim_pid: Some(100),
+                victim_comm: Some("hog".to_owned()),
+                victim_cgroup_id: Some(2),
+                memcg_id: Some(2),
+                constraint: Some(procfs::OomConstraint::Memcg),
+                ..Default::default()
+            },
+            procfs::OomKillEvent {
+                victim_pid: Some(101),
+                victim_cgroup_id: Some(1),
+                constraint: Some(procfs::OomConstraint::None),
+                ..Default::default()
+            },
+        ];
+
+        let model = OomModel::new(Some(&kills), &cgroup);
+        assert_eq!(model.kills.len(), 2);
+        assert_eq!(model.kills[0].kind.as_deref(), Some("cgroup"));
+        assert_eq!(model.kills[0].cgroup.as_deref(), Some("/workload.slice"));
+        assert_eq!(
+            model.kills[0].oom_cgroup.as_deref(),
+            Some("/workload.slice")
+        );
+        assert_eq!(model.kills[1].kind.as_deref(), Some("global"));
+        assert_eq!(model.kills[1].cgroup.as_deref(), Some("/"));
+        assert_eq!(model.kills[1].oom_cgroup, None);
+        assert_eq!(
+            model.query(&OomModelFieldId::from_str("kills.0.comm").unwrap()),
+            Some(Field::Str("hog".to_owned()))
+        );
+
+        let model = OomModel::new(None, &cgroup);
+        assert!(model.kills.is_empty());
+    }
+}
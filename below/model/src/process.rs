@@ -34,10 +34,18 @@ macro_rules! fold_optionals {
 pub struct ProcessModel {
     #[queriable(subquery)]
     pub processes: BTreeMap<i32, SingleProcessModel>,
+    /// Programs executed during the interval, in the order they ran
+    #[queriable(subquery)]
+    pub execs: Vec<SingleExecModel>,
 }
 
 impl ProcessModel {
-    pub fn new(sample: &procfs::PidMap, last: Option<(&procfs::PidMap, Duration)>) -> ProcessModel {
+    pub fn new(
+        sample: &procfs::PidMap,
+        execs: Option<&[procfs::ExecEvent]>,
+        cgroup: &CgroupSample,
+        last: Option<(&procfs::PidMap, Duration)>,
+    ) -> ProcessModel {
         let mut processes: BTreeMap<i32, SingleProcessModel> = BTreeMap::new();
 
         for (pid, pidinfo) in sample.iter() {
@@ -50,7 +58,19 @@ impl ProcessModel {
             );
         }
 
-        ProcessModel { processes }
+        let execs = match execs {
+            Some(execs) if !execs.is_empty() => {
+                let mut cgroup_paths = BTreeMap::new();
+                collect_cgroup_paths(cgroup, "", &mut cgroup_paths);
+                execs
+                    .iter()
+                    .map(|exec| SingleExecModel::new(exec, &cgroup_paths))
+                    .collect()
+            }
+            _ => Vec::new(),
+        };
+
+        ProcessModel { processes, execs }
     }
 }
 
@@ -70,6 +90,14 @@ pub struct SingleProcessModel {
     pub cpu: Option<ProcessCpuModel>,
     pub cmdline: Option<String>,
     pub exe_path: Option<String>,
+    /// Exit status of a process that exited on its own
+    pub exit_code: Option<i32>,
+    /// Signal that terminated the process, e.g. SIGKILL
+    pub exit_signal: Option<String>,
+    /// How the process exited, e.g. "exited (SIGKILL)". None while running.
+    pub exit_status: Option<String>,
+    #[queriable(subquery)]
+    pub tcp: Option<TcpHealthModel>,
 }
 
 impl SingleProcessModel {
@@ -93,6 +121,37 @@ impl SingleProcessModel {
                 Some("?".into())
             },
             exe_path: sample.exe_path.clone(),
+            exit_code: sample.exit.as_ref().and_then(|exit| exit.code),
+            exit_signal: sample.exit.as_ref().and_then(|exit| exit.signal_name()),
+            exit_status: sample.exit.as_ref().map(Self::exit_status),
+            tcp: last.and_then(|(l, d)| match (l.tcp.as_ref(), sample.tcp.as_ref()) {
+                (Some(begin), Some(end)) => Some(TcpHealthModel::new(begin, end, d)),
+                _ => None,
+            }),
+        }
+    }
+
+    fn exit_status(exit: &procfs::PidExit) -> String {
+        let reason = match (exit.oom_killed, exit.signal_name(), exit.code) {
+            (Some(true), _, _) => "OOM killed".to_owned(),
+            (_, Some(signal), _) if exit.core_dumped == Some(true) => {
+                format!("{}, core dumped", signal)
+            }
+            (_, Some(signal), _) => signal,
+            (_, None, Some(code)) => code.to_string(),
+            (_, None, None) => "?".to_owned(),
+        };
+        format!("exited ({})", reason)
+    }
+
+    /// Whether the process failed: it exited with a nonzero code or was
+    /// killed by a signal, including OOM kills. SIGTERM, SIGINT and SIGHUP
+    /// are how processes are usually asked to stop, so they don't count.
+    pub fn is_crashed(&self) -> bool {
+        match (self.exit_signal.as_deref(), self.exit_code) {
+            (Some(signal), _) => !matches!(signal, "SIGTERM" | "SIGINT" | "SIGHUP"),
+            (None, Some(code)) => code != 0,
+            (None, None) => false,
         }
     }
 
@@ -112,6 +171,10 @@ impl SingleProcessModel {
             cpu: fold_optionals!(&left.cpu, &right.cpu, ProcessCpuModel::fold),
             cmdline: None,
             exe_path: None,
+            exit_code: None,
+            exit_signal: None,
+            exit_status: None,
+            tcp: fold_optionals!(&left.tcp, &right.tcp, TcpHealthModel::fold),
         }
     }
 }
@@ -238,6 +301,46 @@ impl ProcessMemoryModel {
     }
 }
 
+#[derive(Default, Clone, Serialize, Deserialize, below_derive::Queriable)]
+pub struct SingleExecModel {
+    /// When the program was executed, in seconds since epoch
+    pub timestamp: Option<u64>,
+    pub pid: Option<i32>,
+    /// Process that forked this one, even if it has since exited
+    pub ppid: Option<i32>,
+    pub comm: Option<String>,
+    pub filename: Option<String>,
+    /// Leading arguments, ending in "..." if they were cut off
+    pub cmdline: Option<String>,
+    pub cgroup_id: Option<u64>,
+    /// None if the cgroup was removed before it could be sampled
+    pub cgroup: Option<String>,
+}
+
+impl SingleExecModel {
+    fn new(sample: &procfs::ExecEvent, cgroup_paths: &BTreeMap<u64, String>) -> SingleExecModel {
+        SingleExecModel {
+            timestamp: sample.timestamp,
+            pid: sample.pid,
+            ppid: sample.ppid,
+            comm: sample.comm.clone(),
+            filename: sample.filename.clone(),
+            cmdline: sample.argv.as_ref().map(|argv| {
+                if sample.argv_truncated == Some(true) {
+                    format!("{}...", argv.join(" "))
+                } else {
+                    argv.join(" ")
+                }
+            }),
+            cgroup_id: sample.cgroup_id,
+            cgroup: sample
+                .cgroup_id
+                .and_then(|id| cgroup_paths.get(&id))
+                .cloned(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -251,7 +354,8 @@ mod test {
                     "pid": 1,
                     "comm": "systemd"
                 }
-            }
+            },
+            "execs": []
         }
         "#;
         let model: ProcessModel = serde_json::from_str(model_json).unwrap();
@@ -260,4 +364,164 @@ mod test {
             Some(Field::Str("systemd".to_owned()))
         );
     }
+
+    #[test]
+    fn exit_status() {
+        let exited = |exit: procfs::PidExit| {
+            SingleProcessModel::new(
+                &procfs::PidInfo {
+                    exit: Some(exit),
+                    ..Default::default()
+                },
+                None,
+            )
+        };
+
+        let model = exited(procfs::PidExit {
+            code: Some(1),
+            core_dumped: Some(false),
+            oom_killed: Some(false),
+            ..Default::default()
+        });
+        assert_eq!(model.exit_code, Some(1));
+        assert_eq!(model.exit_status.as_deref(), Some("exited (1)"));
+        assert!(model.is_crashed());
+
+        let model = exited(procfs::PidExit {
+            code: Some(0),
+            core_dumped: Some(false),
+            oom_killed: Some(false),
+            ..Default::default()
+        });
+        assert_eq!(model.exit_status.as_deref(), Some("exited (0)"));
+        assert!(!model.is_crashed());
+
+        let model = exited(procfs::PidExit {
+            signal: Some(11),
+            core_dumped: Some(true),
+            oom_killed: Some(false),
+            ..Default::default()
+        });
+        assert_eq!(model.exit_signal.as_deref(), Some("SIGSEGV"));
+        assert_eq!(
+            model.exit_status.as_deref(),
+            Some("exited (SIGSEGV, core dumped)")
+        );
+        assert!(model.is_crashed());
+
+        let model = exited(procfs::PidExit {
+            signal: Some(9),
+            core_dumped: Some(false),
+            oom_killed: Some(true),
+            ..Default::default()
+        });
+        assert_eq!(model.exit_signal.as_deref(), Some("SIGKILL"));
+        assert_eq!(model.exit_status.as_deref(), Some("exited (OOM killed)"));
+        assert!(model.is_crashed());
+
+        // Asked to stop
+        for (signal, name) in [(15, "SIGTERM"), (2, "SIGINT"), (1, "SIGHUP")] {
+            let model = exited(procfs::PidExit {
+                signal: Some(signal),
+                core_dumped: Some(false),
+                oom_killed: Some(false),
+                ..Default::default()
+            });
+            assert_eq!(model.exit_signal.as_deref(), Some(name));
+            assert!(!model.is_crashed());
+        }
+
+        let model = SingleProcessModel::new(&Default::default(), None);
+        assert_eq!(model.exit_status, None);
+        assert!(!model.is_crashed());
+    }
+
+    #[test]
+    fn execs() {
+        let cgroup = CgroupSample {
+            inode_number: Some(1),
+            children: Some(
+                vec![(
+                    "system.slice".to_owned(),
+                    CgroupSample {
+                        inode_number: Some(2),
+                        ..Default::default()
+                    },
+                )]
+                .into_iter()
+                .collect(),
+            ),
+            ..Default::default()
+        };
+        let execs = vec![
+            procfs::ExecEvent {
+                pid: Some(100),
+                ppid: Some(1),
+                argv: Some(vec!["ls".to_owned(), "-l".to_owned()]),
+                argv_truncated: Some(false),
+                cgroup_id: Some(2),
+                ..Default::default()
+            },
+            procfs::ExecEvent {
+                pid: Some(101),
+                argv: Some(vec!["sh".to_owned(), "-c".to_owned()]),
+                argv_truncated: Some(true),
+                cgroup_id: Some(1),
+                ..Default::default()
+            },
+            procfs::ExecEvent {
+                pid: Some(102),
+                cgroup_id: Some(3),
+                ..Default::default()
+            },
+        ];
+
+        let model = ProcessModel::new(&Default::default(), Some(&execs), &cgroup, None);
+        assert_eq!(model.execs.len(), 3);
+        assert_eq!(model.execs[0].cmdline.as_deref(), Some("ls -l"));
+        assert_eq!(model.execs[0].cgroup.as_deref(), Some("/system.slice"));
+        assert_eq!(model.execs[1].cmdline.as_deref(), Some("sh -c..."));
+        assert_eq!(model.execs[1].cgroup.as_deref(), Some("/"));
+        // Cgroup was already gone when we sampled
+        assert_eq!(model.execs[2].cgroup, None);
+        assert_eq!(
+            model.query(&ProcessModelFieldId::from_str("execs.0.pid").unwrap()),
+            Some(Field::I32(100))
+        );
+
+        let model = ProcessModel::new(&Default::default(), None, &cgroup, None);
+        assert!(model.execs.is_empty());
+    }
+
+    #[test]
+    fn tcp_health() {
+        let pidinfo = |retransmits, drops| procfs::PidInfo {
+            tcp: Some(procfs::TcpHealthStat {
+                retransmits: Some(retransmits),
+                drops: Some(drops),
+                drop_reasons: None,
+            }),
+            ..Default::default()
+        };
+        let model = SingleProcessModel::new(
+            &pidinfo(30, 1),
+            Some((&pidinfo(10, 1), Duration::from_secs(2))),
+        );
+        let tcp = model.tcp.as_ref().expect("No tcp model");
+        assert_eq!(tcp.retransmits_per_sec, Some(10.0));
+        assert_eq!(tcp.drops_per_sec, Some(0.0));
+        assert_eq!(
+            SingleProcessModel::fold(&model, &model)
+                .tcp
+                .and_then(|tcp| tcp.retransmits_per_sec),
+            Some(20.0)
+        );
+
+        // tcphealth wasn't running for the previous sample
+        let model = SingleProcessModel::new(
+            &pidinfo(30, 1),
+            Some((&Default::default(), Duration::from_secs(2))),
+        );
+        assert!(model.tcp.is_none());
+    }
 }
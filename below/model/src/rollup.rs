@@ -0,0 +1,640 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! How fields of a `Sample` are combined when several consecutive samples
+//! are rolled up into one coarser sample.
+//!
+//! Most of a sample is cumulative counters, e.g. `cpu_stat.usage_usec`.
+//! Keeping the value of the last sample of a window is the correct merge
+//! for those since models derive rates from the difference between two
+//! samples. Gauges, e.g. `memory_current`, only describe a point in time
+//! and are averaged or maxed instead.
+
+/// How to combine a field across the samples of a window
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Rollup {
+    /// Value of the last sample. Right for counters and identifiers.
+    Last,
+    /// Average of the samples that have the field
+    Avg,
+    /// Maximum of the samples that have the field, for spiky gauges
+    Max,
+    /// Concatenation of per-sample event lists
+    Concat,
+    /// Map whose entries appear and disappear between samples. Entries
+    /// seen at any point of the window are kept, the latest one wins.
+    Union,
+}
+
+/// Rules matching paths into `Sample`. Map keys and list indices are part
+/// of the path. `*` matches any single element and `**` any number of them,
+/// e.g. `processes.*.mem.*` matches `processes.1234.mem.anon`. The first
+/// matching rule applies.
+///
+/// Every numeric field needs a rule, counters and identifiers included, so
+/// that a new gauge can't silently fall back to `Rollup::Last`. Subtrees
+/// that only hold counters are covered as a whole, mixed ones field by
+/// field.
+const RULES: &[(&str, Rollup)] = &[
+    // Sample
+    ("execs", Rollup::Concat),
+    ("oom_kills", Rollup::Concat),
+    ("processes", Rollup::Union),
+    // CgroupSample, which nests through `children`
+    ("cgroup.**.memory_current", Rollup::Avg),
+    ("cgroup.**.memory_swap_current", Rollup::Avg),
+    ("cgroup.**.memory_stat.anon", Rollup::Avg),
+    ("cgroup.**.memory_stat.file", Rollup::Avg),
+    ("cgroup.**.memory_stat.kernel_stack", Rollup::Avg),
+    ("cgroup.**.memory_stat.slab", Rollup::Avg),
+    ("cgroup.**.memory_stat.sock", Rollup::Avg),
+    ("cgroup.**.memory_stat.shmem", Rollup::Avg),
+    ("cgroup.**.memory_stat.file_mapped", Rollup::Avg),
+    ("cgroup.**.memory_stat.file_dirty", Rollup::Avg),
+    ("cgroup.**.memory_stat.file_writeback", Rollup::Avg),
+    ("cgroup.**.memory_stat.anon_thp", Rollup::Avg),
+    ("cgroup.**.memory_stat.inactive_anon", Rollup::Avg),
+    ("cgroup.**.memory_stat.active_anon", Rollup::Avg),
+    ("cgroup.**.memory_stat.inactive_file", Rollup::Avg),
+    ("cgroup.**.memory_stat.active_file", Rollup::Avg),
+    ("cgroup.**.memory_stat.unevictable", Rollup::Avg),
+    ("cgroup.**.memory_stat.slab_reclaimable", Rollup::Avg),
+    ("cgroup.**.memory_stat.slab_unreclaimable", Rollup::Avg),
+    ("cgroup.**.pressure.*.*.avg10", Rollup::Max),
+    ("cgroup.**.pressure.*.*.avg60", Rollup::Avg),
+    ("cgroup.**.pressure.*.*.avg300", Rollup::Avg),
+    ("cgroup.**.memory_high", Rollup::Last),
+    ("cgroup.**.inode_number", Rollup::Last),
+    ("cgroup.**.cpu_stat.*", Rollup::Last),
+    ("cgroup.**.io_stat.*.*", Rollup::Last),
+    ("cgroup.**.memory_stat.pgfault", Rollup::Last),
+    ("cgroup.**.memory_stat.pgmajfault", Rollup::Last),
+    ("cgroup.**.memory_stat.workingset_refault", Rollup::Last),
+    ("cgroup.**.memory_stat.workingset_activate", Rollup::Last),
+    ("cgroup.**.memory_stat.workingset_nodereclaim", Rollup::Last),
+    ("cgroup.**.memory_stat.pgrefill", Rollup::Last),
+    ("cgroup.**.memory_stat.pgscan", Rollup::Last),
+    ("cgroup.**.memory_stat.pgsteal", Rollup::Last),
+    ("cgroup.**.memory_stat.pgactivate", Rollup::Last),
+    ("cgroup.**.memory_stat.pgdeactivate", Rollup::Last),
+    ("cgroup.**.memory_stat.pglazyfree", Rollup::Last),
+    ("cgroup.**.memory_stat.pglazyfreed", Rollup::Last),
+    ("cgroup.**.memory_stat.thp_fault_alloc", Rollup::Last),
+    ("cgroup.**.memory_stat.thp_collapse_alloc", Rollup::Last),
+    ("cgroup.**.pressure.*.*.total", Rollup::Last),
+    ("cgroup.**.memory_events.*", Rollup::Last),
+    ("cgroup.**.run_delay_hist.*", Rollup::Last),
+    ("cgroup.**.io_latency_hist.*.*.*", Rollup::Last),
+    ("cgroup.**.tcp_health.**", Rollup::Last),
+    // SystemSample
+    ("system.stat.running_processes", Rollup::Max),
+    ("system.stat.blocked_processes", Rollup::Max),
+    ("system.meminfo.*", Rollup::Avg),
+    ("system.cpu_sysfs.*.scaling_cur_freq_khz", Rollup::Avg),
+    ("system.swaps.*.used_bytes", Rollup::Avg),
+    ("system.zram.*.orig_data_size", Rollup::Avg),
+    ("system.zram.*.compr_data_size", Rollup::Avg),
+    ("system.zram.*.mem_used_total", Rollup::Avg),
+    ("system.zram.*.mem_used_max", Rollup::Max),
+    ("system.zram.*.same_pages", Rollup::Avg),
+    ("system.zram.*.huge_pages", Rollup::Avg),
+    ("system.zswap.pool_total_size_bytes", Rollup::Avg),
+    ("system.zswap.stored_bytes", Rollup::Avg),
+    ("system.kernel_tables.file_handles_allocated", Rollup::Avg),
+    ("system.kernel_tables.inodes_allocated", Rollup::Avg),
+    ("system.kernel_tables.inodes_free", Rollup::Avg),
+    ("system.kernel_tables.dentries", Rollup::Avg),
+    ("system.kernel_tables.dentries_unused", Rollup::Avg),
+    ("system.kernel_tables.nr_threads", Rollup::Avg),
+    ("system.kernel_tables.entropy_avail", Rollup::Avg),
+    ("system.disks.*.ios_in_progress", Rollup::Max),
+    ("system.disks.*.disk_usage", Rollup::Avg),
+    ("system.filesystems.*.free_bytes", Rollup::Avg),
+    ("system.filesystems.*.avail_bytes", Rollup::Avg),
+    ("system.filesystems.*.free_inodes", Rollup::Avg),
+    ("system.filesystems.*.avail_inodes", Rollup::Avg),
+    ("system.cifs.*.open_files", Rollup::Avg),
+    ("system.stat.total_cpu.*", Rollup::Last),
+    ("system.stat.cpus.*.*", Rollup::Last),
+    ("system.stat.cpu_ids.*", Rollup::Last),
+    ("system.stat.total_interrupt_count", Rollup::Last),
+    ("system.stat.context_switches", Rollup::Last),
+    ("system.stat.boot_time_epoch_secs", Rollup::Last),
+    ("system.stat.total_processes", Rollup::Last),
+    ("system.vmstat.**", Rollup::Last),
+    ("system.cpu_sysfs.*.core_throttle_count", Rollup::Last),
+    ("system.cpu_sysfs.*.package_throttle_count", Rollup::Last),
+    ("system.cpu_sysfs.*.idle_states.*.time_usec", Rollup::Last),
+    ("system.cpu_sysfs.*.idle_states.*.usage", Rollup::Last),
+    ("system.swaps.*.size_bytes", Rollup::Last),
+    ("system.swaps.*.priority", Rollup::Last),
+    ("system.zram.*.mem_limit", Rollup::Last),
+    ("system.zram.*.pages_compacted", Rollup::Last),
+    ("system.zram.*.failed_reads", Rollup::Last),
+    ("system.zram.*.failed_writes", Rollup::Last),
+    ("system.zram.*.invalid_io", Rollup::Last),
+    ("system.zram.*.notify_free", Rollup::Last),
+    ("system.zswap.written_back_pages", Rollup::Last),
+    ("system.zswap.pool_limit_hit", Rollup::Last),
+    ("system.zswap.reject_reclaim_fail", Rollup::Last),
+    ("system.zswap.reject_alloc_fail", Rollup::Last),
+    ("system.zswap.reject_kmemcache_fail", Rollup::Last),
+    ("system.zswap.reject_compress_poor", Rollup::Last),
+    ("system.zswap.duplicate_entry", Rollup::Last),
+    ("system.kernel_tables.file_handles_max", Rollup::Last),
+    ("system.kernel_tables.pid_max", Rollup::Last),
+    ("system.kernel_tables.threads_max", Rollup::Last),
+    ("system.kernel_tables.entropy_poolsize", Rollup::Last),
+    ("system.disks.*.major", Rollup::Last),
+    ("system.disks.*.minor", Rollup::Last),
+    ("system.disks.*.read_completed", Rollup::Last),
+    ("system.disks.*.read_merged", Rollup::Last),
+    ("system.disks.*.read_sectors", Rollup::Last),
+    ("system.disks.*.time_spend_read_ms", Rollup::Last),
+    ("system.disks.*.write_completed", Rollup::Last),
+    ("system.disks.*.write_merged", Rollup::Last),
+    ("system.disks.*.write_sectors", Rollup::Last),
+    ("system.disks.*.time_spend_write_ms", Rollup::Last),
+    ("system.disks.*.time_spent_doing_ios_ms", Rollup::Last),
+    ("system.disks.*.weighted_time_ms", Rollup::Last),
+    ("system.disks.*.discard_completed", Rollup::Last),
+    ("system.disks.*.discard_merged", Rollup::Last),
+    ("system.disks.*.discard_sectors", Rollup::Last),
+    ("system.disks.*.time_spend_discard_ms", Rollup::Last),
+    ("system.disks.*.flush_completed", Rollup::Last),
+    ("system.disks.*.time_spend_flush_ms", Rollup::Last),
+    ("system.disks.*.partition_size", Rollup::Last),
+    ("system.disk_io_latency_hist.*.*.*", Rollup::Last),
+    ("system.filesystems.*.total_bytes", Rollup::Last),
+    ("system.filesystems.*.total_inodes", Rollup::Last),
+    ("system.nfs.**", Rollup::Last),
+    ("system.cifs.*.smbs", Rollup::Last),
+    ("system.cifs.*.bytes_read", Rollup::Last),
+    ("system.cifs.*.bytes_written", Rollup::Last),
+    ("system.cifs.*.ops.*.*", Rollup::Last),
+    ("system.exitstat.*", Rollup::Last),
+    // NetStat
+    ("netstats.tcp.curr_estab", Rollup::Avg),
+    ("netstats.interfaces.*.*", Rollup::Last),
+    ("netstats.tcp.active_opens", Rollup::Last),
+    ("netstats.tcp.passive_opens", Rollup::Last),
+    ("netstats.tcp.attempt_fails", Rollup::Last),
+    ("netstats.tcp.estab_resets", Rollup::Last),
+    ("netstats.tcp.in_segs", Rollup::Last),
+    ("netstats.tcp.out_segs", Rollup::Last),
+    ("netstats.tcp.retrans_segs", Rollup::Last),
+    ("netstats.tcp.in_errs", Rollup::Last),
+    ("netstats.tcp.out_rsts", Rollup::Last),
+    ("netstats.tcp.in_csum_errors", Rollup::Last),
+    ("netstats.tcp_ext.*", Rollup::Last),
+    ("netstats.ip.*", Rollup::Last),
+    ("netstats.ip_ext.*", Rollup::Last),
+    ("netstats.ip6.*", Rollup::Last),
+    ("netstats.icmp.*", Rollup::Last),
+    ("netstats.icmp6.*", Rollup::Last),
+    ("netstats.udp.*", Rollup::Last),
+    ("netstats.udp6.*", Rollup::Last),
+    // PidInfo
+    ("processes.*.stat.num_threads", Rollup::Avg),
+    ("processes.*.stat.rss_bytes", Rollup::Avg),
+    ("processes.*.mem.*", Rollup::Avg),
+    ("processes.*.stat.pid", Rollup::Last),
+    ("processes.*.stat.ppid", Rollup::Last),
+    ("processes.*.stat.pgrp", Rollup::Last),
+    ("processes.*.stat.session", Rollup::Last),
+    ("processes.*.stat.minflt", Rollup::Last),
+    ("processes.*.stat.majflt", Rollup::Last),
+    ("processes.*.stat.user_usecs", Rollup::Last),
+    ("processes.*.stat.system_usecs", Rollup::Last),
+    ("processes.*.stat.running_secs", Rollup::Last),
+    ("processes.*.stat.processor", Rollup::Last),
+    ("processes.*.io.*", Rollup::Last),
+    ("processes.*.exit.*", Rollup::Last),
+    ("processes.*.tcp.**", Rollup::Last),
+];
+
+/// Returns whether `rule` matches `path`, or with `prefix` whether it
+/// matches `path` or something below it
+fn matches<S: AsRef<str>>(rule: &str, path: &[S], prefix: bool) -> bool {
+    let (head, rest) = match rule.split_once('.') {
+        Some((head, rest)) => (head, Some(rest)),
+        None => (rule, None),
+    };
+    if head == "**" {
+        return match rest {
+            Some(rest) => (0..=path.len()).any(|skip| matches(rest, &path[skip..], prefix)),
+            None => true,
+        };
+    }
+    match path.split_first() {
+        Some((first, path)) if head == "*" || head == first.as_ref() => match rest {
+            Some(rest) => matches(rest, path, prefix),
+            None => path.is_empty(),
+        },
+        Some(_) => false,
+        None => prefix,
+    }
+}
+
+/// Returns how to combine the field of `Sample` at `path`, e.g.
+/// `["cgroup", "children", "system.slice", "memory_current"]`
+pub fn get_rollup<S: AsRef<str>>(path: &[S]) -> Rollup {
+    RULES
+        .iter()
+        .find(|(rule, _)| matches(rule, path, false))
+        .map_or(Rollup::Last, |(_, rollup)| *rollup)
+}
+
+/// Returns whether any field at or below `path` is combined with something
+/// other than `Rollup::Last`. Subtrees without such fields can be taken
+/// from the last sample as a whole.
+pub fn has_rollup_below<S: AsRef<str>>(path: &[S]) -> bool {
+    RULES
+        .iter()
+        .any(|(rule, rollup)| *rollup != Rollup::Last && matches(rule, path, true))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::de::value::{Error, StrDeserializer, U32Deserializer};
+    use serde::de::{self, IntoDeserializer, Visitor};
+    use serde::{Deserialize, Deserializer};
+
+    /// How deep `Filled` nests before leaving Options and collections empty,
+    /// to stop at recursive types like `CgroupSample::children`
+    const MAX_FILL_DEPTH: usize = 12;
+
+    /// Deserializes any type with every Option set, one entry in every
+    /// collection, and dummy values otherwise. Serializing the result gives
+    /// all paths the type has.
+    #[derive(Clone, Copy)]
+    struct Filled {
+        depth: usize,
+    }
+
+    impl Filled {
+        fn is_deep(&self) -> bool {
+            self.depth >= MAX_FILL_DEPTH
+        }
+
+        fn nested(&self) -> Filled {
+            Filled {
+                depth: self.depth + 1,
+            }
+        }
+    }
+
+    /// Yields `len` elements, or `fields` as keys of a struct
+    struct FilledAccess {
+        filled: Filled,
+        fields: Option<&'static [&'static str]>,
+        len: usize,
+        next: usize,
+    }
+
+    impl<'de> de::SeqAccess<'de> for FilledAccess {
+        type Error = Error;
+
+        fn next_element_seed<T: de::DeserializeSeed<'de>>(
+            &mut self,
+            seed: T,
+        ) -> Result<Option<T::Value>, Error> {
+            if self.next == self.len {
+                return Ok(None);
+            }
+            self.next += 1;
+            seed.deserialize(self.filled).map(Some)
+        }
+    }
+
+    impl<'de> de::MapAccess<'de> for FilledAccess {
+        type Error = Error;
+
+        fn next_key_seed<K: de::DeserializeSeed<'de>>(
+            &mut self,
+            seed: K,
+        ) -> Result<Option<K::Value>, Error> {
+            if self.next == self.len {
+                return Ok(None);
+            }
+            self.next += 1;
+            match self.fields {
+                Some(fields) => {
+                    let field: StrDeserializer<Error> = fields[self.next - 1].into_deserializer();
+                    seed.deserialize(field).map(Some)
+                }
+                None => seed.deserialize(self.filled).map(Some),
+            }
+        }
+
+        fn next_value_seed<V: de::DeserializeSeed<'de>>(
+            &mut self,
+            seed: V,
+        ) -> Result<V::Value, Error> {
+            seed.deserialize(self.filled)
+        }
+    }
+
+    impl<'de> de::EnumAccess<'de> for Filled {
+        type Error = Error;
+        type Variant = Filled;
+
+        fn variant_seed<V: de::DeserializeSeed<'de>>(
+            self,
+            seed: V,
+        ) -> Result<(V::Value, Filled), Error> {
+            // Index of the first variant
+            let variant: U32Deserializer<Error> = 0u32.into_deserializer();
+            Ok((seed.deserialize(variant)?, self))
+        }
+    }
+
+    impl<'de> de::VariantAccess<'de> for Filled {
+        type Error = Error;
+
+        fn unit_variant(self) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn newtype_variant_seed<T: de::DeserializeSeed<'de>>(
+            self,
+            seed: T,
+        ) -> Result<T::Value, Error> {
+            seed.deserialize(self)
+        }
+
+        fn tuple_variant<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value, Error> {
+            self.deserialize_tuple(len, visitor)
+        }
+
+        fn struct_variant<V: Visitor<'de>>(
+            self,
+            fields: &'static [&'static str],
+            visitor: V,
+        ) -> Result<V::Value, Error> {
+            self.deserialize_struct("", fields, visitor)
+        }
+    }
+
+    impl<'de> Deserializer<'de> for Filled {
+        type Error = Error;
+
+        fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            // Numbers of any type accept this
+            visitor.visit_u64(1)
+        }
+
+        fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            visitor.visit_bool(true)
+        }
+
+        fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            visitor.visit_char('x')
+        }
+
+        fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            visitor.visit_str("x")
+        }
+
+        fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            visitor.visit_str("x")
+        }
+
+        fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            visitor.visit_bytes(b"x")
+        }
+
+        fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            visitor.visit_bytes(b"x")
+        }
+
+        fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            if self.is_deep() {
+                visitor.visit_none()
+            } else {
+                visitor.visit_some(self.nested())
+            }
+        }
+
+        fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            visitor.visit_unit()
+        }
+
+        fn deserialize_unit_struct<V: Visitor<'de>>(
+            self,
+            _name: &'static str,
+            visitor: V,
+        ) -> Result<V::Value, Error> {
+            visitor.visit_unit()
+        }
+
+        fn deserialize_newtype_struct<V: Visitor<'de>>(
+            self,
+            _name: &'static str,
+            visitor: V,
+        ) -> Result<V::Value, Error> {
+            visitor.visit_newtype_struct(self)
+        }
+
+        fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            visitor.visit_seq(FilledAccess {
+                filled: self.nested(),
+                fields: None,
+                len: if self.is_deep() { 0 } else { 1 },
+                next: 0,
+            })
+        }
+
+        fn deserialize_tuple<V: Visitor<'de>>(
+            self,
+            len: usize,
+            visitor: V,
+        ) -> Result<V::Value, Error> {
+            visitor.visit_seq(FilledAccess {
+                filled: self.nested(),
+                fields: None,
+                len,
+                next: 0,
+            })
+        }
+
+        fn deserialize_tuple_struct<V: Visitor<'de>>(
+            self,
+            _name: &'static str,
+            len: usize,
+            visitor: V,
+        ) -> Result<V::Value, Error> {
+            self.deserialize_tuple(len, visitor)
+        }
+
+        fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            visitor.visit_map(FilledAccess {
+                filled: self.nested(),
+                fields: None,
+                len: if self.is_deep() { 0 } else { 1 },
+                next: 0,
+            })
+        }
+
+        fn deserialize_struct<V: Visitor<'de>>(
+            self,
+            _name: &'static str,
+            fields: &'static [&'static str],
+            visitor: V,
+        ) -> Result<V::Value, Error> {
+            visitor.visit_map(FilledAccess {
+                filled: self.nested(),
+                fields: Some(fields),
+                len: fields.len(),
+                next: 0,
+            })
+        }
+
+        fn deserialize_enum<V: Visitor<'de>>(
+            self,
+            _name: &'static str,
+            _variants: &'static [&'static str],
+            visitor: V,
+        ) -> Result<V::Value, Error> {
+            visitor.visit_enum(self)
+        }
+
+        fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            self.deserialize_str(visitor)
+        }
+
+        fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            visitor.visit_unit()
+        }
+
+        serde::forward_to_deserialize_any! {
+            i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64
+        }
+    }
+
+    /// Adds the paths of `value` and everything below it to `paths`
+    fn get_paths(value: &serde_json::Value, path: &mut Vec<String>, paths: &mut Vec<Vec<String>>) {
+        paths.push(path.clone());
+        let children: Vec<(String, &serde_json::Value)> = match value {
+            serde_json::Value::Object(map) => map.iter().map(|(k, v)| (k.clone(), v)).collect(),
+            serde_json::Value::Array(values) => values
+                .iter()
+                .enumerate()
+                .map(|(i, v)| (i.to_string(), v))
+                .collect(),
+            _ => return,
+        };
+        for (key, child) in children {
+            path.push(key);
+            get_paths(child, path, paths);
+            path.pop();
+        }
+    }
+
+    /// Returns a filled sample as JSON along with all of its paths
+    fn get_sample_paths() -> (serde_json::Value, Vec<Vec<String>>) {
+        let sample =
+            crate::Sample::deserialize(Filled { depth: 0 }).expect("Failed to fill sample");
+        let value = serde_json::to_value(&sample).expect("Failed to serialize sample");
+        let mut paths = Vec::new();
+        get_paths(&value, &mut Vec::new(), &mut paths);
+        (value, paths)
+    }
+
+    #[test]
+    fn rules_match_sample_fields() {
+        let (_, paths) = get_sample_paths();
+        // The filler must have reached into maps and nested cgroups
+        assert!(paths.iter().any(|path| path.len() > 6));
+
+        // A rule that matches nothing was likely left behind by a renamed
+        // or moved field
+        for (rule, _) in RULES {
+            assert!(
+                paths.iter().any(|path| matches(rule, path, false)),
+                "Rollup rule {} matches no field of Sample",
+                rule
+            );
+        }
+    }
+
+    #[test]
+    fn numeric_fields_have_rules() {
+        let (value, paths) = get_sample_paths();
+        for path in &paths {
+            let is_number = value
+                .pointer(&format!("/{}", path.join("/")))
+                .map_or(false, |value| value.is_number());
+            // Event lists are concatenated as a whole
+            let in_concat = (0..path.len()).any(|len| get_rollup(&path[..len]) == Rollup::Concat);
+            if is_number && !in_concat {
+                assert!(
+                    RULES.iter().any(|(rule, _)| matches(rule, path, false)),
+                    "{} has no rollup rule, add one to RULES (Rollup::Last for counters)",
+                    path.join(".")
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn rollup_rules() {
+        assert_eq!(get_rollup(&["cgroup", "memory_current"]), Rollup::Avg);
+        assert_eq!(
+            get_rollup(&[
+                "cgroup",
+                "children",
+                "a.slice",
+                "children",
+                "b",
+                "memory_current"
+            ]),
+            Rollup::Avg
+        );
+        assert_eq!(
+            get_rollup(&["cgroup", "pressure", "memory", "full", "avg10"]),
+            Rollup::Max
+        );
+        assert_eq!(
+            get_rollup(&["cgroup", "pressure", "memory", "full", "total"]),
+            Rollup::Last
+        );
+        assert_eq!(
+            get_rollup(&["cgroup", "cpu_stat", "usage_usec"]),
+            Rollup::Last
+        );
+        assert_eq!(get_rollup(&["system", "meminfo", "free"]), Rollup::Avg);
+        assert_eq!(get_rollup(&["system", "vmstat", "pgpgin"]), Rollup::Last);
+        assert_eq!(get_rollup(&["processes"]), Rollup::Union);
+        assert_eq!(
+            get_rollup(&["processes", "1234", "mem", "anon"]),
+            Rollup::Avg
+        );
+        assert_eq!(
+            get_rollup(&["processes", "1234", "stat", "user_usecs"]),
+            Rollup::Last
+        );
+        assert_eq!(get_rollup(&["execs"]), Rollup::Concat);
+        // Rules only apply where the field lives
+        assert_eq!(get_rollup(&["system", "memory_current"]), Rollup::Last);
+        assert_eq!(get_rollup(&["cgroup", "children", "execs"]), Rollup::Last);
+
+        assert!(has_rollup_below::<&str>(&[]));
+        assert!(has_rollup_below(&["cgroup", "children", "a.slice"]));
+        assert!(has_rollup_below(&["processes", "1234", "mem"]));
+        assert!(!has_rollup_below(&["processes", "1234", "io"]));
+        assert!(!has_rollup_below(&["system", "vmstat"]));
+    }
+}
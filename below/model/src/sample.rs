@@ -18,8 +18,15 @@ use super::*;
 pub struct Sample {
     pub cgroup: CgroupSample,
     pub processes: procfs::PidMap,
+    /// Programs executed since the previous sample
+    pub execs: Option<Vec<procfs::ExecEvent>>,
+    /// OOM kills since the previous sample
+    pub oom_kills: Option<Vec<procfs::OomKillEvent>>,
     pub system: SystemSample,
     pub netstats: procfs::NetStat,
+    /// Host the sample was merged from by `below debug merge-store`, None
+    /// for samples recorded locally
+    pub source_host: Option<String>,
 }
 
 #[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
@@ -34,6 +41,23 @@ pub struct CgroupSample {
     pub memory_high: Option<i64>,
     pub memory_events: Option<cgroupfs::MemoryEvents>,
     pub inode_number: Option<i64>,
+    /// Cumulative count of wakeup to run delays of tasks in this cgroup and
+    /// its descendants. Bucket i counts delays in [2^i, 2^(i+1)) usec.
+    pub run_delay_hist: Option<Vec<u64>>,
+    /// Cumulative block IO latencies of this cgroup and its descendants, keyed
+    /// by "major:minor" like `io_stat`
+    pub io_latency_hist: Option<BTreeMap<String, IoLatencyHist>>,
+    /// TCP retransmits and drops of processes in this cgroup and its
+    /// descendants
+    pub tcp_health: Option<procfs::TcpHealthStat>,
+}
+
+/// Cumulative block IO latency histograms. Bucket i counts IOs that took
+/// [2^i, 2^(i+1)) usec from issue to completion.
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct IoLatencyHist {
+    pub read: Vec<u64>,
+    pub write: Vec<u64>,
 }
 
 #[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
@@ -41,8 +65,20 @@ pub struct SystemSample {
     pub stat: procfs::Stat,
     pub meminfo: procfs::MemInfo,
     pub vmstat: procfs::VmStat,
+    pub cpu_sysfs: Option<procfs::CpuSysfsMap>,
+    pub swaps: Option<Vec<procfs::SwapDeviceStat>>,
+    pub zram: Option<procfs::ZramMap>,
+    pub zswap: Option<procfs::ZswapStat>,
+    pub kernel_tables: Option<procfs::KernelTableStat>,
     pub hostname: String,
     pub disks: procfs::DiskMap,
+    /// Block IO latencies keyed by "major:minor" of the whole disk
+    pub disk_io_latency_hist: Option<BTreeMap<String, IoLatencyHist>>,
+    pub filesystems: Option<procfs::FsMap>,
+    pub nfs: Option<procfs::NfsMap>,
+    pub cifs: Option<procfs::CifsMap>,
     pub kernel_version: Option<String>,
     pub os_release: Option<String>,
+    /// Health of the exitstat BPF program. None if it was disabled.
+    pub exitstat: Option<procfs::ExitstatHealth>,
 }
@@ -49,7 +49,9 @@ pub const SAMPLE_MODEL_JSON: &str = r#"
             "softirq_pct": 0.2,
             "stolen_pct": 0.0,
             "guest_pct": 0.0,
-            "guest_nice_pct": 0.0
+            "guest_nice_pct": 0.0,
+            "freq_mhz": 2400,
+            "idle_states": {}
         },
         "cpus": [
             {
@@ -64,7 +66,20 @@ pub const SAMPLE_MODEL_JSON: &str = r#"
                 "softirq_pct": 0.2,
                 "stolen_pct": 0.0,
                 "guest_pct": 0.0,
-                "guest_nice_pct": 0.0
+                "guest_nice_pct": 0.0,
+                "freq_mhz": 2400,
+                "core_throttle_per_sec": 0.0,
+                "package_throttle_per_sec": 0.0,
+                "idle_states": {
+                    "POLL": {
+                        "residency_pct": 0.1,
+                        "usage_per_sec": 10.0
+                    },
+                    "C1": {
+                        "residency_pct": 79.9,
+                        "usage_per_sec": 200.0
+                    }
+                }
             },
             {
                 "idx": 1,
@@ -78,7 +93,20 @@ pub const SAMPLE_MODEL_JSON: &str = r#"
                 "softirq_pct": 0.2,
                 "stolen_pct": 0.0,
                 "guest_pct": 0.0,
-                "guest_nice_pct": 0.0
+                "guest_nice_pct": 0.0,
+                "freq_mhz": 2400,
+                "core_throttle_per_sec": 0.0,
+                "package_throttle_per_sec": 0.0,
+                "idle_states": {
+                    "POLL": {
+                        "residency_pct": 0.1,
+                        "usage_per_sec": 10.0
+                    },
+                    "C1": {
+                        "residency_pct": 79.9,
+                        "usage_per_sec": 200.0
+                    }
+                }
             }
         ],
         "mem": {
@@ -129,7 +157,76 @@ pub const SAMPLE_MODEL_JSON: &str = r#"
             "pgsteal_direct": 0,
             "pgscan_kswapd": 0,
             "pgscan_direct": 0,
-            "oom_kill": 0
+            "oom_kill": 0,
+            "counters": {
+                "compact_stall": {
+                    "value": 80260,
+                    "per_sec": 0.0
+                },
+                "nr_free_pages": {
+                    "value": 1091519,
+                    "per_sec": null
+                }
+            }
+        },
+        "swap": {
+            "total_bytes": 10737418240,
+            "used_bytes": 1073741824,
+            "devices": [
+                {
+                    "name": "/dev/zram0",
+                    "swap_type": "partition",
+                    "size_bytes": 8589934592,
+                    "used_bytes": 1073741824,
+                    "usage_pct": 12.5,
+                    "priority": 100
+                },
+                {
+                    "name": "/swapfile",
+                    "swap_type": "file",
+                    "size_bytes": 2147483648,
+                    "used_bytes": 0,
+                    "usage_pct": 0.0,
+                    "priority": -2
+                }
+            ],
+            "zram": {
+                "zram0": {
+                    "orig_data_bytes": 1073741824,
+                    "compr_data_bytes": 268435456,
+                    "mem_used_bytes": 285212672,
+                    "mem_limit_bytes": 0,
+                    "compression_ratio": 4.0,
+                    "same_pages": 1000,
+                    "huge_pages": 10,
+                    "failed_reads_per_sec": 0.0,
+                    "failed_writes_per_sec": 0.0
+                }
+            },
+            "zswap": {}
+        },
+        "kernel_tables": {
+            "file_handles_allocated": 10432,
+            "file_handles_max": 9223372036854775807,
+            "file_handles_pct": 0.0,
+            "inodes_allocated": 402551,
+            "inodes_free": 59917,
+            "dentries": 512734,
+            "dentries_unused": 471920,
+            "nr_threads": 1531,
+            "pid_max": 4194304,
+            "pid_usage_pct": 0.04,
+            "threads_max": 254720,
+            "threads_usage_pct": 0.6,
+            "entropy_avail": 256,
+            "entropy_poolsize": 256,
+            "entropy_pct": 100.0
+        },
+        "exitstat": {
+            "status": "full",
+            "lost_events": 0,
+            "dropped_events": 0,
+            "health": "ok"
         },
         "disks": {
             "vda": {
@@ -138,6 +235,12 @@ pub const SAMPLE_MODEL_JSON: &str = r#"
                 "write_bytes_per_sec": 100000.0,
                 "discard_bytes_per_sec": 0.0,
                 "disk_total_bytes_per_sec": 600000.0,
+                "util_pct": 12.5,
+                "avg_queue_size": 0.2,
+                "read_await_ms": 0.1,
+                "write_await_ms": 3.0,
+                "avg_request_size": 4096.0,
+                "ios_in_progress": 1,
                 "read_completed": 2000000,
                 "read_merged": 1000000,
                 "read_sectors": 6000000,
@@ -150,15 +253,30 @@ pub const SAMPLE_MODEL_JSON: &str = r#"
                 "discard_merged": 0,
                 "discard_sectors": 0,
                 "time_spend_discard_ms": 0,
+                "flush_completed": 0,
+                "time_spend_flush_ms": 0,
                 "major": 20,
-                "minor": 0
+                "minor": 0,
+                "read_latency_p50_us": 128,
+                "read_latency_p99_us": 1024,
+                "write_latency_p50_us": 512,
+                "write_latency_p99_us": 8192
             },
             "vda1": {
                 "name": "vda1",
+                "filesystem_type": "ext4",
+                "mount_point": "/",
+                "parent": "vda",
                 "read_bytes_per_sec": 500000.0,
                 "write_bytes_per_sec": 100000.0,
                 "discard_bytes_per_sec": 0.0,
                 "disk_total_bytes_per_sec": 600000.0,
+                "util_pct": 12.5,
+                "avg_queue_size": 0.2,
+                "read_await_ms": 0.1,
+                "write_await_ms": 3.0,
+                "avg_request_size": 4096.0,
+                "ios_in_progress": 1,
                 "read_completed": 2000000,
                 "read_merged": 1000000,
                 "read_sectors": 6000000,
@@ -171,9 +289,81 @@ pub const SAMPLE_MODEL_JSON: &str = r#"
                 "discard_merged": 0,
                 "discard_sectors": 0,
                 "time_spend_discard_ms": 0,
+                "flush_completed": 0,
+                "time_spend_flush_ms": 0,
                 "major": 20,
                 "minor": 0
             }
+        },
+        "filesystems": {
+            "/": {
+                "mount_point": "/",
+                "mount_source": "/dev/vda1",
+                "fs_type": "ext4",
+                "usage_pct": 40.0,
+                "total_bytes": 100000000000,
+                "used_bytes": 38000000000,
+                "free_bytes": 62000000000,
+                "avail_bytes": 57000000000,
+                "inode_usage_pct": 10.0,
+                "total_inodes": 6000000,
+                "used_inodes": 600000,
+                "free_inodes": 5400000,
+                "avail_inodes": 5400000
+            }
+        },
+        "nfs": {
+            "/mnt/build": {
+                "mount_point": "/mnt/build",
+                "device": "filer:/export/build",
+                "fs_type": "nfs4",
+                "read_bytes_per_sec": 2000000.0,
+                "write_bytes_per_sec": 500000.0,
+                "server_read_bytes_per_sec": 1000000.0,
+                "server_write_bytes_per_sec": 500000.0,
+                "ops_per_sec": 300.0,
+                "retrans_per_sec": 0.0,
+                "read": {
+                    "ops_per_sec": 20.0,
+                    "retrans_per_sec": 0.0,
+                    "timeouts_per_sec": 0.0,
+                    "avg_rtt_ms": 1.5,
+                    "avg_exec_ms": 1.8
+                },
+                "write": {
+                    "ops_per_sec": 10.0,
+                    "retrans_per_sec": 0.0,
+                    "timeouts_per_sec": 0.0,
+                    "avg_rtt_ms": 4.0,
+                    "avg_exec_ms": 4.2
+                },
+                "getattr": {
+                    "ops_per_sec": 250.0,
+                    "retrans_per_sec": 0.0,
+                    "timeouts_per_sec": 0.0,
+                    "avg_rtt_ms": 0.3,
+                    "avg_exec_ms": 0.4
+                },
+                "lookup": {
+                    "ops_per_sec": 20.0,
+                    "retrans_per_sec": 0.0,
+                    "timeouts_per_sec": 0.0,
+                    "avg_rtt_ms": 0.4,
+                    "avg_exec_ms": 0.5
+                }
+            }
+        },
+        "cifs": {
+            "\\\\filer\\share": {
+                "share": "\\\\filer\\share",
+                "smbs_per_sec": 120.0,
+                "read_bytes_per_sec": 1000000.0,
+                "write_bytes_per_sec": 250000.0,
+                "reads_per_sec": 15.0,
+                "writes_per_sec": 4.0,
+                "failed_ops_per_sec": 0.0,
+                "open_files": 3
+            }
         }
     },
     "cgroup": {
@@ -247,7 +437,10 @@ pub const SAMPLE_MODEL_JSON: &str = r#"
                         "system_pct": 0.00400,
                         "nr_periods_per_sec": 0.0,
                         "nr_throttled_per_sec": 0.0,
-                        "throttled_pct": 0.0
+                        "throttled_pct": 0.0,
+                        "run_delay_p50_us": 4,
+                        "run_delay_p90_us": 16,
+                        "run_delay_p99_us": 128
                     },
                     "memory": {
                         "total": 20000000,
@@ -552,8 +745,31 @@ pub const SAMPLE_MODEL_JSON: &str = r#"
                 },
                 "cmdline": "/usr/lib/systemd/systemd",
                 "exe_path": "/usr/lib/systemd/systemd"
+            },
+            "4000": {
+                "pid": 4000,
+                "ppid": 1,
+                "comm": "stress",
+                "state": "Dead",
+                "uptime_secs": 3,
+                "cgroup": "?",
+                "cmdline": "?",
+                "exit_signal": "SIGKILL",
+                "exit_status": "exited (OOM killed)"
             }
-        }
+        },
+        "execs": [
+            {
+                "timestamp": 1611350000,
+                "pid": 4001,
+                "ppid": 1,
+                "comm": "sh",
+                "filename": "/bin/sh",
+                "cmdline": "/bin/sh -c true",
+                "cgroup_id": 17,
+                "cgroup": "/init.scope"
+            }
+        ]
     },
     "network": {
         "interfaces": {
@@ -711,6 +927,22 @@ pub const SAMPLE_MODEL_JSON: &str = r#"
             "in_csum_errors": 0,
             "ignored_multi": 0
         }
+    },
+    "oom": {
+        "kills": [
+            {
+                "timestamp": 1611350010,
+                "pid": 4100,
+                "comm": "memhog",
+                "rss_bytes": 2147483648,
+                "cgroup": "/workload.slice",
+                "kind": "cgroup",
+                "oom_cgroup": "/workload.slice",
+                "total_bytes": 2147483648,
+                "trigger_pid": 4100,
+                "trigger_comm": "memhog"
+            }
+        ]
     }
 }
 "#;
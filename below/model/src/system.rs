@@ -39,20 +39,32 @@ pub struct SystemModel {
     #[queriable(subquery)]
     pub vm: VmModel,
     #[queriable(subquery)]
+    pub swap: SwapModel,
+    #[queriable(subquery)]
+    pub kernel_tables: KernelTablesModel,
+    #[queriable(subquery)]
+    pub exitstat: ExitstatModel,
+    #[queriable(subquery)]
     pub disks: BTreeMap<String, SingleDiskModel>,
+    #[queriable(subquery)]
+    pub filesystems: BTreeMap<String, SingleFsModel>,
+    #[queriable(subquery)]
+    pub nfs: BTreeMap<String, SingleNfsModel>,
+    #[queriable(subquery)]
+    pub cifs: BTreeMap<String, SingleCifsModel>,
 }
 
 impl SystemModel {
     pub fn new(sample: &SystemSample, last: Option<(&SystemSample, Duration)>) -> SystemModel {
         let stat = ProcStatModel::new(&sample.stat);
-        let total_cpu = match (
+        let mut total_cpu = match (
             last.and_then(|(last, _)| last.stat.total_cpu.as_ref()),
             sample.stat.total_cpu.as_ref(),
         ) {
             (Some(prev), Some(curr)) => SingleCpuModel::new(-1, &prev, &curr),
             _ => Default::default(),
         };
-        let cpus = match (
+        let mut cpus: Vec<SingleCpuModel> = match (
             last.and_then(|(last, _)| last.stat.cpus.as_ref()),
             sample.stat.cpus.as_ref(),
         ) {
@@ -63,29 +75,114 @@ impl SystemModel {
             (_, Some(curr)) => curr.iter().map(|_| Default::default()).collect(),
             _ => Default::default(),
         };
+        if let Some(end_sysfs) = sample.cpu_sysfs.as_ref() {
+            for (idx, cpu) in cpus.iter_mut().enumerate() {
+                // /proc/stat leaves out offline CPUs, so the position of a
+                // CPU needn't be its number
+                let cpu_id = match sample.stat.cpu_ids.as_ref() {
+                    Some(cpu_ids) => match cpu_ids.get(idx) {
+                        Some(cpu_id) => *cpu_id,
+                        None => continue,
+                    },
+                    None => idx as u32,
+                };
+                if let Some(end) = end_sysfs.get(&cpu_id) {
+                    let begin = last.and_then(|(last, duration)| {
+                        last.cpu_sysfs
+                            .as_ref()
+                            .and_then(|begin_sysfs| begin_sysfs.get(&cpu_id))
+                            .map(|begin| (begin, duration))
+                    });
+                    cpu.add_sysfs_stats(begin, end);
+                }
+            }
+            // The total only carries the average frequency
+            let freqs: Vec<u64> = cpus.iter().filter_map(|cpu| cpu.freq_mhz).collect();
+            if !freqs.is_empty() {
+                total_cpu.freq_mhz = Some(freqs.iter().sum::<u64>() / freqs.len() as u64);
+            }
+        }
         let mem = Some(MemoryModel::new(&sample.meminfo)).unwrap_or_default();
         let vm = last
             .map(|(last, duration)| VmModel::new(&last.vmstat, &sample.vmstat, duration))
             .unwrap_or_default();
+        let swap = SwapModel::new(sample, last);
+        let kernel_tables = sample
+            .kernel_tables
+            .as_ref()
+            .map(KernelTablesModel::new)
+            .unwrap_or_default();
         let mut disks: BTreeMap<String, SingleDiskModel> = BTreeMap::new();
         sample.disks.iter().for_each(|(disk_name, end_disk_stat)| {
-            disks.insert(
-                disk_name.clone(),
-                match last {
-                    Some((last_sample, duration)) if last_sample.disks.contains_key(disk_name) => {
-                        SingleDiskModel::new(
-                            last_sample.disks.get(disk_name).unwrap(),
-                            &end_disk_stat,
-                            duration,
-                        )
-                    }
-                    _ => SingleDiskModel {
-                        name: Some(disk_name.clone()),
-                        ..Default::default()
-                    },
+            let mut disk = match last {
+                Some((last_sample, duration)) if last_sample.disks.contains_key(disk_name) => {
+                    SingleDiskModel::new(
+                        last_sample.disks.get(disk_name).unwrap(),
+                        &end_disk_stat,
+                        duration,
+                    )
+                }
+                _ => SingleDiskModel {
+                    name: Some(disk_name.clone()),
+                    ..SingleDiskModel::topology(&end_disk_stat)
                 },
-            );
+            };
+            // Latency histograms are keyed like cgroup io.stat
+            if let (Some((last_sample, _)), Some(major), Some(minor)) =
+                (last, end_disk_stat.major, end_disk_stat.minor)
+            {
+                let device = format!("{}:{}", major, minor);
+                if let (Some(begin), Some(end)) = (
+                    last_sample
+                        .disk_io_latency_hist
+                        .as_ref()
+                        .and_then(|hists| hists.get(&device)),
+                    sample
+                        .disk_io_latency_hist
+                        .as_ref()
+                        .and_then(|hists| hists.get(&device)),
+                ) {
+                    disk.set_latency(begin, end);
+                }
+            }
+            disks.insert(disk_name.clone(), disk);
         });
+        Self::infer_partition_parents(&mut disks);
+        Self::aggregate_partitions(&mut disks);
+        let filesystems = sample
+            .filesystems
+            .iter()
+            .flatten()
+            .map(|(mount_point, fs_stat)| (mount_point.clone(), SingleFsModel::new(fs_stat)))
+            .collect();
+        let nfs = sample
+            .nfs
+            .iter()
+            .flatten()
+            .map(|(mount_point, end)| {
+                let begin = last.and_then(|(last, duration)| {
+                    last.nfs
+                        .as_ref()
+                        .and_then(|nfs| nfs.get(mount_point))
+                        .map(|begin| (begin, duration))
+                });
+                (mount_point.clone(), SingleNfsModel::new(begin, end))
+            })
+            .collect();
+        let cifs = sample
+            .cifs
+            .iter()
+            .flatten()
+            .map(|(share, end)| {
+                let begin = last.and_then(|(last, duration)| {
+                    last.cifs
+                        .as_ref()
+                        .and_then(|cifs| cifs.get(share))
+                        .map(|begin| (begin, duration))
+                });
+                (share.clone(), SingleCifsModel::new(begin, end))
+            })
+            .collect();
 
         SystemModel {
             hostname: sample.hostname.clone(),
@@ -96,9 +193,82 @@ impl SystemModel {
             cpus,
             mem,
             vm,
+            swap,
+            kernel_tables,
+            exitstat: ExitstatModel::new(
+                sample.exitstat.as_ref(),
+                last.and_then(|(last, _)| last.exitstat.as_ref()),
+            ),
             disks,
+            filesystems,
+            nfs,
+            cifs,
+        }
+    }
+
+    /// Samples recorded before partition parents were read from sysfs nest
+    /// disks by device number instead, as below used to: minor 0 is a whole
+    /// disk and the other minors of its major are its partitions.
+    fn infer_partition_parents(disks: &mut BTreeMap<String, SingleDiskModel>) {
+        if disks.values().any(|disk| disk.parent.is_some()) {
+            return;
+        }
+        let whole_disks: BTreeMap<u64, String> = disks
+            .iter()
+            .filter(|(_, disk)| disk.minor == Some(0))
+            .filter_map(|(name, disk)| Some((disk.major?, name.clone())))
+            .collect();
+        for disk in disks.values_mut().filter(|disk| disk.minor != Some(0)) {
+            disk.parent = disk
+                .major
+                .and_then(|major| whole_disks.get(&major).cloned());
         }
     }
+
+    /// Whole disks that don't carry a filesystem themselves report the
+    /// combined size and usage of their mounted partitions. IO counters need
+    /// no aggregation as the kernel already accounts partition IO to the disk.
+    fn aggregate_partitions(disks: &mut BTreeMap<String, SingleDiskModel>) {
+        let mut totals: BTreeMap<String, (u64, f64)> = BTreeMap::new();
+        for disk in disks.values() {
+            if let (Some(parent), Some(usage), Some(size)) =
+                (&disk.parent, disk.disk_usage, disk.partition_size)
+            {
+                let total = totals.entry(parent.clone()).or_default();
+                total.0 += size;
+                total.1 += usage as f64 * size as f64;
+            }
+        }
+        for (name, (size, weighted_usage)) in totals {
+            match disks.get_mut(&name) {
+                Some(disk) if disk.partition_size.is_none() && size > 0 => {
+                    disk.partition_size = Some(size);
+                    disk.disk_usage = Some((weighted_usage / size as f64) as f32);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Disks in tree order: every whole disk is followed by its partitions.
+    /// Plain name order doesn't work for this, e.g. "nvme0n10" sorts before
+    /// "nvme0n1p1".
+    pub fn disks_tree(&self) -> Vec<&SingleDiskModel> {
+        let mut partitions: BTreeMap<&str, Vec<&SingleDiskModel>> = BTreeMap::new();
+        for disk in self.disks.values() {
+            if let Some(parent) = &disk.parent {
+                partitions.entry(parent.as_str()).or_default().push(disk);
+            }
+        }
+        let mut tree = Vec::with_capacity(self.disks.len());
+        for (name, disk) in self.disks.iter().filter(|(_, d)| d.parent.is_none()) {
+            tree.push(disk);
+            tree.extend(partitions.remove(name.as_str()).unwrap_or_default());
+        }
+        // Partitions of disks that are missing from diskstats
+        tree.extend(partitions.into_iter().flat_map(|(_, parts)| parts));
+        tree
+    }
 }
 
 #[derive(
@@ -154,6 +324,11 @@ pub struct SingleCpuModel {
     pub stolen_pct: Option<f64>,
     pub guest_pct: Option<f64>,
     pub guest_nice_pct: Option<f64>,
+    pub freq_mhz: Option<u64>,
+    pub core_throttle_per_sec: Option<f64>,
+    pub package_throttle_per_sec: Option<f64>,
+    #[queriable(subquery)]
+    pub idle_states: BTreeMap<String, CpuIdleStateModel>,
 }
 
 impl SingleCpuModel {
@@ -213,6 +388,7 @@ impl SingleCpuModel {
                     stolen_pct: Some(stolen_usec as f64 * 100.0 / total_usec as f64),
                     guest_pct: Some(guest_usec as f64 * 100.0 / total_usec as f64),
                     guest_nice_pct: Some(guest_nice_usec as f64 * 100.0 / total_usec as f64),
+                    ..Default::default()
                 }
             }
             _ => SingleCpuModel {
@@ -221,6 +397,68 @@ impl SingleCpuModel {
             },
         }
     }
+
+    /// Add current frequency, thermal throttling and idle state residency
+    /// read from sysfs.
+    fn add_sysfs_stats(
+        &mut self,
+        begin: Option<(&procfs::CpuSysfsStat, Duration)>,
+        end: &procfs::CpuSysfsStat,
+    ) {
+        self.freq_mhz = end.scaling_cur_freq_khz.map(|khz| khz / 1000);
+        let (begin, duration) = match begin {
+            Some(begin) => begin,
+            None => return,
+        };
+        self.core_throttle_per_sec =
+            count_per_sec!(begin.core_throttle_count, end.core_throttle_count, duration);
+        self.package_throttle_per_sec = count_per_sec!(
+            begin.package_throttle_count,
+            end.package_throttle_count,
+            duration
+        );
+        if let (Some(begin_states), Some(end_states)) = (&begin.idle_states, &end.idle_states) {
+            self.idle_states = begin_states
+                .iter()
+                .zip(end_states.iter())
+                .enumerate()
+                .map(|(state_idx, (begin_state, end_state))| {
+                    (
+                        end_state
+                            .name
+                            .clone()
+                            .unwrap_or_else(|| format!("state{}", state_idx)),
+                        CpuIdleStateModel {
+                            residency_pct: usec_pct!(
+                                begin_state.time_usec,
+                                end_state.time_usec,
+                                duration
+                            ),
+                            usage_per_sec: count_per_sec!(
+                                begin_state.usage,
+                                end_state.usage,
+                                duration
+                            ),
+                        },
+                    )
+                })
+                .collect();
+        }
+    }
+}
+
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Serialize,
+    Deserialize,
+    below_derive::Queriable
+)]
+pub struct CpuIdleStateModel {
+    pub residency_pct: Option<f64>,
+    pub usage_per_sec: Option<f64>,
 }
 
 #[derive(
@@ -316,6 +554,266 @@ impl MemoryModel {
     }
 }
 
+/// Usage of kernel tables against their limits. Running out of any of them
+/// fails allocations (e.g. EMFILE, EAGAIN on fork) well before memory does.
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Serialize,
+    Deserialize,
+    below_derive::Queriable
+)]
+pub struct KernelTablesModel {
+    pub file_handles_allocated: Option<u64>,
+    pub file_handles_max: Option<u64>,
+    pub file_handles_pct: Option<f64>,
+    pub inodes_allocated: Option<u64>,
+    pub inodes_free: Option<u64>,
+    pub dentries: Option<u64>,
+    pub dentries_unused: Option<u64>,
+    pub nr_threads: Option<u64>,
+    pub pid_max: Option<u64>,
+    pub pid_usage_pct: Option<f64>,
+    pub threads_max: Option<u64>,
+    pub threads_usage_pct: Option<f64>,
+    pub entropy_avail: Option<u64>,
+    pub entropy_poolsize: Option<u64>,
+    pub entropy_pct: Option<f64>,
+}
+
+impl KernelTablesModel {
+    fn new(tables: &procfs::KernelTableStat) -> KernelTablesModel {
+        KernelTablesModel {
+            file_handles_allocated: tables.file_handles_allocated,
+            file_handles_max: tables.file_handles_max,
+            file_handles_pct: pct_of_limit(tables.file_handles_allocated, tables.file_handles_max),
+            inodes_allocated: tables.inodes_allocated,
+            inodes_free: tables.inodes_free,
+            dentries: tables.dentries,
+            dentries_unused: tables.dentries_unused,
+            nr_threads: tables.nr_threads,
+            pid_max: tables.pid_max,
+            pid_usage_pct: pct_of_limit(tables.nr_threads, tables.pid_max),
+            threads_max: tables.threads_max,
+            threads_usage_pct: pct_of_limit(tables.nr_threads, tables.threads_max),
+            entropy_avail: tables.entropy_avail,
+            entropy_poolsize: tables.entropy_poolsize,
+            entropy_pct: pct_of_limit(tables.entropy_avail, tables.entropy_poolsize),
+        }
+    }
+}
+
+fn pct_of_limit(used: Option<u64>, limit: Option<u64>) -> Option<f64> {
+    match (used, limit) {
+        (Some(used), Some(limit)) if limit > 0 => Some(used as f64 * 100.0 / limit as f64),
+        _ => None,
+    }
+}
+
+/// Swap areas and the compressed swap backends behind them
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Serialize,
+    Deserialize,
+    below_derive::Queriable
+)]
+pub struct SwapModel {
+    pub total_bytes: Option<u64>,
+    pub used_bytes: Option<u64>,
+    #[queriable(subquery)]
+    pub devices: Vec<SingleSwapDeviceModel>,
+    #[queriable(subquery)]
+    pub zram: BTreeMap<String, ZramModel>,
+    #[queriable(subquery)]
+    pub zswap: ZswapModel,
+}
+
+impl SwapModel {
+    fn new(sample: &SystemSample, last: Option<(&SystemSample, Duration)>) -> SwapModel {
+        let devices: Vec<SingleSwapDeviceModel> = sample
+            .swaps
+            .iter()
+            .flatten()
+            .map(SingleSwapDeviceModel::new)
+            .collect();
+        let zram = sample
+            .zram
+            .iter()
+            .flatten()
+            .map(|(name, end)| {
+                let begin = last.and_then(|(last, duration)| {
+                    last.zram
+                        .as_ref()
+                        .and_then(|zram| zram.get(name))
+                        .map(|begin| (begin, duration))
+                });
+                (name.clone(), ZramModel::new(begin, end))
+            })
+            .collect();
+        let zswap = sample
+            .zswap
+            .as_ref()
+            .map(|end| {
+                let begin = last.and_then(|(last, duration)| {
+                    last.zswap.as_ref().map(|begin| (begin, duration))
+                });
+                ZswapModel::new(begin, end)
+            })
+            .unwrap_or_default();
+
+        SwapModel {
+            total_bytes: devices.iter().map(|d| d.size_bytes).fold(None, opt_add),
+            used_bytes: devices.iter().map(|d| d.used_bytes).fold(None, opt_add),
+            devices,
+            zram,
+            zswap,
+        }
+    }
+}
+
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Serialize,
+    Deserialize,
+    below_derive::Queriable
+)]
+pub struct SingleSwapDeviceModel {
+    pub name: Option<String>,
+    pub swap_type: Option<String>,
+    pub size_bytes: Option<u64>,
+    pub used_bytes: Option<u64>,
+    pub usage_pct: Option<f64>,
+    pub priority: Option<i64>,
+}
+
+impl SingleSwapDeviceModel {
+    fn new(swap: &procfs::SwapDeviceStat) -> SingleSwapDeviceModel {
+        SingleSwapDeviceModel {
+            name: swap.filename.clone(),
+            swap_type: swap.swap_type.clone(),
+            size_bytes: swap.size_bytes,
+            used_bytes: swap.used_bytes,
+            usage_pct: match (swap.used_bytes, swap.size_bytes) {
+                (Some(used), Some(size)) if size > 0 => Some(used as f64 * 100.0 / size as f64),
+                _ => None,
+            },
+            priority: swap.priority,
+        }
+    }
+}
+
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Serialize,
+    Deserialize,
+    below_derive::Queriable
+)]
+pub struct ZramModel {
+    pub orig_data_bytes: Option<u64>,
+    pub compr_data_bytes: Option<u64>,
+    pub mem_used_bytes: Option<u64>,
+    pub mem_limit_bytes: Option<u64>,
+    pub compression_ratio: Option<f64>,
+    pub same_pages: Option<u64>,
+    pub huge_pages: Option<u64>,
+    pub failed_reads_per_sec: Option<f64>,
+    pub failed_writes_per_sec: Option<f64>,
+}
+
+impl ZramModel {
+    fn new(begin: Option<(&procfs::ZramStat, Duration)>, end: &procfs::ZramStat) -> ZramModel {
+        let mut model = ZramModel {
+            orig_data_bytes: end.orig_data_size,
+            compr_data_bytes: end.compr_data_size,
+            mem_used_bytes: end.mem_used_total,
+            mem_limit_bytes: end.mem_limit,
+            compression_ratio: compression_ratio(end.orig_data_size, end.compr_data_size),
+            same_pages: end.same_pages,
+            huge_pages: end.huge_pages,
+            ..Default::default()
+        };
+        if let Some((begin, duration)) = begin {
+            model.failed_reads_per_sec =
+                count_per_sec!(begin.failed_reads, end.failed_reads, duration);
+            model.failed_writes_per_sec =
+                count_per_sec!(begin.failed_writes, end.failed_writes, duration);
+        }
+        model
+    }
+}
+
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Serialize,
+    Deserialize,
+    below_derive::Queriable
+)]
+pub struct ZswapModel {
+    pub pool_total_bytes: Option<u64>,
+    pub stored_bytes: Option<u64>,
+    pub compression_ratio: Option<f64>,
+    pub written_back_pages_per_sec: Option<f64>,
+    pub pool_limit_hit_per_sec: Option<f64>,
+    pub reject_per_sec: Option<f64>,
+}
+
+impl ZswapModel {
+    fn new(begin: Option<(&procfs::ZswapStat, Duration)>, end: &procfs::ZswapStat) -> ZswapModel {
+        let mut model = ZswapModel {
+            pool_total_bytes: end.pool_total_size_bytes,
+            stored_bytes: end.stored_bytes,
+            compression_ratio: compression_ratio(end.stored_bytes, end.pool_total_size_bytes),
+            ..Default::default()
+        };
+        if let Some((begin, duration)) = begin {
+            model.written_back_pages_per_sec =
+                count_per_sec!(begin.written_back_pages, end.written_back_pages, duration);
+            model.pool_limit_hit_per_sec =
+                count_per_sec!(begin.pool_limit_hit, end.pool_limit_hit, duration);
+            model.reject_per_sec = count_per_sec!(
+                Self::total_rejects(begin),
+                Self::total_rejects(end),
+                duration
+            );
+        }
+        model
+    }
+
+    fn total_rejects(stat: &procfs::ZswapStat) -> Option<u64> {
+        [
+            stat.reject_reclaim_fail,
+            stat.reject_alloc_fail,
+            stat.reject_kmemcache_fail,
+            stat.reject_compress_poor,
+        ]
+        .iter()
+        .copied()
+        .fold(None, opt_add)
+    }
+}
+
+/// Ratio of uncompressed to compressed size. None while nothing is stored.
+fn compression_ratio(orig: Option<u64>, compressed: Option<u64>) -> Option<f64> {
+    match (orig, compressed) {
+        (Some(orig), Some(compressed)) if compressed > 0 => Some(orig as f64 / compressed as f64),
+        _ => None,
+    }
+}
+
 #[derive(
     Clone,
     Debug,
@@ -335,6 +833,9 @@ pub struct VmModel {
     pub pgscan_kswapd: Option<u64>,
     pub pgscan_direct: Option<u64>,
     pub oom_kill: Option<u64>,
+    /// Every /proc/vmstat counter by name
+    #[queriable(subquery)]
+    pub counters: BTreeMap<String, VmCounterModel>,
 }
 
 impl VmModel {
@@ -349,6 +850,105 @@ impl VmModel {
             pgscan_kswapd: count_per_sec!(begin.pgscan_kswapd, end.pgscan_kswapd, duration, u64),
             pgscan_direct: count_per_sec!(begin.pgscan_direct, end.pgscan_direct, duration, u64),
             oom_kill: end.oom_kill.map(|v| v as u64),
+            counters: end
+                .counters
+                .iter()
+                .flatten()
+                .map(|(name, &value)| {
+                    let begin_value = begin
+                        .counters
+                        .as_ref()
+                        .and_then(|counters| counters.get(name))
+                        .copied();
+                    (
+                        name.clone(),
+                        VmCounterModel {
+                            value: Some(value),
+                            per_sec: count_per_sec!(begin_value, Some(value), duration),
+                        },
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+/// A /proc/vmstat entry. The rate is only meaningful for event counters, not
+/// for gauges such as nr_free_pages.
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Serialize,
+    Deserialize,
+    below_derive::Queriable
+)]
+pub struct VmCounterModel {
+    pub value: Option<u64>,
+    pub per_sec: Option<f64>,
+}
+
+/// Health of the exitstat BPF program during the interval. While it is
+/// degraded, processes that exited may be missing or lack some stats.
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Serialize,
+    Deserialize,
+    below_derive::Queriable
+)]
+pub struct ExitstatModel {
+    /// "loading", "full", "reduced" or "failed". None if exitstat is disabled.
+    pub status: Option<String>,
+    pub lost_events: Option<u64>,
+    pub dropped_events: Option<u64>,
+    /// "degraded" if exit data may be incomplete for the interval, "ok"
+    /// otherwise
+    pub health: Option<String>,
+}
+
+impl ExitstatModel {
+    fn new(
+        sample: Option<&procfs::ExitstatHealth>,
+        last: Option<&procfs::ExitstatHealth>,
+    ) -> ExitstatModel {
+        let sample = match sample {
+            Some(sample) => sample,
+            None => return Default::default(),
+        };
+        // Counters restart with below, so a drop means everything counted
+        // since the restart
+        let since_last = |begin: Option<u64>, end: Option<u64>| match (begin, end) {
+            (Some(begin), Some(end)) if begin <= end => Some(end - begin),
+            (_, end) => end,
+        };
+        let lost_events = since_last(last.and_then(|l| l.lost_events), sample.lost_events);
+        let dropped_events = since_last(last.and_then(|l| l.dropped_events), sample.dropped_events);
+        ExitstatModel {
+            status: sample.status.map(|status| {
+                match status {
+                    procfs::ExitstatStatus::Loading => "loading",
+                    procfs::ExitstatStatus::Full => "full",
+                    procfs::ExitstatStatus::Reduced => "reduced",
+                    procfs::ExitstatStatus::Failed => "failed",
+                }
+                .to_owned()
+            }),
+            lost_events,
+            dropped_events,
+            health: sample.status.map(|status| {
+                if status != procfs::ExitstatStatus::Full
+                    || lost_events.unwrap_or_default() > 0
+                    || dropped_events.unwrap_or_default() > 0
+                {
+                    "degraded".to_owned()
+                } else {
+                    "ok".to_owned()
+                }
+            }),
         }
     }
 }
@@ -367,10 +967,20 @@ pub struct SingleDiskModel {
     pub disk_usage: Option<f32>,
     pub partition_size: Option<u64>,
     pub filesystem_type: Option<String>,
+    pub mount_point: Option<String>,
+    pub dm_name: Option<String>,
+    pub parent: Option<String>,
+    pub slaves: Option<String>,
     pub read_bytes_per_sec: Option<f64>,
     pub write_bytes_per_sec: Option<f64>,
     pub discard_bytes_per_sec: Option<f64>,
     pub disk_total_bytes_per_sec: Option<f64>,
+    pub util_pct: Option<f64>,
+    pub avg_queue_size: Option<f64>,
+    pub read_await_ms: Option<f64>,
+    pub write_await_ms: Option<f64>,
+    pub avg_request_size: Option<f64>,
+    pub ios_in_progress: Option<u64>,
     pub read_completed: Option<u64>,
     pub read_merged: Option<u64>,
     pub read_sectors: Option<u64>,
@@ -383,13 +993,35 @@ pub struct SingleDiskModel {
     pub discard_merged: Option<u64>,
     pub discard_sectors: Option<u64>,
     pub time_spend_discard_ms: Option<u64>,
+    pub flush_completed: Option<u64>,
+    pub time_spend_flush_ms: Option<u64>,
     pub major: Option<u64>,
     pub minor: Option<u64>,
+    /// Percentiles of block IO latency from issue to completion, rounded up
+    /// to a power of two. Only whole disks are traced.
+    pub read_latency_p50_us: Option<u64>,
+    pub read_latency_p99_us: Option<u64>,
+    pub write_latency_p50_us: Option<u64>,
+    pub write_latency_p99_us: Option<u64>,
+    #[queriable(ignore)]
+    pub read_latency_hist: Option<Vec<u64>>,
+    #[queriable(ignore)]
+    pub write_latency_hist: Option<Vec<u64>>,
+}
+
+/// Average of `total` over completed IOs in the interval. An idle device
+/// averages to 0 rather than None, same as iostat.
+fn per_io(total: Option<u64>, ios: Option<u64>) -> Option<f64> {
+    match (total, ios) {
+        (Some(_), Some(0)) => Some(0.0),
+        (Some(total), Some(ios)) => Some(total as f64 / ios as f64),
+        _ => None,
+    }
 }
 
 impl Recursive for SingleDiskModel {
     fn get_depth(&self) -> usize {
-        if self.minor == Some(0) { 0 } else { 1 }
+        if self.parent.is_some() { 1 } else { 0 }
     }
 }
 
@@ -403,11 +1035,14 @@ impl SingleDiskModel {
             count_per_sec!(begin.read_sectors, end.read_sectors, duration).map(|val| val * 512.0);
         let write_bytes_per_sec =
             count_per_sec!(begin.write_sectors, end.write_sectors, duration).map(|val| val * 512.0);
+        let read_ios = opt_delta(begin.read_completed, end.read_completed);
+        let write_ios = opt_delta(begin.write_completed, end.write_completed);
+        let sectors = opt_add(
+            opt_delta(begin.read_sectors, end.read_sectors),
+            opt_delta(begin.write_sectors, end.write_sectors),
+        );
+        let duration_ms = duration.as_secs_f64() * 1000.0;
         SingleDiskModel {
-            name: end.name.clone(),
-            disk_usage: end.disk_usage.map(|v| v as f32),
-            partition_size: end.partition_size.map(|v| v as u64),
-            filesystem_type: end.filesystem_type.clone(),
             read_bytes_per_sec,
             write_bytes_per_sec,
             discard_bytes_per_sec: count_per_sec!(
@@ -417,6 +1052,20 @@ impl SingleDiskModel {
             )
             .map(|val| val * 512.0),
             disk_total_bytes_per_sec: opt_add(read_bytes_per_sec, write_bytes_per_sec),
+            util_pct: opt_delta(begin.time_spent_doing_ios_ms, end.time_spent_doing_ios_ms)
+                .map(|ms| ms as f64 * 100.0 / duration_ms),
+            avg_queue_size: opt_delta(begin.weighted_time_ms, end.weighted_time_ms)
+                .map(|ms| ms as f64 / duration_ms),
+            read_await_ms: per_io(
+                opt_delta(begin.time_spend_read_ms, end.time_spend_read_ms),
+                read_ios,
+            ),
+            write_await_ms: per_io(
+                opt_delta(begin.time_spend_write_ms, end.time_spend_write_ms),
+                write_ios,
+            ),
+            avg_request_size: per_io(sectors, opt_add(read_ios, write_ios)).map(|val| val * 512.0),
+            ios_in_progress: end.ios_in_progress.map(|v| v as u64),
             read_completed: end.read_completed.map(|v| v as u64),
             read_merged: end.read_merged.map(|v| v as u64),
             read_sectors: end.read_sectors.map(|v| v as u64),
@@ -429,10 +1078,287 @@ impl SingleDiskModel {
             discard_merged: end.discard_merged.map(|v| v as u64),
             discard_sectors: end.discard_sectors.map(|v| v as u64),
             time_spend_discard_ms: end.time_spend_discard_ms.map(|v| v as u64),
+            flush_completed: end.flush_completed.map(|v| v as u64),
+            time_spend_flush_ms: end.time_spend_flush_ms.map(|v| v as u64),
+            ..Self::topology(end)
+        }
+    }
+
+    /// Identity, placement and filesystem of the device, none of which
+    /// need a previous sample.
+    fn topology(end: &procfs::DiskStat) -> SingleDiskModel {
+        SingleDiskModel {
+            name: end.name.clone(),
+            disk_usage: end.disk_usage.map(|v| v as f32),
+            partition_size: end.partition_size.map(|v| v as u64),
+            filesystem_type: end.filesystem_type.clone(),
+            mount_point: end.mount_point.clone(),
+            dm_name: end.dm_name.clone(),
+            parent: end.parent.clone(),
+            slaves: end.slaves.as_ref().map(|slaves| slaves.join(",")),
             major: end.major.map(|v| v as u64),
             minor: end.minor.map(|v| v as u64),
+            ..Default::default()
         }
     }
+
+    fn set_latency(&mut self, begin: &IoLatencyHist, end: &IoLatencyHist) {
+        self.read_latency_hist = log2_hist_delta(&begin.read, &end.read);
+        self.write_latency_hist = log2_hist_delta(&begin.write, &end.write);
+        let read = self.read_latency_hist.as_deref();
+        let write = self.write_latency_hist.as_deref();
+        self.read_latency_p50_us = read.and_then(|hist| log2_hist_percentile(hist, 50.0));
+        self.read_latency_p99_us = read.and_then(|hist| log2_hist_percentile(hist, 99.0));
+        self.write_latency_p50_us = write.and_then(|hist| log2_hist_percentile(hist, 50.0));
+        self.write_latency_p99_us = write.and_then(|hist| log2_hist_percentile(hist, 99.0));
+    }
+}
+
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Serialize,
+    Deserialize,
+    below_derive::Queriable
+)]
+pub struct SingleFsModel {
+    pub mount_point: Option<String>,
+    pub mount_source: Option<String>,
+    pub fs_type: Option<String>,
+    pub usage_pct: Option<f64>,
+    pub total_bytes: Option<u64>,
+    pub used_bytes: Option<u64>,
+    pub free_bytes: Option<u64>,
+    pub avail_bytes: Option<u64>,
+    pub inode_usage_pct: Option<f64>,
+    pub total_inodes: Option<u64>,
+    pub used_inodes: Option<u64>,
+    pub free_inodes: Option<u64>,
+    pub avail_inodes: Option<u64>,
+}
+
+impl SingleFsModel {
+    fn new(fs_stat: &procfs::FsStat) -> SingleFsModel {
+        let used_bytes = fs_stat
+            .total_bytes
+            .and_then(|total| total.checked_sub(fs_stat.free_bytes?));
+        let used_inodes = fs_stat
+            .total_inodes
+            .and_then(|total| total.checked_sub(fs_stat.free_inodes?));
+        SingleFsModel {
+            mount_point: fs_stat.mount_point.clone(),
+            mount_source: fs_stat.mount_source.clone(),
+            fs_type: fs_stat.fs_type.clone(),
+            usage_pct: Self::usage_pct(used_bytes, fs_stat.avail_bytes),
+            total_bytes: fs_stat.total_bytes,
+            used_bytes,
+            free_bytes: fs_stat.free_bytes,
+            avail_bytes: fs_stat.avail_bytes,
+            inode_usage_pct: Self::usage_pct(used_inodes, fs_stat.avail_inodes),
+            total_inodes: fs_stat.total_inodes,
+            used_inodes,
+            free_inodes: fs_stat.free_inodes,
+            avail_inodes: fs_stat.avail_inodes,
+        }
+    }
+
+    /// Usage as df reports it: reserved blocks count as neither used nor
+    /// available, so a full filesystem is at 100% even if root can still
+    /// write to it.
+    fn usage_pct(used: Option<u64>, avail: Option<u64>) -> Option<f64> {
+        match (used, avail) {
+            (Some(used), Some(avail)) if used + avail > 0 => {
+                Some(used as f64 * 100.0 / (used + avail) as f64)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Serialize,
+    Deserialize,
+    below_derive::Queriable
+)]
+pub struct NfsOpModel {
+    pub ops_per_sec: Option<f64>,
+    pub retrans_per_sec: Option<f64>,
+    pub timeouts_per_sec: Option<f64>,
+    pub avg_rtt_ms: Option<f64>,
+    pub avg_exec_ms: Option<f64>,
+}
+
+impl NfsOpModel {
+    fn new(begin: &procfs::NfsOpStat, end: &procfs::NfsOpStat, duration: Duration) -> NfsOpModel {
+        let ops = opt_delta(begin.ops, end.ops);
+        NfsOpModel {
+            ops_per_sec: count_per_sec!(begin.ops, end.ops, duration),
+            retrans_per_sec: opt_delta(begin.transmissions, end.transmissions)
+                .and_then(|transmissions| transmissions.checked_sub(ops?))
+                .map(|retrans| retrans as f64 / duration.as_secs_f64()),
+            timeouts_per_sec: count_per_sec!(begin.major_timeouts, end.major_timeouts, duration),
+            avg_rtt_ms: per_io(opt_delta(begin.rtt_ms, end.rtt_ms), ops),
+            avg_exec_ms: per_io(opt_delta(begin.exec_ms, end.exec_ms), ops),
+        }
+    }
+}
+
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Serialize,
+    Deserialize,
+    below_derive::Queriable
+)]
+pub struct SingleNfsModel {
+    pub mount_point: Option<String>,
+    pub device: Option<String>,
+    pub fs_type: Option<String>,
+    pub read_bytes_per_sec: Option<f64>,
+    pub write_bytes_per_sec: Option<f64>,
+    pub server_read_bytes_per_sec: Option<f64>,
+    pub server_write_bytes_per_sec: Option<f64>,
+    pub ops_per_sec: Option<f64>,
+    pub retrans_per_sec: Option<f64>,
+    #[queriable(subquery)]
+    pub read: NfsOpModel,
+    #[queriable(subquery)]
+    pub write: NfsOpModel,
+    #[queriable(subquery)]
+    pub getattr: NfsOpModel,
+    #[queriable(subquery)]
+    pub lookup: NfsOpModel,
+}
+
+impl SingleNfsModel {
+    fn new(
+        begin: Option<(&procfs::NfsMountStat, Duration)>,
+        end: &procfs::NfsMountStat,
+    ) -> SingleNfsModel {
+        let mut model = SingleNfsModel {
+            mount_point: end.mount_point.clone(),
+            device: end.device.clone(),
+            fs_type: end.fs_type.clone(),
+            ..Default::default()
+        };
+        let (begin, duration) = match begin {
+            Some(begin) => begin,
+            None => return model,
+        };
+
+        // Page cache and O_DIRECT IO together are what applications asked for
+        model.read_bytes_per_sec = count_per_sec!(
+            opt_add(begin.normal_read_bytes, begin.direct_read_bytes),
+            opt_add(end.normal_read_bytes, end.direct_read_bytes),
+            duration
+        );
+        model.write_bytes_per_sec = count_per_sec!(
+            opt_add(begin.normal_write_bytes, begin.direct_write_bytes),
+            opt_add(end.normal_write_bytes, end.direct_write_bytes),
+            duration
+        );
+        model.server_read_bytes_per_sec =
+            count_per_sec!(begin.server_read_bytes, end.server_read_bytes, duration);
+        model.server_write_bytes_per_sec =
+            count_per_sec!(begin.server_write_bytes, end.server_write_bytes, duration);
+
+        let op_models: BTreeMap<&str, NfsOpModel> = match (&begin.ops, &end.ops) {
+            (Some(begin_ops), Some(end_ops)) => end_ops
+                .iter()
+                .filter_map(|(name, end_op)| {
+                    begin_ops.get(name).map(|begin_op| {
+                        (name.as_str(), NfsOpModel::new(begin_op, end_op, duration))
+                    })
+                })
+                .collect(),
+            _ => return model,
+        };
+        model.ops_per_sec = op_models
+            .values()
+            .map(|op| op.ops_per_sec)
+            .fold(Some(0.0), opt_add);
+        model.retrans_per_sec = op_models
+            .values()
+            .map(|op| op.retrans_per_sec)
+            .fold(Some(0.0), opt_add);
+        let op = |name: &str| op_models.get(name).cloned().unwrap_or_default();
+        model.read = op("READ");
+        model.write = op("WRITE");
+        model.getattr = op("GETATTR");
+        model.lookup = op("LOOKUP");
+        model
+    }
+}
+
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Serialize,
+    Deserialize,
+    below_derive::Queriable
+)]
+pub struct SingleCifsModel {
+    pub share: Option<String>,
+    pub smbs_per_sec: Option<f64>,
+    pub read_bytes_per_sec: Option<f64>,
+    pub write_bytes_per_sec: Option<f64>,
+    pub reads_per_sec: Option<f64>,
+    pub writes_per_sec: Option<f64>,
+    pub failed_ops_per_sec: Option<f64>,
+    pub open_files: Option<u64>,
+}
+
+impl SingleCifsModel {
+    fn new(
+        begin: Option<(&procfs::CifsShareStat, Duration)>,
+        end: &procfs::CifsShareStat,
+    ) -> SingleCifsModel {
+        let mut model = SingleCifsModel {
+            share: end.share.clone(),
+            open_files: end.open_files,
+            ..Default::default()
+        };
+        let (begin, duration) = match begin {
+            Some(begin) => begin,
+            None => return model,
+        };
+
+        model.smbs_per_sec = count_per_sec!(begin.smbs, end.smbs, duration);
+        model.read_bytes_per_sec = count_per_sec!(begin.bytes_read, end.bytes_read, duration);
+        model.write_bytes_per_sec =
+            count_per_sec!(begin.bytes_written, end.bytes_written, duration);
+
+        let (begin_ops, end_ops) = match (&begin.ops, &end.ops) {
+            (Some(begin_ops), Some(end_ops)) => (begin_ops, end_ops),
+            _ => return model,
+        };
+        let op = |name: &str| (begin_ops.get(name), end_ops.get(name));
+        if let (Some(begin_op), Some(end_op)) = op("Reads") {
+            model.reads_per_sec = count_per_sec!(begin_op.total, end_op.total, duration);
+        }
+        if let (Some(begin_op), Some(end_op)) = op("Writes") {
+            model.writes_per_sec = count_per_sec!(begin_op.total, end_op.total, duration);
+        }
+        model.failed_ops_per_sec = end_ops
+            .iter()
+            .filter_map(|(name, end_op)| {
+                begin_ops
+                    .get(name)
+                    .map(|begin_op| count_per_sec!(begin_op.failed, end_op.failed, duration))
+            })
+            .fold(Some(0.0), opt_add);
+        model
+    }
 }
 
 #[cfg(test)]
@@ -446,17 +1372,30 @@ mod test {
             "hostname": "example.com",
             "stat": {},
             "total_cpu": {
-                "idx": -1
+                "idx": -1,
+                "idle_states": {}
             },
             "cpus": [],
             "mem": {},
-            "vm": {},
+            "vm": {
+                "counters": {}
+            },
+            "swap": {
+                "devices": [],
+                "zram": {},
+                "zswap": {}
+            },
+            "kernel_tables": {},
+            "exitstat": {},
             "disks": {
                 "sda": {
                     "name": "sda",
                     "read_bytes_per_sec": 42
                 }
-            }
+            },
+            "filesystems": {},
+            "nfs": {},
+            "cifs": {}
         }
         "#;
         let model: SystemModel = serde_json::from_str(model_json).unwrap();
@@ -465,4 +1404,463 @@ mod test {
             Some(Field::F64(42.0))
         );
     }
+
+    #[test]
+    fn disk_iostat_fields() {
+        let begin = procfs::DiskStat {
+            read_completed: Some(100),
+            read_sectors: Some(800),
+            time_spend_read_ms: Some(50),
+            write_completed: Some(200),
+            write_sectors: Some(1600),
+            time_spend_write_ms: Some(400),
+            time_spent_doing_ios_ms: Some(1000),
+            weighted_time_ms: Some(2000),
+            ..Default::default()
+        };
+        let end = procfs::DiskStat {
+            read_completed: Some(110),
+            read_sectors: Some(880),
+            time_spend_read_ms: Some(70),
+            write_completed: Some(200),
+            write_sectors: Some(1600),
+            time_spend_write_ms: Some(400),
+            ios_in_progress: Some(3),
+            time_spent_doing_ios_ms: Some(1500),
+            weighted_time_ms: Some(3000),
+            ..Default::default()
+        };
+        let model = SingleDiskModel::new(&begin, &end, Duration::from_secs(1));
+        assert_eq!(model.util_pct, Some(50.0));
+        assert_eq!(model.avg_queue_size, Some(1.0));
+        assert_eq!(model.read_await_ms, Some(2.0));
+        // No writes completed during the interval
+        assert_eq!(model.write_await_ms, Some(0.0));
+        assert_eq!(model.avg_request_size, Some(4096.0));
+        assert_eq!(model.ios_in_progress, Some(3));
+    }
+
+    #[test]
+    fn exitstat_health() {
+        let health = |status, lost_events| procfs::ExitstatHealth {
+            status: Some(status),
+            lost_events: Some(lost_events),
+            dropped_events: Some(0),
+        };
+        let full = health(procfs::ExitstatStatus::Full, 10);
+
+        let model = ExitstatModel::new(Some(&full), Some(&full));
+        assert_eq!(model.status.as_deref(), Some("full"));
+        assert_eq!(model.lost_events, Some(0));
+        assert_eq!(model.health.as_deref(), Some("ok"));
+
+        let model =
+            ExitstatModel::new(Some(&health(procfs::ExitstatStatus::Full, 15)), Some(&full));
+        assert_eq!(model.lost_events, Some(5));
+        assert_eq!(model.health.as_deref(), Some("degraded"));
+
+        // below restarted in between
+        let model = ExitstatModel::new(
+            Some(&health(procfs::ExitstatStatus::Reduced, 2)),
+            Some(&full),
+        );
+        assert_eq!(model.lost_events, Some(2));
+        assert_eq!(model.health.as_deref(), Some("degraded"));
+
+        assert_eq!(ExitstatModel::new(None, Some(&full)), Default::default());
+    }
+
+    #[test]
+    fn disk_io_latency() {
+        let disk = procfs::DiskStat {
+            name: Some("sda".to_owned()),
+            major: Some(8),
+            minor: Some(0),
+            ..Default::default()
+        };
+        let sample = |read: Vec<u64>, write: Vec<u64>| SystemSample {
+            disks: vec![("sda".to_owned(), disk.clone())].into_iter().collect(),
+            disk_io_latency_hist: Some(
+                vec![("8:0".to_owned(), IoLatencyHist { read, write })]
+                    .into_iter()
+                    .collect(),
+            ),
+            ..Default::default()
+        };
+        let begin = sample(vec![0, 10, 0, 0], vec![0, 0, 5, 0]);
+        // 100 reads completed within [2, 4) usec, one write took [8, 16) usec
+        let end = sample(vec![0, 110, 0, 0], vec![0, 0, 5, 1]);
+
+        let model = SystemModel::new(&end, Some((&begin, Duration::from_secs(1))));
+        let sda = &model.disks["sda"];
+        assert_eq!(sda.read_latency_p50_us, Some(4));
+        assert_eq!(sda.read_latency_p99_us, Some(4));
+        assert_eq!(sda.write_latency_p50_us, Some(16));
+        assert_eq!(sda.write_latency_hist, Some(vec![0, 0, 0, 1]));
+
+        // Nothing to diff against
+        let model = SystemModel::new(&end, None);
+        assert_eq!(model.disks["sda"].read_latency_p50_us, None);
+    }
+
+    #[test]
+    fn disk_tree() {
+        let disk = |name: &str, parent: Option<&str>, usage: Option<f32>, size: Option<u64>| {
+            (
+                name.to_string(),
+                procfs::DiskStat {
+                    name: Some(name.into()),
+                    parent: parent.map(|p| p.into()),
+                    disk_usage: usage,
+                    partition_size: size,
+                    ..Default::default()
+                },
+            )
+        };
+        let sample = SystemSample {
+            disks: vec![
+                disk("nvme0n1", None, None, None),
+                disk("nvme0n10", None, None, None),
+                disk("nvme0n1p1", Some("nvme0n1"), Some(50.0), Some(100)),
+                disk("nvme0n1p2", Some("nvme0n1"), Some(20.0), Some(400)),
+                disk("nvme0n10p1", Some("nvme0n10"), None, None),
+            ]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        };
+        let model = SystemModel::new(&sample, None);
+        let names: Vec<_> = model
+            .disks_tree()
+            .into_iter()
+            .map(|d| d.name.clone().unwrap())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "nvme0n1",
+                "nvme0n1p1",
+                "nvme0n1p2",
+                "nvme0n10",
+                "nvme0n10p1"
+            ]
+        );
+        let nvme0n1 = &model.disks["nvme0n1"];
+        assert_eq!(nvme0n1.partition_size, Some(500));
+        assert_eq!(nvme0n1.disk_usage, Some(26.0));
+        assert_eq!(nvme0n1.get_depth(), 0);
+        assert_eq!(model.disks["nvme0n1p1"].get_depth(), 1);
+        assert_eq!(model.disks["nvme0n10"].partition_size, None);
+    }
+
+    #[test]
+    fn disk_tree_without_parents() {
+        let disk = |name: &str, major: u64, minor: u64| {
+            (
+                name.to_string(),
+                procfs::DiskStat {
+                    name: Some(name.into()),
+                    major: Some(major),
+                    minor: Some(minor),
+                    ..Default::default()
+                },
+            )
+        };
+        // Recorded before partition parents were
+        let sample = SystemSample {
+            disks: vec![disk("sda", 8, 0), disk("sda1", 8, 1), disk("vda", 252, 0)]
+                .into_iter()
+                .collect(),
+            ..Default::default()
+        };
+        let model = SystemModel::new(&sample, None);
+        assert_eq!(model.disks["sda"].get_depth(), 0);
+        assert_eq!(model.disks["sda1"].parent.as_deref(), Some("sda"));
+        assert_eq!(model.disks["sda1"].get_depth(), 1);
+        assert_eq!(model.disks["vda"].get_depth(), 0);
+    }
+
+    #[test]
+    fn nfs_op_rates() {
+        let op = |ops, transmissions, rtt_ms, exec_ms| procfs::NfsOpStat {
+            ops: Some(ops),
+            transmissions: Some(transmissions),
+            major_timeouts: Some(0),
+            rtt_ms: Some(rtt_ms),
+            exec_ms: Some(exec_ms),
+            ..Default::default()
+        };
+        let begin = procfs::NfsMountStat {
+            mount_point: Some("/mnt/build".into()),
+            normal_read_bytes: Some(1000),
+            direct_read_bytes: Some(0),
+            ops: Some(
+                vec![
+                    ("READ".to_string(), op(100, 100, 1000, 1200)),
+                    ("GETATTR".to_string(), op(10, 10, 10, 10)),
+                ]
+                .into_iter()
+                .collect(),
+            ),
+            ..Default::default()
+        };
+        let end = procfs::NfsMountStat {
+            mount_point: Some("/mnt/build".into()),
+            normal_read_bytes: Some(5000),
+            direct_read_bytes: Some(2000),
+            ops: Some(
+                vec![
+                    ("READ".to_string(), op(120, 124, 1100, 1400)),
+                    ("GETATTR".to_string(), op(30, 30, 30, 50)),
+                ]
+                .into_iter()
+                .collect(),
+            ),
+            ..Default::default()
+        };
+        let model = SingleNfsModel::new(Some((&begin, Duration::from_secs(2))), &end);
+        assert_eq!(model.read_bytes_per_sec, Some(3000.0));
+        assert_eq!(model.ops_per_sec, Some(20.0));
+        assert_eq!(model.retrans_per_sec, Some(2.0));
+        assert_eq!(model.read.ops_per_sec, Some(10.0));
+        assert_eq!(model.read.retrans_per_sec, Some(2.0));
+        assert_eq!(model.read.avg_rtt_ms, Some(5.0));
+        assert_eq!(model.read.avg_exec_ms, Some(10.0));
+        assert_eq!(model.getattr.avg_exec_ms, Some(2.0));
+        // No WRITE in the per-op statistics
+        assert_eq!(model.write, NfsOpModel::default());
+    }
+
+    #[test]
+    fn cifs_rates() {
+        let op = |total, failed| procfs::CifsOpStat {
+            total: Some(total),
+            failed: Some(failed),
+        };
+        let begin = procfs::CifsShareStat {
+            share: Some(r"\\filer\build".into()),
+            smbs: Some(100),
+            bytes_read: Some(1000),
+            bytes_written: Some(0),
+            open_files: Some(1),
+            ops: Some(
+                vec![
+                    ("Reads".to_string(), op(10, 0)),
+                    ("Creates".to_string(), op(5, 1)),
+                    ("Closes".to_string(), op(4, 0)),
+                ]
+                .into_iter()
+                .collect(),
+            ),
+        };
+        let end = procfs::CifsShareStat {
+            share: Some(r"\\filer\build".into()),
+            smbs: Some(140),
+            bytes_read: Some(9000),
+            bytes_written: Some(2000),
+            open_files: Some(2),
+            ops: Some(
+                vec![
+                    ("Reads".to_string(), op(30, 0)),
+                    ("Creates".to_string(), op(9, 3)),
+                    ("Closes".to_string(), op(6, 2)),
+                ]
+                .into_iter()
+                .collect(),
+            ),
+        };
+        let model = SingleCifsModel::new(Some((&begin, Duration::from_secs(2))), &end);
+        assert_eq!(model.smbs_per_sec, Some(20.0));
+        assert_eq!(model.read_bytes_per_sec, Some(4000.0));
+        assert_eq!(model.write_bytes_per_sec, Some(1000.0));
+        assert_eq!(model.reads_per_sec, Some(10.0));
+        // No Writes line for this share
+        assert_eq!(model.writes_per_sec, None);
+        assert_eq!(model.failed_ops_per_sec, Some(2.0));
+        assert_eq!(model.open_files, Some(2));
+    }
+
+    #[test]
+    fn fs_usage() {
+        let fs_stat = procfs::FsStat {
+            mount_point: Some("/var".into()),
+            total_bytes: Some(1000),
+            free_bytes: Some(300),
+            avail_bytes: Some(100),
+            total_inodes: Some(50),
+            free_inodes: Some(0),
+            avail_inodes: Some(0),
+            ..Default::default()
+        };
+        let model = SingleFsModel::new(&fs_stat);
+        assert_eq!(model.used_bytes, Some(700));
+        assert_eq!(model.usage_pct, Some(87.5));
+        assert_eq!(model.used_inodes, Some(50));
+        assert_eq!(model.inode_usage_pct, Some(100.0));
+    }
+
+    #[test]
+    fn cpu_sysfs_stats() {
+        let idle_state = |name: &str, time_usec, usage| procfs::CpuIdleStateStat {
+            name: Some(name.into()),
+            time_usec: Some(time_usec),
+            usage: Some(usage),
+        };
+        let begin = procfs::CpuSysfsStat {
+            scaling_cur_freq_khz: Some(3_000_000),
+            core_throttle_count: Some(10),
+            package_throttle_count: Some(0),
+            idle_states: Some(vec![
+                idle_state("POLL", 0, 0),
+                idle_state("C1", 1_000_000, 100),
+            ]),
+        };
+        let end = procfs::CpuSysfsStat {
+            scaling_cur_freq_khz: Some(1_200_000),
+            core_throttle_count: Some(30),
+            package_throttle_count: Some(0),
+            idle_states: Some(vec![
+                idle_state("POLL", 100_000, 20),
+                idle_state("C1", 2_000_000, 500),
+            ]),
+        };
+        let mut model = SingleCpuModel::default();
+        model.add_sysfs_stats(Some((&begin, Duration::from_secs(2))), &end);
+        assert_eq!(model.freq_mhz, Some(1200));
+        assert_eq!(model.core_throttle_per_sec, Some(10.0));
+        assert_eq!(model.package_throttle_per_sec, Some(0.0));
+        assert_eq!(model.idle_states["POLL"].residency_pct, Some(5.0));
+        assert_eq!(model.idle_states["C1"].residency_pct, Some(50.0));
+        assert_eq!(model.idle_states["C1"].usage_per_sec, Some(200.0));
+    }
+
+    #[test]
+    fn cpu_sysfs_offline_cpu() {
+        let cpu_stat = |user_usec| procfs::CpuStat {
+            user_usec: Some(user_usec),
+            ..Default::default()
+        };
+        let freq = |khz| procfs::CpuSysfsStat {
+            scaling_cur_freq_khz: Some(khz),
+            ..Default::default()
+        };
+        // cpu1 is offline, so cpu2 comes second in /proc/stat
+        let sample = SystemSample {
+            stat: procfs::Stat {
+                cpus: Some(vec![cpu_stat(100), cpu_stat(200)]),
+                cpu_ids: Some(vec![0, 2]),
+                ..Default::default()
+            },
+            cpu_sysfs: Some(
+                vec![(0, freq(1_000_000)), (1, freq(0)), (2, freq(2_000_000))]
+                    .into_iter()
+                    .collect(),
+            ),
+            ..Default::default()
+        };
+
+        let model = SystemModel::new(&sample, Some((&sample, Duration::from_secs(1))));
+        assert_eq!(model.cpus[1].freq_mhz, Some(2000));
+    }
+
+    #[test]
+    fn swap_breakdown() {
+        let zswap = |stored_bytes, reject_compress_poor| procfs::ZswapStat {
+            pool_total_size_bytes: Some(1 << 20),
+            stored_bytes: Some(stored_bytes),
+            reject_alloc_fail: Some(1),
+            reject_compress_poor: Some(reject_compress_poor),
+            ..Default::default()
+        };
+        let begin = SystemSample {
+            zswap: Some(zswap(1 << 20, 10)),
+            ..Default::default()
+        };
+        let end = SystemSample {
+            swaps: Some(vec![
+                procfs::SwapDeviceStat {
+                    filename: Some("/dev/zram0".into()),
+                    size_bytes: Some(4 << 30),
+                    used_bytes: Some(1 << 30),
+                    ..Default::default()
+                },
+                procfs::SwapDeviceStat {
+                    filename: Some("/swapfile".into()),
+                    size_bytes: Some(4 << 30),
+                    used_bytes: Some(0),
+                    ..Default::default()
+                },
+            ]),
+            zram: Some(
+                vec![(
+                    "zram0".to_string(),
+                    procfs::ZramStat {
+                        orig_data_size: Some(1 << 30),
+                        compr_data_size: Some(1 << 28),
+                        ..Default::default()
+                    },
+                )]
+                .into_iter()
+                .collect(),
+            ),
+            zswap: Some(zswap(3 << 20, 30)),
+            ..Default::default()
+        };
+        let swap = SwapModel::new(&end, Some((&begin, Duration::from_secs(2))));
+        assert_eq!(swap.total_bytes, Some(8 << 30));
+        assert_eq!(swap.used_bytes, Some(1 << 30));
+        assert_eq!(swap.devices[0].usage_pct, Some(25.0));
+        assert_eq!(swap.zram["zram0"].compression_ratio, Some(4.0));
+        // No io_stat in either sample
+        assert_eq!(swap.zram["zram0"].failed_reads_per_sec, None);
+        assert_eq!(swap.zswap.compression_ratio, Some(3.0));
+        assert_eq!(swap.zswap.reject_per_sec, Some(10.0));
+    }
+
+    #[test]
+    fn vm_counters() {
+        let vmstat = |compact_stall, nr_free_pages| procfs::VmStat {
+            counters: Some(
+                vec![
+                    ("compact_stall".to_string(), compact_stall),
+                    ("nr_free_pages".to_string(), nr_free_pages),
+                ]
+                .into_iter()
+                .collect(),
+            ),
+            ..Default::default()
+        };
+        let model = VmModel::new(
+            &vmstat(100, 5000),
+            &vmstat(160, 4000),
+            Duration::from_secs(2),
+        );
+        assert_eq!(model.counters["compact_stall"].value, Some(160));
+        assert_eq!(model.counters["compact_stall"].per_sec, Some(30.0));
+        assert_eq!(model.counters["nr_free_pages"].value, Some(4000));
+        assert_eq!(model.counters["nr_free_pages"].per_sec, None);
+
+        let field_id = VmModelFieldId::from_str("counters.compact_stall.per_sec")
+            .expect("Failed to parse vmstat counter field id");
+        assert_eq!(model.query(&field_id), Some(Field::F64(30.0)));
+    }
+
+    #[test]
+    fn kernel_tables_usage() {
+        let tables = procfs::KernelTableStat {
+            file_handles_allocated: Some(750),
+            file_handles_max: Some(1000),
+            nr_threads: Some(2000),
+            pid_max: Some(32768),
+            threads_max: Some(4000),
+            entropy_avail: Some(256),
+            entropy_poolsize: Some(0),
+            ..Default::default()
+        };
+        let model = KernelTablesModel::new(&tables);
+        assert_eq!(model.file_handles_pct, Some(75.0));
+        assert_eq!(model.pid_usage_pct, Some(2000.0 * 100.0 / 32768.0));
+        assert_eq!(model.threads_usage_pct, Some(50.0));
+        assert_eq!(model.entropy_pct, None);
+    }
 }
@@ -17,11 +17,13 @@ use nix::sys;
 use openat::Dir;
 use std::collections::BTreeMap;
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::fs::File;
 use std::io::{BufRead, BufReader, ErrorKind, Read};
 use std::path::{Path, PathBuf};
-use std::sync::mpsc::{channel, RecvTimeoutError};
-use std::time::Duration;
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use lazy_static::lazy_static;
 use thiserror::Error;
@@ -35,6 +37,34 @@ mod test;
 
 pub const NET_SYSFS: &str = "/sys/class/net/";
 pub const NET_PROCFS: &str = "/proc/net";
+pub const SYSFS: &str = "/sys";
+
+/// Filesystems without backing storage. Their statvfs is either all zeros or,
+/// for autofs, would trigger the mount.
+const PSEUDO_FS_TYPES: &[&str] = &[
+    "autofs",
+    "binfmt_misc",
+    "bpf",
+    "cgroup",
+    "cgroup2",
+    "configfs",
+    "debugfs",
+    "devpts",
+    "efivarfs",
+    "fusectl",
+    "mqueue",
+    "nsfs",
+    "proc",
+    "pstore",
+    "rpc_pipefs",
+    "securityfs",
+    "selinuxfs",
+    "sysfs",
+    "tracefs",
+];
+
+/// Filesystems whose statvfs goes over the network and may hang.
+const NETWORK_FS_TYPES: &[&str] = &["nfs", "nfs4", "cifs", "smb3", "ceph", "glusterfs", "9p"];
 
 lazy_static! {
     /// The number of microseconds per clock tick
@@ -139,15 +169,24 @@ macro_rules! parse_kb {
 
 pub struct ProcReader {
     path: PathBuf,
+    sysfs_path: PathBuf,
     threadpool: ThreadPool,
+    /// statvfs calls on network filesystems get their own threads so that
+    /// hung mounts can't hold up the cmdline reads
+    fs_stat_threadpool: ThreadPool,
+    /// Mount points with a statvfs call still in flight
+    fs_stat_in_flight: Arc<Mutex<HashSet<String>>>,
 }
 
 impl ProcReader {
     pub fn new() -> ProcReader {
         ProcReader {
             path: Path::new("/proc").to_path_buf(),
+            sysfs_path: Path::new(SYSFS).to_path_buf(),
             // 5 threads max
             threadpool: ThreadPool::with_name("procreader_worker".to_string(), 5),
+            fs_stat_threadpool: ThreadPool::with_name("procreader_fs_worker".to_string(), 5),
+            fs_stat_in_flight: Default::default(),
         }
     }
 
@@ -157,6 +196,13 @@ impl ProcReader {
         reader
     }
 
+    pub fn new_with_custom_paths(procfs_path: PathBuf, sysfs_path: PathBuf) -> ProcReader {
+        let mut reader = ProcReader::new();
+        reader.path = procfs_path;
+        reader.sysfs_path = sysfs_path;
+        reader
+    }
+
     fn read_uptime_secs(&self) -> Result<u64> {
         let path = self.path.join("uptime");
         let file = File::open(&path).map_err(|e| Error::IoError(path.clone(), e))?;
@@ -175,6 +221,101 @@ impl ProcReader {
         }
     }
 
+    /// Read a sysfs attribute. Attributes that don't exist on this machine
+    /// (e.g. no cpufreq driver in a VM) are None.
+    fn read_sysfs_str(path: &Path) -> Result<Option<String>> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => Ok(Some(content.trim().to_string())),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(Error::IoError(path.to_path_buf(), e)),
+        }
+    }
+
+    /// Read a sysfs attribute holding a single number.
+    fn read_sysfs_u64(path: &Path) -> Result<Option<u64>> {
+        match Self::read_sysfs_str(path)? {
+            Some(content) => parse_item!(path, Some(content.as_str()), u64, content),
+            None => Ok(None),
+        }
+    }
+
+    /// Like `read_sysfs_u64`, but an attribute that exists and can't be read
+    /// only loses its own value. The error is added to `errors`.
+    fn read_sysfs_u64_or_none(path: &Path, errors: &mut Vec<Error>) -> Option<u64> {
+        Self::read_sysfs_u64(path).unwrap_or_else(|e| {
+            errors.push(e);
+            None
+        })
+    }
+
+    fn read_cpu_idle_states(
+        cpuidle_path: &Path,
+        errors: &mut Vec<Error>,
+    ) -> Option<Vec<CpuIdleStateStat>> {
+        let mut idle_states = Vec::new();
+        // States are numbered from 0 without gaps
+        for state_idx in 0.. {
+            let state_path = cpuidle_path.join(format!("state{}", state_idx));
+            if !state_path.exists() {
+                break;
+            }
+            idle_states.push(CpuIdleStateStat {
+                name: std::fs::read_to_string(state_path.join("name"))
+                    .ok()
+                    .map(|name| name.trim().to_string()),
+                time_usec: Self::read_sysfs_u64_or_none(&state_path.join("time"), errors),
+                usage: Self::read_sysfs_u64_or_none(&state_path.join("usage"), errors),
+            });
+        }
+        if idle_states.is_empty() {
+            None
+        } else {
+            Some(idle_states)
+        }
+    }
+
+    /// Frequency, thermal throttling and idle state counters of each CPU,
+    /// keyed by CPU number. A file that can't be read only leaves its own
+    /// field None, and its error is returned along with the stats. Files
+    /// this machine doesn't have are not errors.
+    pub fn read_cpu_sysfs_stats(&self) -> Result<(CpuSysfsMap, Vec<Error>)> {
+        let path = self.sysfs_path.join("devices/system/cpu");
+        let entries = std::fs::read_dir(&path).map_err(|e| Error::IoError(path.clone(), e))?;
+        let mut cpu_map: CpuSysfsMap = Default::default();
+        let mut errors = Vec::new();
+
+        for entry in entries {
+            let entry = entry.map_err(|e| Error::IoError(path.clone(), e))?;
+            let cpu = match entry
+                .file_name()
+                .to_str()
+                .and_then(|name| name.strip_prefix("cpu"))
+                .and_then(|idx| idx.parse::<u32>().ok())
+            {
+                Some(cpu) => cpu,
+                // Not a CPU, e.g. cpufreq or cpuidle
+                None => continue,
+            };
+            let cpu_path = entry.path();
+            // e.g. EIO from throttle counters of a CPU going offline
+            let mut read_u64 =
+                |name: &str| Self::read_sysfs_u64_or_none(&cpu_path.join(name), &mut errors);
+            let stat = CpuSysfsStat {
+                scaling_cur_freq_khz: read_u64("cpufreq/scaling_cur_freq"),
+                core_throttle_count: read_u64("thermal_throttle/core_throttle_count"),
+                package_throttle_count: read_u64("thermal_throttle/package_throttle_count"),
+                idle_states: Self::read_cpu_idle_states(&cpu_path.join("cpuidle"), &mut errors),
+            };
+            cpu_map.insert(cpu, stat);
+        }
+
+        if cpu_map.is_empty() {
+            Err(Error::InvalidFileFormat(path))
+        } else {
+            Ok((cpu_map, errors))
+        }
+    }
+
     fn process_cpu_stat(path: &PathBuf, line: &String) -> Result<CpuStat> {
         //Format is like "cpu9 6124418 452468 3062529 230073290 216237 0 45647 0 0 0"
         let mut items = line.split_whitespace();
@@ -205,12 +346,71 @@ impl ProcReader {
         }
     }
 
+    /// Read a file of whitespace separated numbers, e.g. /proc/sys/fs/file-nr.
+    /// None if the file doesn't exist.
+    fn read_u64_items(path: &Path) -> Result<Option<Vec<u64>>> {
+        match Self::read_sysfs_str(path)? {
+            Some(line) => {
+                let mut items = Vec::new();
+                for item in line.split_whitespace() {
+                    items.extend(parse_item!(path, Some(item), u64, line)?);
+                }
+                Ok(Some(items))
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn read_kernel_tables(&self) -> Result<KernelTableStat> {
+        let path = self.path.join("sys");
+        let file_nr = Self::read_u64_items(&path.join("fs/file-nr"))?.unwrap_or_default();
+        let inode_nr = Self::read_u64_items(&path.join("fs/inode-nr"))?.unwrap_or_default();
+        let dentry_state = Self::read_u64_items(&path.join("fs/dentry-state"))?.unwrap_or_default();
+
+        // Format is "0.20 0.18 0.12 1/80 11206", where 80 is the number of
+        // threads
+        let loadavg_path = self.path.join("loadavg");
+        let nr_threads = match Self::read_sysfs_str(&loadavg_path)? {
+            Some(line) => parse_item!(
+                loadavg_path,
+                line.split_whitespace()
+                    .nth(3)
+                    .and_then(|entities| entities.split('/').nth(1)),
+                u64,
+                line
+            )?,
+            None => None,
+        };
+
+        let tables = KernelTableStat {
+            // file-nr is "allocated free max" where free is always 0 since 2.6
+            file_handles_allocated: file_nr.get(0).copied(),
+            file_handles_max: file_nr.get(2).copied(),
+            inodes_allocated: inode_nr.get(0).copied(),
+            inodes_free: inode_nr.get(1).copied(),
+            dentries: dentry_state.get(0).copied(),
+            dentries_unused: dentry_state.get(1).copied(),
+            nr_threads,
+            pid_max: Self::read_sysfs_u64(&path.join("kernel/pid_max"))?,
+            threads_max: Self::read_sysfs_u64(&path.join("kernel/threads-max"))?,
+            entropy_avail: Self::read_sysfs_u64(&path.join("kernel/random/entropy_avail"))?,
+            entropy_poolsize: Self::read_sysfs_u64(&path.join("kernel/random/poolsize"))?,
+        };
+
+        if tables == Default::default() {
+            Err(Error::InvalidFileFormat(path))
+        } else {
+            Ok(tables)
+        }
+    }
+
     pub fn read_stat(&self) -> Result<Stat> {
         let path = self.path.join("stat");
         let file = File::open(&path).map_err(|e| Error::IoError(path.clone(), e))?;
         let buf_reader = BufReader::new(file);
         let mut stat: Stat = Default::default();
         let mut cpus = Vec::new();
+        let mut cpu_ids = Vec::new();
         for line in buf_reader.lines() {
             let line = line.map_err(|e| Error::IoError(path.clone(), e))?;
 
@@ -236,8 +436,9 @@ impl ProcReader {
                     x => {
                         if x == "cpu" {
                             stat.total_cpu = Some(Self::process_cpu_stat(&path, &line)?);
-                        } else if x.starts_with("cpu") {
+                        } else if let Some(cpu_id) = x.strip_prefix("cpu") {
                             cpus.push(Self::process_cpu_stat(&path, &line)?);
+                            cpu_ids.extend(parse_item!(&path, Some(cpu_id), u32, line)?);
                         }
                     }
                 }
@@ -245,6 +446,7 @@ impl ProcReader {
         }
         if !cpus.is_empty() {
             stat.cpus = Some(cpus);
+            stat.cpu_ids = Some(cpu_ids);
         }
 
         if stat == Default::default() {
@@ -339,39 +541,147 @@ impl ProcReader {
         }
     }
 
+    /// Swap areas from /proc/swaps in the order the kernel lists them. Empty
+    /// if swap is off.
+    pub fn read_swaps(&self) -> Result<Vec<SwapDeviceStat>> {
+        let path = self.path.join("swaps");
+        let file = File::open(&path).map_err(|e| Error::IoError(path.clone(), e))?;
+        let mut lines = BufReader::new(file).lines();
+
+        // Header is "Filename Type Size Used Priority"
+        match lines.next() {
+            Some(Ok(header)) if header.starts_with("Filename") => {}
+            Some(Err(e)) => return Err(Error::IoError(path, e)),
+            _ => return Err(Error::InvalidFileFormat(path)),
+        }
+
+        let mut swaps = Vec::new();
+        for line in lines {
+            let line = line.map_err(|e| Error::IoError(path.clone(), e))?;
+            let mut items = line.split_whitespace();
+            swaps.push(SwapDeviceStat {
+                filename: items.next().map(|s| s.to_string()),
+                swap_type: items.next().map(|s| s.to_string()),
+                size_bytes: parse_kb!(path, items.next(), line)?,
+                used_bytes: parse_kb!(path, items.next(), line)?,
+                priority: parse_item!(path, items.next(), i64, line)?,
+            });
+        }
+        Ok(swaps)
+    }
+
+    /// Stats of zram devices under /sys/block, keyed by device name. Empty if
+    /// there are none.
+    pub fn read_zram_stats(&self) -> Result<ZramMap> {
+        let path = self.sysfs_path.join("block");
+        let entries = std::fs::read_dir(&path).map_err(|e| Error::IoError(path.clone(), e))?;
+        let mut zram_map: ZramMap = Default::default();
+
+        for entry in entries {
+            let entry = entry.map_err(|e| Error::IoError(path.clone(), e))?;
+            let name = match entry.file_name().into_string() {
+                Ok(name) if name.starts_with("zram") => name,
+                _ => continue,
+            };
+            let mut zram: ZramStat = Default::default();
+
+            // Format is "orig_data_size compr_data_size mem_used_total mem_limit
+            // mem_used_max same_pages pages_compacted huge_pages [huge_pages_since]".
+            // Older kernels have fewer columns.
+            let mm_stat_path = entry.path().join("mm_stat");
+            if let Some(line) = Self::read_sysfs_str(&mm_stat_path)? {
+                let mut items = line.split_whitespace();
+                zram.orig_data_size = parse_item!(mm_stat_path, items.next(), u64, line)?;
+                zram.compr_data_size = parse_item!(mm_stat_path, items.next(), u64, line)?;
+                zram.mem_used_total = parse_item!(mm_stat_path, items.next(), u64, line)?;
+                zram.mem_limit = parse_item!(mm_stat_path, items.next(), u64, line)?;
+                zram.mem_used_max = parse_item!(mm_stat_path, items.next(), u64, line)?;
+                zram.same_pages = parse_item!(mm_stat_path, items.next(), u64, line)?;
+                zram.pages_compacted = parse_item!(mm_stat_path, items.next(), u64, line)?;
+                zram.huge_pages = parse_item!(mm_stat_path, items.next(), u64, line)?;
+            }
+
+            // Format is "failed_reads failed_writes invalid_io notify_free"
+            let io_stat_path = entry.path().join("io_stat");
+            if let Some(line) = Self::read_sysfs_str(&io_stat_path)? {
+                let mut items = line.split_whitespace();
+                zram.failed_reads = parse_item!(io_stat_path, items.next(), u64, line)?;
+                zram.failed_writes = parse_item!(io_stat_path, items.next(), u64, line)?;
+                zram.invalid_io = parse_item!(io_stat_path, items.next(), u64, line)?;
+                zram.notify_free = parse_item!(io_stat_path, items.next(), u64, line)?;
+            }
+
+            zram_map.insert(name, zram);
+        }
+        Ok(zram_map)
+    }
+
+    /// zswap stats from debugfs. None if zswap isn't loaded or debugfs is not
+    /// mounted or not readable by us.
+    pub fn read_zswap_stats(&self) -> Result<Option<ZswapStat>> {
+        let path = self.sysfs_path.join("kernel/debug/zswap");
+        match std::fs::read_dir(&path) {
+            Ok(_) => {}
+            Err(e)
+                if e.kind() == ErrorKind::NotFound || e.kind() == ErrorKind::PermissionDenied =>
+            {
+                return Ok(None);
+            }
+            Err(e) => return Err(Error::IoError(path, e)),
+        }
+
+        Ok(Some(ZswapStat {
+            pool_total_size_bytes: Self::read_sysfs_u64(&path.join("pool_total_size"))?,
+            stored_bytes: Self::read_sysfs_u64(&path.join("stored_pages"))?
+                .map(|pages| pages * *PAGE_SIZE),
+            written_back_pages: Self::read_sysfs_u64(&path.join("written_back_pages"))?,
+            pool_limit_hit: Self::read_sysfs_u64(&path.join("pool_limit_hit"))?,
+            reject_reclaim_fail: Self::read_sysfs_u64(&path.join("reject_reclaim_fail"))?,
+            reject_alloc_fail: Self::read_sysfs_u64(&path.join("reject_alloc_fail"))?,
+            reject_kmemcache_fail: Self::read_sysfs_u64(&path.join("reject_kmemcache_fail"))?,
+            reject_compress_poor: Self::read_sysfs_u64(&path.join("reject_compress_poor"))?,
+            duplicate_entry: Self::read_sysfs_u64(&path.join("duplicate_entry"))?,
+        }))
+    }
+
     pub fn read_vmstat(&self) -> Result<VmStat> {
         let path = self.path.join("vmstat");
         let file = File::open(&path).map_err(|e| Error::IoError(path.clone(), e))?;
         let buf_reader = BufReader::new(file);
         let mut vmstat: VmStat = Default::default();
+        let mut counters = BTreeMap::new();
 
         for line in buf_reader.lines() {
             let line = line.map_err(|e| Error::IoError(path.clone(), e))?;
 
             let mut items = line.split_whitespace();
             if let Some(item) = items.next() {
+                // Skip what doesn't parse rather than losing the whole file,
+                // e.g. gauges that drift below zero on some kernels
+                let value = match parse_item!(path, items.next(), u64, line) {
+                    Ok(value) => value,
+                    Err(_) => continue,
+                };
                 match item {
-                    "pgpgin" => vmstat.pgpgin = parse_item!(path, items.next(), u64, line)?,
-                    "pgpgout" => vmstat.pgpgout = parse_item!(path, items.next(), u64, line)?,
-                    "pswpin" => vmstat.pswpin = parse_item!(path, items.next(), u64, line)?,
-                    "pswpout" => vmstat.pswpout = parse_item!(path, items.next(), u64, line)?,
-                    "pgsteal_kswapd" => {
-                        vmstat.pgsteal_kswapd = parse_item!(path, items.next(), u64, line)?
-                    }
-                    "pgsteal_direct" => {
-                        vmstat.pgsteal_direct = parse_item!(path, items.next(), u64, line)?
-                    }
-                    "pgscan_kswapd" => {
-                        vmstat.pgscan_kswapd = parse_item!(path, items.next(), u64, line)?
-                    }
-                    "pgscan_direct" => {
-                        vmstat.pgscan_direct = parse_item!(path, items.next(), u64, line)?
-                    }
-                    "oom_kill" => vmstat.oom_kill = parse_item!(path, items.next(), u64, line)?,
+                    "pgpgin" => vmstat.pgpgin = value,
+                    "pgpgout" => vmstat.pgpgout = value,
+                    "pswpin" => vmstat.pswpin = value,
+                    "pswpout" => vmstat.pswpout = value,
+                    "pgsteal_kswapd" => vmstat.pgsteal_kswapd = value,
+                    "pgsteal_direct" => vmstat.pgsteal_direct = value,
+                    "pgscan_kswapd" => vmstat.pgscan_kswapd = value,
+                    "pgscan_direct" => vmstat.pgscan_direct = value,
+                    "oom_kill" => vmstat.oom_kill = value,
                     _ => {}
                 }
+                if let Some(value) = value {
+                    counters.insert(item.to_string(), value);
+                }
             }
         }
+        if !counters.is_empty() {
+            vmstat.counters = Some(counters);
+        }
 
         if vmstat == Default::default() {
             Err(Error::InvalidFileFormat(path))
@@ -392,6 +702,307 @@ impl ProcReader {
         None
     }
 
+    fn read_fs_stat_blocking(mount_info: &MountInfo) -> Option<FsStat> {
+        let stat = sys::statvfs::statvfs(Path::new(mount_info.mount_point.as_ref()?)).ok()?;
+        // Filesystems with no blocks don't hold data, e.g. nsfs
+        if stat.blocks() == 0 {
+            return None;
+        }
+        let fragment_size = stat.fragment_size() as u64;
+        Some(FsStat {
+            mount_point: mount_info.mount_point.clone(),
+            mount_source: mount_info.mount_source.clone(),
+            fs_type: mount_info.fs_type.clone(),
+            total_bytes: Some(stat.blocks() as u64 * fragment_size),
+            free_bytes: Some(stat.blocks_free() as u64 * fragment_size),
+            avail_bytes: Some(stat.blocks_available() as u64 * fragment_size),
+            total_inodes: Some(stat.files() as u64),
+            free_inodes: Some(stat.files_free() as u64),
+            avail_inodes: Some(stat.files_available() as u64),
+        })
+    }
+
+    /// Starts the statvfs call of a mount and returns where its result will
+    /// be sent. Nothing is sent if the mount is skipped.
+    ///
+    /// statvfs on a network filesystem needs a round trip to the server and
+    /// blocks for as long as the server is unresponsive. Do those off-thread
+    /// so that a hung mount only costs us its own stats. A mount is skipped
+    /// while its previous call is still in flight, so hung mounts occupy at
+    /// most one thread each instead of piling up calls.
+    fn start_fs_stat(&self, mount_info: MountInfo) -> Receiver<Option<FsStat>> {
+        let (tx, rx) = channel();
+        let fs_type = mount_info.fs_type.as_deref().unwrap_or_default();
+        if !NETWORK_FS_TYPES.contains(&fs_type) && !fs_type.starts_with("fuse.") {
+            let _ = tx.send(Self::read_fs_stat_blocking(&mount_info));
+            return rx;
+        }
+        let mount_point = match mount_info.mount_point.clone() {
+            Some(mount_point) => mount_point,
+            None => return rx,
+        };
+        if !self
+            .fs_stat_in_flight
+            .lock()
+            .expect("fs stat lock poisoned")
+            .insert(mount_point.clone())
+        {
+            return rx;
+        }
+        let in_flight = self.fs_stat_in_flight.clone();
+        self.fs_stat_threadpool.execute(move || {
+            let fs_stat = Self::read_fs_stat_blocking(&mount_info);
+            in_flight
+                .lock()
+                .expect("fs stat lock poisoned")
+                .remove(&mount_point);
+            // Receiver is gone if we already timed out
+            let _ = tx.send(fs_stat);
+        });
+        rx
+    }
+
+    /// Capacity and inode usage of every mount backed by storage, keyed by
+    /// mount point. Unlike the disk fsinfo this covers tmpfs, overlay,
+    /// network and bind mounts as well.
+    pub fn read_fs_stats(&self) -> Result<FsMap> {
+        let path = self.path.join("self/mountinfo");
+        let file = File::open(&path).map_err(|e| Error::IoError(path.clone(), e))?;
+        let buf_reader = BufReader::new(file);
+        let mut pending = Vec::new();
+
+        for line in buf_reader.lines() {
+            let line = line.map_err(|e| Error::IoError(path.clone(), e))?;
+            let mount_info = match self.process_mount_info(&path, &line) {
+                Ok(mount_info) => mount_info,
+                Err(_) => continue,
+            };
+            match mount_info.fs_type.as_deref() {
+                Some(fs_type) if !PSEUDO_FS_TYPES.contains(&fs_type) => {}
+                _ => continue,
+            }
+            let mount_point = match mount_info.mount_point.clone() {
+                Some(mount_point) => mount_point,
+                None => continue,
+            };
+            pending.push((mount_point, self.start_fs_stat(mount_info)));
+        }
+
+        // All network mounts are waited on at once, so hung mounts cost 100ms
+        // in total rather than each
+        let deadline = Instant::now() + Duration::from_millis(100);
+        let mut fs_map: FsMap = Default::default();
+        for (mount_point, rx) in pending {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            // Later entries are mounted on top of earlier ones at the same
+            // mount point, so the last one is what statvfs sees.
+            match rx.recv_timeout(timeout).ok().flatten() {
+                Some(fs_stat) => {
+                    fs_map.insert(mount_point, fs_stat);
+                }
+                None => {
+                    fs_map.remove(&mount_point);
+                }
+            }
+        }
+
+        Ok(fs_map)
+    }
+
+    fn process_nfs_bytes(path: &Path, line: &str, mount_stat: &mut NfsMountStat) -> Result<()> {
+        // Format is like "bytes:	1024 512 0 0 1024 512 1 1"
+        // Parse everything before assigning so a bad line leaves no partial
+        // counters behind
+        let mut items = line.split_whitespace().skip(1);
+        let normal_read = parse_item!(path, items.next(), u64, line)?;
+        let normal_write = parse_item!(path, items.next(), u64, line)?;
+        let direct_read = parse_item!(path, items.next(), u64, line)?;
+        let direct_write = parse_item!(path, items.next(), u64, line)?;
+        let server_read = parse_item!(path, items.next(), u64, line)?;
+        let server_write = parse_item!(path, items.next(), u64, line)?;
+        mount_stat.normal_read_bytes = normal_read;
+        mount_stat.normal_write_bytes = normal_write;
+        mount_stat.direct_read_bytes = direct_read;
+        mount_stat.direct_write_bytes = direct_write;
+        mount_stat.server_read_bytes = server_read;
+        mount_stat.server_write_bytes = server_write;
+        Ok(())
+    }
+
+    fn process_nfs_op(path: &Path, line: &str) -> Result<(String, NfsOpStat)> {
+        // Format is like "READ: 100 101 0 15600 409600 2 150 160 0". The
+        // trailing errors column only exists since kernel 5.3.
+        let mut items = line.split_whitespace();
+        let name = match items.next().and_then(|name| name.strip_suffix(':')) {
+            Some(name) => name.to_string(),
+            None => return Err(Error::UnexpectedLine(path.to_path_buf(), line.to_string())),
+        };
+        let op_stat = NfsOpStat {
+            ops: parse_item!(path, items.next(), u64, line)?,
+            transmissions: parse_item!(path, items.next(), u64, line)?,
+            major_timeouts: parse_item!(path, items.next(), u64, line)?,
+            bytes_sent: parse_item!(path, items.next(), u64, line)?,
+            bytes_recv: parse_item!(path, items.next(), u64, line)?,
+            queue_ms: parse_item!(path, items.next(), u64, line)?,
+            rtt_ms: parse_item!(path, items.next(), u64, line)?,
+            exec_ms: parse_item!(path, items.next(), u64, line)?,
+        };
+        Ok((name, op_stat))
+    }
+
+    /// NFS client stats of every NFS mount, keyed by mount point. CIFS and
+    /// other filesystems don't export statistics through mountstats.
+    pub fn read_mountstats(&self) -> Result<NfsMap> {
+        let path = self.path.join("self/mountstats");
+        let file = File::open(&path).map_err(|e| Error::IoError(path.clone(), e))?;
+        let buf_reader = BufReader::new(file);
+        let mut nfs_map: NfsMap = Default::default();
+        let mut current: Option<NfsMountStat> = None;
+        let mut in_per_op = false;
+
+        for line in buf_reader.lines() {
+            let line = line.map_err(|e| Error::IoError(path.clone(), e))?;
+            let trimmed = line.trim();
+
+            // Each mount starts with a line like
+            // "device srv:/export mounted on /mnt with fstype nfs4 statvers=1.1"
+            if line.starts_with("device ") {
+                if let Some(mount_stat) = current.take() {
+                    if let Some(mount_point) = mount_stat.mount_point.clone() {
+                        nfs_map.insert(mount_point, mount_stat);
+                    }
+                }
+                in_per_op = false;
+                let items: Vec<&str> = line.split_whitespace().collect();
+                match (items.get(1), items.get(4), items.get(7)) {
+                    (Some(device), Some(mount_point), Some(fs_type))
+                        if fs_type.starts_with("nfs") =>
+                    {
+                        current = Some(NfsMountStat {
+                            device: Some(device.to_string()),
+                            mount_point: Some(mount_point.to_string()),
+                            fs_type: Some(fs_type.to_string()),
+                            ..Default::default()
+                        });
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+
+            let mount_stat = match current.as_mut() {
+                Some(mount_stat) => mount_stat,
+                None => continue,
+            };
+            if trimmed.starts_with("bytes:") {
+                // A bad bytes line only costs this mount its byte counters
+                Self::process_nfs_bytes(&path, trimmed, mount_stat).ok();
+            } else if trimmed == "per-op statistics" {
+                in_per_op = true;
+            } else if in_per_op && !trimmed.is_empty() {
+                // An op we can't parse shouldn't cost us the other mounts
+                if let Ok((name, op_stat)) = Self::process_nfs_op(&path, trimmed) {
+                    mount_stat
+                        .ops
+                        .get_or_insert_with(Default::default)
+                        .insert(name, op_stat);
+                }
+            }
+        }
+        if let Some(mount_stat) = current.take() {
+            if let Some(mount_point) = mount_stat.mount_point.clone() {
+                nfs_map.insert(mount_point, mount_stat);
+            }
+        }
+
+        Ok(nfs_map)
+    }
+
+    fn process_cifs_line(path: &Path, line: &str, share_stat: &mut CifsShareStat) -> Result<()> {
+        let items: Vec<&str> = line.split_whitespace().collect();
+        match items.as_slice() {
+            // "SMBs: 20"
+            ["SMBs:", smbs, ..] => {
+                share_stat.smbs = parse_item!(path, Some(smbs), u64, line)?;
+            }
+            // "Bytes read: 1024  Bytes written: 512"
+            ["Bytes", "read:", read, "Bytes", "written:", written, ..] => {
+                let bytes_read = parse_item!(path, Some(read), u64, line)?;
+                share_stat.bytes_written = parse_item!(path, Some(written), u64, line)?;
+                share_stat.bytes_read = bytes_read;
+            }
+            // "Open files: 1 total (local), 1 open on server"
+            ["Open", "files:", open_files, ..] => {
+                share_stat.open_files = parse_item!(path, Some(open_files), u64, line)?;
+            }
+            // "Creates: 5 total 0 failed", or "OplockBreaks: 0 sent 0 failed".
+            // SMB1 shares use a different layout that we don't break down.
+            [name, total, _, failed, "failed"] if name.ends_with(':') => {
+                let op_stat = CifsOpStat {
+                    total: parse_item!(path, Some(total), u64, line)?,
+                    failed: parse_item!(path, Some(failed), u64, line)?,
+                };
+                share_stat
+                    .ops
+                    .get_or_insert_with(Default::default)
+                    .insert(name.trim_end_matches(':').to_string(), op_stat);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// SMB client stats of every mounted CIFS share, keyed by share. None if
+    /// the cifs module isn't loaded.
+    pub fn read_cifs_stats(&self) -> Result<Option<CifsMap>> {
+        let path = self.path.join("fs/cifs/Stats");
+        let file = match File::open(&path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(Error::IoError(path, e)),
+        };
+        let buf_reader = BufReader::new(file);
+        let mut cifs_map: CifsMap = Default::default();
+        let mut current: Option<CifsShareStat> = None;
+
+        for line in buf_reader.lines() {
+            let line = line.map_err(|e| Error::IoError(path.clone(), e))?;
+            let trimmed = line.trim();
+
+            // Each share starts with a line like "1) \\server\share", which
+            // may be followed by "DISCONNECTED"
+            let header = trimmed
+                .split_once(") ")
+                .filter(|(idx, _)| !idx.is_empty() && idx.chars().all(|c| c.is_ascii_digit()))
+                .and_then(|(_, rest)| rest.split_whitespace().next());
+            if let Some(share) = header {
+                if let Some(share_stat) = current.take() {
+                    if let Some(share) = share_stat.share.clone() {
+                        cifs_map.insert(share, share_stat);
+                    }
+                }
+                current = Some(CifsShareStat {
+                    share: Some(share.to_string()),
+                    ..Default::default()
+                });
+                continue;
+            }
+
+            // Lines before the first share are session wide
+            if let Some(share_stat) = current.as_mut() {
+                // A line we can't parse shouldn't cost us the other shares
+                Self::process_cifs_line(&path, trimmed, share_stat).ok();
+            }
+        }
+        if let Some(share_stat) = current.take() {
+            if let Some(share) = share_stat.share.clone() {
+                cifs_map.insert(share, share_stat);
+            }
+        }
+
+        Ok(Some(cifs_map))
+    }
+
     fn read_mount_info_map(&self) -> Result<HashMap<String, MountInfo>> {
         // Map contains a MountInfo object corresponding to the first
         // mount of each mount source. The first mount is what shows in
@@ -440,12 +1051,70 @@ impl ProcReader {
         }
     }
 
+    /// Map each partition to its whole disk. Partitions show up as
+    /// subdirectories of their disk under /sys/block that contain a
+    /// `partition` file.
+    fn read_partition_parents(&self) -> HashMap<String, String> {
+        let mut parents = HashMap::new();
+        let disks = match std::fs::read_dir(self.sysfs_path.join("block")) {
+            Ok(disks) => disks,
+            Err(_) => return parents,
+        };
+        for disk in disks.filter_map(|entry| entry.ok()) {
+            let disk_name = disk.file_name().to_string_lossy().into_owned();
+            let entries = match std::fs::read_dir(disk.path()) {
+                Ok(entries) => entries,
+                Err(_) => continue,
+            };
+            for entry in entries.filter_map(|entry| entry.ok()) {
+                if entry.path().join("partition").exists() {
+                    parents.insert(
+                        entry.file_name().to_string_lossy().into_owned(),
+                        disk_name.clone(),
+                    );
+                }
+            }
+        }
+        parents
+    }
+
+    /// Name given to a device-mapper device, e.g. "vg0-root" for dm-0.
+    fn read_dm_name(&self, disk_name: &str) -> Option<String> {
+        std::fs::read_to_string(
+            self.sysfs_path
+                .join("block")
+                .join(disk_name)
+                .join("dm/name"),
+        )
+        .ok()
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+    }
+
+    /// Devices stacked below a virtual device, e.g. the members of an md
+    /// array or the physical volumes backing a dm device.
+    fn read_disk_slaves(&self, disk_name: &str) -> Option<Vec<String>> {
+        let slaves_dir = self.sysfs_path.join("block").join(disk_name).join("slaves");
+        let mut slaves: Vec<String> = std::fs::read_dir(slaves_dir)
+            .ok()?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .collect();
+        if slaves.is_empty() {
+            None
+        } else {
+            slaves.sort();
+            Some(slaves)
+        }
+    }
+
     pub fn read_disk_stats_and_fsinfo(&self) -> Result<DiskMap> {
         let path = self.path.join("diskstats");
         let file = File::open(&path).map_err(|e| Error::IoError(path.clone(), e))?;
         let buf_reader = BufReader::new(file);
         let mut disk_map: DiskMap = Default::default();
         let mount_info_map = self.read_mount_info_map().unwrap_or_default();
+        let partition_parents = self.read_partition_parents();
 
         for line in buf_reader.lines() {
             let line = line.map_err(|e| Error::IoError(path.clone(), e))?;
@@ -473,19 +1142,38 @@ impl ProcReader {
             disk_stat.write_merged = parse_item!(path, stats_iter.next(), u64, line)?;
             disk_stat.write_sectors = parse_item!(path, stats_iter.next(), u64, line)?;
             disk_stat.time_spend_write_ms = parse_item!(path, stats_iter.next(), u64, line)?;
-            let mut stats_iter = stats_iter.skip(3);
+            disk_stat.ios_in_progress = parse_item!(path, stats_iter.next(), u64, line)?;
+            disk_stat.time_spent_doing_ios_ms = parse_item!(path, stats_iter.next(), u64, line)?;
+            disk_stat.weighted_time_ms = parse_item!(path, stats_iter.next(), u64, line)?;
+            // Discard fields are available since kernel 4.18
             disk_stat.discard_completed = parse_item!(path, stats_iter.next(), u64, line)?;
             disk_stat.discard_merged = parse_item!(path, stats_iter.next(), u64, line)?;
             disk_stat.discard_sectors = parse_item!(path, stats_iter.next(), u64, line)?;
             disk_stat.time_spend_discard_ms = parse_item!(path, stats_iter.next(), u64, line)?;
-
-            let device_path = format!("/dev/{}", disk_name);
-            if let Some(mount_info) = mount_info_map.get(&device_path) {
+            // Flush fields are available since kernel 5.5
+            disk_stat.flush_completed = parse_item!(path, stats_iter.next(), u64, line)?;
+            disk_stat.time_spend_flush_ms = parse_item!(path, stats_iter.next(), u64, line)?;
+
+            disk_stat.parent = partition_parents.get(&disk_name).cloned();
+            disk_stat.dm_name = self.read_dm_name(&disk_name);
+            disk_stat.slaves = self.read_disk_slaves(&disk_name);
+
+            // Device-mapper devices are usually mounted by their /dev/mapper alias
+            let mount_info = mount_info_map
+                .get(&format!("/dev/{}", disk_name))
+                .or_else(|| {
+                    disk_stat
+                        .dm_name
+                        .as_ref()
+                        .and_then(|dm_name| mount_info_map.get(&format!("/dev/mapper/{}", dm_name)))
+                });
+            if let Some(mount_info) = mount_info {
                 if let Some((disk_usage, partition_size)) = self.read_disk_fsinfo(mount_info) {
                     disk_stat.disk_usage = Some(disk_usage);
                     disk_stat.partition_size = Some(partition_size);
                 }
                 disk_stat.filesystem_type = mount_info.fs_type.clone();
+                disk_stat.mount_point = mount_info.mount_point.clone();
             }
 
             disk_map.insert(disk_name, disk_stat);
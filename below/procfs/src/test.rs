@@ -40,7 +40,7 @@ impl TestProcfs {
     }
 
     fn get_reader(&self) -> ProcReader {
-        ProcReader::new_with_custom_procfs(self.path().to_path_buf())
+        ProcReader::new_with_custom_paths(self.path().to_path_buf(), self.path().join("sys"))
     }
 
     fn create_dir<P: AsRef<Path>>(&self, p: P) {
@@ -191,6 +191,7 @@ softirq 15518280031 0 3506477306 138437 1090758178 117192437 0 24543 800441241 9
 
     let cpu23 = &stat.cpus.expect("Failed to read cpus")[23];
     assert_eq!(cpu23.user_usec, Some(59379430000));
+    assert_eq!(stat.cpu_ids, Some((0..24).collect()));
 
     assert_eq!(stat.total_interrupt_count, Some(29638874355));
     assert_eq!(stat.context_switches, Some(48203489122));
@@ -200,6 +201,24 @@ softirq 15518280031 0 3506477306 138437 1090758178 117192437 0 24543 800441241 9
     assert_eq!(stat.blocked_processes, Some(0));
 }
 
+#[test]
+fn test_stat_offline_cpus() {
+    let stat = b"cpu  300 0 0 0 0 0 0 0 0 0
+cpu0 100 0 0 0 0 0 0 0 0 0
+cpu2 200 0 0 0 0 0 0 0 0 0
+ctxt 48203489122
+";
+
+    let procfs = TestProcfs::new();
+    procfs.create_file_with_content("stat", stat);
+    let reader = procfs.get_reader();
+    let stat = reader.read_stat().expect("Failed to read stat file");
+
+    // cpu1 is offline
+    assert_eq!(stat.cpus.map(|cpus| cpus.len()), Some(2));
+    assert_eq!(stat.cpu_ids, Some(vec![0, 2]));
+}
+
 #[test]
 fn test_meminfo_success() {
     let meminfo = b"MemTotal:       58603192 kB
@@ -455,6 +474,35 @@ swap_ra_hit 139012
     assert_eq!(vmstat.pgscan_kswapd, Some(1_743_683_511));
     assert_eq!(vmstat.pgscan_direct, Some(5_877_901));
     assert_eq!(vmstat.oom_kill, Some(0));
+
+    let counters = vmstat.counters.expect("vmstat counters not found");
+    assert_eq!(counters.len(), 132);
+    assert_eq!(counters.get("pgpgin"), Some(&5_245_063_123));
+    assert_eq!(counters.get("allocstall_movable"), Some(&63605));
+    assert_eq!(counters.get("compact_stall"), Some(&80260));
+    assert_eq!(counters.get("thp_fault_fallback"), Some(&397));
+    assert_eq!(counters.get("swap_ra_hit"), Some(&139012));
+}
+
+#[test]
+fn test_vmstat_skips_bad_lines() {
+    let vmstat = b"nr_free_pages 1091519
+nr_zone_write_pending -3
+pgpgin 5245063123
+pgpgout
+";
+    let procfs = TestProcfs::new();
+    procfs.create_file_with_content("vmstat", vmstat);
+    let reader = procfs.get_reader();
+    let vmstat = reader.read_vmstat().expect("Failed to read vmstat file");
+
+    assert_eq!(vmstat.pgpgin, Some(5_245_063_123));
+    assert_eq!(vmstat.pgpgout, None);
+
+    let counters = vmstat.counters.expect("vmstat counters not found");
+    assert_eq!(counters.len(), 2);
+    assert_eq!(counters.get("nr_free_pages"), Some(&1_091_519));
+    assert_eq!(counters.get("nr_zone_write_pending"), None);
 }
 
 #[test]
@@ -475,7 +523,7 @@ fn test_disk_stat() {
     1      13 ram13 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0
     1      14 ram14 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0
     1      15 ram15 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0
-  253       0 vda 187110061 6006969 23225661674 128112391 136557913 12023946 28151760010 615065070 0 107730702 623152538 1 2 3 4
+  253       0 vda 187110061 6006969 23225661674 128112391 136557913 12023946 28151760010 615065070 0 107730702 623152538 1 2 3 4 5 6
   253       1 vda1 15333 522 288946 4125 1707 2227 253642 3073 0 5343 3060 0 0 0 0
   253       2 vda2 1183986 94095 10301816 266679 2457101 1248583 29645480 3253603 0 1556514 2531673 0 0 0 0
   253       3 vda3 185910515 5912352 23215062392 127841533 132254952 10773136 28121859920 611595170 0 106665419 620613687 0 0 0 0
@@ -624,10 +672,15 @@ fn test_disk_stat() {
     assert_eq!(vda_stat.write_merged, Some(12_023_946));
     assert_eq!(vda_stat.write_sectors, Some(28_151_760_010));
     assert_eq!(vda_stat.time_spend_write_ms, Some(615_065_070));
+    assert_eq!(vda_stat.ios_in_progress, Some(0));
+    assert_eq!(vda_stat.time_spent_doing_ios_ms, Some(107_730_702));
+    assert_eq!(vda_stat.weighted_time_ms, Some(623_152_538));
     assert_eq!(vda_stat.discard_completed, Some(1));
     assert_eq!(vda_stat.discard_merged, Some(2));
     assert_eq!(vda_stat.discard_sectors, Some(3));
     assert_eq!(vda_stat.time_spend_discard_ms, Some(4));
+    assert_eq!(vda_stat.flush_completed, Some(5));
+    assert_eq!(vda_stat.time_spend_flush_ms, Some(6));
 
     let vda_stat = diskmap.get("vda1").expect("Fail to get vda1");
     assert_eq!(vda_stat.name, Some("vda1".into()));
@@ -639,10 +692,15 @@ fn test_disk_stat() {
     assert_eq!(vda_stat.write_merged, Some(2227));
     assert_eq!(vda_stat.write_sectors, Some(253_642));
     assert_eq!(vda_stat.time_spend_write_ms, Some(3073));
+    assert_eq!(vda_stat.ios_in_progress, Some(0));
+    assert_eq!(vda_stat.time_spent_doing_ios_ms, Some(5343));
+    assert_eq!(vda_stat.weighted_time_ms, Some(3060));
     assert_eq!(vda_stat.discard_completed, Some(0));
     assert_eq!(vda_stat.discard_merged, Some(0));
     assert_eq!(vda_stat.discard_sectors, Some(0));
     assert_eq!(vda_stat.time_spend_discard_ms, Some(0));
+    assert_eq!(vda_stat.flush_completed, None);
+    assert_eq!(vda_stat.time_spend_flush_ms, None);
 
     let vda_stat = diskmap.get("vda2").expect("Fail to get vda2");
     assert_eq!(vda_stat.name, Some("vda2".into()));
@@ -675,6 +733,446 @@ fn test_disk_stat() {
     assert_eq!(vda_stat.time_spend_discard_ms, Some(0));
 }
 
+#[test]
+fn test_disk_topology() {
+    let diskstats = b"   8       0 sda 10 0 80 1 20 0 160 2 0 3 3 0 0 0 0
+   8       1 sda1 10 0 80 1 20 0 160 2 0 3 3 0 0 0 0
+   8      16 sdb 10 0 80 1 20 0 160 2 0 3 3 0 0 0 0
+   9       0 md0 10 0 80 1 20 0 160 2 0 3 3 0 0 0 0
+ 253       0 dm-0 10 0 80 1 20 0 160 2 0 3 3 0 0 0 0";
+
+    let procfs = TestProcfs::new();
+    procfs.create_file_with_content("diskstats", diskstats);
+    procfs.create_dir("self");
+    let mountinfo = format!(
+        "36 1 253:0 / {} rw,relatime shared:1 - xfs /dev/mapper/vg0-root rw",
+        procfs.path().display()
+    );
+    procfs.create_file_with_content("self/mountinfo", mountinfo.as_bytes());
+    procfs.create_dir("sys/block/sda/sda1");
+    procfs.create_file_with_content("sys/block/sda/sda1/partition", b"1");
+    procfs.create_dir("sys/block/sdb");
+    procfs.create_dir("sys/block/md0/slaves/sda1");
+    procfs.create_dir("sys/block/md0/slaves/sdb");
+    procfs.create_dir("sys/block/dm-0/dm");
+    procfs.create_file_with_content("sys/block/dm-0/dm/name", b"vg0-root\n");
+    procfs.create_dir("sys/block/dm-0/slaves/md0");
+
+    let reader = procfs.get_reader();
+    let diskmap = reader
+        .read_disk_stats_and_fsinfo()
+        .expect("Failed to read diskstats file");
+
+    let sda = diskmap.get("sda").expect("Fail to get sda");
+    assert_eq!(sda.parent, None);
+    assert_eq!(sda.slaves, None);
+    let sda1 = diskmap.get("sda1").expect("Fail to get sda1");
+    assert_eq!(sda1.parent, Some("sda".into()));
+    assert_eq!(sda1.mount_point, None);
+    let md0 = diskmap.get("md0").expect("Fail to get md0");
+    assert_eq!(md0.parent, None);
+    assert_eq!(md0.slaves, Some(vec!["sda1".into(), "sdb".into()]));
+    let dm0 = diskmap.get("dm-0").expect("Fail to get dm-0");
+    assert_eq!(dm0.dm_name, Some("vg0-root".into()));
+    assert_eq!(dm0.slaves, Some(vec!["md0".into()]));
+    assert_eq!(dm0.filesystem_type, Some("xfs".into()));
+    assert_eq!(
+        dm0.mount_point,
+        Some(procfs.path().to_string_lossy().into_owned())
+    );
+    assert!(dm0.partition_size.is_some());
+}
+
+#[test]
+fn test_read_fs_stats() {
+    let procfs = TestProcfs::new();
+    let mount_point = procfs.path().to_string_lossy().into_owned();
+    let mountinfo = format!(
+        "22 1 0:21 / /proc rw,nosuid,nodev,noexec,relatime shared:5 - proc proc rw
+    36 1 0:40 / {} rw,relatime shared:20 - tmpfs tmpfs rw
+    37 1 0:41 / /does/not/exist rw,relatime shared:21 - ext4 /dev/vda1 rw",
+        mount_point
+    );
+    procfs.create_dir("self");
+    procfs.create_file_with_content("self/mountinfo", mountinfo.as_bytes());
+
+    let reader = procfs.get_reader();
+    let fs_map = reader.read_fs_stats().expect("Failed to read fs stats");
+
+    assert_eq!(fs_map.len(), 1);
+    let fs_stat = fs_map
+        .get(&mount_point)
+        .expect("Failed to get tempdir mount");
+    assert_eq!(fs_stat.mount_point, Some(mount_point.clone()));
+    assert_eq!(fs_stat.mount_source, Some("tmpfs".into()));
+    assert_eq!(fs_stat.fs_type, Some("tmpfs".into()));
+    assert!(fs_stat.total_bytes.unwrap() > 0);
+    assert!(fs_stat.free_bytes.unwrap() <= fs_stat.total_bytes.unwrap());
+    assert!(fs_stat.avail_bytes.unwrap() <= fs_stat.free_bytes.unwrap());
+    assert!(fs_stat.free_inodes.unwrap() <= fs_stat.total_inodes.unwrap());
+}
+
+#[test]
+fn test_read_mountstats() {
+    let mountstats = b"device rootfs mounted on / with fstype rootfs
+device /dev/vda1 mounted on /boot with fstype ext4
+device proc mounted on /proc with fstype proc
+device filer:/export/build mounted on /mnt/build with fstype nfs4 statvers=1.1
+\topts:\trw,vers=4.1,rsize=1048576,wsize=1048576,hard,proto=tcp
+\tage:\t3600
+\tcaps:\tcaps=0x3ffbffff,wtmult=512,dtsize=32768,bsize=0,namlen=255
+\tsec:\tflavor=1,pseudoflavor=1
+\tevents:\t40 2000 0 10 50 30 2500 100 0 10 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0
+\tbytes:\t4096000 2048000 1024 512 4097024 2048512 1000 500
+\tRPC iostats version: 1.1  p/v: 100003/4 (nfs)
+\txprt:\ttcp 0 1 2 0 11 5000 5000 0 12000 0 4 100 1000
+\tper-op statistics
+\t        NULL: 1 1 0 44 24 0 0 0 0
+\t        READ: 100 102 1 15600 4110000 20 300 350 0
+\t       WRITE: 50 50 0 2060000 8000 10 500 520 0
+\t     GETATTR: 2000 2000 0 300000 480000 5 800 900
+\t      LOOKUP: 30 30 0 4800 7200 1 30 33 2
+
+device server:/home mounted on /home with fstype nfs statvers=1.1
+\tbytes:\t1 2 3 4 5 6 7 8
+\tper-op statistics
+\t        READ: 1 1 0 100 200 0 1 1 0
+\t       WRITE: 1 -1 0 100 200 0 1 1 0
+\t     GETATTR 1 1 0 100 200 0 1 1 0
+
+device server:/scratch mounted on /scratch with fstype nfs statvers=1.1
+\tbytes:\t1 2 3 x 5 6 7 8
+\tper-op statistics
+\t        READ: 1 1 0 100 200 0 1 1 0
+";
+
+    let procfs = TestProcfs::new();
+    procfs.create_dir("self");
+    procfs.create_file_with_content("self/mountstats", mountstats);
+
+    let reader = procfs.get_reader();
+    let nfs_map = reader.read_mountstats().expect("Failed to read mountstats");
+
+    assert_eq!(nfs_map.len(), 3);
+    let build = nfs_map.get("/mnt/build").expect("Failed to get /mnt/build");
+    assert_eq!(build.device, Some("filer:/export/build".into()));
+    assert_eq!(build.fs_type, Some("nfs4".into()));
+    assert_eq!(build.normal_read_bytes, Some(4_096_000));
+    assert_eq!(build.normal_write_bytes, Some(2_048_000));
+    assert_eq!(build.direct_read_bytes, Some(1024));
+    assert_eq!(build.direct_write_bytes, Some(512));
+    assert_eq!(build.server_read_bytes, Some(4_097_024));
+    assert_eq!(build.server_write_bytes, Some(2_048_512));
+
+    let ops = build.ops.as_ref().expect("Failed to get per-op stats");
+    assert_eq!(ops.len(), 5);
+    let read = ops.get("READ").expect("Failed to get READ");
+    assert_eq!(read.ops, Some(100));
+    assert_eq!(read.transmissions, Some(102));
+    assert_eq!(read.major_timeouts, Some(1));
+    assert_eq!(read.bytes_sent, Some(15600));
+    assert_eq!(read.bytes_recv, Some(4_110_000));
+    assert_eq!(read.queue_ms, Some(20));
+    assert_eq!(read.rtt_ms, Some(300));
+    assert_eq!(read.exec_ms, Some(350));
+    // Older kernels have no errors column
+    let getattr = ops.get("GETATTR").expect("Failed to get GETATTR");
+    assert_eq!(getattr.exec_ms, Some(900));
+
+    let home = nfs_map.get("/home").expect("Failed to get /home");
+    assert_eq!(home.fs_type, Some("nfs".into()));
+    assert_eq!(home.server_write_bytes, Some(6));
+    // Malformed ops are skipped
+    assert_eq!(home.ops.as_ref().map(|ops| ops.len()), Some(1));
+
+    // A malformed bytes line leaves the byte counters unset but keeps the
+    // rest of the mount
+    let scratch = nfs_map.get("/scratch").expect("Failed to get /scratch");
+    assert_eq!(scratch.normal_read_bytes, None);
+    assert_eq!(scratch.server_write_bytes, None);
+    assert_eq!(scratch.ops.as_ref().map(|ops| ops.len()), Some(1));
+}
+
+#[test]
+fn test_read_cpu_sysfs_stats() {
+    let procfs = TestProcfs::new();
+    let cpu_dir = "sys/devices/system/cpu";
+    procfs.create_dir(format!("{}/cpufreq", cpu_dir));
+    procfs.create_dir(format!("{}/cpu0/cpufreq", cpu_dir));
+    procfs.create_dir(format!("{}/cpu0/thermal_throttle", cpu_dir));
+    procfs.create_dir(format!("{}/cpu0/cpuidle/state0", cpu_dir));
+    procfs.create_dir(format!("{}/cpu0/cpuidle/state1", cpu_dir));
+    procfs.create_file_with_content(
+        format!("{}/cpu0/cpufreq/scaling_cur_freq", cpu_dir),
+        b"2400000\n",
+    );
+    procfs.create_file_with_content(
+        format!("{}/cpu0/thermal_throttle/core_throttle_count", cpu_dir),
+        b"12\n",
+    );
+    procfs.create_file_with_content(
+        format!("{}/cpu0/thermal_throttle/package_throttle_count", cpu_dir),
+        b"34\n",
+    );
+    procfs.create_file_with_content(format!("{}/cpu0/cpuidle/state0/name", cpu_dir), b"POLL\n");
+    procfs.create_file_with_content(format!("{}/cpu0/cpuidle/state0/time", cpu_dir), b"100\n");
+    procfs.create_file_with_content(format!("{}/cpu0/cpuidle/state0/usage", cpu_dir), b"10\n");
+    procfs.create_file_with_content(format!("{}/cpu0/cpuidle/state1/name", cpu_dir), b"C6\n");
+    procfs.create_file_with_content(format!("{}/cpu0/cpuidle/state1/time", cpu_dir), b"900000\n");
+    procfs.create_file_with_content(format!("{}/cpu0/cpuidle/state1/usage", cpu_dir), b"300\n");
+    // No cpufreq driver, thermal_throttle or cpuidle, e.g. in a VM
+    procfs.create_dir(format!("{}/cpu1", cpu_dir));
+    // Unreadable files only lose their own field
+    procfs.create_dir(format!(
+        "{}/cpu2/thermal_throttle/core_throttle_count",
+        cpu_dir
+    ));
+    procfs.create_file_with_content(
+        format!("{}/cpu2/thermal_throttle/package_throttle_count", cpu_dir),
+        b"56\n",
+    );
+
+    let reader = procfs.get_reader();
+    let (cpu_map, errors) = reader
+        .read_cpu_sysfs_stats()
+        .expect("Failed to read cpu sysfs stats");
+
+    // Only the unreadable file is an error, not the missing ones
+    assert_eq!(errors.len(), 1);
+    assert_eq!(cpu_map.len(), 3);
+    let cpu0 = cpu_map.get(&0).expect("Failed to get cpu0");
+    assert_eq!(cpu0.scaling_cur_freq_khz, Some(2_400_000));
+    assert_eq!(cpu0.core_throttle_count, Some(12));
+    assert_eq!(cpu0.package_throttle_count, Some(34));
+    assert_eq!(
+        cpu0.idle_states,
+        Some(vec![
+            CpuIdleStateStat {
+                name: Some("POLL".into()),
+                time_usec: Some(100),
+                usage: Some(10),
+            },
+            CpuIdleStateStat {
+                name: Some("C6".into()),
+                time_usec: Some(900_000),
+                usage: Some(300),
+            },
+        ])
+    );
+    assert_eq!(cpu_map.get(&1), Some(&CpuSysfsStat::default()));
+    assert_eq!(
+        cpu_map.get(&2),
+        Some(&CpuSysfsStat {
+            package_throttle_count: Some(56),
+            ..Default::default()
+        })
+    );
+}
+
+#[test]
+fn test_read_kernel_tables() {
+    let procfs = TestProcfs::new();
+    procfs.create_dir("sys/fs");
+    procfs.create_dir("sys/kernel/random");
+    procfs.create_file_with_content("sys/fs/file-nr", b"10432\t0\t9223372036854775807\n");
+    procfs.create_file_with_content("sys/fs/inode-nr", b"402551\t59917\n");
+    procfs.create_file_with_content("sys/fs/dentry-state", b"512734\t471920\t45\t0\t2016\t0\n");
+    procfs.create_file_with_content("sys/kernel/pid_max", b"4194304\n");
+    procfs.create_file_with_content("sys/kernel/threads-max", b"254720\n");
+    procfs.create_file_with_content("sys/kernel/random/entropy_avail", b"256\n");
+    procfs.create_file_with_content("sys/kernel/random/poolsize", b"256\n");
+    procfs.create_file_with_content("loadavg", b"0.20 0.18 0.12 2/1531 11206\n");
+    let reader = procfs.get_reader();
+    let tables = reader
+        .read_kernel_tables()
+        .expect("Failed to read kernel tables");
+
+    assert_eq!(
+        tables,
+        KernelTableStat {
+            file_handles_allocated: Some(10432),
+            file_handles_max: Some(9223372036854775807),
+            inodes_allocated: Some(402551),
+            inodes_free: Some(59917),
+            dentries: Some(512734),
+            dentries_unused: Some(471920),
+            nr_threads: Some(1531),
+            pid_max: Some(4194304),
+            threads_max: Some(254720),
+            entropy_avail: Some(256),
+            entropy_poolsize: Some(256),
+        }
+    );
+}
+
+#[test]
+fn test_read_swaps() {
+    let swaps = b"Filename\t\t\t\tType\t\tSize\t\tUsed\t\tPriority
+/dev/zram0                              partition\t8388604\t\t1048576\t\t100
+/swapfile                               file\t\t2097148\t\t0\t\t-2
+";
+    let procfs = TestProcfs::new();
+    procfs.create_file_with_content("swaps", swaps);
+    let reader = procfs.get_reader();
+    let swaps = reader.read_swaps().expect("Failed to read swaps");
+
+    assert_eq!(
+        swaps,
+        vec![
+            SwapDeviceStat {
+                filename: Some("/dev/zram0".into()),
+                swap_type: Some("partition".into()),
+                size_bytes: Some(8388604 * 1024),
+                used_bytes: Some(1048576 * 1024),
+                priority: Some(100),
+            },
+            SwapDeviceStat {
+                filename: Some("/swapfile".into()),
+                swap_type: Some("file".into()),
+                size_bytes: Some(2097148 * 1024),
+                used_bytes: Some(0),
+                priority: Some(-2),
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_read_zram_and_zswap_stats() {
+    let procfs = TestProcfs::new();
+    procfs.create_dir("sys/block/zram0");
+    procfs.create_dir("sys/block/sda");
+    procfs.create_dir("sys/kernel/debug/zswap");
+    procfs.create_file_with_content(
+        "sys/block/zram0/mm_stat",
+        b"  4194304  1048576  1310720        0  1310720       12        0        3\n",
+    );
+    procfs.create_file_with_content(
+        "sys/block/zram0/io_stat",
+        b"       1        2        0      300\n",
+    );
+    procfs.create_file_with_content("sys/kernel/debug/zswap/pool_total_size", b"65536\n");
+    procfs.create_file_with_content("sys/kernel/debug/zswap/stored_pages", b"64\n");
+    procfs.create_file_with_content("sys/kernel/debug/zswap/written_back_pages", b"5\n");
+
+    let reader = procfs.get_reader();
+    let zram_map = reader.read_zram_stats().expect("Failed to read zram stats");
+    assert_eq!(zram_map.len(), 1);
+    let zram0 = zram_map.get("zram0").expect("Failed to get zram0");
+    assert_eq!(zram0.orig_data_size, Some(4194304));
+    assert_eq!(zram0.compr_data_size, Some(1048576));
+    assert_eq!(zram0.mem_used_total, Some(1310720));
+    assert_eq!(zram0.huge_pages, Some(3));
+    assert_eq!(zram0.failed_writes, Some(2));
+    assert_eq!(zram0.notify_free, Some(300));
+
+    let zswap = reader
+        .read_zswap_stats()
+        .expect("Failed to read zswap stats")
+        .expect("zswap stats not found");
+    assert_eq!(zswap.pool_total_size_bytes, Some(65536));
+    assert_eq!(zswap.stored_bytes, Some(64 * *PAGE_SIZE));
+    assert_eq!(zswap.written_back_pages, Some(5));
+    assert_eq!(zswap.pool_limit_hit, None);
+
+    let procfs = TestProcfs::new();
+    procfs.create_dir("sys/block");
+    let reader = procfs.get_reader();
+    assert!(reader
+        .read_zram_stats()
+        .expect("Failed to read zram stats")
+        .is_empty());
+    assert_eq!(
+        reader
+            .read_zswap_stats()
+            .expect("Failed to read zswap stats"),
+        None
+    );
+}
+
+#[test]
+fn test_read_cifs_stats() {
+    let stats = br"Resources in use
+CIFS Session: 1
+Share (unique mount targets): 3
+SMB Request/Response Buffer: 1 Pool size: 5
+SMB Small Req/Resp Buffer: 1 Pool size: 30
+Operations (MIDs): 0
+
+0 session 0 share reconnects
+Total vfs operations: 16 maximum at one time: 2
+
+Max requests in flight: 2
+1) \\filer\build
+SMBs: 200
+Bytes read: 4096000  Bytes written: 2048000
+Open files: 3 total (local), 3 open on server
+TreeConnects: 1 total 0 failed
+Creates: 50 total 2 failed
+Closes: 47 total 0 failed
+Reads: 100 total 0 failed
+Writes: 40 total 1 failed
+Locks: x total 0 failed
+OplockBreaks: 3 sent 0 failed
+2) \\filer\home	DISCONNECTED 
+SMBs: 10
+Bytes read: 1 Bytes written: -1
+Open files: 0 total (local), 0 open on server
+3) \\old\share
+SMBs: 5 Oplocks breaks: 0
+Reads:  0 Bytes: 0
+Writes: 0 Bytes: 0
+";
+
+    let procfs = TestProcfs::new();
+    procfs.create_dir("fs/cifs");
+    procfs.create_file_with_content("fs/cifs/Stats", stats);
+
+    let reader = procfs.get_reader();
+    let cifs_map = reader
+        .read_cifs_stats()
+        .expect("Failed to read cifs stats")
+        .expect("Missing cifs stats");
+
+    assert_eq!(cifs_map.len(), 3);
+    let build = cifs_map.get(r"\\filer\build").expect("Failed to get build");
+    assert_eq!(build.smbs, Some(200));
+    assert_eq!(build.bytes_read, Some(4_096_000));
+    assert_eq!(build.bytes_written, Some(2_048_000));
+    assert_eq!(build.open_files, Some(3));
+    let ops = build.ops.as_ref().expect("Failed to get ops");
+    // The malformed Locks line is skipped
+    assert_eq!(ops.len(), 6);
+    let creates = ops.get("Creates").expect("Failed to get Creates");
+    assert_eq!(creates.total, Some(50));
+    assert_eq!(creates.failed, Some(2));
+    assert_eq!(ops.get("OplockBreaks").and_then(|op| op.total), Some(3));
+
+    let home = cifs_map.get(r"\\filer\home").expect("Failed to get home");
+    assert_eq!(home.smbs, Some(10));
+    assert_eq!(home.bytes_read, None);
+    assert_eq!(home.bytes_written, None);
+    assert_eq!(home.open_files, Some(0));
+    assert_eq!(home.ops, None);
+
+    // SMB1 shares only get the SMB count
+    let old = cifs_map.get(r"\\old\share").expect("Failed to get old");
+    assert_eq!(old.smbs, Some(5));
+    assert_eq!(old.ops, None);
+}
+
+#[test]
+fn test_no_cifs_stats() {
+    let procfs = TestProcfs::new();
+    let reader = procfs.get_reader();
+    assert_eq!(
+        reader
+            .read_cifs_stats()
+            .expect("Failed to read missing cifs stats"),
+        None
+    );
+}
+
 #[test]
 fn test_pid_stat() {
     let uptime = b"1631826.55 37530838.66";
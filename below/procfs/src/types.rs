@@ -30,10 +30,30 @@ pub struct CpuStat {
     pub guest_nice_usec: Option<u64>,
 }
 
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct CpuIdleStateStat {
+    pub name: Option<String>,
+    /// Cumulative time spent in this state
+    pub time_usec: Option<u64>,
+    /// Number of times this state was entered
+    pub usage: Option<u64>,
+}
+
+/// Per-CPU stats from /sys/devices/system/cpu/cpuN
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct CpuSysfsStat {
+    pub scaling_cur_freq_khz: Option<u64>,
+    pub core_throttle_count: Option<u64>,
+    pub package_throttle_count: Option<u64>,
+    pub idle_states: Option<Vec<CpuIdleStateStat>>,
+}
+
 #[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct Stat {
     pub total_cpu: Option<CpuStat>,
     pub cpus: Option<Vec<CpuStat>>,
+    /// CPU number of each of `cpus`, which leaves out offline CPUs
+    pub cpu_ids: Option<Vec<u32>>,
     pub total_interrupt_count: Option<u64>,
     pub context_switches: Option<u64>,
     pub boot_time_epoch_secs: Option<u64>,
@@ -91,6 +111,68 @@ pub struct MemInfo {
     pub hugetlb: Option<u64>,
 }
 
+/// Usage and limits of kernel tables from /proc/sys and /proc/loadavg
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct KernelTableStat {
+    pub file_handles_allocated: Option<u64>,
+    pub file_handles_max: Option<u64>,
+    pub inodes_allocated: Option<u64>,
+    pub inodes_free: Option<u64>,
+    pub dentries: Option<u64>,
+    pub dentries_unused: Option<u64>,
+    /// Number of threads in the system, each of which holds a pid
+    pub nr_threads: Option<u64>,
+    pub pid_max: Option<u64>,
+    pub threads_max: Option<u64>,
+    pub entropy_avail: Option<u64>,
+    pub entropy_poolsize: Option<u64>,
+}
+
+/// A swap area from /proc/swaps
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct SwapDeviceStat {
+    pub filename: Option<String>,
+    /// "partition" or "file"
+    pub swap_type: Option<String>,
+    pub size_bytes: Option<u64>,
+    pub used_bytes: Option<u64>,
+    pub priority: Option<i64>,
+}
+
+/// Stats of a zram device from /sys/block/zramN/{mm_stat,io_stat}. Sizes in
+/// bytes.
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ZramStat {
+    pub orig_data_size: Option<u64>,
+    pub compr_data_size: Option<u64>,
+    pub mem_used_total: Option<u64>,
+    pub mem_limit: Option<u64>,
+    pub mem_used_max: Option<u64>,
+    pub same_pages: Option<u64>,
+    pub pages_compacted: Option<u64>,
+    pub huge_pages: Option<u64>,
+    pub failed_reads: Option<u64>,
+    pub failed_writes: Option<u64>,
+    pub invalid_io: Option<u64>,
+    pub notify_free: Option<u64>,
+}
+
+pub type ZramMap = BTreeMap<String, ZramStat>;
+
+/// zswap stats from /sys/kernel/debug/zswap
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ZswapStat {
+    pub pool_total_size_bytes: Option<u64>,
+    pub stored_bytes: Option<u64>,
+    pub written_back_pages: Option<u64>,
+    pub pool_limit_hit: Option<u64>,
+    pub reject_reclaim_fail: Option<u64>,
+    pub reject_alloc_fail: Option<u64>,
+    pub reject_kmemcache_fail: Option<u64>,
+    pub reject_compress_poor: Option<u64>,
+    pub duplicate_entry: Option<u64>,
+}
+
 #[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct InterfaceStat {
     pub collisions: Option<u64>,
@@ -268,6 +350,8 @@ pub struct VmStat {
     pub pgscan_kswapd: Option<u64>,
     pub pgscan_direct: Option<u64>,
     pub oom_kill: Option<u64>,
+    /// Every counter in /proc/vmstat by name, including the ones above
+    pub counters: Option<BTreeMap<String, u64>>,
 }
 
 #[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
@@ -295,13 +379,82 @@ pub struct DiskStat {
     pub write_merged: Option<u64>,
     pub write_sectors: Option<u64>,
     pub time_spend_write_ms: Option<u64>,
+    pub ios_in_progress: Option<u64>,
+    pub time_spent_doing_ios_ms: Option<u64>,
+    pub weighted_time_ms: Option<u64>,
     pub discard_completed: Option<u64>,
     pub discard_merged: Option<u64>,
     pub discard_sectors: Option<u64>,
     pub time_spend_discard_ms: Option<u64>,
+    pub flush_completed: Option<u64>,
+    pub time_spend_flush_ms: Option<u64>,
     pub disk_usage: Option<f32>,
     pub partition_size: Option<u64>,
     pub filesystem_type: Option<String>,
+    pub mount_point: Option<String>,
+    pub dm_name: Option<String>,
+    /// Whole disk this partition belongs to. None for whole disks.
+    pub parent: Option<String>,
+    pub slaves: Option<Vec<String>>,
+}
+
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct FsStat {
+    pub mount_point: Option<String>,
+    pub mount_source: Option<String>,
+    pub fs_type: Option<String>,
+    pub total_bytes: Option<u64>,
+    pub free_bytes: Option<u64>,
+    pub avail_bytes: Option<u64>,
+    pub total_inodes: Option<u64>,
+    pub free_inodes: Option<u64>,
+    pub avail_inodes: Option<u64>,
+}
+
+/// Cumulative counters of one NFS operation, from the per-op statistics
+/// section of /proc/self/mountstats.
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct NfsOpStat {
+    pub ops: Option<u64>,
+    pub transmissions: Option<u64>,
+    pub major_timeouts: Option<u64>,
+    pub bytes_sent: Option<u64>,
+    pub bytes_recv: Option<u64>,
+    pub queue_ms: Option<u64>,
+    pub rtt_ms: Option<u64>,
+    pub exec_ms: Option<u64>,
+}
+
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct NfsMountStat {
+    pub device: Option<String>,
+    pub mount_point: Option<String>,
+    pub fs_type: Option<String>,
+    pub normal_read_bytes: Option<u64>,
+    pub normal_write_bytes: Option<u64>,
+    pub direct_read_bytes: Option<u64>,
+    pub direct_write_bytes: Option<u64>,
+    pub server_read_bytes: Option<u64>,
+    pub server_write_bytes: Option<u64>,
+    pub ops: Option<BTreeMap<String, NfsOpStat>>,
+}
+
+/// Cumulative counters of one SMB command, from a "Creates: 5 total 0 failed"
+/// line of /proc/fs/cifs/Stats.
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct CifsOpStat {
+    pub total: Option<u64>,
+    pub failed: Option<u64>,
+}
+
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct CifsShareStat {
+    pub share: Option<String>,
+    pub smbs: Option<u64>,
+    pub bytes_read: Option<u64>,
+    pub bytes_written: Option<u64>,
+    pub open_files: Option<u64>,
+    pub ops: Option<BTreeMap<String, CifsOpStat>>,
 }
 
 #[derive(Clone, PartialEq, PartialOrd, Debug, Serialize, Deserialize)]
@@ -363,11 +516,141 @@ pub struct PidInfo {
     pub cmdline_vec: Option<Vec<String>>,
     pub exe_path: Option<String>,
     pub mem: PidMem,
+    /// Only set for tasks reported by the exitstat BPF program
+    pub exit: Option<PidExit>,
+    /// Only set if the tcphealth BPF program is running
+    pub tcp: Option<TcpHealthStat>,
+}
+
+/// TCP trouble of a process or cgroup, counted by the tcphealth BPF program
+/// since it was loaded. Only sockets the process sent or read data on are
+/// attributed.
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct TcpHealthStat {
+    pub retransmits: Option<u64>,
+    pub drops: Option<u64>,
+    /// Drops by kfree_skb drop reason, e.g. "TCP_CSUM". Only kept for
+    /// cgroups, on kernels that report the reason.
+    pub drop_reasons: Option<BTreeMap<String, u64>>,
+}
+
+impl TcpHealthStat {
+    /// Adds `other` to the counters. Missing counters are treated as 0.
+    pub fn add(&mut self, other: &TcpHealthStat) {
+        self.retransmits =
+            Some(self.retransmits.unwrap_or_default() + other.retransmits.unwrap_or_default());
+        self.drops = Some(self.drops.unwrap_or_default() + other.drops.unwrap_or_default());
+        if let Some(other_reasons) = other.drop_reasons.as_ref() {
+            let reasons = self.drop_reasons.get_or_insert_with(Default::default);
+            for (reason, drops) in other_reasons {
+                *reasons.entry(reason.clone()).or_default() += drops;
+            }
+        }
+    }
+}
+
+/// How a task exited
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct PidExit {
+    /// Exit status if the task exited on its own
+    pub code: Option<i32>,
+    /// Signal that terminated the task
+    pub signal: Option<i32>,
+    pub core_dumped: Option<bool>,
+    pub oom_killed: Option<bool>,
+}
+
+impl PidExit {
+    /// Name of the terminating signal, e.g. "SIGKILL"
+    pub fn signal_name(&self) -> Option<String> {
+        self.signal.map(|signal| {
+            nix::sys::signal::Signal::try_from(signal)
+                .map_or_else(|_| format!("SIG{}", signal), |s| s.as_str().to_string())
+        })
+    }
+}
+
+/// A program execution reported by the execsnoop BPF program
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ExecEvent {
+    /// When the event was received, in seconds since epoch
+    pub timestamp: Option<u64>,
+    pub pid: Option<i32>,
+    /// Process that forked this one, even if it has since exited
+    pub ppid: Option<i32>,
+    pub comm: Option<String>,
+    /// Path passed to execve()
+    pub filename: Option<String>,
+    /// Leading arguments, cut off at a fixed number of bytes
+    pub argv: Option<Vec<String>>,
+    pub argv_truncated: Option<bool>,
+    /// cgroup2 ID, which is the inode number of the cgroup directory
+    pub cgroup_id: Option<u64>,
+}
+
+/// An OOM kill reported by the oomkill BPF program
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct OomKillEvent {
+    /// When the event was received, in seconds since epoch
+    pub timestamp: Option<u64>,
+    pub victim_pid: Option<i32>,
+    pub victim_comm: Option<String>,
+    pub victim_rss_bytes: Option<u64>,
+    /// cgroup2 ID of the victim, which is the inode number of its cgroup
+    pub victim_cgroup_id: Option<u64>,
+    /// Process whose allocation invoked the OOM killer
+    pub trigger_pid: Option<i32>,
+    pub trigger_comm: Option<String>,
+    /// cgroup2 ID of the memory cgroup that ran out of memory. None for
+    /// system-wide OOMs.
+    pub memcg_id: Option<u64>,
+    /// Memory available to the OOMing domain
+    pub total_bytes: Option<u64>,
+    pub constraint: Option<OomConstraint>,
+}
+
+/// What limit the OOM killer was invoked for, see enum oom_constraint
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum OomConstraint {
+    /// Ran out of system memory
+    None,
+    Cpuset,
+    MemoryPolicy,
+    /// Hit a memory cgroup limit
+    Memcg,
+}
+
+/// Which exitstat BPF program is running
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum ExitstatStatus {
+    Loading,
+    /// Reports everything in `PidInfo` that procfs does
+    Full,
+    /// The kernel lacks something the full program needs. IO, RSS, session
+    /// and OOM kill data of exited processes is missing.
+    Reduced,
+    /// Neither program could be loaded, exited processes are missing
+    Failed,
+}
+
+/// Health of the exitstat BPF program. Counters are cumulative since below
+/// started.
+#[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ExitstatHealth {
+    pub status: Option<ExitstatStatus>,
+    /// Events the kernel overwrote before we read them from the perf buffer
+    pub lost_events: Option<u64>,
+    /// Events the BPF program failed to submit to the perf buffer
+    pub dropped_events: Option<u64>,
 }
 
 pub type PidMap = BTreeMap<i32, PidInfo>;
 pub type NetMap = BTreeMap<String, InterfaceStat>;
 pub type DiskMap = BTreeMap<String, DiskStat>;
+pub type FsMap = BTreeMap<String, FsStat>;
+pub type NfsMap = BTreeMap<String, NfsMountStat>;
+pub type CifsMap = BTreeMap<String, CifsShareStat>;
+pub type CpuSysfsMap = BTreeMap<u32, CpuSysfsStat>;
 
 #[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct NetStat {
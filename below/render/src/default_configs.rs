@@ -33,6 +33,7 @@ impl HasRenderConfig for model::SingleCgroupModel {
             }
             Mem(field_id) => model::CgroupMemoryModel::get_render_config_builder(field_id),
             Pressure(field_id) => model::CgroupPressureModel::get_render_config_builder(field_id),
+            Tcp(field_id) => model::TcpHealthModel::get_render_config_builder(field_id),
         }
     }
 }
@@ -48,6 +49,9 @@ impl HasRenderConfig for model::CgroupCpuModel {
             NrPeriodsPerSec => rc.title("Nr Period").suffix("/s").format(Precision(2)),
             NrThrottledPerSec => rc.title("Nr Throttled").suffix("/s").format(Precision(2)),
             ThrottledPct => rc.title("Throttled").suffix("%").format(Precision(2)),
+            RunDelayP50Us => rc.title("Run Delay P50").suffix(" us"),
+            RunDelayP90Us => rc.title("Run Delay P90").suffix(" us"),
+            RunDelayP99Us => rc.title("Run Delay P99").suffix(" us"),
         }
     }
 }
@@ -64,6 +68,10 @@ impl HasRenderConfig for model::CgroupIoModel {
             DbytesPerSec => rc.title("Discards").suffix("/s").format(ReadableSize),
             DiosPerSec => rc.title("Discard IOPS").format(Precision(1)),
             RwbytesPerSec => rc.title("RW Total").suffix("/s").format(ReadableSize),
+            ReadLatencyP50Us => rc.title("Read Lat P50").suffix(" us"),
+            ReadLatencyP99Us => rc.title("Read Lat P99").suffix(" us"),
+            WriteLatencyP50Us => rc.title("Write Lat P50").suffix(" us"),
+            WriteLatencyP99Us => rc.title("Write Lat P99").suffix(" us"),
         }
     }
 }
@@ -175,6 +183,18 @@ impl HasRenderConfig for model::TcpModel {
     }
 }
 
+impl HasRenderConfig for model::TcpHealthModel {
+    fn get_render_config_builder(field_id: &Self::FieldId) -> RenderConfigBuilder {
+        use model::TcpHealthModelFieldId::*;
+        let rc = RenderConfigBuilder::new();
+        match field_id {
+            RetransmitsPerSec => rc.title("TCP Retrans").suffix("/s").format(Precision(1)),
+            DropsPerSec => rc.title("TCP Drops").suffix("/s").format(Precision(1)),
+            TopDropReason => rc.title("TCP Top Drop Reason").width(20),
+        }
+    }
+}
+
 impl HasRenderConfig for model::IpModel {
     fn get_render_config_builder(field_id: &Self::FieldId) -> RenderConfigBuilder {
         use model::IpModelFieldId::*;
@@ -348,6 +368,10 @@ impl HasRenderConfig for model::SingleProcessModel {
             Cpu(field_id) => model::ProcessCpuModel::get_render_config_builder(field_id),
             Cmdline => rc.title("Cmdline").width(50),
             ExePath => rc.title("Exe Path"),
+            ExitCode => rc.title("Exit Code"),
+            ExitSignal => rc.title("Exit Signal"),
+            ExitStatus => rc.title("Exit Status").width(30),
+            Tcp(field_id) => model::TcpHealthModel::get_render_config_builder(field_id),
         }
     }
 }
@@ -398,6 +422,42 @@ impl HasRenderConfig for model::ProcessCpuModel {
     }
 }
 
+impl HasRenderConfig for model::SingleExecModel {
+    fn get_render_config_builder(field_id: &Self::FieldId) -> RenderConfigBuilder {
+        use model::SingleExecModelFieldId::*;
+        let rc = RenderConfigBuilder::new();
+        match field_id {
+            Timestamp => rc.title("Exec Time").width(11),
+            Pid => rc.title("Pid"),
+            Ppid => rc.title("Ppid"),
+            Comm => rc.title("Comm").width(30),
+            Filename => rc.title("Filename").width(50),
+            Cmdline => rc.title("Cmdline").width(50),
+            CgroupId => rc.title("Cgroup Id"),
+            Cgroup => rc.title("Cgroup").width(50).fold(FoldOption::Name),
+        }
+    }
+}
+
+impl HasRenderConfig for model::SingleOomKillModel {
+    fn get_render_config_builder(field_id: &Self::FieldId) -> RenderConfigBuilder {
+        use model::SingleOomKillModelFieldId::*;
+        let rc = RenderConfigBuilder::new();
+        match field_id {
+            Timestamp => rc.title("Kill Time").width(11),
+            Pid => rc.title("Pid"),
+            Comm => rc.title("Comm").width(30),
+            RssBytes => rc.title("RSS").format(ReadableSize),
+            Cgroup => rc.title("Cgroup").width(50).fold(FoldOption::Name),
+            Kind => rc.title("Kind"),
+            OomCgroup => rc.title("OOM Cgroup").width(50).fold(FoldOption::Name),
+            TotalBytes => rc.title("OOM Limit").format(ReadableSize),
+            TriggerPid => rc.title("Trigger Pid"),
+            TriggerComm => rc.title("Trigger Comm").width(30),
+        }
+    }
+}
+
 impl HasRenderConfig for model::SystemModel {
     fn get_render_config_builder(field_id: &Self::FieldId) -> RenderConfigBuilder {
         use model::SystemModelFieldId::*;
@@ -411,9 +471,21 @@ impl HasRenderConfig for model::SystemModel {
             Cpus(field_id) => Vec::<model::SingleCpuModel>::get_render_config_builder(field_id),
             Mem(field_id) => model::MemoryModel::get_render_config_builder(field_id),
             Vm(field_id) => model::VmModel::get_render_config_builder(field_id),
+            Swap(field_id) => model::SwapModel::get_render_config_builder(field_id),
+            KernelTables(field_id) => model::KernelTablesModel::get_render_config_builder(field_id),
+            Exitstat(field_id) => model::ExitstatModel::get_render_config_builder(field_id),
             Disks(field_id) => {
                 model::SingleDiskModel::get_render_config_builder(&field_id.subquery_id)
             }
+            Filesystems(field_id) => {
+                model::SingleFsModel::get_render_config_builder(&field_id.subquery_id)
+            }
+            Nfs(field_id) => {
+                model::SingleNfsModel::get_render_config_builder(&field_id.subquery_id)
+            }
+            Cifs(field_id) => {
+                model::SingleCifsModel::get_render_config_builder(&field_id.subquery_id)
+            }
         }
     }
 }
@@ -450,6 +522,33 @@ impl HasRenderConfig for model::SingleCpuModel {
             StolenPct => rc.title("Stolen").suffix("%").format(Precision(2)),
             GuestPct => rc.title("Guest").suffix("%").format(Precision(2)),
             GuestNicePct => rc.title("Guest Nice").suffix("%").format(Precision(2)),
+            FreqMhz => rc.title("Freq").suffix(" MHz"),
+            CoreThrottlePerSec => rc.title("Core Throttle").suffix("/s").format(Precision(1)),
+            PackageThrottlePerSec => rc
+                .title("Package Throttle")
+                .suffix("/s")
+                .format(Precision(1)),
+            IdleStates(field_id) => {
+                let rc: RenderConfig =
+                    model::CpuIdleStateModel::get_render_config_builder(&field_id.subquery_id)
+                        .into();
+                let title = match field_id.key.as_ref() {
+                    Some(state) => format!("{} {}", state, rc.get_title()),
+                    None => rc.get_title().to_owned(),
+                };
+                RenderConfigBuilder::from(rc).title(title)
+            }
+        }
+    }
+}
+
+impl HasRenderConfig for model::CpuIdleStateModel {
+    fn get_render_config_builder(field_id: &Self::FieldId) -> RenderConfigBuilder {
+        use model::CpuIdleStateModelFieldId::*;
+        let rc = RenderConfigBuilder::new();
+        match field_id {
+            ResidencyPct => rc.title("Residency").suffix("%").format(Precision(2)),
+            UsagePerSec => rc.title("Usage").suffix("/s").format(Precision(1)),
         }
     }
 }
@@ -530,6 +629,160 @@ impl HasRenderConfig for model::VmModel {
             PgscanKswapd => rc.title("Pgscan Kswapd").suffix(" pages/s"),
             PgscanDirect => rc.title("Pgscan Direct").suffix(" pages/s"),
             OomKill => rc.title("OOM Kills"),
+            Counters(field_id) => {
+                let rc: RenderConfig =
+                    model::VmCounterModel::get_render_config_builder(&field_id.subquery_id).into();
+                let title = match field_id.key.as_ref() {
+                    Some(name) => format!("{} {}", name, rc.get_title()),
+                    None => rc.get_title().to_owned(),
+                };
+                RenderConfigBuilder::from(rc).title(title)
+            }
+        }
+    }
+}
+
+impl HasRenderConfig for model::VmCounterModel {
+    fn get_render_config_builder(field_id: &Self::FieldId) -> RenderConfigBuilder {
+        use model::VmCounterModelFieldId::*;
+        let rc = RenderConfigBuilder::new();
+        match field_id {
+            Value => rc.title("Value"),
+            PerSec => rc.title("Rate").suffix("/s").format(Precision(1)),
+        }
+    }
+}
+
+impl HasRenderConfig for model::ExitstatModel {
+    fn get_render_config_builder(field_id: &Self::FieldId) -> RenderConfigBuilder {
+        use model::ExitstatModelFieldId::*;
+        let rc = RenderConfigBuilder::new();
+        match field_id {
+            Status => rc.title("Exitstat"),
+            LostEvents => rc.title("Exitstat Lost"),
+            DroppedEvents => rc.title("Exitstat Dropped"),
+            Health => rc.title("Exitstat Health"),
+        }
+    }
+}
+
+impl HasRenderConfig for model::KernelTablesModel {
+    fn get_render_config_builder(field_id: &Self::FieldId) -> RenderConfigBuilder {
+        use model::KernelTablesModelFieldId::*;
+        let rc = RenderConfigBuilder::new();
+        match field_id {
+            FileHandlesAllocated => rc.title("File Handles"),
+            FileHandlesMax => rc.title("File Max"),
+            FileHandlesPct => rc
+                .title("File Handles Usage")
+                .suffix("%")
+                .format(Precision(2)),
+            InodesAllocated => rc.title("Inodes"),
+            InodesFree => rc.title("Inodes Free"),
+            Dentries => rc.title("Dentries"),
+            DentriesUnused => rc.title("Dentries Unused"),
+            NrThreads => rc.title("Threads"),
+            PidMax => rc.title("Pid Max"),
+            PidUsagePct => rc.title("Pid Usage").suffix("%").format(Precision(2)),
+            ThreadsMax => rc.title("Threads Max"),
+            ThreadsUsagePct => rc.title("Threads Usage").suffix("%").format(Precision(2)),
+            EntropyAvail => rc.title("Entropy"),
+            EntropyPoolsize => rc.title("Entropy Pool"),
+            EntropyPct => rc.title("Entropy Avail").suffix("%").format(Precision(2)),
+        }
+    }
+}
+
+impl HasRenderConfig for model::SwapModel {
+    fn get_render_config_builder(field_id: &Self::FieldId) -> RenderConfigBuilder {
+        use model::SwapModelFieldId::*;
+        let rc = RenderConfigBuilder::new();
+        match field_id {
+            TotalBytes => rc.title("Swap Size").format(ReadableSize),
+            UsedBytes => rc.title("Swap Used").format(ReadableSize),
+            Devices(field_id) => {
+                Vec::<model::SingleSwapDeviceModel>::get_render_config_builder(field_id)
+            }
+            Zram(field_id) => {
+                let rc: RenderConfig =
+                    model::ZramModel::get_render_config_builder(&field_id.subquery_id).into();
+                let title = match field_id.key.as_ref() {
+                    Some(device) => format!("{} {}", device, rc.get_title()),
+                    None => rc.get_title().to_owned(),
+                };
+                RenderConfigBuilder::from(rc).title(title)
+            }
+            Zswap(field_id) => {
+                let rc: RenderConfig =
+                    model::ZswapModel::get_render_config_builder(field_id).into();
+                let title = format!("Zswap {}", rc.get_title());
+                RenderConfigBuilder::from(rc).title(title)
+            }
+        }
+    }
+}
+
+impl HasRenderConfig for model::SingleSwapDeviceModel {
+    fn get_render_config_builder(field_id: &Self::FieldId) -> RenderConfigBuilder {
+        use model::SingleSwapDeviceModelFieldId::*;
+        let rc = RenderConfigBuilder::new();
+        match field_id {
+            Name => rc.title("Name").width(20),
+            SwapType => rc.title("Type"),
+            SizeBytes => rc.title("Size").format(ReadableSize),
+            UsedBytes => rc.title("Used").format(ReadableSize),
+            UsagePct => rc.title("Usage").suffix("%").format(Precision(2)),
+            Priority => rc.title("Priority"),
+        }
+    }
+}
+
+impl HasRenderConfig for Vec<model::SingleSwapDeviceModel> {
+    fn get_render_config_builder(field_id: &Self::FieldId) -> RenderConfigBuilder {
+        let mut rc =
+            model::SingleSwapDeviceModel::get_render_config_builder(&field_id.subquery_id).get();
+        rc.title = rc.title.map(|title| {
+            format!(
+                "Swap {} {}",
+                field_id
+                    .idx
+                    .expect("VecFieldId without idx should not have render config"),
+                title
+            )
+        });
+        rc.into()
+    }
+}
+
+impl HasRenderConfig for model::ZramModel {
+    fn get_render_config_builder(field_id: &Self::FieldId) -> RenderConfigBuilder {
+        use model::ZramModelFieldId::*;
+        let rc = RenderConfigBuilder::new();
+        match field_id {
+            OrigDataBytes => rc.title("Orig Data").format(ReadableSize),
+            ComprDataBytes => rc.title("Compr Data").format(ReadableSize),
+            MemUsedBytes => rc.title("Mem Used").format(ReadableSize),
+            MemLimitBytes => rc.title("Mem Limit").format(ReadableSize),
+            CompressionRatio => rc.title("Compr Ratio").format(Precision(2)),
+            SamePages => rc.title("Same Pages"),
+            HugePages => rc.title("Huge Pages"),
+            FailedReadsPerSec => rc.title("Failed Reads").suffix("/s").format(Precision(1)),
+            FailedWritesPerSec => rc.title("Failed Writes").suffix("/s").format(Precision(1)),
+        }
+    }
+}
+
+impl HasRenderConfig for model::ZswapModel {
+    fn get_render_config_builder(field_id: &Self::FieldId) -> RenderConfigBuilder {
+        use model::ZswapModelFieldId::*;
+        let rc = RenderConfigBuilder::new();
+        match field_id {
+            PoolTotalBytes => rc.title("Pool").format(ReadableSize),
+            StoredBytes => rc.title("Stored").format(ReadableSize),
+            CompressionRatio => rc.title("Compr Ratio").format(Precision(2)),
+            WrittenBackPagesPerSec => rc.title("Written Back").suffix(" pages/s"),
+            PoolLimitHitPerSec => rc.title("Pool Limit Hit").suffix("/s").format(Precision(1)),
+            RejectPerSec => rc.title("Reject").suffix("/s").format(Precision(1)),
         }
     }
 }
@@ -544,6 +797,12 @@ impl HasRenderConfig for model::SingleDiskModel {
             WriteBytesPerSec => rc.title("Write").format(ReadableSize).suffix("/s"),
             DiscardBytesPerSec => rc.title("Discard").format(ReadableSize).suffix("/s"),
             DiskTotalBytesPerSec => rc.title("Disk").format(ReadableSize).suffix("/s"),
+            UtilPct => rc.title("Util").suffix("%").format(Precision(2)),
+            AvgQueueSize => rc.title("Avg Queue Size").format(Precision(2)),
+            ReadAwaitMs => rc.title("Read Await").suffix(" ms").format(Precision(2)),
+            WriteAwaitMs => rc.title("Write Await").suffix(" ms").format(Precision(2)),
+            AvgRequestSize => rc.title("Avg Request Size").format(ReadableSize),
+            IosInProgress => rc.title("IOs In Progress"),
             ReadCompleted => rc.title("Read Completed"),
             ReadMerged => rc.title("Read Merged"),
             ReadSectors => rc.title("Read Sectors"),
@@ -556,11 +815,103 @@ impl HasRenderConfig for model::SingleDiskModel {
             DiscardMerged => rc.title("Discard Merged"),
             DiscardSectors => rc.title("Discard Sectors"),
             TimeSpendDiscardMs => rc.title("Time Spend Discard").suffix(" ms"),
+            FlushCompleted => rc.title("Flush Completed"),
+            TimeSpendFlushMs => rc.title("Time Spend Flush").suffix(" ms"),
             Major => rc.title("Major").width(7),
             Minor => rc.title("Minor").width(7),
             DiskUsage => rc.title("Disk Usage").suffix("%").format(Precision(2)),
             PartitionSize => rc.title("Partition Size").format(ReadableSize),
             FilesystemType => rc.title("Filesystem Type"),
+            MountPoint => rc.title("Mount Point").width(20),
+            DmName => rc.title("DM Name").width(20),
+            Parent => rc.title("Parent").width(15),
+            Slaves => rc.title("Slaves").width(20),
+            ReadLatencyP50Us => rc.title("Read Lat P50").suffix(" us"),
+            ReadLatencyP99Us => rc.title("Read Lat P99").suffix(" us"),
+            WriteLatencyP50Us => rc.title("Write Lat P50").suffix(" us"),
+            WriteLatencyP99Us => rc.title("Write Lat P99").suffix(" us"),
+        }
+    }
+}
+
+impl HasRenderConfig for model::NfsOpModel {
+    fn get_render_config_builder(field_id: &Self::FieldId) -> RenderConfigBuilder {
+        use model::NfsOpModelFieldId::*;
+        let rc = RenderConfigBuilder::new();
+        match field_id {
+            OpsPerSec => rc.title("Ops").suffix("/s").format(Precision(1)),
+            RetransPerSec => rc.title("Retrans").suffix("/s").format(Precision(1)),
+            TimeoutsPerSec => rc.title("Timeouts").suffix("/s").format(Precision(1)),
+            AvgRttMs => rc.title("RTT").suffix(" ms").format(Precision(2)),
+            AvgExecMs => rc.title("Exec").suffix(" ms").format(Precision(2)),
+        }
+    }
+}
+
+/// Per-op fields share a sub-model, so prefix their titles with the op.
+fn nfs_op_render_config(op: &str, field_id: &model::NfsOpModelFieldId) -> RenderConfigBuilder {
+    let rc: RenderConfig = model::NfsOpModel::get_render_config_builder(field_id).into();
+    let title = format!("{} {}", op, rc.get_title());
+    RenderConfigBuilder::from(rc).title(title)
+}
+
+impl HasRenderConfig for model::SingleNfsModel {
+    fn get_render_config_builder(field_id: &Self::FieldId) -> RenderConfigBuilder {
+        use model::SingleNfsModelFieldId::*;
+        let rc = RenderConfigBuilder::new();
+        match field_id {
+            MountPoint => rc.title("Mount Point").width(30),
+            Device => rc.title("Device").width(30),
+            FsType => rc.title("Fs Type"),
+            ReadBytesPerSec => rc.title("Read").format(ReadableSize).suffix("/s"),
+            WriteBytesPerSec => rc.title("Write").format(ReadableSize).suffix("/s"),
+            ServerReadBytesPerSec => rc.title("Server Read").format(ReadableSize).suffix("/s"),
+            ServerWriteBytesPerSec => rc.title("Server Write").format(ReadableSize).suffix("/s"),
+            OpsPerSec => rc.title("Ops").suffix("/s").format(Precision(1)),
+            RetransPerSec => rc.title("Retrans").suffix("/s").format(Precision(1)),
+            Read(field_id) => nfs_op_render_config("Read", field_id),
+            Write(field_id) => nfs_op_render_config("Write", field_id),
+            Getattr(field_id) => nfs_op_render_config("Getattr", field_id),
+            Lookup(field_id) => nfs_op_render_config("Lookup", field_id),
+        }
+    }
+}
+
+impl HasRenderConfig for model::SingleCifsModel {
+    fn get_render_config_builder(field_id: &Self::FieldId) -> RenderConfigBuilder {
+        use model::SingleCifsModelFieldId::*;
+        let rc = RenderConfigBuilder::new();
+        match field_id {
+            Share => rc.title("Share").width(30),
+            SmbsPerSec => rc.title("SMBs").suffix("/s").format(Precision(1)),
+            ReadBytesPerSec => rc.title("Read").format(ReadableSize).suffix("/s"),
+            WriteBytesPerSec => rc.title("Write").format(ReadableSize).suffix("/s"),
+            ReadsPerSec => rc.title("Reads").suffix("/s").format(Precision(1)),
+            WritesPerSec => rc.title("Writes").suffix("/s").format(Precision(1)),
+            FailedOpsPerSec => rc.title("Failed Ops").suffix("/s").format(Precision(1)),
+            OpenFiles => rc.title("Open Files"),
+        }
+    }
+}
+
+impl HasRenderConfig for model::SingleFsModel {
+    fn get_render_config_builder(field_id: &Self::FieldId) -> RenderConfigBuilder {
+        use model::SingleFsModelFieldId::*;
+        let rc = RenderConfigBuilder::new();
+        match field_id {
+            MountPoint => rc.title("Mount Point").width(30),
+            MountSource => rc.title("Mount Source").width(20),
+            FsType => rc.title("Fs Type"),
+            UsagePct => rc.title("Usage").suffix("%").format(Precision(2)),
+            TotalBytes => rc.title("Total").format(ReadableSize),
+            UsedBytes => rc.title("Used").format(ReadableSize),
+            FreeBytes => rc.title("Free").format(ReadableSize),
+            AvailBytes => rc.title("Avail").format(ReadableSize),
+            InodeUsagePct => rc.title("Inode Usage").suffix("%").format(Precision(2)),
+            TotalInodes => rc.title("Total Inodes"),
+            UsedInodes => rc.title("Used Inodes"),
+            FreeInodes => rc.title("Free Inodes"),
+            AvailInodes => rc.title("Avail Inodes"),
         }
     }
 }
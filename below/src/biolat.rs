@@ -0,0 +1,89 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use libbpf_rs::MapFlags;
+use plain::Plain;
+
+use model::collector_plugin::AsyncCollectorPlugin;
+use model::IoLatencyHists;
+
+use crate::runqlat::Hist;
+use crate::{BiolatSkel, BiolatSkelBuilder, SendSkel};
+
+const MINORBITS: u32 = 20;
+const REQ_OP_READ: u32 = 0;
+const REQ_OP_WRITE: u32 = 1;
+
+// See bpf prog for comments on what each field is
+#[repr(C)]
+#[derive(Default)]
+struct HistKey {
+    cgroup_id: u64,
+    dev: u32,
+    op: u32,
+}
+
+unsafe impl Plain for HistKey {}
+
+/// Reads the block IO latency histograms maintained by the biolat BPF
+/// program, keyed by cgroup and device. Loaded lazily like `RunqlatPlugin`.
+pub struct BiolatPlugin {
+    debug: bool,
+    skel: Option<SendSkel<BiolatSkel<'static>>>,
+}
+
+impl BiolatPlugin {
+    pub fn new(debug: bool) -> Self {
+        Self { debug, skel: None }
+    }
+
+    fn load(&self) -> Result<BiolatSkel<'static>> {
+        let mut skel_builder = BiolatSkelBuilder::default();
+        skel_builder.obj_builder.debug(self.debug);
+        let mut skel = skel_builder.open()?.load()?;
+        skel.attach()?;
+        Ok(skel)
+    }
+}
+
+#[async_trait]
+impl AsyncCollectorPlugin for BiolatPlugin {
+    type T = IoLatencyHists;
+
+    async fn try_collect(&mut self) -> Result<Option<IoLatencyHists>> {
+        if self.skel.is_none() {
+            self.skel = Some(SendSkel(self.load()?));
+        }
+        let skel = &self.skel.as_ref().unwrap().0;
+        let maps = skel.maps();
+        let hists_map = maps.hists();
+
+        let mut hists = IoLatencyHists::new();
+        for key in hists_map.keys() {
+            // The entry may have been evicted since we got the key
+            if let Some(value) = hists_map.lookup(&key, MapFlags::ANY)? {
+                let mut hist_key = HistKey::default();
+                plain::copy_from_bytes(&mut hist_key, &key).expect("Key buffer was too short");
+                let mut hist = Hist::default();
+                plain::copy_from_bytes(&mut hist, &value).expect("Data buffer was too short");
+
+                // Same naming as cgroup io.stat
+                let device = format!(
+                    "{}:{}",
+                    hist_key.dev >> MINORBITS,
+                    hist_key.dev & ((1 << MINORBITS) - 1)
+                );
+                let entry = hists
+                    .entry(hist_key.cgroup_id)
+                    .or_default()
+                    .entry(device)
+                    .or_default();
+                match hist_key.op {
+                    REQ_OP_READ => entry.read = hist.slots.to_vec(),
+                    REQ_OP_WRITE => entry.write = hist.slots.to_vec(),
+                    _ => {}
+                }
+            }
+        }
+        Ok(Some(hists))
+    }
+}
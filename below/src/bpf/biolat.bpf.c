@@ -0,0 +1,180 @@
+#ifdef FBCODE_BUILD
+#include <bpf/vmlinux/vmlinux.h>
+#else
+#include "../open_source/vmlinux.h"
+#endif // FBCODE_BUILD
+
+#include <bpf/bpf_core_read.h>
+#include <bpf/bpf_helpers.h>
+#include <bpf/bpf_tracing.h>
+
+#define MAX_SLOTS 26
+#define MAX_ENTRIES 10240
+#define REQ_OP_BITS 8
+#define REQ_OP_MASK ((1 << REQ_OP_BITS) - 1)
+#define REQ_OP_READ 0
+#define REQ_OP_WRITE 1
+#define MINORBITS 20
+#define MKDEV(ma, mi) (((ma) << MINORBITS) | (mi))
+
+#ifndef KERNEL_VERSION
+#define KERNEL_VERSION(a, b, c) (((a) << 16) + ((b) << 8) + ((c) > 255 ? 255 : (c)))
+#endif
+
+extern int LINUX_KERNEL_VERSION __kconfig;
+
+struct start_val {
+  uint64_t ts; // when the request was issued to the device, ns
+  uint64_t cgroup_id; // cgroup2 ID the IO is charged to
+};
+
+struct hist_key {
+  uint64_t cgroup_id;
+  uint32_t dev; // kernel dev_t of the whole disk
+  uint32_t op; // REQ_OP_READ or REQ_OP_WRITE
+};
+
+// Bucket i counts latencies in [2^i, 2^(i+1)) usec, the last one everything
+// above
+struct hist {
+  uint64_t slots[MAX_SLOTS];
+};
+
+struct {
+  __uint(type, BPF_MAP_TYPE_HASH);
+  __uint(max_entries, MAX_ENTRIES);
+  __type(key, struct request*);
+  __type(value, struct start_val);
+} start SEC(".maps");
+
+// Cumulative histograms. LRU so that removed cgroups age out.
+struct {
+  __uint(type, BPF_MAP_TYPE_LRU_HASH);
+  __uint(max_entries, MAX_ENTRIES);
+  __type(key, struct hist_key);
+  __type(value, struct hist);
+} hists SEC(".maps");
+
+static const struct hist zero;
+
+// request::rq_disk was replaced by request_queue::disk in 5.17
+struct request_queue___x {
+  struct gendisk* disk;
+} __attribute__((preserve_access_index));
+
+struct request___x {
+  struct request_queue___x* q;
+  struct gendisk* rq_disk;
+} __attribute__((preserve_access_index));
+
+static __always_inline struct gendisk* get_disk(void* request) {
+  struct request___x* r = request;
+
+  if (bpf_core_field_exists(r->rq_disk)) {
+    return BPF_CORE_READ(r, rq_disk);
+  }
+  return BPF_CORE_READ(r, q, disk);
+}
+
+static __always_inline u64 log2(u32 v) {
+  u32 shift, r;
+
+  r = (v > 0xFFFF) << 4;
+  v >>= r;
+  shift = (v > 0xFF) << 3;
+  v >>= shift;
+  r |= shift;
+  shift = (v > 0xF) << 2;
+  v >>= shift;
+  r |= shift;
+  shift = (v > 0x3) << 1;
+  v >>= shift;
+  r |= shift;
+  r |= (v >> 1);
+  return r;
+}
+
+static __always_inline u64 log2l(u64 v) {
+  u32 hi = v >> 32;
+
+  if (hi) {
+    return log2(hi) + 32;
+  }
+  return log2(v);
+}
+
+static __always_inline int trace_rq_start(struct request* rq) {
+  struct start_val val = {};
+  struct blkcg_gq* blkg = BPF_CORE_READ(rq, bio, bi_blkg);
+
+  val.ts = bpf_ktime_get_ns();
+  // Charge the IO to the cgroup that submitted the bio rather than whoever
+  // happens to issue it, e.g. a writeback kworker
+  if (blkg) {
+    val.cgroup_id = BPF_CORE_READ(blkg, blkcg, css.cgroup, kn, id);
+  } else {
+    val.cgroup_id = bpf_get_current_cgroup_id();
+  }
+  bpf_map_update_elem(&start, &rq, &val, BPF_ANY);
+  return 0;
+}
+
+// Commit a54895fa (v5.11-rc1) dropped the request_queue argument
+SEC("tp_btf/block_rq_issue")
+int block_rq_issue(u64* ctx) {
+  if (LINUX_KERNEL_VERSION < KERNEL_VERSION(5, 11, 0)) {
+    return trace_rq_start((void*)ctx[1]);
+  }
+  return trace_rq_start((void*)ctx[0]);
+}
+
+SEC("tp_btf/block_rq_complete")
+int BPF_PROG(
+    block_rq_complete,
+    struct request* rq,
+    int error,
+    unsigned int nr_bytes) {
+  struct hist_key key = {};
+  struct start_val* valp;
+  struct gendisk* disk;
+  struct hist* histp;
+  u64 delta_us, slot;
+
+  valp = bpf_map_lookup_elem(&start, &rq);
+  if (!valp) {
+    return 0;
+  }
+  delta_us = (bpf_ktime_get_ns() - valp->ts) / 1000;
+  key.cgroup_id = valp->cgroup_id;
+  bpf_map_delete_elem(&start, &rq);
+
+  key.op = BPF_CORE_READ(rq, cmd_flags) & REQ_OP_MASK;
+  // Discards and flushes have very different latencies, leave them out
+  if (key.op != REQ_OP_READ && key.op != REQ_OP_WRITE) {
+    return 0;
+  }
+  disk = get_disk(rq);
+  if (!disk) {
+    return 0;
+  }
+  key.dev =
+      MKDEV(BPF_CORE_READ(disk, major), BPF_CORE_READ(disk, first_minor));
+
+  histp = bpf_map_lookup_elem(&hists, &key);
+  if (!histp) {
+    bpf_map_update_elem(&hists, &key, &zero, BPF_NOEXIST);
+    histp = bpf_map_lookup_elem(&hists, &key);
+    if (!histp) {
+      return 0;
+    }
+  }
+
+  slot = log2l(delta_us);
+  if (slot >= MAX_SLOTS) {
+    slot = MAX_SLOTS - 1;
+  }
+  __sync_fetch_and_add(&histp->slots[slot], 1);
+  return 0;
+}
+
+char _license[] SEC("license") = "GPL";
@@ -0,0 +1,106 @@
+#ifdef FBCODE_BUILD
+#include <bpf/vmlinux/vmlinux.h>
+#else
+#include "../open_source/vmlinux.h"
+#endif // FBCODE_BUILD
+
+#include <bpf/bpf_core_read.h>
+#include <bpf/bpf_helpers.h>
+#include <bpf/bpf_tracing.h>
+
+#define TASK_COMM_LEN 16
+#define FILENAME_LEN 256
+#define ARGV_LEN 256
+#define MAX_FORK_PARENTS 32768
+
+struct {
+  __uint(type, BPF_MAP_TYPE_PERF_EVENT_ARRAY);
+  __uint(key_size, sizeof(u32));
+  __uint(value_size, sizeof(u32));
+} events SEC(".maps");
+
+// Process ID -> ID of the process that forked it. Once a parent exits its
+// children are reparented, so task->real_parent at exec time may no longer
+// point at the process that actually spawned the child.
+struct {
+  __uint(type, BPF_MAP_TYPE_LRU_HASH);
+  __uint(max_entries, MAX_FORK_PARENTS);
+  __type(key, pid_t);
+  __type(value, pid_t);
+} fork_parents SEC(".maps");
+
+struct event {
+  pid_t pid; // process ID after exec
+  pid_t ppid; // process ID of the forking parent
+  uint64_t cgroup_id; // cgroup2 ID, the inode number of the cgroup directory
+  uint32_t argv_len; // number of bytes copied into argv
+  uint8_t argv_truncated; // whether argv was longer than ARGV_LEN
+  char comm[TASK_COMM_LEN]; // process name after exec
+  char filename[FILENAME_LEN]; // path passed to execve()
+  char argv[ARGV_LEN]; // NUL-separated argument prefix
+};
+
+// struct event doesn't fit on the 512 byte BPF stack
+struct {
+  __uint(type, BPF_MAP_TYPE_PERCPU_ARRAY);
+  __uint(max_entries, 1);
+  __type(key, u32);
+  __type(value, struct event);
+} scratch SEC(".maps");
+
+// sched:sched_process_fork is triggered in the context of the parent for every
+// new task. We only remember the forking process, which lets us report the
+// original parent when the child execs.
+SEC("tracepoint/sched/sched_process_fork")
+int tracepoint__sched__sched_process_fork(
+    struct trace_event_raw_sched_process_fork* args
+) {
+  pid_t child = args->child_pid;
+  pid_t parent = bpf_get_current_pid_tgid() >> 32;
+
+  bpf_map_update_elem(&fork_parents, &child, &parent, BPF_ANY);
+  return 0;
+}
+
+// sched:sched_process_exec is triggered once the new program image is in
+// place, so current->mm already describes the new argv.
+SEC("tracepoint/sched/sched_process_exec")
+int tracepoint__sched__sched_process_exec(
+    struct trace_event_raw_sched_process_exec* args
+) {
+  struct task_struct* task = (struct task_struct*)bpf_get_current_task();
+  u32 zero = 0;
+
+  struct event* data = bpf_map_lookup_elem(&scratch, &zero);
+  if (!data) {
+    return 0;
+  }
+
+  data->pid = bpf_get_current_pid_tgid() >> 32;
+  pid_t* parent = bpf_map_lookup_elem(&fork_parents, &data->pid);
+  data->ppid = parent ? *parent : BPF_CORE_READ(task, real_parent, tgid);
+  data->cgroup_id = bpf_get_current_cgroup_id();
+  bpf_get_current_comm(&data->comm, sizeof(data->comm));
+
+  unsigned int filename_off = args->__data_loc_filename & 0xFFFF;
+  bpf_probe_read_kernel_str(
+      &data->filename, sizeof(data->filename), (void*)args + filename_off);
+
+  unsigned long arg_start = BPF_CORE_READ(task, mm, arg_start);
+  unsigned long arg_end = BPF_CORE_READ(task, mm, arg_end);
+  unsigned long argv_len = arg_end - arg_start;
+  data->argv_truncated = argv_len > ARGV_LEN;
+  if (argv_len > ARGV_LEN) {
+    argv_len = ARGV_LEN;
+  }
+  if (bpf_probe_read_user(&data->argv, argv_len, (void*)arg_start) != 0) {
+    argv_len = 0;
+  }
+  data->argv_len = argv_len;
+
+  bpf_perf_event_output(
+      args, &events, BPF_F_CURRENT_CPU, data, sizeof(struct event));
+  return 0;
+}
+
+char _license[] SEC("license") = "GPL";
@@ -23,8 +23,18 @@ struct metadata {
   uint32_t sid; // session ID
   uint32_t cpu; // CPU task is running on
   char comm[TASK_COMM_LEN]; // process name
+  int32_t exit_code; // exit status, only meaningful if exit_signal is 0
+  int32_t exit_signal; // signal that terminated the task, 0 for normal exit
+  uint8_t core_dumped; // whether the terminating signal dumped core
+  uint8_t oom_killed; // whether the task was picked by the OOM killer
+  uint8_t missing; // MISSING_* bits of the fields this kernel doesn't have
 };
 
+#define MISSING_SID (1 << 0)
+#define MISSING_OOM_KILLED (1 << 1)
+#define MISSING_IO (1 << 2)
+#define MISSING_RSS (1 << 3)
+
 struct exitstats {
   uint64_t min_flt; /* Minor Page Fault Count - copy on write */
   uint64_t maj_flt; /* Major Page Fault Count - virtual memory */
@@ -42,6 +52,46 @@ struct event {
   struct exitstats stats;
 };
 
+// Set by userspace before loading. The reduced program only reads task fields
+// every supported kernel has, for when the full one fails to load anyway.
+// Fields that depend on config options or moved between kernel versions are
+// checked one by one and reported in metadata.missing instead.
+const volatile bool reduced = false;
+
+// Events bpf_perf_event_output() failed to submit, e.g. because the buffer
+// was full
+u64 dropped = 0;
+
+// rss_stat became an array of percpu counters in 6.2
+struct mm_struct___post62 {
+  struct percpu_counter rss_stat[NR_MM_COUNTERS];
+} __attribute__((preserve_access_index));
+
+// Reads the resident pages of mm. Returns false if this kernel has neither
+// rss_stat layout. The branch for the other layout fails its CO-RE
+// relocations, which is fine as the verifier prunes it as dead code.
+static __always_inline bool read_rss_pages(
+    const struct mm_struct* mm,
+    u64* pages) {
+  s64 sum;
+  if (bpf_core_field_exists(mm->rss_stat.count)) {
+    sum = BPF_CORE_READ(mm, rss_stat.count[MM_FILEPAGES].counter) +
+        BPF_CORE_READ(mm, rss_stat.count[MM_ANONPAGES].counter) +
+        BPF_CORE_READ(mm, rss_stat.count[MM_SHMEMPAGES].counter);
+  } else {
+    const struct mm_struct___post62* mm_post62 = (const void*)mm;
+    if (!bpf_core_field_exists(mm_post62->rss_stat)) {
+      return false;
+    }
+    // Only the shared part of the counters, off by at most the percpu batches
+    sum = BPF_CORE_READ(mm_post62, rss_stat[MM_FILEPAGES].count) +
+        BPF_CORE_READ(mm_post62, rss_stat[MM_ANONPAGES].count) +
+        BPF_CORE_READ(mm_post62, rss_stat[MM_SHMEMPAGES].count);
+  }
+  *pages = sum > 0 ? sum : 0;
+  return true;
+}
+
 // sched:sched_process_exit is triggered right before process/thread exits. At
 // this point we capture last taskstats to account resource usage of short-lived
 // processes. We also check tas->signal.live counter to determine if this thread
@@ -58,36 +108,57 @@ int tracepoint__sched__sched_process_exit(
   data.meta.tid = pid_tgid & 0xFFFFFFFF;
   data.meta.ppid = BPF_CORE_READ(task, real_parent, tgid);
   data.meta.pgrp = BPF_CORE_READ(task, group_leader, tgid);
-  data.meta.sid = BPF_CORE_READ(task, sessionid);
   data.meta.cpu = BPF_CORE_READ(task, cpu);
   bpf_get_current_comm(&data.meta.comm, sizeof(data.meta.comm));
 
+  /* do_exit() has already set exit_code in wait(2) status format */
+  int exit_code = BPF_CORE_READ(task, exit_code);
+  data.meta.exit_code = (exit_code >> 8) & 0xff;
+  data.meta.exit_signal = exit_code & 0x7f;
+  data.meta.core_dumped = (exit_code & 0x80) != 0;
+
   /* read/calculate exitstats */
   data.stats.min_flt = BPF_CORE_READ(task, min_flt);
   data.stats.maj_flt = BPF_CORE_READ(task, maj_flt);
   data.stats.utime_us = BPF_CORE_READ(task, utime) / 1000;
   data.stats.stime_us = BPF_CORE_READ(task, stime) / 1000;
   data.stats.nr_threads = BPF_CORE_READ(task, signal, nr_threads);
+  data.stats.etime_us = (now - BPF_CORE_READ(task, start_time)) / 1000;
 
-  /* CONFIG_TASK_IO_ACCOUNTING is always enabled in fbk kernels */
-  data.stats.io_read_bytes = BPF_CORE_READ(task, ioac.read_bytes);
-  data.stats.io_write_bytes = BPF_CORE_READ(task, ioac.write_bytes);
+  /* The verifier prunes this as dead code when reduced is set, so failed CO-RE
+   * relocations in here don't prevent loading */
+  if (!reduced) {
+    /* CONFIG_AUDIT */
+    if (bpf_core_field_exists(task->sessionid)) {
+      data.meta.sid = BPF_CORE_READ(task, sessionid);
+    } else {
+      data.meta.missing |= MISSING_SID;
+    }
+    /* signal->oom_mm is set by mark_oom_victim() and never cleared */
+    if (bpf_core_field_exists(task->signal->oom_mm)) {
+      data.meta.oom_killed = BPF_CORE_READ(task, signal, oom_mm) != NULL;
+    } else {
+      data.meta.missing |= MISSING_OOM_KILLED;
+    }
 
-  data.stats.etime_us = (now - BPF_CORE_READ(task, start_time)) / 1000;
-  const struct mm_struct* mm = BPF_CORE_READ(task, mm);
-  if (mm) {
-    u64 file_pages = BPF_CORE_READ(mm, rss_stat.count[MM_FILEPAGES].counter);
-    u64 anon_pages = BPF_CORE_READ(mm, rss_stat.count[MM_ANONPAGES].counter);
-    u64 shmem_pages = BPF_CORE_READ(mm, rss_stat.count[MM_SHMEMPAGES].counter);
-    u64 active_rss = file_pages + anon_pages + shmem_pages;
-
-    data.stats.active_rss_pages = active_rss;
-  } else {
-    data.stats.active_rss_pages = 0;
+    /* CONFIG_TASK_IO_ACCOUNTING */
+    if (bpf_core_field_exists(task->ioac)) {
+      data.stats.io_read_bytes = BPF_CORE_READ(task, ioac.read_bytes);
+      data.stats.io_write_bytes = BPF_CORE_READ(task, ioac.write_bytes);
+    } else {
+      data.meta.missing |= MISSING_IO;
+    }
+
+    const struct mm_struct* mm = BPF_CORE_READ(task, mm);
+    if (mm && !read_rss_pages(mm, &data.stats.active_rss_pages)) {
+      data.meta.missing |= MISSING_RSS;
+    }
   }
 
-  bpf_perf_event_output(
-      args, &events, BPF_F_CURRENT_CPU, &data, sizeof(struct event));
+  if (bpf_perf_event_output(
+          args, &events, BPF_F_CURRENT_CPU, &data, sizeof(struct event))) {
+    __sync_fetch_and_add(&dropped, 1);
+  }
   return 1;
 }
 
@@ -0,0 +1,92 @@
+#ifdef FBCODE_BUILD
+#include <bpf/vmlinux/vmlinux.h>
+#else
+#include "../open_source/vmlinux.h"
+#endif // FBCODE_BUILD
+
+#include <bpf/bpf_core_read.h>
+#include <bpf/bpf_helpers.h>
+#include <bpf/bpf_tracing.h>
+
+#define TASK_COMM_LEN 16
+
+struct {
+  __uint(type, BPF_MAP_TYPE_PERF_EVENT_ARRAY);
+  __uint(key_size, sizeof(u32));
+  __uint(value_size, sizeof(u32));
+} events SEC(".maps");
+
+struct event {
+  pid_t victim_pid; // process ID of the task being killed
+  pid_t trigger_pid; // process ID of the task whose allocation failed
+  uint64_t victim_cgroup_id; // cgroup2 ID of the victim
+  uint64_t memcg_id; // cgroup2 ID of the OOMing memcg, 0 for global OOMs
+  uint64_t victim_rss_pages; // RSS of the victim, pages
+  uint64_t totalpages; // memory available to the OOMing domain, pages
+  uint32_t constraint; // enum oom_constraint
+  char victim_comm[TASK_COMM_LEN];
+  char trigger_comm[TASK_COMM_LEN];
+};
+
+// rss_stat became an array of percpu counters in 6.2
+struct mm_struct___post62 {
+  struct percpu_counter rss_stat[NR_MM_COUNTERS];
+} __attribute__((preserve_access_index));
+
+// Reads the resident pages of mm. Returns false if this kernel has neither
+// rss_stat layout. The branch for the other layout fails its CO-RE
+// relocations, which is fine as the verifier prunes it as dead code.
+static __always_inline bool read_rss_pages(
+    const struct mm_struct* mm,
+    u64* pages) {
+  s64 sum;
+  if (bpf_core_field_exists(mm->rss_stat.count)) {
+    sum = BPF_CORE_READ(mm, rss_stat.count[MM_FILEPAGES].counter) +
+        BPF_CORE_READ(mm, rss_stat.count[MM_ANONPAGES].counter) +
+        BPF_CORE_READ(mm, rss_stat.count[MM_SHMEMPAGES].counter);
+  } else {
+    const struct mm_struct___post62* mm_post62 = (const void*)mm;
+    if (!bpf_core_field_exists(mm_post62->rss_stat)) {
+      return false;
+    }
+    // Only the shared part of the counters, off by at most the percpu batches
+    sum = BPF_CORE_READ(mm_post62, rss_stat[MM_FILEPAGES].count) +
+        BPF_CORE_READ(mm_post62, rss_stat[MM_ANONPAGES].count) +
+        BPF_CORE_READ(mm_post62, rss_stat[MM_SHMEMPAGES].count);
+  }
+  *pages = sum > 0 ? sum : 0;
+  return true;
+}
+
+// oom_kill_process() is called once the OOM killer has picked a victim, in the
+// context of the task that failed to allocate. oc->memcg is only set when the
+// OOM is confined to a memory cgroup.
+SEC("kprobe/oom_kill_process")
+int BPF_KPROBE(kprobe__oom_kill_process, struct oom_control* oc) {
+  struct task_struct* victim = BPF_CORE_READ(oc, chosen);
+  struct mem_cgroup* memcg = BPF_CORE_READ(oc, memcg);
+
+  struct event data = {};
+  data.victim_pid = BPF_CORE_READ(victim, tgid);
+  data.trigger_pid = bpf_get_current_pid_tgid() >> 32;
+  data.victim_cgroup_id = BPF_CORE_READ(victim, cgroups, dfl_cgrp, kn, id);
+  if (memcg) {
+    data.memcg_id = BPF_CORE_READ(memcg, css.cgroup, kn, id);
+  }
+  data.totalpages = BPF_CORE_READ(oc, totalpages);
+  data.constraint = BPF_CORE_READ(oc, constraint);
+  BPF_CORE_READ_STR_INTO(&data.victim_comm, victim, comm);
+  bpf_get_current_comm(&data.trigger_comm, sizeof(data.trigger_comm));
+
+  const struct mm_struct* mm = BPF_CORE_READ(victim, mm);
+  if (mm) {
+    // Left at 0 if unknown
+    read_rss_pages(mm, &data.victim_rss_pages);
+  }
+
+  bpf_perf_event_output(
+      ctx, &events, BPF_F_CURRENT_CPU, &data, sizeof(struct event));
+  return 0;
+}
+
+char _license[] SEC("license") = "GPL";
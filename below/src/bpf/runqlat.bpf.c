@@ -0,0 +1,145 @@
+#ifdef FBCODE_BUILD
+#include <bpf/vmlinux/vmlinux.h>
+#else
+#include "../open_source/vmlinux.h"
+#endif // FBCODE_BUILD
+
+#include <bpf/bpf_core_read.h>
+#include <bpf/bpf_helpers.h>
+#include <bpf/bpf_tracing.h>
+
+#define MAX_SLOTS 26
+#define MAX_ENTRIES 10240
+#define TASK_RUNNING 0
+
+// Bucket i counts delays in [2^i, 2^(i+1)) usec, the last one everything above
+struct hist {
+  uint64_t slots[MAX_SLOTS];
+};
+
+// pid -> when it was put on a run queue, ns
+struct {
+  __uint(type, BPF_MAP_TYPE_HASH);
+  __uint(max_entries, MAX_ENTRIES);
+  __type(key, u32);
+  __type(value, u64);
+} start SEC(".maps");
+
+// cgroup2 ID -> cumulative histogram. LRU so that removed cgroups age out.
+struct {
+  __uint(type, BPF_MAP_TYPE_LRU_HASH);
+  __uint(max_entries, MAX_ENTRIES);
+  __type(key, u64);
+  __type(value, struct hist);
+} hists SEC(".maps");
+
+static const struct hist zero;
+
+// task_struct::state was renamed to __state in 5.14
+struct task_struct___o {
+  volatile long int state;
+} __attribute__((preserve_access_index));
+
+struct task_struct___x {
+  unsigned int __state;
+} __attribute__((preserve_access_index));
+
+static __always_inline long get_task_state(void* task) {
+  struct task_struct___x* t = task;
+
+  if (bpf_core_field_exists(t->__state)) {
+    return BPF_CORE_READ(t, __state);
+  }
+  return BPF_CORE_READ((struct task_struct___o*)task, state);
+}
+
+static __always_inline u64 log2(u32 v) {
+  u32 shift, r;
+
+  r = (v > 0xFFFF) << 4;
+  v >>= r;
+  shift = (v > 0xFF) << 3;
+  v >>= shift;
+  r |= shift;
+  shift = (v > 0xF) << 2;
+  v >>= shift;
+  r |= shift;
+  shift = (v > 0x3) << 1;
+  v >>= shift;
+  r |= shift;
+  r |= (v >> 1);
+  return r;
+}
+
+static __always_inline u64 log2l(u64 v) {
+  u32 hi = v >> 32;
+
+  if (hi) {
+    return log2(hi) + 32;
+  }
+  return log2(v);
+}
+
+static __always_inline int trace_enqueue(u32 pid) {
+  u64 ts;
+
+  if (!pid) {
+    return 0;
+  }
+  ts = bpf_ktime_get_ns();
+  bpf_map_update_elem(&start, &pid, &ts, BPF_ANY);
+  return 0;
+}
+
+SEC("tp_btf/sched_wakeup")
+int BPF_PROG(sched_wakeup, struct task_struct* p) {
+  return trace_enqueue(BPF_CORE_READ(p, pid));
+}
+
+SEC("tp_btf/sched_wakeup_new")
+int BPF_PROG(sched_wakeup_new, struct task_struct* p) {
+  return trace_enqueue(BPF_CORE_READ(p, pid));
+}
+
+SEC("tp_btf/sched_switch")
+int BPF_PROG(
+    sched_switch,
+    bool preempt,
+    struct task_struct* prev,
+    struct task_struct* next) {
+  struct hist* histp;
+  u64 *tsp, delta_us, slot, cgroup_id;
+  u32 pid;
+
+  // A preempted task goes straight back on the run queue
+  if (get_task_state(prev) == TASK_RUNNING) {
+    trace_enqueue(BPF_CORE_READ(prev, pid));
+  }
+
+  pid = BPF_CORE_READ(next, pid);
+  tsp = bpf_map_lookup_elem(&start, &pid);
+  if (!tsp) {
+    return 0;
+  }
+  delta_us = (bpf_ktime_get_ns() - *tsp) / 1000;
+  bpf_map_delete_elem(&start, &pid);
+
+  cgroup_id = BPF_CORE_READ(next, cgroups, dfl_cgrp, kn, id);
+  histp = bpf_map_lookup_elem(&hists, &cgroup_id);
+  if (!histp) {
+    bpf_map_update_elem(&hists, &cgroup_id, &zero, BPF_NOEXIST);
+    histp = bpf_map_lookup_elem(&hists, &cgroup_id);
+    if (!histp) {
+      return 0;
+    }
+  }
+
+  slot = log2l(delta_us);
+  if (slot >= MAX_SLOTS) {
+    slot = MAX_SLOTS - 1;
+  }
+  __sync_fetch_and_add(&histp->slots[slot], 1);
+  return 0;
+}
+
+char _license[] SEC("license") = "GPL";
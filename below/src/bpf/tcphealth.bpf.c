@@ -0,0 +1,185 @@
+#ifdef FBCODE_BUILD
+#include <bpf/vmlinux/vmlinux.h>
+#else
+#include "../open_source/vmlinux.h"
+#endif // FBCODE_BUILD
+
+#include <bpf/bpf_core_read.h>
+#include <bpf/bpf_helpers.h>
+#include <bpf/bpf_tracing.h>
+
+#define MAX_ENTRIES 10240
+
+// Only the running kernel is known to have the drop reasons, so they are
+// declared under a local name. CO-RE relocates them against the kernel's
+// enum skb_drop_reason, ignoring the ___local suffix.
+enum skb_drop_reason___local {
+  SKB_NOT_DROPPED_YET___local = 0,
+  SKB_CONSUMED___local = 1,
+};
+
+// Process and cgroup that last used a socket from process context
+struct owner {
+  uint64_t cgroup_id; // cgroup2 ID
+  uint32_t pid; // tgid
+  uint32_t pad;
+};
+
+struct counts {
+  uint64_t retransmits; // segments retransmitted
+  uint64_t drops; // packets freed by kfree_skb for any reason but consumption
+};
+
+struct reason_key {
+  uint64_t cgroup_id; // cgroup2 ID
+  uint32_t reason; // enum skb_drop_reason
+  uint32_t pad;
+};
+
+// Retransmits and most drops happen in softirq context where the current task
+// is whoever got interrupted, so remember who owns each socket instead
+struct {
+  __uint(type, BPF_MAP_TYPE_LRU_HASH);
+  __uint(max_entries, MAX_ENTRIES);
+  __type(key, struct sock*);
+  __type(value, struct owner);
+} owners SEC(".maps");
+
+// Cumulative counts per process. LRU so that exited processes age out.
+struct {
+  __uint(type, BPF_MAP_TYPE_LRU_HASH);
+  __uint(max_entries, MAX_ENTRIES);
+  __type(key, uint32_t);
+  __type(value, struct counts);
+} pid_counts SEC(".maps");
+
+// Cumulative counts per cgroup, kept apart from the processes so that they
+// don't go backwards when processes exit and age out. Cgroups are far fewer
+// than processes, so only removed ones are evicted in practice.
+struct {
+  __uint(type, BPF_MAP_TYPE_LRU_HASH);
+  __uint(max_entries, MAX_ENTRIES);
+  __type(key, uint64_t);
+  __type(value, struct counts);
+} cgroup_counts SEC(".maps");
+
+// Cumulative drops per cgroup and drop reason. Only kernels that pass the
+// reason to kfree_skb have these.
+struct {
+  __uint(type, BPF_MAP_TYPE_LRU_HASH);
+  __uint(max_entries, MAX_ENTRIES);
+  __type(key, struct reason_key);
+  __type(value, uint64_t);
+} drop_reasons SEC(".maps");
+
+static const struct counts zero;
+static const uint64_t zero_drops;
+
+static __always_inline void*
+lookup_or_init(void* map, const void* key, const void* init) {
+  void* value = bpf_map_lookup_elem(map, key);
+
+  if (!value) {
+    bpf_map_update_elem(map, key, init, BPF_NOEXIST);
+    value = bpf_map_lookup_elem(map, key);
+  }
+  return value;
+}
+
+static __always_inline int record_owner(struct sock* sk) {
+  struct owner owner = {};
+
+  owner.pid = bpf_get_current_pid_tgid() >> 32;
+  if (!owner.pid) {
+    return 0;
+  }
+  owner.cgroup_id = bpf_get_current_cgroup_id();
+  bpf_map_update_elem(&owners, &sk, &owner, BPF_ANY);
+  return 0;
+}
+
+// Counts a retransmit or drop for the process and cgroup that own sk. Returns
+// the cgroup ID of the owner, or 0 if sk has none.
+static __always_inline uint64_t count(struct sock* sk, bool retransmit) {
+  struct owner* ownerp;
+  struct owner owner;
+  struct counts* countsp;
+
+  if (!sk) {
+    return 0;
+  }
+  // Sockets we haven't seen used from process context can't be attributed
+  ownerp = bpf_map_lookup_elem(&owners, &sk);
+  if (!ownerp) {
+    return 0;
+  }
+  owner = *ownerp;
+
+  countsp = lookup_or_init(&pid_counts, &owner.pid, &zero);
+  if (countsp) {
+    __sync_fetch_and_add(retransmit ? &countsp->retransmits : &countsp->drops, 1);
+  }
+  countsp = lookup_or_init(&cgroup_counts, &owner.cgroup_id, &zero);
+  if (countsp) {
+    __sync_fetch_and_add(retransmit ? &countsp->retransmits : &countsp->drops, 1);
+  }
+  return owner.cgroup_id;
+}
+
+SEC("fentry/tcp_sendmsg")
+int BPF_PROG(tcp_sendmsg, struct sock* sk) {
+  return record_owner(sk);
+}
+
+// Called whenever a reader consumed data, regardless of the recvmsg flavour
+SEC("fentry/tcp_cleanup_rbuf")
+int BPF_PROG(tcp_cleanup_rbuf, struct sock* sk) {
+  return record_owner(sk);
+}
+
+SEC("tp_btf/tcp_retransmit_skb")
+int BPF_PROG(tcp_retransmit_skb, const struct sock* sk) {
+  count((struct sock*)sk, true);
+  return 0;
+}
+
+// Commit c504e5c2f964 (v5.17-rc1) added the drop reason along with its enum,
+// also in backports of it. Before that consume_skb() had its own tracepoint,
+// so everything here was a drop.
+SEC("tp_btf/kfree_skb")
+int kfree_skb(u64* ctx) {
+  struct sk_buff* skb = (struct sk_buff*)ctx[0];
+  struct reason_key key = {};
+  uint64_t* dropsp;
+  bool has_reason = bpf_core_type_exists(enum skb_drop_reason___local);
+
+  if (has_reason) {
+    key.reason = ctx[2];
+
+    if (bpf_core_enum_value_exists(
+            enum skb_drop_reason___local, SKB_NOT_DROPPED_YET___local) &&
+        key.reason ==
+            bpf_core_enum_value(
+                enum skb_drop_reason___local, SKB_NOT_DROPPED_YET___local)) {
+      return 0;
+    }
+    if (bpf_core_enum_value_exists(
+            enum skb_drop_reason___local, SKB_CONSUMED___local) &&
+        key.reason ==
+            bpf_core_enum_value(
+                enum skb_drop_reason___local, SKB_CONSUMED___local)) {
+      return 0;
+    }
+  }
+
+  key.cgroup_id = count(BPF_CORE_READ(skb, sk), false);
+  if (has_reason && key.cgroup_id) {
+    dropsp = lookup_or_init(&drop_reasons, &key, &zero_drops);
+    if (dropsp) {
+      __sync_fetch_and_add(dropsp, 1);
+    }
+  }
+  return 0;
+}
+
+char _license[] SEC("license") = "GPL";
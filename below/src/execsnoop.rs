@@ -0,0 +1,142 @@
+use core::time::Duration;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use anyhow::{Context, Result};
+use libbpf_rs::PerfBufferBuilder;
+use plain::Plain;
+use slog::warn;
+
+use common::util::get_unix_timestamp;
+
+use crate::exitstat::c_buf_to_string;
+use crate::ExecsnoopSkelBuilder;
+
+/// Upper bound on events kept between two collections so that an exec storm
+/// (e.g. a large build) can't grow the buffer or the store without bound.
+const MAX_BUFFERED_EVENTS: usize = 4096;
+
+// See bpf prog for comments on what each field is
+#[repr(C)]
+pub struct Event {
+    pub pid: i32,
+    pub ppid: i32,
+    pub cgroup_id: u64,
+    pub argv_len: u32,
+    pub argv_truncated: u8,
+    pub comm: [u8; 16],
+    pub filename: [u8; 256],
+    pub argv: [u8; 256],
+}
+
+impl Default for Event {
+    fn default() -> Self {
+        Self {
+            pid: 0,
+            ppid: 0,
+            cgroup_id: 0,
+            argv_len: 0,
+            argv_truncated: 0,
+            comm: [0; 16],
+            filename: [0; 256],
+            argv: [0; 256],
+        }
+    }
+}
+
+unsafe impl Plain for Event {}
+
+pub struct ExecsnoopDriver {
+    logger: slog::Logger,
+    debug: bool,
+    buffer: Arc<Mutex<Vec<procfs::ExecEvent>>>,
+}
+
+impl ExecsnoopDriver {
+    pub fn new(logger: slog::Logger, debug: bool) -> Self {
+        Self {
+            logger,
+            debug,
+            buffer: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    pub fn get_buffer(&self) -> Arc<Mutex<Vec<procfs::ExecEvent>>> {
+        self.buffer.clone()
+    }
+
+    fn handle_event(
+        logger: &slog::Logger,
+        handle: &Arc<Mutex<Vec<procfs::ExecEvent>>>,
+        dropping: &mut bool,
+        data: &[u8],
+    ) {
+        let mut event = Event::default();
+        plain::copy_from_bytes(&mut event, data).expect("Data buffer was too short");
+
+        let argv_len = std::cmp::min(event.argv_len as usize, event.argv.len());
+        let exec_event = procfs::ExecEvent {
+            timestamp: Some(get_unix_timestamp(SystemTime::now())),
+            pid: Some(event.pid),
+            ppid: Some(event.ppid),
+            comm: c_buf_to_string(&event.comm),
+            filename: c_buf_to_string(&event.filename),
+            // Arguments are laid out back to back in user memory, each
+            // terminated by a NUL
+            argv: Some(
+                event.argv[..argv_len]
+                    .split(|b| *b == 0)
+                    .filter(|arg| !arg.is_empty())
+                    .map(|arg| String::from_utf8_lossy(arg).into_owned())
+                    .collect(),
+            ),
+            argv_truncated: Some(event.argv_truncated != 0),
+            cgroup_id: Some(event.cgroup_id),
+        };
+
+        // handle.lock() only fails if a thread holding the lock panic'd, in which
+        // case we should probably panic too.
+        let mut buffer = handle.lock().unwrap();
+        if buffer.len() < MAX_BUFFERED_EVENTS {
+            buffer.push(exec_event);
+            *dropping = false;
+        } else if !*dropping {
+            // Only warn once until the collector drains the buffer
+            warn!(
+                logger,
+                "More than {} execs since last collection, dropping the rest", MAX_BUFFERED_EVENTS
+            );
+            *dropping = true;
+        }
+    }
+
+    fn handle_lost_events(logger: &slog::Logger, cpu: i32, count: u64) {
+        warn!(logger, "Lost {} exec events on CPU {}", count, cpu);
+    }
+
+    /// Loops forever unless an error is hit
+    pub fn drive(&mut self) -> Result<()> {
+        let mut skel_builder = ExecsnoopSkelBuilder::default();
+        skel_builder.obj_builder.debug(self.debug);
+        let mut skel = skel_builder.open()?.load()?;
+        skel.attach()?;
+
+        // Set up perf ring buffer
+        let buffer = self.get_buffer();
+        let logger_clone = self.logger.clone();
+        let lost_logger = self.logger.clone();
+        let mut dropping = false;
+        let perf = PerfBufferBuilder::new(skel.maps().events())
+            .sample_cb(move |_, data: &[u8]| {
+                Self::handle_event(&logger_clone, &buffer, &mut dropping, data)
+            })
+            .lost_cb(move |cpu, count| Self::handle_lost_events(&lost_logger, cpu, count))
+            .build()?;
+
+        // Poll events
+        loop {
+            perf.poll(Duration::from_millis(100))
+                .context("Error polling perf buffer")?;
+        }
+    }
+}
@@ -6,11 +6,11 @@ use anyhow::{Context, Result};
 use libbpf_rs::PerfBufferBuilder;
 use once_cell::sync::Lazy;
 use plain::Plain;
-use slog::warn;
+use slog::{info, warn};
 
-use crate::ExitstatSkelBuilder;
+use crate::{ExitstatSkel, ExitstatSkelBuilder};
 
-static PAGE_SIZE: Lazy<u64> = Lazy::new(page_size);
+pub(crate) static PAGE_SIZE: Lazy<u64> = Lazy::new(page_size);
 
 #[repr(C)]
 #[derive(Default)]
@@ -21,8 +21,19 @@ pub struct Metadata {
     pub sid: i32,
     pub cpu: i32,
     pub comm: [u8; 16],
+    pub exit_code: i32,
+    pub exit_signal: i32,
+    pub core_dumped: u8,
+    pub oom_killed: u8,
+    pub missing: u8,
 }
 
+// Bits of Metadata::missing
+const MISSING_SID: u8 = 1 << 0;
+const MISSING_OOM_KILLED: u8 = 1 << 1;
+const MISSING_IO: u8 = 1 << 2;
+const MISSING_RSS: u8 = 1 << 3;
+
 // See bpf prog for comments on what each field is
 #[repr(C)]
 #[derive(Default)]
@@ -47,6 +58,16 @@ pub struct Event {
 
 unsafe impl Plain for Event {}
 
+/// Converts a NUL-terminated buffer, ignoring anything after the first NUL
+pub(crate) fn c_buf_to_string(buf: &[u8]) -> Option<String> {
+    let len = buf.iter().position(|b| *b == 0).unwrap_or(buf.len());
+    if len == 0 {
+        None
+    } else {
+        Some(String::from_utf8_lossy(&buf[..len]).into_owned())
+    }
+}
+
 fn page_size() -> u64 {
     match unsafe { libc::sysconf(libc::_SC_PAGESIZE) } {
         -1 => panic!("Failed to query page size"),
@@ -58,6 +79,7 @@ pub struct ExitstatDriver {
     logger: slog::Logger,
     debug: bool,
     buffer: Arc<Mutex<procfs::PidMap>>,
+    health: Arc<Mutex<procfs::ExitstatHealth>>,
 }
 
 impl ExitstatDriver {
@@ -66,6 +88,11 @@ impl ExitstatDriver {
             logger,
             debug,
             buffer: Arc::new(Mutex::new(procfs::PidMap::default())),
+            health: Arc::new(Mutex::new(procfs::ExitstatHealth {
+                status: Some(procfs::ExitstatStatus::Loading),
+                lost_events: Some(0),
+                dropped_events: Some(0),
+            })),
         }
     }
 
@@ -73,7 +100,43 @@ impl ExitstatDriver {
         self.buffer.clone()
     }
 
-    fn handle_event(handle: &Arc<Mutex<procfs::PidMap>>, data: &[u8]) {
+    pub fn get_health(&self) -> Arc<Mutex<procfs::ExitstatHealth>> {
+        self.health.clone()
+    }
+
+    fn load(&self, reduced: bool) -> Result<ExitstatSkel<'static>> {
+        let mut skel_builder = ExitstatSkelBuilder::default();
+        skel_builder.obj_builder.debug(self.debug);
+        let mut open_skel = skel_builder.open()?;
+        open_skel.rodata().reduced = reduced;
+        let mut skel = open_skel.load()?;
+        skel.attach()?;
+        Ok(skel)
+    }
+
+    /// Loads the full program, or the reduced one if the kernel can't run it
+    fn load_with_fallback(&self) -> Result<(ExitstatSkel<'static>, procfs::ExitstatStatus)> {
+        match self.load(false) {
+            Ok(skel) => Ok((skel, procfs::ExitstatStatus::Full)),
+            Err(e) => {
+                warn!(
+                    self.logger,
+                    "Failed to load exitstat: {:#}. Falling back to reduced exitstat, \
+                    session, IO, RSS and OOM kills of exited processes will be missing.",
+                    e
+                );
+                let skel = self.load(true).context("Failed to load reduced exitstat")?;
+                info!(self.logger, "Loaded reduced exitstat");
+                Ok((skel, procfs::ExitstatStatus::Reduced))
+            }
+        }
+    }
+
+    fn set_status(&self, status: procfs::ExitstatStatus) {
+        self.health.lock().unwrap().status = Some(status);
+    }
+
+    fn handle_event(handle: &Arc<Mutex<procfs::PidMap>>, reduced: bool, data: &[u8]) {
         let mut event = Event::default();
         plain::copy_from_bytes(&mut event, data).expect("Data buffer was too short");
 
@@ -88,6 +151,9 @@ impl ExitstatDriver {
         }
         comm_no_interior_nul.push(0);
 
+        // The reduced program leaves these zeroed, as does the full one for
+        // fields this kernel doesn't have. That is different from not knowing.
+        let has = |field| !reduced && event.meta.missing & field == 0;
         let pidinfo = procfs::PidInfo {
             stat: procfs::PidStat {
                 pid: Some(event.meta.tid), // event.meta.pid is actually tgid
@@ -98,24 +164,38 @@ impl ExitstatDriver {
                 state: Some(procfs::PidState::Dead),
                 ppid: Some(event.meta.ppid),
                 pgrp: Some(event.meta.pgrp),
-                session: Some(event.meta.sid),
+                session: has(MISSING_SID).then(|| event.meta.sid),
                 minflt: Some(event.stats.min_flt),
                 majflt: Some(event.stats.maj_flt),
                 user_usecs: Some(event.stats.utime_us),
                 system_usecs: Some(event.stats.stime_us),
                 num_threads: Some(event.stats.nr_threads),
                 running_secs: Some(event.stats.etime_us / 1000000),
-                rss_bytes: Some(event.stats.active_rss_pages * *PAGE_SIZE),
+                rss_bytes: has(MISSING_RSS).then(|| event.stats.active_rss_pages * *PAGE_SIZE),
                 processor: Some(event.meta.cpu),
             },
             io: procfs::PidIo {
-                rbytes: Some(event.stats.io_read_bytes),
-                wbytes: Some(event.stats.io_write_bytes),
+                rbytes: has(MISSING_IO).then(|| event.stats.io_read_bytes),
+                wbytes: has(MISSING_IO).then(|| event.stats.io_write_bytes),
             },
             // It seems to be somewhat tricky to get a cgroup name using bpf. It might be possible
             // with the bpf_get_current_cgroup_id() helper, but that returns what looks like an
             // inode number. I'm not sure if it's easy/possible to translate an inode # to a path.
             cgroup: "?".to_string(),
+            exit: Some(procfs::PidExit {
+                code: if event.meta.exit_signal == 0 {
+                    Some(event.meta.exit_code)
+                } else {
+                    None
+                },
+                signal: if event.meta.exit_signal != 0 {
+                    Some(event.meta.exit_signal)
+                } else {
+                    None
+                },
+                core_dumped: Some(event.meta.core_dumped != 0),
+                oom_killed: has(MISSING_OOM_KILLED).then(|| event.meta.oom_killed != 0),
+            }),
             // We can't access cmdline b/c it requires taking mmap_sem and a
             // bunch of memory management helpers.
             ..Default::default()
@@ -126,29 +206,45 @@ impl ExitstatDriver {
         handle.lock().unwrap().insert(event.meta.tid, pidinfo);
     }
 
-    fn handle_lost_events(logger: &slog::Logger, cpu: i32, count: u64) {
+    fn handle_lost_events(
+        logger: &slog::Logger,
+        health: &Arc<Mutex<procfs::ExitstatHealth>>,
+        cpu: i32,
+        count: u64,
+    ) {
         warn!(logger, "Lost {} events on CPU {}", count, cpu);
+        let mut health = health.lock().unwrap();
+        health.lost_events = Some(health.lost_events.unwrap_or_default() + count);
     }
 
     /// Loops forever unless an error is hit
     pub fn drive(&mut self) -> Result<()> {
-        let mut skel_builder = ExitstatSkelBuilder::default();
-        skel_builder.obj_builder.debug(self.debug);
-        let mut skel = skel_builder.open()?.load()?;
-        skel.attach()?;
+        let (skel, status) = match self.load_with_fallback() {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                self.set_status(procfs::ExitstatStatus::Failed);
+                return Err(e);
+            }
+        };
+        let reduced = status == procfs::ExitstatStatus::Reduced;
+        self.set_status(status);
 
         // Set up perf ring buffer
         let buffer = self.get_buffer();
         let logger_clone = self.logger.clone();
+        let health = self.get_health();
         let perf = PerfBufferBuilder::new(skel.maps().events())
-            .sample_cb(move |_, data: &[u8]| Self::handle_event(&buffer, data))
-            .lost_cb(move |cpu, count| Self::handle_lost_events(&logger_clone, cpu, count))
+            .sample_cb(move |_, data: &[u8]| Self::handle_event(&buffer, reduced, data))
+            .lost_cb(move |cpu, count| Self::handle_lost_events(&logger_clone, &health, cpu, count))
             .build()?;
 
         // Poll events
         loop {
-            perf.poll(Duration::from_millis(100))
-                .context("Error polling perf buffer")?;
+            if let Err(e) = perf.poll(Duration::from_millis(100)) {
+                self.set_status(procfs::ExitstatStatus::Failed);
+                return Err(e).context("Error polling perf buffer");
+            }
+            self.health.lock().unwrap().dropped_events = Some(skel.bss().dropped);
         }
     }
 }
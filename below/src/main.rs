@@ -19,10 +19,10 @@ use std::cell::RefCell;
 use std::fs;
 use std::io;
 use std::os::unix::fs::PermissionsExt;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::exit;
 use std::rc::Rc;
-use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, TryRecvError};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender, TryRecvError};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::{Duration, Instant, SystemTime};
@@ -41,7 +41,12 @@ use tar::{Archive, Builder as TarBuilder};
 use tempdir::TempDir;
 use users::{get_current_uid, get_user_by_uid};
 
+mod biolat;
+mod execsnoop;
 mod exitstat;
+mod oomkill;
+mod runqlat;
+mod tcphealth;
 #[cfg(test)]
 mod test;
 
@@ -50,6 +55,7 @@ use config::BelowConfig;
 use dump::DumpCommand;
 use model;
 use store::advance::{new_advance_local, new_advance_remote};
+use store::compression::Codec;
 use store::{self, ChunkSizePo2, CompressionMode, DataFrame, Store};
 use view::ViewState;
 
@@ -70,7 +76,8 @@ struct Opt {
 
 #[derive(Debug, StructOpt)]
 struct CompressOpts {
-    /// Enable zstd data file compression
+    /// Enable data file compression, with zstd unless --compress-codec
+    /// says otherwise
     ///
     /// Depending on typical data, you can expect around 10x
     /// smaller data files, and an even higher compression ratio if
@@ -89,22 +96,78 @@ struct CompressOpts {
     /// 20-30x smaller data files.
     #[structopt(long, requires("compress"), parse(try_from_str = parse_chunk_size))]
     dict_compress_chunk_size: Option<u32>,
+    /// Only valid when used with --compress. Codec to compress with, zstd
+    /// or lz4.
+    ///
+    /// lz4 takes a fraction of the CPU time of zstd but data files end up
+    /// several times larger. It does not support dictionaries.
+    #[structopt(long, requires("compress"))]
+    compress_codec: Option<Codec>,
+    /// Only valid when used with --compress and zstd. Must be between 1
+    /// and 22, zstd's default is 3.
+    ///
+    /// Higher levels compress better at the cost of CPU time. The level is
+    /// recorded in the store and used again when not given.
+    #[structopt(long, requires("compress"))]
+    zstd_level: Option<i32>,
+    /// Only valid when used with --compress and zstd.
+    ///
+    /// If specified, a zstd dictionary is trained on the frames written
+    /// before each shard starts and stored along with the shard. Unlike
+    /// --dict-compress-chunk-size, no frame has to be stored without a
+    /// dictionary, at the cost of training once per shard.
+    #[structopt(long, requires("compress"), conflicts_with("dict-compress-chunk-size"))]
+    train_dict: bool,
 }
 
 impl CompressOpts {
     fn to_compression_mode(&self) -> Result<CompressionMode> {
-        Ok(match (self.compress, self.dict_compress_chunk_size) {
-            (true, Some(chunk_size)) => {
+        if !self.compress {
+            if self.dict_compress_chunk_size.is_some()
+                || self.compress_codec.is_some()
+                || self.zstd_level.is_some()
+                || self.train_dict
+            {
+                bail!("bug: compression options can only be used with --compress");
+            }
+            return Ok(CompressionMode::None);
+        }
+        let codec = self.compress_codec.unwrap_or(Codec::Zstd);
+        let mode = match (codec, self.dict_compress_chunk_size, self.train_dict) {
+            (Codec::Lz4, None, false) => {
+                if self.zstd_level.is_some() {
+                    bail!("--zstd-level can only be used with zstd");
+                }
+                CompressionMode::Lz4
+            }
+            (Codec::Lz4, _, _) => bail!("lz4 does not support dictionaries"),
+            (Codec::Zstd, Some(chunk_size), false) => {
                 assert_eq!(chunk_size.count_ones(), 1, "chunk size not a power of 2");
                 let chunk_size_po2 = chunk_size.trailing_zeros();
                 CompressionMode::ZstdDictionary(ChunkSizePo2(chunk_size_po2))
             }
-            (true, None) => CompressionMode::Zstd,
-            (false, Some(_)) => {
-                bail!("bug: --dict-compress-chunk-size can only be used with --compress");
+            (Codec::Zstd, Some(_), true) => {
+                bail!("bug: --train-dict can't be used with --dict-compress-chunk-size");
             }
-            (false, None) => CompressionMode::None,
-        })
+            (Codec::Zstd, None, true) => CompressionMode::ZstdTrainedDictionary,
+            (Codec::Zstd, None, false) => CompressionMode::Zstd,
+        };
+        Ok(mode)
+    }
+
+    /// Creates a writer for the store at `path` compressing as specified
+    fn new_store_writer(
+        &self,
+        logger: slog::Logger,
+        path: &Path,
+        format: store::Format,
+    ) -> Result<store::StoreWriter> {
+        let mut writer =
+            store::StoreWriter::new(logger, path, self.to_compression_mode()?, format)?;
+        if let Some(level) = self.zstd_level {
+            writer.set_zstd_level(level)?;
+        }
+        Ok(writer)
     }
 }
 
@@ -142,22 +205,41 @@ enum Command {
         /// Override default port to connect remote viewing to
         #[structopt(long)]
         port: Option<u16>,
+        /// Flag to disable eBPF-based exec tracing
+        #[structopt(long)]
+        disable_exec_trace: bool,
+        /// Flag to disable eBPF-based OOM kill tracing
+        #[structopt(long)]
+        disable_oom_trace: bool,
+        /// Flag to enable eBPF-based run queue latency histograms per cgroup.
+        /// Off by default as it traces every context switch.
+        #[structopt(long)]
+        enable_runq_latency: bool,
+        /// Flag to enable eBPF-based block IO latency histograms per disk and
+        /// cgroup. Off by default as it traces every IO request.
+        #[structopt(long)]
+        enable_io_latency: bool,
+        /// Flag to enable eBPF-based attribution of TCP retransmits and
+        /// drops to processes and cgroups
+        #[structopt(long)]
+        enable_tcp_health: bool,
     },
     /// Record local system data (daemon mode)
     Record {
         #[structopt(short, long, default_value = "5")]
         interval_s: u64,
-        /// Store retention in seconds. Data is stored in 24 hour shards.
-        /// Whever an entire shard of data is outside the retention period it
-        /// is discarded. That is, any data older than retention + 24 hours
-        /// is guaranteed to be discarded.
+        /// Store retention in seconds. Data is stored in shards, 24 hours
+        /// long unless --shard-duration says otherwise. Whever an entire
+        /// shard of data is outside the retention period it is discarded.
+        /// That is, any data older than retention + the shard duration is
+        /// guaranteed to be discarded.
         ///
         /// N.B. If --store-size-limit is set, data may be discarded earlier
         ///      than the specified retention.
         #[structopt(long)]
         retain_for_s: Option<u64>,
-        /// Store size limit in bytes. Data is stored in 24 hour shards.
-        /// Shards before the active shard are deleted, oldest first,
+        /// Store size limit in bytes. Data is stored in shards, see
+        /// --retain-for-s. Shards before the active shard are deleted, oldest first,
         /// according to the size limit. Enforcement is only triggered on new
         /// shard creation.
         ///
@@ -165,11 +247,30 @@ enum Command {
         ///      be exceeded by a single active shard.
         #[structopt(long)]
         store_size_limit: Option<u64>,
+        /// Roll up shards entirely older than this many seconds into one
+        /// sample per --rollup-interval-s. Counters stay exact while gauges
+        /// are averaged or maxed over each interval. Applies before
+        /// --retain-for-s, which can then be set much longer.
+        #[structopt(long)]
+        rollup_after_s: Option<u64>,
+        /// Sample interval of rolled up shards in seconds
+        #[structopt(long, default_value = "60")]
+        rollup_interval_s: u64,
+        /// Length of new shards, e.g. "1h" or "6h". Shorter shards let
+        /// --retain-for-s and --store-size-limit discard data in smaller
+        /// steps. Must be whole minutes dividing a day. Recorded in the
+        /// store, so it only needs to be given when changing it. Existing
+        /// shards keep their length.
+        #[structopt(long)]
+        shard_duration: Option<humantime::Duration>,
         /// Whether or not to collect io.stat for cgroups which could
         /// be expensive
         #[structopt(long)]
         collect_io_stat: bool,
-        /// Override default port for remote viewing server
+        /// Override default port for remote viewing server. Open source
+        /// builds only serve remote viewing when this is given, on the
+        /// remote_store_bind_address of the config file (loopback by
+        /// default).
         #[structopt(long)]
         port: Option<u16>,
         /// Threshold for hold long data collection takes to trigger warnings.
@@ -181,6 +282,27 @@ enum Command {
         /// Flag to disable eBPF-based exitstats
         #[structopt(long)]
         disable_exitstats: bool,
+        /// Flag to disable eBPF-based exec tracing
+        #[structopt(long)]
+        disable_exec_trace: bool,
+        /// Flag to disable eBPF-based OOM kill tracing
+        #[structopt(long)]
+        disable_oom_trace: bool,
+        /// Flag to enable eBPF-based run queue latency histograms per cgroup.
+        /// Off by default as it traces every context switch.
+        #[structopt(long)]
+        enable_runq_latency: bool,
+        /// Flag to enable eBPF-based block IO latency histograms per disk and
+        /// cgroup. Off by default as it traces every IO request.
+        #[structopt(long)]
+        enable_io_latency: bool,
+        /// Flag to enable eBPF-based attribution of TCP retransmits and
+        /// drops to processes and cgroups
+        #[structopt(long)]
+        enable_tcp_health: bool,
+        /// Serialization format of new frames: cbor or msgpack
+        #[structopt(long, default_value = "cbor")]
+        store_format: store::Format,
         /// Options for compression
         #[structopt(flatten)]
         compress_opts: CompressOpts,
@@ -282,10 +404,79 @@ enum DebugCommand {
         host: Option<String>,
         #[structopt(long)]
         port: Option<u16>,
+        /// Serialization format to convert to: cbor or msgpack. Both keep
+        /// field names, so frames of either are upgraded when the model
+        /// changes.
+        #[structopt(long, default_value = "cbor")]
+        store_format: store::Format,
         /// Options for compression
         #[structopt(flatten)]
         compress_opts: CompressOpts,
     },
+    /// Report the shards of a store and the schema versions and
+    /// compression its frames were written with
+    StoreInfo {
+        /// Store to inspect instead of the configured one
+        #[structopt(long)]
+        store_dir: Option<PathBuf>,
+        /// Also decompress and compress every frame again to report the
+        /// compression ratio and the time recording and reading takes per
+        /// compression. Reads the whole store.
+        #[structopt(long)]
+        compression_stats: bool,
+    },
+    /// Check every shard of a store for corrupt index entries and data,
+    /// undecodable frames and gaps between samples. Exits with an error if
+    /// damage is found.
+    Fsck {
+        /// Store to check instead of the configured one
+        #[structopt(long)]
+        store_dir: Option<PathBuf>,
+        /// Report gaps between samples longer than this. Defaults to 4 times
+        /// the typical sample interval of each shard.
+        #[structopt(long)]
+        max_gap_s: Option<u64>,
+        /// Write all readable frames into a new store at this path
+        #[structopt(long)]
+        repair_to: Option<PathBuf>,
+        /// Serialization format of the repaired store: cbor or msgpack
+        #[structopt(long, default_value = "cbor")]
+        store_format: store::Format,
+        /// Compression of the repaired store
+        #[structopt(flatten)]
+        compress_opts: CompressOpts,
+    },
+    /// Combine stores or snapshot files, e.g. collected from several hosts,
+    /// into one new store with the compression settings of the first one.
+    /// Every frame records the host it came from. Repeated frames of a host
+    /// are dropped, and of frames of several hosts in the same second only
+    /// the one of the source given first is kept.
+    MergeStore {
+        /// Store directories or snapshot files. Prefix with HOSTNAME= to
+        /// record that host as the source of all frames of a source instead
+        /// of the hostname they were collected with.
+        #[structopt(required = true)]
+        sources: Vec<String>,
+        /// Directory to create the merged store in
+        #[structopt(long)]
+        to_store_dir: PathBuf,
+    },
+    /// Partition a store into one store per hostname and/or time window,
+    /// keeping its compression settings
+    SplitStore {
+        /// Store to split instead of the configured one
+        #[structopt(long)]
+        store_dir: Option<PathBuf>,
+        /// Directory to create the stores in
+        #[structopt(long)]
+        to_dir: PathBuf,
+        /// One store per hostname the frames were collected on
+        #[structopt(long)]
+        by_hostname: bool,
+        /// One store per window of this many seconds
+        #[structopt(long)]
+        interval_s: Option<u64>,
+    },
 }
 
 // Whether or not to start a service to respond to network request
@@ -358,14 +549,19 @@ fn create_log_dir(path: &PathBuf) -> Result<()> {
 }
 
 // Exitstat runs a bpf program that hooks into process exit events. This allows below to keep
-// track of processes whose lifetimes are shorter than polling interval.
+// track of processes whose lifetimes are shorter than polling interval. Its health is recorded
+// with every sample so gaps in exit data can be told apart from quiet periods.
 fn start_exitstat(
     logger: slog::Logger,
     debug: bool,
-) -> (Arc<Mutex<procfs::PidMap>>, Option<Receiver<Error>>) {
+    bpf_err_send: Sender<Error>,
+) -> (
+    Arc<Mutex<procfs::PidMap>>,
+    Arc<Mutex<procfs::ExitstatHealth>>,
+) {
     let mut exit_driver = exitstat::ExitstatDriver::new(logger, debug);
     let exit_buffer = exit_driver.get_buffer();
-    let (bpf_err_send, bpf_err_recv) = channel();
+    let exit_health = exit_driver.get_health();
     thread::Builder::new()
         .name("exit_driver".to_owned())
         .spawn(move || {
@@ -376,11 +572,92 @@ fn start_exitstat(
         })
         .expect("Failed to spawn thread");
 
-    (exit_buffer, Some(bpf_err_recv))
+    (exit_buffer, exit_health)
+}
+
+// Execsnoop runs a bpf program that hooks into exec and fork events so below can tell which
+// processes were spawned during an interval, and by whom, even if they already exited.
+fn start_execsnoop(
+    logger: slog::Logger,
+    debug: bool,
+    bpf_err_send: Sender<Error>,
+) -> Arc<Mutex<Vec<procfs::ExecEvent>>> {
+    let mut exec_driver = execsnoop::ExecsnoopDriver::new(logger, debug);
+    let exec_buffer = exec_driver.get_buffer();
+    thread::Builder::new()
+        .name("exec_driver".to_owned())
+        .spawn(move || {
+            match exec_driver.drive() {
+                Ok(_) => {}
+                Err(e) => bpf_err_send.send(e).unwrap(),
+            };
+        })
+        .expect("Failed to spawn thread");
+
+    exec_buffer
+}
+
+// Oomkill runs a bpf program that hooks into the OOM killer so below can record who was killed,
+// for which memory cgroup, and whether the OOM was system-wide.
+fn start_oomkill(
+    logger: slog::Logger,
+    debug: bool,
+    bpf_err_send: Sender<Error>,
+) -> Arc<Mutex<Vec<procfs::OomKillEvent>>> {
+    let mut oom_driver = oomkill::OomkillDriver::new(logger, debug);
+    let oom_buffer = oom_driver.get_buffer();
+    thread::Builder::new()
+        .name("oom_driver".to_owned())
+        .spawn(move || {
+            match oom_driver.drive() {
+                Ok(_) => {}
+                Err(e) => bpf_err_send.send(e).unwrap(),
+            };
+        })
+        .expect("Failed to spawn thread");
+
+    oom_buffer
+}
+
+/// A bpf skeleton owned by a collector plugin, which is created on one
+/// thread and then moved to the thread that drives it. Only that thread
+/// ever uses the skeleton, and libbpf objects aren't tied to the thread
+/// that created them.
+pub(crate) struct SendSkel<S>(pub S);
+
+unsafe impl<S> Send for SendSkel<S> {}
+
+// Runs a bpf-backed collector plugin such as runqlat or biolat. Their histograms are cumulative, so
+// polling more often than the sample interval just keeps the consumer's copy fresh. Unrecoverable
+// errors, e.g. failing to load the bpf program, are reported like those of the other bpf programs.
+fn start_collector_plugin<T, P>(
+    name: &str,
+    plugin: P,
+    bpf_err_send: Sender<Error>,
+) -> model::collector_plugin::Consumer<T>
+where
+    T: Send + 'static,
+    P: model::collector_plugin::AsyncCollectorPlugin<T = T> + Send + 'static,
+{
+    let (mut collector, consumer) = model::collector_plugin::collector_consumer(plugin);
+    let name = name.to_owned();
+    thread::Builder::new()
+        .name(format!("{}_collector", name))
+        .spawn(move || loop {
+            // No point in retrying
+            if let Err(e) = futures::executor::block_on(collector.collect_and_update()) {
+                let _ = bpf_err_send.send(e.context(format!("Stopped {}", name)));
+                return;
+            }
+            thread::sleep(Duration::from_secs(1));
+        })
+        .expect("Failed to spawn thread");
+
+    consumer
 }
 
 /// Returns true if other end disconnected, false otherwise
-fn check_for_exitstat_errors(logger: &slog::Logger, receiver: &Receiver<Error>) -> bool {
+fn check_for_bpf_errors(logger: &slog::Logger, receiver: &Receiver<Error>) -> bool {
     // Print an error but don't exit on bpf issues. Do this b/c we can't always
     // be sure what kind of kernel we're running on and if it's new enough.
     match receiver.try_recv() {
@@ -422,6 +699,39 @@ fn cleanup_store(
     Ok(())
 }
 
+/// Periodically rolls up shards older than `rollup_after` in the background.
+/// Rolling up a whole shard takes a while, so it doesn't run on the
+/// collection thread.
+fn start_rollup(
+    logger: slog::Logger,
+    store_dir: PathBuf,
+    rollup_after: Duration,
+    rollup_interval: Duration,
+    compression_mode: store::CompressionMode,
+    store_format: store::Format,
+) -> Result<()> {
+    // Each pass reads the index of every old shard, so even with short shards
+    // don't check more than hourly. Rolling up a little late costs nothing.
+    const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+    thread::Builder::new()
+        .name("rollup".to_owned())
+        .spawn(move || loop {
+            if let Err(e) = store::rollup::rollup_earlier(
+                &logger,
+                &store_dir,
+                SystemTime::now() - rollup_after,
+                rollup_interval,
+                compression_mode,
+                store_format,
+            ) {
+                error!(logger, "Failed to roll up store: {:#}", e);
+            }
+            std::thread::sleep(CHECK_INTERVAL);
+        })
+        .context("Failed to spawn rollup thread")?;
+    Ok(())
+}
+
 /// Special Error that indicates the program should stop now. It represents an
 /// actual signal, e.g. SIGINT, SIGTERM, that is handled by below and thus below
 /// can shutdown gracefully.
@@ -438,11 +748,37 @@ impl std::fmt::Display for StopSignal {
     }
 }
 
+/// Serves the local store to `--host` clients until below exits. Binding
+/// happens up front so a taken port fails the command right away.
+#[cfg(not(fbcode_build))]
+fn start_remote_store_server(
+    logger: slog::Logger,
+    below_config: &BelowConfig,
+    port: u16,
+    err_sender: Sender<Error>,
+) -> Result<()> {
+    let server = store::RemoteStoreServer::bind(
+        logger,
+        below_config.store_dir.clone(),
+        below_config.remote_store_bind_address,
+        port,
+    )?;
+    thread::Builder::new()
+        .name("remote_store".to_owned())
+        .spawn(move || {
+            if let Err(e) = server.run() {
+                err_sender.send(e).unwrap();
+            }
+        })
+        .context("Failed to spawn remote store server thread")?;
+    Ok(())
+}
+
 pub fn run<F>(
     init: init::InitToken,
     debug: bool,
     below_config: &BelowConfig,
-    _service: Service,
+    service: Service,
     redirect: RedirectLogOnFail,
     command: F,
 ) -> i32
@@ -493,10 +829,17 @@ where
     facebook::init(
         init,
         logger.clone(),
-        _service,
+        service,
         below_config.store_dir.clone(),
         err_sender,
     );
+    #[cfg(not(fbcode_build))]
+    if let Service::On(Some(port)) = service {
+        if let Err(e) = start_remote_store_server(logger.clone(), below_config, port, err_sender) {
+            error!(logger, "{:#}", e);
+            return 1;
+        }
+    }
     let res = command(init, below_config, logger.clone(), err_receiver);
 
     match res {
@@ -554,6 +897,11 @@ fn real_main(init: init::InitToken) {
         interval_s: 5,
         host: None,
         port: None,
+        disable_exec_trace: false,
+        disable_oom_trace: false,
+        enable_runq_latency: false,
+        enable_io_latency: false,
+        enable_tcp_health: false,
     });
     let rc = match cmd {
         Command::External(command) => commands::run_command(init, debug, below_config, command),
@@ -561,6 +909,11 @@ fn real_main(init: init::InitToken) {
             ref interval_s,
             ref host,
             ref port,
+            ref disable_exec_trace,
+            ref disable_oom_trace,
+            ref enable_runq_latency,
+            ref enable_io_latency,
+            ref enable_tcp_health,
         } => {
             let host = host.clone();
             let port = port.clone();
@@ -579,6 +932,11 @@ fn real_main(init: init::InitToken) {
                         below_config,
                         host,
                         port,
+                        *disable_exec_trace,
+                        *disable_oom_trace,
+                        *enable_runq_latency,
+                        *enable_io_latency,
+                        *enable_tcp_health,
                     )
                 },
             )
@@ -587,11 +945,20 @@ fn real_main(init: init::InitToken) {
             ref interval_s,
             ref retain_for_s,
             ref store_size_limit,
+            ref rollup_after_s,
+            ref rollup_interval_s,
+            ref shard_duration,
             ref collect_io_stat,
             ref port,
             ref skew_detection_threshold_ms,
             ref disable_disk_stat,
             ref disable_exitstats,
+            ref disable_exec_trace,
+            ref disable_oom_trace,
+            ref enable_runq_latency,
+            ref enable_io_latency,
+            ref enable_tcp_health,
+            ref store_format,
             ref compress_opts,
         } => {
             logutil::set_current_log_target(logutil::TargetLog::Term);
@@ -609,11 +976,20 @@ fn real_main(init: init::InitToken) {
                         below_config,
                         retain_for_s.map(|r| Duration::from_secs(r as u64)),
                         *store_size_limit,
+                        rollup_after_s.map(Duration::from_secs),
+                        Duration::from_secs(*rollup_interval_s),
+                        shard_duration.map(Duration::from),
                         *collect_io_stat,
                         Duration::from_millis(*skew_detection_threshold_ms),
                         debug,
                         *disable_disk_stat,
                         *disable_exitstats,
+                        *disable_exec_trace,
+                        *disable_oom_trace,
+                        *enable_runq_latency,
+                        *enable_io_latency,
+                        *enable_tcp_health,
+                        *store_format,
                         compress_opts,
                     )
                 },
@@ -692,6 +1068,7 @@ fn real_main(init: init::InitToken) {
                 ref to_store_dir,
                 ref host,
                 ref port,
+                ref store_format,
                 ref compress_opts,
             } => {
                 let start_time = start_time.clone();
@@ -716,11 +1093,97 @@ fn real_main(init: init::InitToken) {
                             to_store_dir,
                             host,
                             port,
+                            *store_format,
+                            compress_opts,
+                        )
+                    },
+                )
+            }
+            DebugCommand::StoreInfo {
+                ref store_dir,
+                ref compression_stats,
+            } => {
+                let store_dir = store_dir.clone();
+                run(
+                    init,
+                    debug,
+                    below_config,
+                    Service::Off,
+                    RedirectLogOnFail::Off,
+                    |_, below_config, logger, _errs| {
+                        store_info(logger, below_config, store_dir, *compression_stats)
+                    },
+                )
+            }
+            DebugCommand::Fsck {
+                ref store_dir,
+                ref max_gap_s,
+                ref repair_to,
+                ref store_format,
+                ref compress_opts,
+            } => {
+                let store_dir = store_dir.clone();
+                let repair_to = repair_to.clone();
+                run(
+                    init,
+                    debug,
+                    below_config,
+                    Service::Off,
+                    RedirectLogOnFail::Off,
+                    |_, below_config, logger, _errs| {
+                        fsck(
+                            logger,
+                            below_config,
+                            store_dir,
+                            max_gap_s.map(Duration::from_secs),
+                            repair_to,
+                            *store_format,
                             compress_opts,
                         )
                     },
                 )
             }
+            DebugCommand::MergeStore {
+                ref sources,
+                ref to_store_dir,
+            } => {
+                let sources = sources.clone();
+                let to_store_dir = to_store_dir.clone();
+                run(
+                    init,
+                    debug,
+                    below_config,
+                    Service::Off,
+                    RedirectLogOnFail::Off,
+                    |_, _below_config, logger, _errs| merge_store(logger, sources, to_store_dir),
+                )
+            }
+            DebugCommand::SplitStore {
+                ref store_dir,
+                ref to_dir,
+                ref by_hostname,
+                ref interval_s,
+            } => {
+                let store_dir = store_dir.clone();
+                let to_dir = to_dir.clone();
+                run(
+                    init,
+                    debug,
+                    below_config,
+                    Service::Off,
+                    RedirectLogOnFail::Off,
+                    |_, below_config, logger, _errs| {
+                        split_store(
+                            logger,
+                            below_config,
+                            store_dir,
+                            to_dir,
+                            *by_hostname,
+                            interval_s.map(Duration::from_secs),
+                        )
+                    },
+                )
+            }
         },
         Command::Dump {
             ref host,
@@ -834,33 +1297,104 @@ fn record(
     below_config: &BelowConfig,
     retention: Option<Duration>,
     store_size_limit: Option<u64>,
+    rollup_after: Option<Duration>,
+    rollup_interval: Duration,
+    shard_duration: Option<Duration>,
     collect_io_stat: bool,
     skew_detection_threshold: Duration,
     debug: bool,
     disable_disk_stat: bool,
     disable_exitstats: bool,
+    disable_exec_trace: bool,
+    disable_oom_trace: bool,
+    enable_runq_latency: bool,
+    enable_io_latency: bool,
+    enable_tcp_health: bool,
+    store_format: store::Format,
     compress_opts: &CompressOpts,
 ) -> Result<()> {
     debug!(logger, "Starting up!");
 
-    if !disable_exitstats {
+    if !disable_exitstats
+        || !disable_exec_trace
+        || !disable_oom_trace
+        || enable_runq_latency
+        || enable_io_latency
+        || enable_tcp_health
+    {
         bump_memlock_rlimit()?;
     }
 
-    let mut store = store::StoreWriter::new(
-        logger.clone(),
-        &below_config.store_dir,
-        compress_opts.to_compression_mode()?,
-        store::Format::Cbor,
-    )?;
+    if let Some(shard_duration) = shard_duration {
+        store::set_shard_duration(&below_config.store_dir, shard_duration)?;
+    }
+    let mut store =
+        compress_opts.new_store_writer(logger.clone(), &below_config.store_dir, store_format)?;
+    if let Some(rollup_after) = rollup_after {
+        start_rollup(
+            logger.clone(),
+            below_config.store_dir.clone(),
+            rollup_after,
+            rollup_interval,
+            compress_opts.to_compression_mode()?,
+            store_format,
+        )?;
+    }
     let mut stats = statistics::Statistics::new();
 
-    let (exit_buffer, bpf_errs) = if disable_exitstats {
+    let (bpf_err_send, bpf_errs) = channel();
+    let (exit_buffer, exit_health) = if disable_exitstats {
         (Arc::new(Mutex::new(procfs::PidMap::new())), None)
     } else {
-        start_exitstat(logger.clone(), debug)
+        let (exit_buffer, exit_health) =
+            start_exitstat(logger.clone(), debug, bpf_err_send.clone());
+        (exit_buffer, Some(exit_health))
     };
-    let mut bpf_err_warned = false;
+    let exec_buffer = if disable_exec_trace {
+        None
+    } else {
+        Some(start_execsnoop(logger.clone(), debug, bpf_err_send.clone()))
+    };
+    let oom_buffer = if disable_oom_trace {
+        None
+    } else {
+        Some(start_oomkill(logger.clone(), debug, bpf_err_send.clone()))
+    };
+    let run_delay_receiver = if enable_runq_latency {
+        Some(start_collector_plugin(
+            "runqlat",
+            runqlat::RunqlatPlugin::new(debug),
+            bpf_err_send.clone(),
+        ))
+    } else {
+        None
+    };
+    let io_latency_receiver = if enable_io_latency {
+        Some(start_collector_plugin(
+            "biolat",
+            biolat::BiolatPlugin::new(debug),
+            bpf_err_send.clone(),
+        ))
+    } else {
+        None
+    };
+    let tcp_health_receiver = if enable_tcp_health {
+        Some(start_collector_plugin(
+            "tcphealth",
+            tcphealth::TcpHealthPlugin::new(debug),
+            bpf_err_send.clone(),
+        ))
+    } else {
+        None
+    };
+    drop(bpf_err_send);
+    // Nothing will ever be reported if no bpf program is running
+    let mut bpf_err_warned = disable_exitstats
+        && disable_exec_trace
+        && disable_oom_trace
+        && !enable_runq_latency
+        && !enable_io_latency
+        && !enable_tcp_health;
 
     // Handle cgroup filter from conf and generate Regex
     let cgroup_re = if !below_config.cgroup_filter_out.is_empty() {
@@ -877,6 +1411,12 @@ fn record(
         model::CollectorOptions {
             cgroup_root: below_config.cgroup_root.clone(),
             exit_data: exit_buffer,
+            exit_health,
+            exec_data: exec_buffer,
+            oom_data: oom_buffer,
+            run_delay_receiver,
+            io_latency_receiver,
+            tcp_health_receiver,
             collect_io_stat,
             disable_disk_stat,
             cgroup_re,
@@ -891,15 +1431,10 @@ fn record(
                 Err(TryRecvError::Empty) => {}
                 Err(TryRecvError::Disconnected) => bail!("error channel disconnected"),
             };
+        }
 
-            if !bpf_err_warned {
-                bpf_err_warned = check_for_exitstat_errors(
-                    &logger,
-                    bpf_errs
-                        .as_ref()
-                        .expect("Failed to unwrap bpf_errs receiver"),
-                );
-            }
+        if !bpf_err_warned {
+            bpf_err_warned = check_for_bpf_errors(&logger, &bpf_errs);
         }
 
         let collect_instant = Instant::now();
@@ -965,6 +1500,11 @@ fn live_local(
     interval: Duration,
     debug: bool,
     below_config: &BelowConfig,
+    disable_exec_trace: bool,
+    disable_oom_trace: bool,
+    enable_runq_latency: bool,
+    enable_io_latency: bool,
+    enable_tcp_health: bool,
 ) -> Result<()> {
     match bump_memlock_rlimit() {
         Err(e) => {
@@ -979,7 +1519,45 @@ fn live_local(
         _ => {}
     };
 
-    let (exit_buffer, bpf_errs) = start_exitstat(logger.clone(), debug);
+    let (bpf_err_send, bpf_errs) = channel();
+    let (exit_buffer, exit_health) = start_exitstat(logger.clone(), debug, bpf_err_send.clone());
+    let exec_buffer = if disable_exec_trace {
+        None
+    } else {
+        Some(start_execsnoop(logger.clone(), debug, bpf_err_send.clone()))
+    };
+    let oom_buffer = if disable_oom_trace {
+        None
+    } else {
+        Some(start_oomkill(logger.clone(), debug, bpf_err_send.clone()))
+    };
+    let run_delay_receiver = if enable_runq_latency {
+        Some(start_collector_plugin(
+            "runqlat",
+            runqlat::RunqlatPlugin::new(debug),
+            bpf_err_send.clone(),
+        ))
+    } else {
+        None
+    };
+    let io_latency_receiver = if enable_io_latency {
+        Some(start_collector_plugin(
+            "biolat",
+            biolat::BiolatPlugin::new(debug),
+            bpf_err_send.clone(),
+        ))
+    } else {
+        None
+    };
+    let tcp_health_receiver = if enable_tcp_health {
+        Some(start_collector_plugin(
+            "tcphealth",
+            tcphealth::TcpHealthPlugin::new(debug),
+            bpf_err_send,
+        ))
+    } else {
+        None
+    };
     let mut bpf_err_warned = false;
 
     let mut collector = model::Collector::new(
@@ -987,6 +1565,12 @@ fn live_local(
         model::CollectorOptions {
             cgroup_root: below_config.cgroup_root.clone(),
             exit_data: exit_buffer,
+            exit_health: Some(exit_health),
+            exec_data: exec_buffer,
+            oom_data: oom_buffer,
+            run_delay_receiver,
+            io_latency_receiver,
+            tcp_health_receiver,
             ..Default::default()
         },
     );
@@ -1010,12 +1594,7 @@ fn live_local(
         .spawn(move || {
             loop {
                 if !bpf_err_warned {
-                    bpf_err_warned = check_for_exitstat_errors(
-                        &logger,
-                        bpf_errs
-                            .as_ref()
-                            .expect("Failed to unwrap bpf_errs receiver"),
-                    );
+                    bpf_err_warned = check_for_bpf_errors(&logger, &bpf_errs);
                 }
 
                 // Rely on timeout to guarantee interval between samples
@@ -1136,11 +1715,27 @@ fn live(
     below_config: &BelowConfig,
     host: Option<String>,
     port: Option<u16>,
+    disable_exec_trace: bool,
+    disable_oom_trace: bool,
+    enable_runq_latency: bool,
+    enable_io_latency: bool,
+    enable_tcp_health: bool,
 ) -> Result<()> {
     if let Some(host) = host {
         live_remote(logger, errs, interval, host, port)
     } else {
-        live_local(logger, errs, interval, debug, below_config)
+        live_local(
+            logger,
+            errs,
+            interval,
+            debug,
+            below_config,
+            disable_exec_trace,
+            disable_oom_trace,
+            enable_runq_latency,
+            enable_io_latency,
+            enable_tcp_health,
+        )
     }
 }
 
@@ -1176,6 +1771,110 @@ fn dump_store(
     Ok(())
 }
 
+fn store_info(
+    logger: slog::Logger,
+    below_config: &BelowConfig,
+    store_dir: Option<PathBuf>,
+    compression_stats: bool,
+) -> Result<()> {
+    let store_dir = store_dir.unwrap_or_else(|| below_config.store_dir.clone());
+    let shards = store::get_store_info(&store_dir)?;
+
+    println!("Store: {}", store_dir.display());
+    println!(
+        "Current schema version: {}",
+        store::schema::CURRENT_SCHEMA_VERSION
+    );
+    println!(
+        "Shard duration: {}",
+        humantime::format_duration(store::get_shard_duration(&store_dir)?)
+    );
+    println!();
+    println!(
+        "{:<20} {:>8} {:>8} {:>10} {:>10}  {}",
+        "Shard", "Entries", "Corrupt", "Index", "Data", "Schema versions"
+    );
+    let mut total_versions = std::collections::BTreeMap::new();
+    let mut total_compression = std::collections::BTreeMap::new();
+    let mut total_dict_bytes = 0;
+    let mut total_entries = 0;
+    let mut total_corrupt = 0;
+    for shard in &shards {
+        let versions = shard
+            .schema_versions
+            .iter()
+            .map(|(version, count)| format!("v{}: {}", version, count))
+            .collect::<Vec<_>>()
+            .join(", ");
+        println!(
+            "{:<20} {:>8} {:>8} {:>10} {:>10}  {}",
+            common::util::timestamp_to_datetime(&(shard.shard as i64)),
+            shard.entries,
+            shard.corrupt_entries,
+            common::util::convert_bytes(shard.index_bytes as f64),
+            common::util::convert_bytes(shard.data_bytes as f64),
+            versions
+        );
+        for (version, count) in &shard.schema_versions {
+            *total_versions.entry(*version).or_insert(0) += count;
+        }
+        for (name, count) in &shard.compression {
+            *total_compression.entry(*name).or_insert(0) += count;
+        }
+        total_dict_bytes += shard.dict_bytes;
+        total_entries += shard.entries;
+        total_corrupt += shard.corrupt_entries;
+    }
+    println!();
+    println!(
+        "{} shards, {} entries, {} corrupt",
+        shards.len(),
+        total_entries,
+        total_corrupt
+    );
+    for (version, count) in &total_versions {
+        let note = match version.cmp(&store::schema::CURRENT_SCHEMA_VERSION) {
+            std::cmp::Ordering::Less if *version == 0 => " (unversioned, upgraded on read)",
+            std::cmp::Ordering::Less => " (upgraded on read)",
+            std::cmp::Ordering::Equal => "",
+            std::cmp::Ordering::Greater => " (newer than this below, read best effort)",
+        };
+        println!("Schema version {}: {} entries{}", version, count, note);
+    }
+    for (name, count) in &total_compression {
+        println!("Compression {}: {} entries", name, count);
+    }
+    if total_dict_bytes > 0 {
+        println!(
+            "Trained dictionaries: {}",
+            common::util::convert_bytes(total_dict_bytes as f64)
+        );
+    }
+
+    if compression_stats {
+        let stats = store::get_compression_stats(logger, &store_dir)?;
+        println!();
+        println!(
+            "{:<20} {:>8} {:>10} {:>10} {:>6} {:>16} {:>16}",
+            "Compression", "Frames", "Stored", "Raw", "Ratio", "Compress/frame", "Decompress/frame"
+        );
+        for (name, stats) in &stats {
+            println!(
+                "{:<20} {:>8} {:>10} {:>10} {:>6.1} {:>16} {:>16}",
+                name,
+                stats.frames,
+                common::util::convert_bytes(stats.stored_bytes as f64),
+                common::util::convert_bytes(stats.raw_bytes as f64),
+                stats.raw_bytes as f64 / stats.stored_bytes.max(1) as f64,
+                format!("{:?}", stats.compress_time / stats.frames.max(1) as u32),
+                format!("{:?}", stats.decompress_time / stats.frames.max(1) as u32),
+            );
+        }
+    }
+
+    Ok(())
+}
+
 fn generate_completions(shell: Shell, output: Option<PathBuf>) -> Result<()> {
     let mut file: Box<dyn io::Write> = match output {
         Some(path) => Box::new(fs::File::create(path)?),
@@ -1185,6 +1884,184 @@ fn generate_completions(shell: Shell, output: Option<PathBuf>) -> Result<()> {
     Ok(())
 }
 
+fn fsck(
+    logger: slog::Logger,
+    below_config: &BelowConfig,
+    store_dir: Option<PathBuf>,
+    max_gap: Option<Duration>,
+    repair_to: Option<PathBuf>,
+    store_format: store::Format,
+    compress_opts: &CompressOpts,
+) -> Result<()> {
+    let store_dir = store_dir.unwrap_or_else(|| below_config.store_dir.clone());
+    let report = store::fsck::check_store(logger.clone(), &store_dir, max_gap)?;
+
+    println!("Store: {}", store_dir.display());
+    println!();
+    println!(
+        "{:<20} {:>8} {:>8} {:>8}",
+        "Shard", "Frames", "Padding", "Problems"
+    );
+    for shard in &report.shards {
+        println!(
+            "{:<20} {:>8} {:>8} {:>8}",
+            common::util::timestamp_to_datetime(&(shard.shard as i64)),
+            shard.frames,
+            shard.padding,
+            shard.problems.len()
+        );
+        for problem in &shard.problems {
+            println!("    {}", problem);
+        }
+    }
+    if !report.gaps.is_empty() {
+        println!();
+        println!("Gaps:");
+        for gap in &report.gaps {
+            println!(
+                "    {} - {} ({}s)",
+                common::util::timestamp_to_datetime(&(gap.from as i64)),
+                common::util::timestamp_to_datetime(&(gap.to as i64)),
+                gap.to - gap.from
+            );
+        }
+    }
+    println!();
+    let nr_problems = report.nr_problems();
+    println!(
+        "{} shards, {} frames, {} problems, {} gaps",
+        report.shards.len(),
+        report.shards.iter().map(|shard| shard.frames).sum::<u64>(),
+        nr_problems,
+        report.gaps.len()
+    );
+
+    if let Some(repair_to) = repair_to {
+        // Takes precedence over the level of the damaged store
+        if let Some(level) = compress_opts.zstd_level {
+            store::set_zstd_level(&repair_to, level)?;
+        }
+        let nr_frames = store::fsck::repair_store(
+            logger,
+            &store_dir,
+            &repair_to,
+            compress_opts.to_compression_mode()?,
+            store_format,
+        )?;
+        println!(
+            "Wrote {} frames to repaired store at {}",
+            nr_frames,
+            repair_to.display()
+        );
+    } else if nr_problems > 0 {
+        bail!(
+            "Found {} problems. Use --repair-to to write a store without them.",
+            nr_problems
+        );
+    }
+    Ok(())
+}
+
+/// Unpacks a snapshot file created by `below snapshot` and returns the store
+/// directory inside. The store is removed when the returned TempDir drops.
+fn unpack_snapshot(snapshot: &Path) -> Result<(TempDir, PathBuf)> {
+    let mut tarball = Archive::new(
+        fs::File::open(snapshot)
+            .with_context(|| format!("Failed to open snapshot file {}", snapshot.display()))?,
+    );
+    let dir = TempDir::new("snapshot_merge")?;
+    tarball
+        .unpack(dir.path())
+        .with_context(|| format!("Failed to unpack snapshot file {}", snapshot.display()))?;
+    // Snapshots contain a single store directory
+    let mut entries = fs::read_dir(dir.path())?.collect::<io::Result<Vec<_>>>()?;
+    match entries.pop() {
+        Some(entry) if entries.is_empty() && entry.path().is_dir() => {
+            let store_dir = entry.path();
+            Ok((dir, store_dir))
+        }
+        _ => bail!("{} is not a snapshot file", snapshot.display()),
+    }
+}
+
+fn merge_store(logger: slog::Logger, sources: Vec<String>, to_store_dir: PathBuf) -> Result<()> {
+    // Unpacked snapshots are removed once merged
+    let mut snapshot_dirs = Vec::new();
+    let mut merge_sources = Vec::new();
+    for source in &sources {
+        let (hostname, path) = match source.split_once('=') {
+            Some((hostname, path)) if !hostname.contains('/') => {
+                (Some(hostname.to_owned()), PathBuf::from(path))
+            }
+            _ => (None, PathBuf::from(source)),
+        };
+        let path = if path.is_file() {
+            let (snapshot_dir, store_dir) = unpack_snapshot(&path)?;
+            snapshot_dirs.push(snapshot_dir);
+            store_dir
+        } else {
+            path
+        };
+        merge_sources.push(store::merge::MergeSource { path, hostname });
+    }
+    let (settings_dir, (compression_mode, format)) = merge_sources
+        .iter()
+        .find_map(|source| {
+            store::get_store_settings(&source.path)
+                .map(|settings| settings.map(|settings| (&source.path, settings)))
+                .transpose()
+        })
+        .transpose()?
+        .ok_or_else(|| anyhow!("No frames to merge"))?;
+    store::copy_store_settings(settings_dir, &to_store_dir)?;
+
+    let stats = store::merge::merge_stores(
+        logger,
+        &merge_sources,
+        &to_store_dir,
+        compression_mode,
+        format,
+    )?;
+    println!("Merged {} frames", stats.frames);
+    println!("Dropped {} repeated frames", stats.duplicates);
+    for (hostname, collisions) in &stats.collisions {
+        println!(
+            "Dropped {} frames of {} at the same second as another host",
+            collisions, hostname
+        );
+    }
+    Ok(())
+}
+
+fn split_store(
+    logger: slog::Logger,
+    below_config: &BelowConfig,
+    store_dir: Option<PathBuf>,
+    to_dir: PathBuf,
+    by_hostname: bool,
+    interval: Option<Duration>,
+) -> Result<()> {
+    let store_dir = store_dir.unwrap_or_else(|| below_config.store_dir.clone());
+    let (compression_mode, format) = store::get_store_settings(&store_dir)?
+        .ok_or_else(|| anyhow!("{} has no frames to split", store_dir.display()))?;
+    let split_by = store::merge::SplitBy {
+        hostname: by_hostname,
+        interval,
+    };
+    let nr_frames = store::merge::split_store(
+        logger,
+        &store_dir,
+        &to_dir,
+        &split_by,
+        compression_mode,
+        format,
+    )?;
+    for (path, frames) in &nr_frames {
+        println!("{:>8} frames  {}", frames, path.display());
+    }
+    Ok(())
+}
+
 fn convert_store(
     logger: slog::Logger,
     below_config: &BelowConfig,
@@ -1194,6 +2071,7 @@ fn convert_store(
     to_store_dir: PathBuf,
     host: Option<String>,
     port: Option<u16>,
+    store_format: store::Format,
     compress_opts: &CompressOpts,
 ) -> Result<()> {
     let (time_begin, time_end) = cliutil::system_time_range_from_date_and_adjuster(
@@ -1207,6 +2085,12 @@ fn convert_store(
     );
     let pb = ProgressBar::new(timestamp_end - timestamp_begin);
 
+    // Settings of a local source carry over unless given explicitly
+    let local_store_dir = match (&from_store_dir, &host) {
+        (Some(from_store_dir), None) => Some(from_store_dir.clone()),
+        (None, None) => Some(below_config.store_dir.clone()),
+        _ => None,
+    };
     let mut store: Box<dyn Store<SampleType = DataFrame>> = match (from_store_dir, host) {
         (Some(_from_store_dir), Some(_host)) => {
             bail!("Only one of --from-store-dir and --host should be specified");
@@ -1231,35 +2115,21 @@ fn convert_store(
         }
     };
 
-    let mut dest_store = store::StoreWriter::new(
-        logger.clone(),
-        &to_store_dir,
-        compress_opts.to_compression_mode()?,
-        store::Format::Cbor,
-    )?;
+    if let Some(local_store_dir) = &local_store_dir {
+        store::copy_store_settings(local_store_dir, &to_store_dir)?;
+    }
+    let mut dest_store =
+        compress_opts.new_store_writer(logger.clone(), &to_store_dir, store_format)?;
 
     pb.set_message(&format!("Writing to local store at {:?}", to_store_dir));
 
     let mut nr_samples = 0;
-    let mut cur_time = time_begin;
-    while cur_time < time_end {
-        match store.get_sample_at_timestamp(cur_time, store::Direction::Forward)? {
-            Some((frame_time, frame)) => {
-                cur_time = frame_time;
-                pb.set_message(&format!("Storing frame at t = {:?}", frame_time));
-                dest_store.put(frame_time, &frame)?;
-                nr_samples += 1;
-            }
-            None => {
-                pb.set_message(&format!(
-                    "Error: Breaking early. Couldn't find any frames after t = {:?}",
-                    cur_time
-                ));
-                break;
-            }
-        }
-        pb.set_position(common::util::get_unix_timestamp(cur_time) - timestamp_begin);
-        cur_time += Duration::from_secs(1); // To actually move forward
+    for item in store::iter_range(store.as_mut(), time_begin, time_end, 1) {
+        let (frame_time, frame) = item?;
+        pb.set_message(&format!("Storing frame at t = {:?}", frame_time));
+        dest_store.put(frame_time, &frame)?;
+        nr_samples += 1;
+        pb.set_position(common::util::get_unix_timestamp(frame_time) - timestamp_begin);
     }
     pb.set_message(&format!("Done. Logged {} samples.", nr_samples));
     Ok(())
@@ -1296,6 +2166,9 @@ fn snapshot(
     let compress_opts = CompressOpts {
         compress: true,
         dict_compress_chunk_size: Some(16),
+        compress_codec: None,
+        zstd_level: None,
+        train_dict: false,
     };
     convert_store(
         logger,
@@ -1306,6 +2179,7 @@ fn snapshot(
         snapshot_store_path.clone(),
         host,
         port,
+        store::Format::Cbor,
         &compress_opts,
     )
     .context("Failed to convert store for snapshot")?;
@@ -0,0 +1,130 @@
+use core::time::Duration;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use anyhow::{Context, Result};
+use libbpf_rs::PerfBufferBuilder;
+use plain::Plain;
+use slog::{info, warn};
+
+use common::util::get_unix_timestamp;
+
+use crate::exitstat::{c_buf_to_string, PAGE_SIZE};
+use crate::OomkillSkelBuilder;
+
+// See bpf prog for comments on what each field is
+#[repr(C)]
+#[derive(Default)]
+pub struct Event {
+    pub victim_pid: i32,
+    pub trigger_pid: i32,
+    pub victim_cgroup_id: u64,
+    pub memcg_id: u64,
+    pub victim_rss_pages: u64,
+    pub totalpages: u64,
+    pub constraint: u32,
+    pub victim_comm: [u8; 16],
+    pub trigger_comm: [u8; 16],
+}
+
+unsafe impl Plain for Event {}
+
+/// Maps the kernel's enum oom_constraint
+fn to_constraint(constraint: u32) -> Option<procfs::OomConstraint> {
+    match constraint {
+        0 => Some(procfs::OomConstraint::None),
+        1 => Some(procfs::OomConstraint::Cpuset),
+        2 => Some(procfs::OomConstraint::MemoryPolicy),
+        3 => Some(procfs::OomConstraint::Memcg),
+        _ => None,
+    }
+}
+
+pub struct OomkillDriver {
+    logger: slog::Logger,
+    debug: bool,
+    buffer: Arc<Mutex<Vec<procfs::OomKillEvent>>>,
+}
+
+impl OomkillDriver {
+    pub fn new(logger: slog::Logger, debug: bool) -> Self {
+        Self {
+            logger,
+            debug,
+            buffer: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    pub fn get_buffer(&self) -> Arc<Mutex<Vec<procfs::OomKillEvent>>> {
+        self.buffer.clone()
+    }
+
+    fn handle_event(
+        logger: &slog::Logger,
+        handle: &Arc<Mutex<Vec<procfs::OomKillEvent>>>,
+        page_size: u64,
+        data: &[u8],
+    ) {
+        let mut event = Event::default();
+        plain::copy_from_bytes(&mut event, data).expect("Data buffer was too short");
+
+        let oom_event = procfs::OomKillEvent {
+            timestamp: Some(get_unix_timestamp(SystemTime::now())),
+            victim_pid: Some(event.victim_pid),
+            victim_comm: c_buf_to_string(&event.victim_comm),
+            victim_rss_bytes: Some(event.victim_rss_pages * page_size),
+            victim_cgroup_id: Some(event.victim_cgroup_id),
+            trigger_pid: Some(event.trigger_pid),
+            trigger_comm: c_buf_to_string(&event.trigger_comm),
+            memcg_id: if event.memcg_id == 0 {
+                None
+            } else {
+                Some(event.memcg_id)
+            },
+            total_bytes: Some(event.totalpages * page_size),
+            constraint: to_constraint(event.constraint),
+        };
+
+        info!(
+            logger,
+            "OOM killed {}({})",
+            oom_event.victim_comm.as_deref().unwrap_or("?"),
+            event.victim_pid
+        );
+
+        // handle.lock() only fails if a thread holding the lock panic'd, in which
+        // case we should probably panic too.
+        handle.lock().unwrap().push(oom_event);
+    }
+
+    fn handle_lost_events(logger: &slog::Logger, cpu: i32, count: u64) {
+        warn!(logger, "Lost {} OOM kill events on CPU {}", count, cpu);
+    }
+
+    /// Loops forever unless an error is hit
+    pub fn drive(&mut self) -> Result<()> {
+        let mut skel_builder = OomkillSkelBuilder::default();
+        skel_builder.obj_builder.debug(self.debug);
+        let mut skel = skel_builder.open()?.load()?;
+        skel.attach()?;
+
+        let page_size = *PAGE_SIZE;
+
+        // Set up perf ring buffer
+        let buffer = self.get_buffer();
+        let logger_clone = self.logger.clone();
+        let lost_logger = self.logger.clone();
+        let perf = PerfBufferBuilder::new(skel.maps().events())
+            .sample_cb(move |_, data: &[u8]| {
+                Self::handle_event(&logger_clone, &buffer, page_size, data)
+            })
+            .lost_cb(move |cpu, count| Self::handle_lost_events(&lost_logger, cpu, count))
+            .build()?;
+
+        // Poll events
+        loop {
+            perf.poll(Duration::from_millis(100))
+                .context("Error polling perf buffer")?;
+        }
+    }
+}
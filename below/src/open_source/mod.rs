@@ -14,9 +14,24 @@
 
 #[path = "../bpf/.output/exitstat.skel.rs"]
 mod bpf;
+#[path = "../bpf/.output/biolat.skel.rs"]
+mod biolat_bpf;
+#[path = "../bpf/.output/execsnoop.skel.rs"]
+mod execsnoop_bpf;
+#[path = "../bpf/.output/oomkill.skel.rs"]
+mod oomkill_bpf;
+#[path = "../bpf/.output/runqlat.skel.rs"]
+mod runqlat_bpf;
+#[path = "../bpf/.output/tcphealth.skel.rs"]
+mod tcphealth_bpf;
 pub mod commands;
 pub mod init;
 pub mod logging;
 pub mod statistics;
 
-pub use bpf::ExitstatSkelBuilder;
+pub use biolat_bpf::{BiolatSkel, BiolatSkelBuilder};
+pub use bpf::{ExitstatSkel, ExitstatSkelBuilder};
+pub use execsnoop_bpf::ExecsnoopSkelBuilder;
+pub use oomkill_bpf::OomkillSkelBuilder;
+pub use runqlat_bpf::{RunqlatSkel, RunqlatSkelBuilder};
+pub use tcphealth_bpf::{TcphealthSkel, TcphealthSkelBuilder};
@@ -0,0 +1,75 @@
+use std::convert::TryInto;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use libbpf_rs::MapFlags;
+use plain::Plain;
+
+use model::collector_plugin::AsyncCollectorPlugin;
+use model::RunDelayHists;
+
+use crate::{RunqlatSkel, RunqlatSkelBuilder, SendSkel};
+
+// Must match MAX_SLOTS of the bpf programs
+const MAX_SLOTS: usize = model::LOG2_HIST_BUCKETS;
+
+// See bpf prog for comments on what each field is
+#[repr(C)]
+#[derive(Default)]
+pub struct Hist {
+    pub slots: [u64; MAX_SLOTS],
+}
+
+unsafe impl Plain for Hist {}
+
+/// Reads the per-cgroup run queue latency histograms maintained by the
+/// runqlat BPF program. The program is loaded on first collection so that
+/// failures are reported to the consumer like any other collection error.
+pub struct RunqlatPlugin {
+    debug: bool,
+    skel: Option<SendSkel<RunqlatSkel<'static>>>,
+}
+
+impl RunqlatPlugin {
+    pub fn new(debug: bool) -> Self {
+        Self { debug, skel: None }
+    }
+
+    fn load(&self) -> Result<RunqlatSkel<'static>> {
+        let mut skel_builder = RunqlatSkelBuilder::default();
+        skel_builder.obj_builder.debug(self.debug);
+        let mut skel = skel_builder.open()?.load()?;
+        skel.attach()?;
+        Ok(skel)
+    }
+}
+
+#[async_trait]
+impl AsyncCollectorPlugin for RunqlatPlugin {
+    type T = RunDelayHists;
+
+    async fn try_collect(&mut self) -> Result<Option<RunDelayHists>> {
+        if self.skel.is_none() {
+            self.skel = Some(SendSkel(self.load()?));
+        }
+        let skel = &self.skel.as_ref().unwrap().0;
+        let maps = skel.maps();
+        let hists_map = maps.hists();
+
+        let mut hists = RunDelayHists::new();
+        for key in hists_map.keys() {
+            let cgroup_id = u64::from_ne_bytes(
+                key.as_slice()
+                    .try_into()
+                    .map_err(|_| anyhow!("Unexpected key size {}", key.len()))?,
+            );
+            // The entry may have been evicted since we got the key
+            if let Some(value) = hists_map.lookup(&key, MapFlags::ANY)? {
+                let mut hist = Hist::default();
+                plain::copy_from_bytes(&mut hist, &value).expect("Data buffer was too short");
+                hists.insert(cgroup_id, hist.slots.to_vec());
+            }
+        }
+        Ok(Some(hists))
+    }
+}
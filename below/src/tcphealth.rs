@@ -0,0 +1,182 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use libbpf_rs::MapFlags;
+use plain::Plain;
+
+use model::collector_plugin::AsyncCollectorPlugin;
+use model::TcpHealthStats;
+
+use crate::{SendSkel, TcphealthSkel, TcphealthSkelBuilder};
+
+// See bpf prog for comments on what each field is
+#[repr(C)]
+#[derive(Default)]
+struct ReasonKey {
+    cgroup_id: u64,
+    reason: u32,
+    pad: u32,
+}
+
+unsafe impl Plain for ReasonKey {}
+
+#[repr(C)]
+#[derive(Default)]
+struct Counts {
+    retransmits: u64,
+    drops: u64,
+}
+
+unsafe impl Plain for Counts {}
+
+impl From<Counts> for procfs::TcpHealthStat {
+    fn from(counts: Counts) -> Self {
+        procfs::TcpHealthStat {
+            retransmits: Some(counts.retransmits),
+            drops: Some(counts.drops),
+            drop_reasons: None,
+        }
+    }
+}
+
+/// Tracepoint formats, which map drop reasons to their names. Where tracefs
+/// is mounted depends on the distribution.
+const KFREE_SKB_FORMATS: &[&str] = &[
+    "/sys/kernel/tracing/events/skb/kfree_skb/format",
+    "/sys/kernel/debug/tracing/events/skb/kfree_skb/format",
+];
+
+/// Parses the names of drop reasons from the `{ 2, "NOT_SPECIFIED" }` pairs
+/// that `__print_symbolic()` adds to the print fmt of a tracepoint format.
+fn parse_drop_reason_names(format: &str) -> BTreeMap<u32, String> {
+    let print_fmt = match format.lines().find(|line| line.starts_with("print fmt:")) {
+        Some(print_fmt) => print_fmt,
+        None => return BTreeMap::new(),
+    };
+    print_fmt
+        .split('{')
+        .skip(1)
+        .filter_map(|pair| {
+            let (value, name) = pair.split('}').next()?.split_once(',')?;
+            let value = value.trim().parse::<u32>().ok()?;
+            let name = name.trim().strip_prefix('"')?.strip_suffix('"')?;
+            Some((value, name.to_owned()))
+        })
+        .collect()
+}
+
+/// Reads the TCP retransmit and drop counters the tcphealth BPF program keeps
+/// per cgroup and process. Loaded lazily like `RunqlatPlugin`.
+pub struct TcpHealthPlugin {
+    debug: bool,
+    skel: Option<SendSkel<TcphealthSkel<'static>>>,
+    /// Names of the drop reasons of the running kernel
+    drop_reason_names: BTreeMap<u32, String>,
+}
+
+impl TcpHealthPlugin {
+    pub fn new(debug: bool) -> Self {
+        Self {
+            debug,
+            skel: None,
+            drop_reason_names: BTreeMap::new(),
+        }
+    }
+
+    fn get_drop_reason_name(&self, reason: u32) -> String {
+        match self.drop_reason_names.get(&reason) {
+            Some(name) => name.clone(),
+            None => reason.to_string(),
+        }
+    }
+
+    fn load(&self) -> Result<TcphealthSkel<'static>> {
+        let mut skel_builder = TcphealthSkelBuilder::default();
+        skel_builder.obj_builder.debug(self.debug);
+        let mut skel = skel_builder.open()?.load()?;
+        skel.attach()?;
+        Ok(skel)
+    }
+}
+
+#[async_trait]
+impl AsyncCollectorPlugin for TcpHealthPlugin {
+    type T = TcpHealthStats;
+
+    async fn try_collect(&mut self) -> Result<Option<TcpHealthStats>> {
+        if self.skel.is_none() {
+            self.skel = Some(SendSkel(self.load()?));
+            // Numbers are shown if the names aren't available
+            self.drop_reason_names = KFREE_SKB_FORMATS
+                .iter()
+                .find_map(|path| std::fs::read_to_string(path).ok())
+                .map(|format| parse_drop_reason_names(&format))
+                .unwrap_or_default();
+        }
+        let skel = &self.skel.as_ref().unwrap().0;
+        let maps = skel.maps();
+
+        // Entries may be evicted between getting a key and looking it up
+        let mut stats = TcpHealthStats::default();
+        let pid_counts = maps.pid_counts();
+        for key in pid_counts.keys() {
+            if let Some(value) = pid_counts.lookup(&key, MapFlags::ANY)? {
+                let mut pid = 0u32;
+                plain::copy_from_bytes(&mut pid, &key).expect("Key buffer was too short");
+                let mut counts = Counts::default();
+                plain::copy_from_bytes(&mut counts, &value).expect("Data buffer was too short");
+                stats.processes.insert(pid as i32, counts.into());
+            }
+        }
+        let cgroup_counts = maps.cgroup_counts();
+        for key in cgroup_counts.keys() {
+            if let Some(value) = cgroup_counts.lookup(&key, MapFlags::ANY)? {
+                let mut cgroup_id = 0u64;
+                plain::copy_from_bytes(&mut cgroup_id, &key).expect("Key buffer was too short");
+                let mut counts = Counts::default();
+                plain::copy_from_bytes(&mut counts, &value).expect("Data buffer was too short");
+                stats.cgroups.insert(cgroup_id, counts.into());
+            }
+        }
+        let drop_reasons = maps.drop_reasons();
+        for key in drop_reasons.keys() {
+            if let Some(value) = drop_reasons.lookup(&key, MapFlags::ANY)? {
+                let mut reason_key = ReasonKey::default();
+                plain::copy_from_bytes(&mut reason_key, &key).expect("Key buffer was too short");
+                let mut drops = 0u64;
+                plain::copy_from_bytes(&mut drops, &value).expect("Data buffer was too short");
+                // Only counted along with the cgroup's drops
+                if let Some(stat) = stats.cgroups.get_mut(&reason_key.cgroup_id) {
+                    stat.drop_reasons
+                        .get_or_insert_with(Default::default)
+                        .insert(self.get_drop_reason_name(reason_key.reason), drops);
+                }
+            }
+        }
+        Ok(Some(stats))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drop_reason_names() {
+        let format = r#"name: kfree_skb
+ID: 1496
+format:
+	field:unsigned short common_type;	offset:0;	size:2;	signed:0;
+	field:enum skb_drop_reason reason;	offset:32;	size:4;	signed:0;
+
+print fmt: "skbaddr=%p protocol=%u location=%p reason: %s", REC->skbaddr, REC->protocol, REC->location, __print_symbolic(REC->reason, { 1, "CONSUMED" }, { 2, "NOT_SPECIFIED" }, { 3, "NO_SOCKET" }, { 5, "TCP_CSUM" })
+"#;
+        let names = parse_drop_reason_names(format);
+        assert_eq!(names.get(&3).map(String::as_str), Some("NO_SOCKET"));
+        assert_eq!(names.get(&5).map(String::as_str), Some("TCP_CSUM"));
+        assert_eq!(names.len(), 4);
+        // Kernels without drop reasons
+        assert!(parse_drop_reason_names("print fmt: \"skbaddr=%p\", REC->skbaddr").is_empty());
+    }
+}
@@ -0,0 +1,146 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Measures seeking to timestamps and scanning through a synthetic store
+//! spanning several shards, as replay does when jumping across days.
+
+use std::collections::BTreeMap;
+use std::time::{Duration, SystemTime};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use slog::Drain;
+use tempdir::TempDir;
+
+use model::CgroupSample;
+use store::cursor::{Cursor, KeyedCursor, StoreCursor};
+use store::{ChunkSizePo2, CompressionMode, DataFrame, Direction, Format, StoreWriter};
+
+/// Store shards are one day long
+const SHARD_TIME: u64 = 24 * 60 * 60;
+const NR_SHARDS: u64 = 4;
+const INTERVAL_S: u64 = 60;
+const NR_SEEKS: u64 = 64;
+
+fn get_logger() -> slog::Logger {
+    let plain = slog_term::PlainSyncDecorator::new(std::io::stderr());
+    slog::Logger::root(slog_term::FullFormat::new(plain).build().fuse(), slog::o!())
+}
+
+fn first_timestamp() -> u64 {
+    SHARD_TIME
+}
+
+fn last_timestamp() -> u64 {
+    first_timestamp() + NR_SHARDS * SHARD_TIME - INTERVAL_S
+}
+
+/// A frame with a few cgroups whose values change with the timestamp.
+fn make_frame(timestamp: u64) -> DataFrame {
+    let mut frame = DataFrame::default();
+    let children = (0..16)
+        .map(|i| {
+            let child = CgroupSample {
+                memory_current: Some((timestamp * (i + 1)) as i64),
+                memory_swap_current: Some(i as i64),
+                inode_number: Some(i as i64),
+                ..Default::default()
+            };
+            (format!("child_{}.slice", i), child)
+        })
+        .collect::<BTreeMap<_, _>>();
+    frame.sample.cgroup.memory_current = Some(timestamp as i64);
+    frame.sample.cgroup.children = Some(children);
+    frame
+}
+
+fn write_store() -> TempDir {
+    let dir = TempDir::new("below_cursor_bench").expect("tempdir failed");
+    let mut writer = StoreWriter::new_with_timestamp(
+        get_logger(),
+        &dir,
+        SystemTime::UNIX_EPOCH + Duration::from_secs(first_timestamp()),
+        CompressionMode::ZstdDictionary(ChunkSizePo2(4)),
+        Format::Cbor,
+    )
+    .expect("Failed to create store");
+    for ts in (first_timestamp()..=last_timestamp()).step_by(INTERVAL_S as usize) {
+        writer
+            .put(
+                SystemTime::UNIX_EPOCH + Duration::from_secs(ts),
+                &make_frame(ts),
+            )
+            .expect("Failed to store data");
+    }
+    dir
+}
+
+/// Timestamps spread over the whole store in an order that jumps between
+/// days, generated with a fixed LCG so that runs are comparable.
+fn seek_timestamps() -> Vec<u64> {
+    // Start after the first sample so that every seek has a sample before it
+    let begin = first_timestamp() + INTERVAL_S;
+    let span = last_timestamp() - begin;
+    let mut state: u64 = 42;
+    (0..NR_SEEKS)
+        .map(|_| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            begin + (state >> 33) % span
+        })
+        .collect()
+}
+
+fn scan(dir: &TempDir, direction: Direction) -> usize {
+    let mut cursor = StoreCursor::new(get_logger(), dir.path().to_path_buf());
+    let mut nr_frames = 0;
+    while cursor.next(direction).expect("Failed to read").is_some() {
+        nr_frames += 1;
+    }
+    nr_frames
+}
+
+fn cursor_benchmark(c: &mut Criterion) {
+    let dir = write_store();
+    let nr_frames = ((last_timestamp() - first_timestamp()) / INTERVAL_S + 1) as usize;
+    let timestamps = seek_timestamps();
+
+    let mut group = c.benchmark_group("store_cursor");
+    group.bench_function("seek", |b| {
+        let mut cursor = StoreCursor::new(get_logger(), dir.path().to_path_buf());
+        b.iter(|| {
+            for ts in &timestamps {
+                // Replay reads the sample and the one before it
+                cursor
+                    .get_next(ts, Direction::Forward)
+                    .expect("Failed to read")
+                    .expect("Did not find sample");
+                cursor
+                    .next(Direction::Reverse)
+                    .expect("Failed to read")
+                    .expect("Did not find sample");
+            }
+        });
+    });
+    group.bench_function("scan_forward", |b| {
+        b.iter(|| assert_eq!(scan(&dir, Direction::Forward), nr_frames));
+    });
+    group.bench_function("scan_reverse", |b| {
+        b.iter(|| assert_eq!(scan(&dir, Direction::Reverse), nr_frames));
+    });
+    group.finish();
+}
+
+criterion_group!(benches, cursor_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,122 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Compares store size and read speed of the serialization formats on
+//! samples collected from the running host. Sizes are printed before the
+//! read benchmarks run.
+
+use std::time::{Duration, SystemTime};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use slog::Drain;
+use tempdir::TempDir;
+
+use common::fileutil::get_dir_size;
+use common::util::get_unix_timestamp;
+use model::Collector;
+use store::cursor::{Cursor, KeyedCursor, StoreCursor};
+use store::{ChunkSizePo2, CompressionMode, DataFrame, Direction, Format, StoreWriter};
+
+const NR_SAMPLES: u64 = 32;
+
+fn get_logger() -> slog::Logger {
+    let plain = slog_term::PlainSyncDecorator::new(std::io::stderr());
+    slog::Logger::root(slog_term::FullFormat::new(plain).build().fuse(), slog::o!())
+}
+
+fn collect_frames() -> Vec<DataFrame> {
+    let collector = Collector::new(get_logger(), Default::default());
+    (0..NR_SAMPLES)
+        .map(|_| {
+            std::thread::sleep(Duration::from_millis(100));
+            DataFrame {
+                sample: collector
+                    .collect_sample()
+                    .expect("Failed to collect sample"),
+            }
+        })
+        .collect()
+}
+
+fn write_store(frames: &[DataFrame], compression_mode: CompressionMode, format: Format) -> TempDir {
+    let dir = TempDir::new("below_format_bench").expect("tempdir failed");
+    // Keep everything in one shard
+    let ts = SystemTime::UNIX_EPOCH + Duration::from_secs(24 * 60 * 60);
+    let mut writer =
+        StoreWriter::new_with_timestamp(get_logger(), &dir, ts, compression_mode, format)
+            .expect("Failed to create store");
+    for (i, frame) in frames.iter().enumerate() {
+        writer
+            .put(ts + Duration::from_secs(i as u64), frame)
+            .expect("Failed to store data");
+    }
+    dir
+}
+
+fn read_store(dir: &TempDir) -> usize {
+    let mut cursor = StoreCursor::new(get_logger(), dir.path().to_path_buf());
+    let ts = get_unix_timestamp(SystemTime::UNIX_EPOCH + Duration::from_secs(24 * 60 * 60));
+    let mut nr_frames = 0;
+    if cursor
+        .jump_to_key(&ts, Direction::Forward)
+        .expect("Failed to read")
+    {
+        while cursor.get().is_some() {
+            nr_frames += 1;
+            if !cursor.advance(Direction::Forward).expect("Failed to read") {
+                break;
+            }
+        }
+    }
+    nr_frames
+}
+
+fn format_benchmark(c: &mut Criterion) {
+    let frames = collect_frames();
+    let configs = [
+        ("uncompressed", CompressionMode::None),
+        ("zstd", CompressionMode::Zstd),
+        (
+            "zstd_dict16",
+            CompressionMode::ZstdDictionary(ChunkSizePo2(4)),
+        ),
+        ("lz4", CompressionMode::Lz4),
+    ];
+    let formats = [("cbor", Format::Cbor), ("msgpack", Format::MessagePack)];
+
+    let mut group = c.benchmark_group("read_store");
+    for (compression_name, compression_mode) in configs.iter() {
+        for (format_name, format) in formats.iter() {
+            let dir = write_store(&frames, *compression_mode, *format);
+            println!(
+                "{}/{}: {} bytes for {} frames",
+                format_name,
+                compression_name,
+                get_dir_size(dir.path().to_path_buf()),
+                NR_SAMPLES
+            );
+            group.bench_with_input(
+                BenchmarkId::new(*format_name, compression_name),
+                &dir,
+                |b, dir| {
+                    b.iter(|| assert_eq!(read_store(dir), NR_SAMPLES as usize));
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, format_benchmark);
+criterion_main!(benches);
@@ -17,7 +17,7 @@ use bytes::Bytes;
 
 /// This file defines a minimalistic compressor and decompressor interface
 /// optimized for below's usage. They are wrappers around general compression
-/// libraries. Currently only zstd is supported.
+/// libraries. zstd and lz4 are supported, dictionaries are zstd only.
 
 // TODO: Use latest zstd as implementation
 // TODO: Consider using experimental feature to load dict by reference
@@ -26,23 +26,98 @@ fn code_to_err(code: zstd_safe::ErrorCode) -> Error {
     anyhow!(zstd_safe::get_error_name(code))
 }
 
+/// Compression algorithm of a frame.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Codec {
+    Zstd,
+    /// Faster than zstd at a worse ratio, for hosts where the CPU time of
+    /// the recorder matters more than disk space. No dictionary support.
+    Lz4,
+}
+
+impl std::str::FromStr for Codec {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "zstd" => Ok(Codec::Zstd),
+            "lz4" => Ok(Codec::Lz4),
+            _ => bail!("Unknown compression codec {}, expected zstd or lz4", s),
+        }
+    }
+}
+
+/// Returns the zstd compression levels that can be passed to
+/// `Compressor::set_level`.
+pub fn zstd_levels() -> std::ops::RangeInclusive<i32> {
+    1..=zstd_safe::max_c_level()
+}
+
+/// Trains a zstd dictionary of at most `max_size` bytes from `samples`,
+/// e.g. recently written frames.
+pub fn train_dict<S: AsRef<[u8]>>(samples: &[S], max_size: usize) -> Result<Bytes> {
+    let dict = zstd::dict::from_samples(samples, max_size)
+        .with_context(|| format!("Failed to train dictionary on {} samples", samples.len()))?;
+    Ok(dict.into())
+}
+
 pub struct Compressor {
-    cctx: zstd_safe::CCtx<'static>,
+    codec: Codec,
+    /// None unless zstd is used, lz4 needs no context
+    cctx: Option<zstd_safe::CCtx<'static>>,
     dict_loaded: bool,
 }
 
 impl Compressor {
     pub fn new() -> Self {
+        Self::with_codec(Codec::Zstd)
+    }
+
+    pub fn with_codec(codec: Codec) -> Self {
         Self {
-            cctx: zstd_safe::CCtx::create(),
+            codec,
+            cctx: (codec == Codec::Zstd).then(zstd_safe::CCtx::create),
             dict_loaded: false,
         }
     }
 
+    pub fn get_codec(&self) -> Codec {
+        self.codec
+    }
+
+    fn zstd_cctx(&mut self) -> Result<&mut zstd_safe::CCtx<'static>> {
+        let codec = self.codec;
+        self.cctx
+            .as_mut()
+            .ok_or_else(|| anyhow!("{:?} has no zstd context", codec))
+    }
+
+    /// Whether a dict is loaded.
+    pub fn has_dict(&self) -> bool {
+        self.dict_loaded
+    }
+
+    /// Sets the zstd compression level, see `zstd_levels`.
+    pub fn set_level(&mut self, level: i32) -> Result<()> {
+        if !zstd_levels().contains(&level) {
+            bail!(
+                "zstd level must be between {} and {}, got {}",
+                zstd_levels().start(),
+                zstd_levels().end(),
+                level
+            );
+        }
+        self.zstd_cctx()?
+            .set_parameter(zstd_safe::CParameter::CompressionLevel(level))
+            .map_err(code_to_err)
+            .context("Failed to set compression level")?;
+        Ok(())
+    }
+
     /// Resets the dict loaded.
     fn reset_dict(&mut self) -> Result<()> {
         if self.dict_loaded {
-            self.cctx
+            self.zstd_cctx()?
                 .load_dictionary(&[])
                 .map_err(code_to_err)
                 .context("Failed to load empty dictionary")?;
@@ -53,7 +128,10 @@ impl Compressor {
 
     /// Loads the given dict.
     pub fn load_dict(&mut self, dict: &[u8]) -> Result<()> {
-        self.cctx
+        if self.codec != Codec::Zstd {
+            bail!("Dictionaries are only supported by zstd");
+        }
+        self.zstd_cctx()?
             .load_dictionary(dict)
             .map_err(code_to_err)
             .context("Failed to load dictionary")?;
@@ -63,8 +141,11 @@ impl Compressor {
 
     /// Compresses the given frame using the previously loaded dict, if any.
     pub fn compress_with_loaded_dict(&mut self, frame: &[u8]) -> Result<Bytes> {
+        if self.codec == Codec::Lz4 {
+            return Ok(lz4_flex::compress_prepend_size(frame).into());
+        }
         let mut buf = Vec::with_capacity(zstd_safe::compress_bound(frame.len()));
-        self.cctx
+        self.zstd_cctx()?
             .compress2(&mut buf, frame)
             .map_err(code_to_err)
             .context("zstd compress2 failed")?;
@@ -155,6 +236,14 @@ impl<K> Decompressor<K> {
     }
 }
 
+/// Decompresses the given lz4 frame. lz4 needs no context, so there is no
+/// decompressor to keep around.
+pub fn decompress_lz4(frame: &[u8]) -> Result<Bytes> {
+    Ok(lz4_flex::decompress_size_prepended(frame)
+        .map_err(|e| anyhow!("lz4 decompress failed: {}", e))?
+        .into())
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -217,6 +306,63 @@ mod test {
         assert_eq!(decomp_dict_reset, data);
     }
 
+    #[test]
+    fn levels_and_lz4() {
+        let data: Bytes = gen_data(4096).into();
+        let mut d = Decompressor::<()>::new();
+
+        let mut c = Compressor::new();
+        assert!(c.set_level(0).is_err());
+        c.set_level(*zstd_levels().end())
+            .expect("Failed to set level");
+        let comp = c
+            .compress_with_dict_reset(&data)
+            .expect("Failed to compress");
+        let decomp = d
+            .decompress_with_dict_reset(&comp)
+            .expect("Failed to decompress");
+        assert_eq!(decomp, data);
+
+        let mut c = Compressor::with_codec(Codec::Lz4);
+        assert!(c.load_dict(&data).is_err());
+        let comp = c
+            .compress_with_dict_reset(&data)
+            .expect("Failed to compress");
+        assert_eq!(decompress_lz4(&comp).expect("Failed to decompress"), data);
+        assert!(decompress_lz4(&comp[..comp.len() / 2]).is_err());
+    }
+
+    #[test]
+    fn trained_dict() {
+        // Frames sharing most of their content, like consecutive samples
+        let frames = (0..64)
+            .map(|i| {
+                let mut frame = gen_data(1024);
+                frame.extend((0..256).map(|j| ((i * 31 + j * 7) % 251) as u8));
+                frame
+            })
+            .collect::<Vec<_>>();
+        let dict = train_dict(&frames, 4096).expect("Failed to train dict");
+
+        let mut c = Compressor::new();
+        let comp_default = c
+            .compress_with_dict_reset(&frames[0])
+            .expect("Failed to compress");
+        c.load_dict(&dict).expect("Failed to load dict");
+        assert!(c.has_dict());
+        let comp_with_dict = c
+            .compress_with_loaded_dict(&frames[0])
+            .expect("Failed to compress");
+        assert!(comp_with_dict.len() < comp_default.len());
+
+        let mut d = Decompressor::new();
+        d.load_dict(dict, ()).expect("Failed to load dict");
+        let decomp = d
+            .decompress_with_loaded_dict(&comp_with_dict)
+            .expect("Failed to decompress");
+        assert_eq!(decomp, frames[0]);
+    }
+
     #[test]
     fn compatibility() {
         let data: Bytes = gen_data(128).into();
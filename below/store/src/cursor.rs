@@ -13,7 +13,7 @@
 // limitations under the License.
 
 use std::cell::RefCell;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::ErrorKind;
 use std::path::PathBuf;
 use std::time::SystemTime;
@@ -22,10 +22,10 @@ use anyhow::{anyhow, bail, Context, Result};
 use memmap::{Mmap, MmapOptions};
 use slog::{warn, Logger};
 
-use crate::compression::Decompressor;
+use crate::compression::{decompress_lz4, Codec, Decompressor};
 use crate::{
-    deserialize_frame, get_index_files, Crc32, DataFrame, Direction, Format, IndexEntry,
-    IndexEntryFlags, SerializedFrame, INDEX_ENTRY_SIZE, SHARD_TIME,
+    deserialize_frame, get_index_files, get_shards, Crc32, DataFrame, Direction, Format,
+    IndexEntry, IndexEntryFlags, SerializedFrame, INDEX_ENTRY_SIZE,
 };
 
 /// A read-only Iterator that can move back and forth.
@@ -146,11 +146,22 @@ pub struct StoreCursor {
     // locates the exact sample of this store. Offset could be None if shard
     // does not exist or just moved to a newly initialized shard.
     index_offset: Option<usize>,
-    // Used for extracting compressed frames. If dictionary is used, it's also
-    // cached, along with the shard and dict_index_offset that identify it.
+    // Used for extracting compressed frames without dictionary.
     decompressor: RefCell<Option<Decompressor<(u64, usize)>>>,
+    // Decompressors with the dictionaries of recently used chunks loaded,
+    // least recently used first. Each is identified by the shard and
+    // dict_index_offset of its key frame.
+    dict_decompressors: RefCell<Vec<Decompressor<(u64, usize)>>>,
+    // Decompressor with the trained dictionary of a shard loaded, identified
+    // by the shard.
+    trained_dict_decompressor: RefCell<Option<Decompressor<u64>>>,
 }
 
+/// Number of chunk dictionaries kept loaded. Replay reads a sample and the
+/// one before it, which may be in different chunks, and going back and forth
+/// in time shouldn't decompress the same key frames over and over.
+const DICT_CACHE_SIZE: usize = 4;
+
 enum StoreFile {
     Index,
     Data,
@@ -167,6 +178,8 @@ impl StoreCursor {
             data_mmap: None,
             index_offset: None,
             decompressor: RefCell::new(None),
+            dict_decompressors: RefCell::new(Vec::new()),
+            trained_dict_decompressor: RefCell::new(None),
         }
     }
 
@@ -366,6 +379,25 @@ impl StoreCursor {
         }
     }
 
+    /// Binary search the current shard for the first valid index entry with a
+    /// timestamp not less than `key`. Returns the offset of the last entry if
+    /// there is no such entry, or None if the shard has no index.
+    fn search_index_offset(&self, key: u64) -> Option<usize> {
+        let nr_entries = self.index_mmap.as_ref()?.len() / INDEX_ENTRY_SIZE;
+        let (mut lo, mut hi) = (0, nr_entries);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            // Skip over padding and corrupt entries to the next valid one
+            let next_valid = (mid..hi)
+                .find_map(|i| Some((i, self.get_index_entry_at(i * INDEX_ENTRY_SIZE)?.timestamp)));
+            match next_valid {
+                Some((i, timestamp)) if timestamp < key => lo = i + 1,
+                _ => hi = mid,
+            }
+        }
+        Some(std::cmp::min(lo, nr_entries.checked_sub(1)?) * INDEX_ENTRY_SIZE)
+    }
+
     /// Get the index entry the cursor currently pointing at.
     fn get_index_entry(&self) -> Option<&IndexEntry> {
         self.get_index_entry_at(self.index_offset?)
@@ -375,20 +407,43 @@ impl StoreCursor {
     /// necessary.
     fn get_serialized_single_frame<'a>(
         data_slice: &'a [u8],
-        compressed: bool,
+        flags: IndexEntryFlags,
         decompressor: &mut Option<Decompressor<(u64, usize)>>,
     ) -> Result<SerializedFrame<'a>> {
-        let serialized_frame = if compressed {
-            SerializedFrame::Owned(
-                decompressor
-                    .get_or_insert_with(Decompressor::new)
-                    .decompress_with_dict_reset(data_slice)
-                    .context("Failed to decompress data frame")?,
-            )
-        } else {
-            SerializedFrame::Borrowed(data_slice)
+        if !flags.contains(IndexEntryFlags::COMPRESSED) {
+            return Ok(SerializedFrame::Borrowed(data_slice));
+        }
+        let bytes = match flags.get_codec() {
+            Some(Codec::Zstd) => decompressor
+                .get_or_insert_with(Decompressor::new)
+                .decompress_with_dict_reset(data_slice),
+            Some(Codec::Lz4) => decompress_lz4(data_slice),
+            None => bail!("Unknown codec of data frame: flags={:#x}", flags.bits()),
         };
-        Ok(serialized_frame)
+        Ok(SerializedFrame::Owned(
+            bytes.context("Failed to decompress data frame")?,
+        ))
+    }
+
+    /// Get the serialized frame that is compressed with the trained
+    /// dictionary of the current shard.
+    fn get_serialized_trained_dict_frame(&self, data_slice: &[u8]) -> Result<SerializedFrame> {
+        let shard = self.shard.expect("shard should be set");
+        let mut decompressor = self.trained_dict_decompressor.borrow_mut();
+        let decompressor = decompressor.get_or_insert_with(Decompressor::new);
+        if decompressor.get_dict_key() != Some(&shard) {
+            let dict_path = self.path.join(format!("dict_{:011}", shard));
+            let dict = fs::read(&dict_path)
+                .with_context(|| format!("Failed to read {}", dict_path.display()))?;
+            decompressor
+                .load_dict(dict.into(), shard)
+                .context("Failed to set decompressor dict")?;
+        }
+        Ok(SerializedFrame::Owned(
+            decompressor
+                .decompress_with_loaded_dict(data_slice)
+                .context("Failed to decompress data frame with trained dictionary")?,
+        ))
     }
 
     /// Get the serialized, uncompressed frame that is part of a
@@ -404,7 +459,6 @@ impl StoreCursor {
         data_slice: &[u8],
         index_offset: usize,
         chunk_compress_size_po2: u32,
-        decompressor: &mut Option<Decompressor<(u64, usize)>>,
     ) -> Result<SerializedFrame> {
         // Calculate offset into the chunk. If this is 0, then this
         // is the first frame and hence key frame of the chunk.
@@ -414,17 +468,27 @@ impl StoreCursor {
         let shard = self.shard.expect("shard should be set");
         let dict_key = (shard, dict_index_offset);
 
-        let decompressor = match decompressor {
-            Some(d) if d.get_dict_key() == Some(&dict_key) => d,
-            _ => {
+        let mut dict_decompressors = self.dict_decompressors.borrow_mut();
+        let cached = dict_decompressors
+            .iter()
+            .position(|d| d.get_dict_key() == Some(&dict_key));
+        let mut decompressor = match cached {
+            Some(pos) => dict_decompressors.remove(pos),
+            None => {
                 let (index_entry, data_slice) = self.get_index_and_data_at(dict_index_offset)?;
                 let dict_key_frame = Self::get_serialized_single_frame(
                     data_slice,
-                    index_entry.flags.contains(IndexEntryFlags::COMPRESSED),
-                    decompressor,
+                    index_entry.flags,
+                    &mut self.decompressor.borrow_mut(),
                 )
                 .context("Failed to get serialized dict key frame")?;
-                let d = decompressor.get_or_insert_with(Decompressor::new);
+                // Reuse the least recently used decompressor once the cache
+                // is full
+                let mut d = if dict_decompressors.len() >= DICT_CACHE_SIZE {
+                    dict_decompressors.remove(0)
+                } else {
+                    Decompressor::new()
+                };
                 d.load_dict(dict_key_frame.into_owned(), dict_key)
                     .context("Failed to set decompressor dict")?;
                 d
@@ -434,13 +498,14 @@ impl StoreCursor {
         // First frame in chunk is the dict key frame. Other frames
         // in the chunk are decompressed using the dict key frame.
         let bytes = if index_offset == dict_index_offset {
-            decompressor.get_dict().clone()
+            Ok(decompressor.get_dict().clone())
         } else {
             decompressor
                 .decompress_with_loaded_dict(data_slice)
-                .context("Failed to decompress data frame with dictionary")?
+                .context("Failed to decompress data frame with dictionary")
         };
-        Ok(SerializedFrame::Owned(bytes))
+        dict_decompressors.push(decompressor);
+        Ok(SerializedFrame::Owned(bytes?))
     }
 
     /// Get index entry at offset and it's corresponding data slice.
@@ -474,9 +539,31 @@ impl StoreCursor {
         Ok((index_entry, data_slice))
     }
 
+    /// Get the timestamp and deserialized frame at an index offset in the
+    /// current shard.
+    pub(crate) fn get_frame_at(&self, index_offset: usize) -> Result<(SystemTime, DataFrame)> {
+        let (index_entry, serialized_data) = self
+            .get_index_and_serialized_frame_at(index_offset)
+            .context("Failed to extract serialized data frame")?;
+        let format = Format::from_flags(index_entry.flags).ok_or_else(|| {
+            anyhow!(
+                "Unknown format of data frame: flags={:#x}",
+                index_entry.flags.bits()
+            )
+        })?;
+        let ts = std::time::UNIX_EPOCH + std::time::Duration::from_secs(index_entry.timestamp);
+        let df = deserialize_frame(
+            serialized_data.as_ref(),
+            format,
+            index_entry.flags.get_schema_version(),
+        )
+        .context("Failed to deserialize data frame")?;
+        Ok((ts, df))
+    }
+
     /// Get the index entry and uncompressed serialized data at an
     /// index offset in the current shard.
-    fn get_index_and_serialized_frame_at(
+    pub(crate) fn get_index_and_serialized_frame_at(
         &self,
         index_offset: usize,
     ) -> Result<(&IndexEntry, SerializedFrame)> {
@@ -486,17 +573,15 @@ impl StoreCursor {
             // This frame is dictionary compressed, or it is the
             // first frame of a chunk which should be stored as
             // dictionary.
-            self.get_serialized_chunk_frame(
-                data_slice,
-                index_offset,
-                chunk_compress_size_po2,
-                &mut self.decompressor.borrow_mut(),
-            )
-            .context("Failed to get serialized chunk frame")?
+            self.get_serialized_chunk_frame(data_slice, index_offset, chunk_compress_size_po2)
+                .context("Failed to get serialized chunk frame")?
+        } else if index_entry.flags.contains(IndexEntryFlags::TRAINED_DICT) {
+            self.get_serialized_trained_dict_frame(data_slice)
+                .context("Failed to get serialized frame")?
         } else {
             Self::get_serialized_single_frame(
                 data_slice,
-                index_entry.flags.contains(IndexEntryFlags::COMPRESSED),
+                index_entry.flags,
                 &mut self.decompressor.borrow_mut(),
             )
             .context("Failed to get serialized single frame")?
@@ -513,11 +598,11 @@ pub struct StoreOffset {
 }
 
 impl StoreOffset {
-    /// Shard and index offset are trimmed to multiples of SHARD_TIME and
-    /// INDEX_ENTRY_SIZE respectively. Index offset is ignored if shard is None.
+    /// Shard is the timestamp the shard starts at. Index offset is trimmed to
+    /// a multiple of INDEX_ENTRY_SIZE and ignored if shard is None.
     pub fn new(shard: Option<u64>, index_offset: Option<usize>) -> Self {
         StoreOffset {
-            shard: shard.as_ref().map(|s| s - s % SHARD_TIME),
+            shard,
             index_offset: shard.and(index_offset.map(|o| o - o % INDEX_ENTRY_SIZE)),
         }
     }
@@ -575,28 +660,10 @@ impl Cursor for StoreCursor {
     /// This does not mean samples are depleted. More could be retrieved by
     /// advancing further to skip the holes.
     fn get(&self) -> Option<(SystemTime, DataFrame)> {
-        match self.get_index_and_serialized_frame_at(self.index_offset?) {
-            Ok((index_entry, serialized_data)) => {
-                let format = if index_entry.flags.contains(IndexEntryFlags::CBOR) {
-                    Format::Cbor
-                } else {
-                    panic!("Unexpected format");
-                };
-                let ts =
-                    std::time::UNIX_EPOCH + std::time::Duration::from_secs(index_entry.timestamp);
-                match deserialize_frame(serialized_data.as_ref(), format) {
-                    Ok(df) => Some((ts, df)),
-                    Err(e) => {
-                        warn!(self.logger, "Failed to deserialize data frame: {}", e);
-                        None
-                    }
-                }
-            }
+        match self.get_frame_at(self.index_offset?) {
+            Ok(frame) => Some(frame),
             Err(e) => {
-                warn!(
-                    self.logger,
-                    "Failed to extract serialized data frame: {}", e
-                );
+                warn!(self.logger, "{:#}", e);
                 None
             }
         }
@@ -614,34 +681,27 @@ impl KeyedCursor<u64> for StoreCursor {
     /// Set the cursor offset near the given timestamp by inferring shard and
     /// index offset.
     fn jump_near_key(&mut self, key: &u64, _direction: Direction) {
-        let time_offset = key % SHARD_TIME;
-        let shard = key - time_offset;
-        self.set_offset(StoreOffset::new(Some(shard), None));
-        // Move to the end of the shard.
-        if self.advance_index(Direction::Reverse) {
-            if let Some(last_entry) = self.get_index_entry() {
-                let last_entry_index_offset = self
-                    .get_offset()
-                    .get_index_offset()
-                    .expect("get_index_offset should return Some if get_index_entry returns Some");
-                let last_entry_time_offset = last_entry.timestamp % SHARD_TIME;
-                if last_entry_time_offset != 0 {
-                    // Assume samples are recorded in constant interval and
-                    // scale index offset by time offset
-                    let index_offset_hint = (last_entry_index_offset as f64
-                        / last_entry_time_offset as f64
-                        * time_offset as f64) as usize;
-                    self.set_offset(StoreOffset::new(Some(shard), Some(index_offset_hint)));
-                }
+        // Shards may differ in length, so look for the last one starting at
+        // or before key. Otherwise advancing from key finds the closest one.
+        let shard = match get_shards(&self.path) {
+            Ok(shards) => shards.into_iter().rev().find(|shard| shard <= key),
+            Err(e) => {
+                warn!(self.logger, "{:#}", e);
+                None
             }
         }
+        .unwrap_or(*key);
+        self.set_offset(StoreOffset::new(Some(shard), None));
+        if let Some(index_offset) = self.search_index_offset(*key) {
+            self.set_offset(StoreOffset::new(Some(shard), Some(index_offset)));
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{serialize_frame, ChunkSizePo2, CompressionMode, StoreWriter};
+    use crate::{serialize_frame, ChunkSizePo2, CompressionMode, StoreWriter, SHARD_TIME};
     use common::util::get_unix_timestamp;
     use slog::Drain;
     use std::fs::OpenOptions;
@@ -1047,10 +1107,11 @@ mod tests {
         writer.put(ts + 5).unwrap();
         writer.put(ts + SHARD_TIME * 2 + 5).unwrap();
 
+        let shard = ts - ts % SHARD_TIME;
         let expected_offsets = &[
-            StoreOffset::new(Some(ts), Some(0)),
-            StoreOffset::new(Some(ts), Some(INDEX_ENTRY_SIZE)),
-            StoreOffset::new(Some(ts + SHARD_TIME * 2), Some(0)),
+            StoreOffset::new(Some(shard), Some(0)),
+            StoreOffset::new(Some(shard), Some(INDEX_ENTRY_SIZE)),
+            StoreOffset::new(Some(shard + SHARD_TIME * 2), Some(0)),
         ];
 
         // Verify offset values.
@@ -1094,7 +1155,10 @@ mod tests {
         writer.put(ts + SHARD_TIME * 2).unwrap();
 
         // To same shard
-        cursor.set_offset(StoreOffset::new(Some(ts), Some(INDEX_ENTRY_SIZE)));
+        cursor.set_offset(StoreOffset::new(
+            Some(ts - ts % SHARD_TIME),
+            Some(INDEX_ENTRY_SIZE),
+        ));
         assert!(cursor.advance(Reverse).unwrap());
         assert_eq!(cursor.get_key(), Some(ts));
         // To different shard
@@ -1141,4 +1205,103 @@ mod tests {
         cursor.jump_to_key(&(ts + 5 * 100), Reverse).unwrap();
         assert_eq!(cursor.get_key(), Some(ts + 5 * 21));
     }
+
+    /// Seeking back and forth between dictionary compressed chunks, across
+    /// the padding left by a restarted writer, reads back the right frames.
+    #[test]
+    fn jump_between_chunks() {
+        let dir = TempDir::new("below_store_test").expect("tempdir failed");
+        let ts = SHARD_TIME;
+        let timestamps: Vec<u64> = (0..40).map(|i| ts + i * 5).collect();
+        // Restarting the writer mid chunk pads the index to the next chunk
+        for session in timestamps.chunks(13) {
+            let mut writer = StoreWriter::new_with_timestamp(
+                get_logger(),
+                &dir,
+                std::time::UNIX_EPOCH + std::time::Duration::from_secs(session[0]),
+                CompressionMode::ZstdDictionary(ChunkSizePo2(2)),
+                Format::Cbor,
+            )
+            .expect("Failed to create store");
+            for key in session {
+                let mut frame = DataFrame::default();
+                frame.sample.cgroup.memory_current = Some(*key as i64);
+                writer
+                    .put(
+                        std::time::UNIX_EPOCH + std::time::Duration::from_secs(*key),
+                        &frame,
+                    )
+                    .expect("Failed to store data");
+            }
+        }
+
+        let mut cursor = StoreCursor::new(get_logger(), dir.path().to_path_buf());
+        for i in [3, 37, 4, 22, 13, 0, 39, 14, 12, 3] {
+            let key = timestamps[i];
+            let (frame_ts, frame) = cursor
+                .get_next(&key, Forward)
+                .expect("Failed to read sample")
+                .expect("Did not find stored sample");
+            assert_eq!(get_unix_timestamp(frame_ts), key);
+            assert_eq!(frame.sample.cgroup.memory_current, Some(key as i64));
+            // Keys between samples round in the requested direction
+            cursor.jump_to_key(&(key + 1), Reverse).unwrap();
+            assert_eq!(cursor.get_key(), Some(key));
+        }
+    }
+
+    /// Frames written before schema versions were recorded are read back as
+    /// is and reported as version 0.
+    #[test]
+    fn read_unversioned_frames() {
+        let dir = TempDir::new("below_store_test").expect("tempdir failed");
+        let ts = SHARD_TIME;
+        let writer = TestWriter::new(&dir);
+        writer.put(ts).unwrap();
+        writer.put_corrupt_index(ts + 5).unwrap();
+        {
+            let mut writer =
+                StoreWriter::new(get_logger(), &dir, CompressionMode::None, Format::Cbor)
+                    .expect("Failed to create store");
+            writer
+                .put(
+                    std::time::UNIX_EPOCH + std::time::Duration::from_secs(ts + 10),
+                    &DataFrame::default(),
+                )
+                .expect("Failed to store data");
+            writer
+                .put(
+                    std::time::UNIX_EPOCH + std::time::Duration::from_secs(ts + SHARD_TIME),
+                    &DataFrame::default(),
+                )
+                .expect("Failed to store data");
+        }
+
+        let mut cursor = StoreCursor::new(get_logger(), dir.path().to_path_buf());
+        for key in &[ts, ts + 10] {
+            let (frame_ts, frame) = cursor
+                .get_next(key, Forward)
+                .expect("Failed to read sample")
+                .expect("Did not find stored sample");
+            assert_eq!(get_unix_timestamp(frame_ts), *key);
+            assert_eq!(frame, DataFrame::default());
+        }
+
+        let info = crate::get_store_info(&dir).expect("Failed to get store info");
+        assert_eq!(info.len(), 2);
+        assert_eq!(info[0].shard, ts);
+        assert_eq!(info[0].entries, 2);
+        assert_eq!(info[0].corrupt_entries, 1);
+        assert_eq!(
+            info[0].schema_versions,
+            maplit::btreemap! {0 => 1, crate::schema::CURRENT_SCHEMA_VERSION => 1}
+        );
+        assert_eq!(info[1].shard, ts + SHARD_TIME);
+        assert_eq!(info[1].entries, 1);
+        assert_eq!(info[1].corrupt_entries, 0);
+        assert_eq!(
+            info[1].schema_versions,
+            maplit::btreemap! {crate::schema::CURRENT_SCHEMA_VERSION => 1}
+        );
+    }
 }
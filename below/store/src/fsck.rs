@@ -0,0 +1,531 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Integrity checks of a store. Readers skip over anything damaged, so
+//! this is the only place that reports it.
+
+use std::fmt;
+use std::fs;
+use std::io::ErrorKind;
+use std::os::unix::fs::FileExt;
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+
+use common::util::get_unix_timestamp;
+
+use crate::cursor::{Cursor, KeyedCursor, StoreCursor, StoreOffset};
+use crate::{
+    copy_store_settings, get_index_files, get_shard_ranges, read_index_entry, CompressionMode,
+    Crc32, Direction, Format, IndexEntry, StoreWriter, INDEX_ENTRY_SIZE,
+};
+
+/// Without an explicit limit, gaps are reported when samples are further
+/// apart than this many times the typical interval of their shard
+const GAP_FACTOR: u64 = 4;
+
+/// Something wrong with an entry of a shard. Entries are identified by
+/// their byte offset into the index file.
+#[derive(Debug, PartialEq)]
+pub enum Problem {
+    /// The index file ends with a partially written entry
+    TruncatedIndex { trailing_bytes: usize },
+    /// The index has entries but the data file is missing or empty
+    MissingData,
+    /// The index entry fails its crc
+    CorruptIndexEntry { index_offset: usize },
+    /// The index entry points past the end of the data file
+    DataOutOfBounds {
+        index_offset: usize,
+        timestamp: u64,
+        offset: u64,
+        len: u32,
+    },
+    /// The data fails the crc recorded in the index entry
+    CorruptData { index_offset: usize, timestamp: u64 },
+    /// The timestamp is outside the shard or not after the previous entry
+    MisplacedTimestamp { index_offset: usize, timestamp: u64 },
+    /// A dictionary compressed entry whose chunk doesn't start with a valid
+    /// key frame of the same chunk size
+    MisalignedChunk { index_offset: usize, timestamp: u64 },
+    /// Index and data look fine but the frame can't be decompressed or
+    /// deserialized
+    UnreadableFrame {
+        index_offset: usize,
+        timestamp: u64,
+        error: String,
+    },
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::TruncatedIndex { trailing_bytes } => {
+                write!(
+                    f,
+                    "index ends with {} bytes of a partial entry",
+                    trailing_bytes
+                )
+            }
+            Problem::MissingData => write!(f, "data file is missing or empty"),
+            Problem::CorruptIndexEntry { index_offset } => {
+                write!(f, "index entry at {:#x} is corrupt", index_offset)
+            }
+            Problem::DataOutOfBounds {
+                index_offset,
+                timestamp,
+                offset,
+                len,
+            } => write!(
+                f,
+                "index entry at {:#x} (ts={}) points past the end of data: offset={:#x} len={}",
+                index_offset, timestamp, offset, len
+            ),
+            Problem::CorruptData {
+                index_offset,
+                timestamp,
+            } => write!(
+                f,
+                "data of index entry at {:#x} (ts={}) is corrupt",
+                index_offset, timestamp
+            ),
+            Problem::MisplacedTimestamp {
+                index_offset,
+                timestamp,
+            } => write!(
+                f,
+                "index entry at {:#x} has out of order timestamp {}",
+                index_offset, timestamp
+            ),
+            Problem::MisalignedChunk {
+                index_offset,
+                timestamp,
+            } => write!(
+                f,
+                "index entry at {:#x} (ts={}) has no valid dictionary key frame",
+                index_offset, timestamp
+            ),
+            Problem::UnreadableFrame {
+                index_offset,
+                timestamp,
+                error,
+            } => write!(
+                f,
+                "frame of index entry at {:#x} (ts={}) is unreadable: {}",
+                index_offset, timestamp, error
+            ),
+        }
+    }
+}
+
+/// Result of checking a single shard
+#[derive(Debug, Default, PartialEq)]
+pub struct ShardReport {
+    pub shard: u64,
+    /// Frames that can be read back
+    pub frames: u64,
+    /// Empty entries from dictionary compression chunk alignment. These are
+    /// expected after restarts and not a problem.
+    pub padding: u64,
+    pub problems: Vec<Problem>,
+}
+
+/// Two consecutive readable samples further apart than expected
+#[derive(Debug, PartialEq)]
+pub struct Gap {
+    /// Timestamps in seconds since epoch of the samples around the gap
+    pub from: u64,
+    pub to: u64,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct FsckReport {
+    /// Shards oldest first
+    pub shards: Vec<ShardReport>,
+    pub gaps: Vec<Gap>,
+}
+
+impl FsckReport {
+    pub fn nr_problems(&self) -> usize {
+        self.shards.iter().map(|shard| shard.problems.len()).sum()
+    }
+}
+
+/// Checks a single shard ending at `shard_end` and returns the timestamps of
+/// its readable frames
+fn check_shard(
+    cursor: &mut StoreCursor,
+    path: &Path,
+    shard: u64,
+    shard_end: Option<u64>,
+) -> Result<(ShardReport, Vec<u64>)> {
+    let mut report = ShardReport {
+        shard,
+        ..Default::default()
+    };
+    let mut timestamps = Vec::new();
+
+    let index_path = path.join(format!("index_{:011}", shard));
+    let index = fs::read(&index_path)
+        .with_context(|| format!("Failed to read {}", index_path.display()))?;
+    let data_path = path.join(format!("data_{:011}", shard));
+    let data_file = match fs::File::open(&data_path) {
+        Ok(file) => Some(file),
+        Err(e) if e.kind() == ErrorKind::NotFound => None,
+        Err(e) => {
+            return Err(e).context(format!("Failed to open {}", data_path.display()));
+        }
+    };
+    let data_len = match data_file.as_ref() {
+        Some(file) => file.metadata()?.len(),
+        None => 0,
+    };
+    let trailing_bytes = index.len() % INDEX_ENTRY_SIZE;
+    if trailing_bytes != 0 {
+        report
+            .problems
+            .push(Problem::TruncatedIndex { trailing_bytes });
+    }
+    let data_file = match data_file {
+        Some(file) if data_len > 0 => file,
+        _ => {
+            if index.len() >= INDEX_ENTRY_SIZE {
+                report.problems.push(Problem::MissingData);
+            }
+            return Ok((report, timestamps));
+        }
+    };
+
+    let entries: Vec<Option<IndexEntry>> = index
+        .chunks_exact(INDEX_ENTRY_SIZE)
+        .map(|slice| {
+            if slice == [0; INDEX_ENTRY_SIZE] {
+                None
+            } else {
+                Some(read_index_entry(slice))
+            }
+        })
+        .collect();
+    let is_valid = |entry: &IndexEntry| entry.crc32() == entry.index_crc;
+
+    // Loads the mmaps of the shard for get_frame_at
+    cursor.set_offset(StoreOffset::new(Some(shard), None));
+    let mut last_timestamp = None;
+    for (i, entry) in entries.iter().enumerate() {
+        let index_offset = i * INDEX_ENTRY_SIZE;
+        let entry = match entry {
+            Some(entry) => entry,
+            None => {
+                report.padding += 1;
+                continue;
+            }
+        };
+        if !is_valid(entry) {
+            report
+                .problems
+                .push(Problem::CorruptIndexEntry { index_offset });
+            continue;
+        }
+        let timestamp = entry.timestamp;
+
+        if entry.offset.saturating_add(entry.len as u64) > data_len {
+            report.problems.push(Problem::DataOutOfBounds {
+                index_offset,
+                timestamp,
+                offset: entry.offset,
+                len: entry.len,
+            });
+            continue;
+        }
+        let mut data = vec![0; entry.len as usize];
+        data_file
+            .read_exact_at(&mut data, entry.offset)
+            .with_context(|| format!("Failed to read {}", data_path.display()))?;
+        if data.crc32() != entry.data_crc {
+            report.problems.push(Problem::CorruptData {
+                index_offset,
+                timestamp,
+            });
+            continue;
+        }
+
+        let chunk_compress_size_po2 = entry.flags.get_chunk_compress_size_po2();
+        if chunk_compress_size_po2 > 0 {
+            let key_index = i & !((1 << chunk_compress_size_po2) - 1);
+            let key_is_valid = entries[key_index].as_ref().map_or(false, |key| {
+                is_valid(key) && key.flags.get_chunk_compress_size_po2() == chunk_compress_size_po2
+            });
+            if !key_is_valid {
+                report.problems.push(Problem::MisalignedChunk {
+                    index_offset,
+                    timestamp,
+                });
+                continue;
+            }
+        }
+
+        if timestamp < shard
+            || shard_end.map_or(false, |end| timestamp >= end)
+            || last_timestamp.map_or(false, |last| timestamp <= last)
+        {
+            report.problems.push(Problem::MisplacedTimestamp {
+                index_offset,
+                timestamp,
+            });
+        }
+        last_timestamp = Some(timestamp);
+
+        match cursor.get_frame_at(index_offset) {
+            Ok(_) => {
+                report.frames += 1;
+                timestamps.push(timestamp);
+            }
+            Err(e) => report.problems.push(Problem::UnreadableFrame {
+                index_offset,
+                timestamp,
+                error: format!("{:#}", e),
+            }),
+        }
+    }
+    Ok((report, timestamps))
+}
+
+/// Typical distance between consecutive `timestamps`
+fn get_median_interval(timestamps: &[u64]) -> Option<u64> {
+    let mut intervals: Vec<u64> = timestamps.windows(2).map(|w| w[1] - w[0]).collect();
+    intervals.sort_unstable();
+    intervals.get(intervals.len() / 2).copied()
+}
+
+/// Checks every shard of the store at `path`. Gaps are reported where
+/// consecutive samples are more than `max_gap` apart, or if None, more than
+/// a few times the typical sample interval of the shard. The latter keeps
+/// shards of different resolution, e.g. rolled up ones, from being flagged.
+pub fn check_store<P: AsRef<Path>>(
+    logger: slog::Logger,
+    path: P,
+    max_gap: Option<Duration>,
+) -> Result<FsckReport> {
+    let path = path.as_ref();
+    let mut cursor = StoreCursor::new(logger, path.to_path_buf());
+    let mut report = FsckReport::default();
+    let mut prev_timestamp = None;
+    let mut prev_interval = None;
+    for (shard, shard_end) in get_shard_ranges(path)? {
+        let (shard_report, timestamps) = check_shard(&mut cursor, path, shard, shard_end)?;
+        report.shards.push(shard_report);
+
+        let interval = get_median_interval(&timestamps).or(prev_interval);
+        let max_gap = max_gap
+            .map(|max_gap| max_gap.as_secs())
+            .or_else(|| interval.map(|interval| interval.max(1) * GAP_FACTOR));
+        for timestamp in timestamps {
+            if let (Some(prev), Some(max_gap)) = (prev_timestamp, max_gap) {
+                if timestamp > prev + max_gap {
+                    report.gaps.push(Gap {
+                        from: prev,
+                        to: timestamp,
+                    });
+                }
+            }
+            prev_timestamp = Some(timestamp);
+        }
+        prev_interval = interval;
+    }
+    Ok(report)
+}
+
+/// Writes every readable frame of the store at `path` into a new store at
+/// `to_path`, dropping anything damaged. The new store keeps the settings
+/// of the old one, see `copy_store_settings`. Returns the number of frames
+/// written.
+pub fn repair_store<P: AsRef<Path>, Q: AsRef<Path>>(
+    logger: slog::Logger,
+    path: P,
+    to_path: Q,
+    compression_mode: CompressionMode,
+    format: Format,
+) -> Result<u64> {
+    let to_path = to_path.as_ref();
+    if to_path.is_dir() && !get_index_files(to_path)?.is_empty() {
+        bail!(
+            "{} already contains a store, repair needs an empty directory",
+            to_path.display()
+        );
+    }
+    copy_store_settings(&path, to_path)?;
+    let mut cursor = StoreCursor::new(logger.clone(), path.as_ref().to_path_buf());
+    let mut item = cursor.get_next(&0, Direction::Forward)?;
+    let mut writer: Option<StoreWriter> = None;
+    let mut last_timestamp = None;
+    let mut nr_frames = 0;
+    while let Some((ts, frame)) = item {
+        // Keep the repaired store strictly ordered
+        let timestamp = get_unix_timestamp(ts);
+        if last_timestamp.map_or(true, |last| timestamp > last) {
+            let writer = match writer.as_mut() {
+                Some(writer) => writer,
+                None => writer.get_or_insert(StoreWriter::new_with_timestamp(
+                    logger.clone(),
+                    to_path,
+                    ts,
+                    compression_mode,
+                    format,
+                )?),
+            };
+            writer.put(ts, &frame)?;
+            last_timestamp = Some(timestamp);
+            nr_frames += 1;
+        }
+        item = cursor.next(Direction::Forward)?;
+    }
+    Ok(nr_frames)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DataFrame, SHARD_TIME};
+    use slog::Drain;
+    use std::io::{Seek, SeekFrom, Write};
+    use std::time::SystemTime;
+    use tempdir::TempDir;
+
+    fn get_logger() -> slog::Logger {
+        let plain = slog_term::PlainSyncDecorator::new(std::io::stderr());
+        slog::Logger::root(slog_term::FullFormat::new(plain).build().fuse(), slog::o!())
+    }
+
+    fn overwrite(path: &Path, offset: u64, bytes: &[u8]) {
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .open(path)
+            .expect("Failed to open file");
+        file.seek(SeekFrom::Start(offset)).expect("Failed to seek");
+        file.write_all(bytes).expect("Failed to write");
+    }
+
+    #[test]
+    fn check_and_repair() {
+        let dir = TempDir::new("below_fsck_test").expect("tempdir failed");
+        let shard = SHARD_TIME;
+        let ts = SystemTime::UNIX_EPOCH + Duration::from_secs(shard);
+        {
+            let mut writer = StoreWriter::new_with_timestamp(
+                get_logger(),
+                &dir,
+                ts,
+                CompressionMode::Zstd,
+                Format::Cbor,
+            )
+            .expect("Failed to create store");
+            let mut frame = DataFrame::default();
+            // 10 samples 5s apart, a 100s hole, then 2 more
+            for (i, secs) in (0..10).map(|i| i * 5).chain(vec![145, 150]).enumerate() {
+                frame.sample.cgroup.memory_current = Some(i as i64);
+                writer
+                    .put(ts + Duration::from_secs(secs), &frame)
+                    .expect("Failed to store data");
+            }
+        }
+        let clean = check_store(get_logger(), dir.path(), None).expect("Failed to check");
+        assert_eq!(clean.nr_problems(), 0);
+        assert_eq!(clean.shards[0].frames, 12);
+        assert_eq!(
+            clean.gaps,
+            vec![Gap {
+                from: shard + 45,
+                to: shard + 145
+            }]
+        );
+        // Explicit limits override the typical interval
+        let report = check_store(get_logger(), dir.path(), Some(Duration::from_secs(200)))
+            .expect("Failed to check");
+        assert!(report.gaps.is_empty());
+
+        let index_path = dir.path().join(format!("index_{:011}", shard));
+        let data_path = dir.path().join(format!("data_{:011}", shard));
+        // Corrupt the timestamp of the 4th entry
+        overwrite(&index_path, 3 * INDEX_ENTRY_SIZE as u64, &[0xff]);
+        // Corrupt the data of the 6th entry
+        let index = fs::read(&index_path).expect("Failed to read index");
+        let data = fs::read(&data_path).expect("Failed to read data");
+        let offset = read_index_entry(&index[5 * INDEX_ENTRY_SIZE..]).offset;
+        overwrite(&data_path, offset, &[!data[offset as usize]]);
+        // Partially write another entry
+        fs::OpenOptions::new()
+            .append(true)
+            .open(&index_path)
+            .expect("Failed to open index")
+            .write_all(&[1; 7])
+            .expect("Failed to write index");
+
+        let report = check_store(get_logger(), dir.path(), None).expect("Failed to check");
+        let shard_report = &report.shards[0];
+        assert_eq!(shard_report.frames, 10);
+        assert_eq!(
+            shard_report.problems,
+            vec![
+                Problem::TruncatedIndex { trailing_bytes: 7 },
+                Problem::CorruptIndexEntry {
+                    index_offset: 3 * INDEX_ENTRY_SIZE
+                },
+                Problem::CorruptData {
+                    index_offset: 5 * INDEX_ENTRY_SIZE,
+                    timestamp: shard + 25
+                },
+            ]
+        );
+
+        let hour = Duration::from_secs(60 * 60);
+        crate::set_shard_duration(dir.path(), hour).expect("Failed to set shard duration");
+        crate::set_zstd_level(dir.path(), 5).expect("Failed to set level");
+        let repaired = TempDir::new("below_fsck_test").expect("tempdir failed");
+        let nr_frames = repair_store(
+            get_logger(),
+            dir.path(),
+            repaired.path(),
+            CompressionMode::ZstdDictionary(crate::ChunkSizePo2(2)),
+            Format::Cbor,
+        )
+        .expect("Failed to repair");
+        assert_eq!(nr_frames, 10);
+        let report = check_store(
+            get_logger(),
+            repaired.path(),
+            Some(Duration::from_secs(200)),
+        )
+        .expect("Failed to check");
+        assert_eq!(report.nr_problems(), 0);
+        assert_eq!(report.shards[0].frames, 10);
+        assert_eq!(
+            crate::get_shard_duration(repaired.path()).expect("Failed to read"),
+            hour
+        );
+        assert_eq!(
+            crate::get_zstd_level(repaired.path()).expect("Failed to read"),
+            Some(5)
+        );
+        // Refuses to mix into an existing store
+        assert!(repair_store(
+            get_logger(),
+            dir.path(),
+            repaired.path(),
+            CompressionMode::None,
+            Format::Cbor,
+        )
+        .is_err());
+    }
+}
@@ -12,11 +12,12 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::{BTreeMap, VecDeque};
 use std::fs::{self, File, OpenOptions};
 use std::io::{ErrorKind, Write};
 use std::os::unix::io::AsRawFd;
 use std::path::{Path, PathBuf};
-use std::time::SystemTime;
+use std::time::{Duration, Instant, SystemTime};
 
 use anyhow::{bail, Context, Result};
 use bitflags::bitflags;
@@ -24,8 +25,8 @@ use serde::{Deserialize, Serialize};
 use slog::{info, warn};
 use static_assertions::const_assert_eq;
 
-use crate::compression::Compressor;
-use crate::cursor::{KeyedCursor, StoreCursor};
+use crate::compression::{Codec, Compressor};
+use crate::cursor::{Cursor, KeyedCursor, StoreCursor, StoreOffset};
 
 use common::fileutil::get_dir_size;
 use common::open_source_shim;
@@ -36,13 +37,22 @@ use model::{self, Model};
 pub mod advance;
 pub mod compression;
 pub mod cursor;
+pub mod fsck;
+pub mod merge;
+mod range;
+pub mod rollup;
+pub mod schema;
 #[cfg(test)]
 mod test;
 
 pub type Advance = advance::Advance<DataFrame, Model>;
+pub use range::{iter_range, RangeIter};
 
 open_source_shim!();
 
+#[cfg(not(fbcode_build))]
+pub use remote_store::RemoteStoreServer;
+
 /// This data store works as follows:
 ///
 /// Each data item (e.g. DataFrame) is simply appended to a data file.
@@ -66,18 +76,36 @@ open_source_shim!();
 /// Data and Index files are append-only and never modified (only ever
 /// removed).
 ///
-/// Data and Index files are sharded by SHARD_TIME - e.g. any one file
-/// only contains data or index entries whose timestamps are congruent
-/// modulo SHARD_TIME. This allows data and index files to be cleaned
-/// up by just unlinking the files.
+/// Data and Index files are sharded by time. Each pair of files is named
+/// after the timestamp its shard starts at, and a shard ends where the next
+/// one starts. New shards are aligned to the shard duration recorded in the
+/// store (SHARD_TIME unless set), so a store may contain shards of different
+/// lengths. This allows data and index files to be cleaned up by just
+/// unlinking the files.
+///
+/// A shard may also have a Dict file holding a zstd dictionary trained on
+/// frames written before the shard started. It is written before any frame
+/// using it and removed along with the shard.
 
 #[derive(Default, Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct DataFrame {
     pub sample: model::Sample,
 }
 
+/// Default shard duration in seconds
 const SHARD_TIME: u64 = 24 * 60 * 60;
 
+/// File in the store directory recording the duration of new shards in
+/// seconds
+const SHARD_DURATION_FILE: &str = "shard_duration";
+
+/// File in the store directory recording the zstd level new frames are
+/// compressed at
+const ZSTD_LEVEL_FILE: &str = "zstd_level";
+
+/// File in the store directory locked while shards are removed or replaced
+const SHARD_LOCK_FILE: &str = "shard_lock";
+
 // Number of bits used by other bit flags in IndexEntry before the
 // chunk compress flags.
 const CHUNK_COMPRESS_SHIFT: u32 = 2;
@@ -86,6 +114,20 @@ const MAX_CHUNK_COMPRESS_SIZE_PO2: u32 = 0x0F;
 pub const MAX_CHUNK_COMPRESS_SIZE: u32 = 1 << MAX_CHUNK_COMPRESS_SIZE_PO2;
 const_assert_eq!(MAX_CHUNK_COMPRESS_SIZE, 32768);
 
+// Number of bits used by other bit flags in IndexEntry before the
+// schema version.
+const SCHEMA_VERSION_SHIFT: u32 = 6;
+
+// Number of bits used by other bit flags in IndexEntry before the
+// codec.
+const CODEC_SHIFT: u32 = 15;
+const MAX_CODEC: u32 = 0x3;
+
+/// Number of recent frames a shard's dictionary is trained on
+const DICT_TRAINING_FRAMES: usize = 32;
+/// Maximum size of a trained dictionary, zstd's default
+const MAX_DICT_SIZE: usize = 110 * 1024;
+
 bitflags! {
     struct IndexEntryFlags: u32 {
         /// If set, data item is compressed with zstd.
@@ -110,6 +152,21 @@ bitflags! {
         /// can also occur if possible data corruption has been
         /// detected.
         const CHUNK_COMPRESS_SIZE_PO2 = MAX_CHUNK_COMPRESS_SIZE_PO2 << CHUNK_COMPRESS_SHIFT;
+        /// Version of the schema the data item was serialized with,
+        /// see `schema`. Zero for entries written before schema
+        /// versions were recorded.
+        const SCHEMA_VERSION = schema::MAX_SCHEMA_VERSION << SCHEMA_VERSION_SHIFT;
+        /// If set, data item is serialized as MessagePack with
+        /// structs encoded as maps keyed by field name, like CBOR.
+        const MSGPACK = 0x4000;
+        /// Codec of a `COMPRESSED` data item: 0 for zstd, which is
+        /// what entries written before codecs were recorded use, 1
+        /// for lz4.
+        const CODEC = MAX_CODEC << CODEC_SHIFT;
+        /// If `COMPRESSED` is set and this is set, the data item is
+        /// zstd compressed with the trained dictionary in the Dict
+        /// file of its shard.
+        const TRAINED_DICT = 0x20000;
     }
 }
 
@@ -128,6 +185,38 @@ impl IndexEntryFlags {
         self.bits |= chunk_compress_size_po2 << CHUNK_COMPRESS_SHIFT;
         Ok(())
     }
+
+    fn get_schema_version(&self) -> u32 {
+        (self.bits & Self::SCHEMA_VERSION.bits) >> SCHEMA_VERSION_SHIFT
+    }
+
+    fn set_schema_version(&mut self, schema_version: u32) -> Result<()> {
+        if schema_version > schema::MAX_SCHEMA_VERSION {
+            bail!(
+                "Schema version should be less than or equal to {}",
+                schema::MAX_SCHEMA_VERSION
+            );
+        }
+        self.bits |= schema_version << SCHEMA_VERSION_SHIFT;
+        Ok(())
+    }
+
+    /// Returns None for codecs unknown to this version of below
+    fn get_codec(&self) -> Option<Codec> {
+        match (self.bits & Self::CODEC.bits) >> CODEC_SHIFT {
+            0 => Some(Codec::Zstd),
+            1 => Some(Codec::Lz4),
+            _ => None,
+        }
+    }
+
+    fn set_codec(&mut self, codec: Codec) {
+        let value = match codec {
+            Codec::Zstd => 0,
+            Codec::Lz4 => 1,
+        };
+        self.bits = (self.bits & !Self::CODEC.bits) | (value << CODEC_SHIFT);
+    }
 }
 
 #[repr(C)]
@@ -158,6 +247,107 @@ pub enum CompressionMode {
     None,
     Zstd,
     ZstdDictionary(ChunkSizePo2),
+    /// zstd with a dictionary per shard, trained on the frames written
+    /// before the shard started
+    ZstdTrainedDictionary,
+    Lz4,
+}
+
+impl CompressionMode {
+    fn from_flags(flags: IndexEntryFlags) -> Self {
+        if !flags.contains(IndexEntryFlags::COMPRESSED) {
+            CompressionMode::None
+        } else if flags.get_codec() == Some(Codec::Lz4) {
+            CompressionMode::Lz4
+        } else if flags.contains(IndexEntryFlags::TRAINED_DICT) {
+            CompressionMode::ZstdTrainedDictionary
+        } else {
+            match flags.get_chunk_compress_size_po2() {
+                0 => CompressionMode::Zstd,
+                chunk_size_po2 => CompressionMode::ZstdDictionary(ChunkSizePo2(chunk_size_po2)),
+            }
+        }
+    }
+
+    /// Short name, e.g. for reporting how frames are compressed
+    pub fn name(&self) -> &'static str {
+        match self {
+            CompressionMode::None => "none",
+            CompressionMode::Zstd => "zstd",
+            CompressionMode::ZstdDictionary(_) => "zstd-chunk-dict",
+            CompressionMode::ZstdTrainedDictionary => "zstd-trained-dict",
+            CompressionMode::Lz4 => "lz4",
+        }
+    }
+
+    fn codec(&self) -> Option<Codec> {
+        match self {
+            CompressionMode::None => None,
+            CompressionMode::Lz4 => Some(Codec::Lz4),
+            _ => Some(Codec::Zstd),
+        }
+    }
+}
+
+/// Compresses a serialized frame as `compression_mode` and sets the
+/// compression flags of its index entry. `get_dict` returns the trained
+/// dictionary of the shard, if any. Shared by `StoreWriter` and
+/// `get_compression_stats` so that the latter measures what recording costs.
+fn compress_frame<F>(
+    compression_mode: CompressionMode,
+    compressor: &mut Compressor,
+    frame_bytes: &bytes::Bytes,
+    is_key_frame: bool,
+    flags: &mut IndexEntryFlags,
+    get_dict: F,
+) -> Result<bytes::Bytes>
+where
+    F: FnOnce() -> Result<Option<bytes::Bytes>>,
+{
+    *flags |= IndexEntryFlags::COMPRESSED;
+    flags.set_codec(compressor.get_codec());
+    let serialized = match compression_mode {
+        CompressionMode::None => unreachable!("bug: uncompressed frame"),
+        CompressionMode::Zstd | CompressionMode::Lz4 => compressor
+            .compress_with_dict_reset(frame_bytes)
+            .context("Failed to compress data")?,
+        CompressionMode::ZstdTrainedDictionary => {
+            if !compressor.has_dict() {
+                if let Some(dict) = get_dict()? {
+                    compressor
+                        .load_dict(&dict)
+                        .context("Failed to load trained dict")?;
+                }
+            }
+            // Frames written before there was enough data to train a
+            // dictionary on are compressed without one
+            if compressor.has_dict() {
+                *flags |= IndexEntryFlags::TRAINED_DICT;
+            }
+            compressor
+                .compress_with_loaded_dict(frame_bytes)
+                .context("Failed to compress data frame")?
+        }
+        CompressionMode::ZstdDictionary(ChunkSizePo2(chunk_size_po2)) => {
+            flags
+                .set_chunk_compress_size_po2(chunk_size_po2)
+                .expect("bug: invalid chunk compress size");
+            if is_key_frame {
+                let serialized = compressor
+                    .compress_with_dict_reset(frame_bytes)
+                    .context("Failed to compress key frame")?;
+                compressor
+                    .load_dict(frame_bytes)
+                    .context("Failed to set key frame as dict")?;
+                serialized
+            } else {
+                compressor
+                    .compress_with_loaded_dict(frame_bytes)
+                    .context("Failed to compress data frame")?
+            }
+        }
+    };
+    Ok(serialized)
 }
 
 /// The StoreWriter struct maintains state to put more data in the
@@ -180,14 +370,23 @@ pub struct StoreWriter {
     data_len: u64,
     /// Active shard
     shard: u64,
+    /// Duration of new shards in seconds
+    shard_duration: u64,
     /// Cached compressor for memory efficiency. Compressor also stores key
     /// frame for dict compression.
     compressor: Option<Compressor>,
     /// If non-empty, individual frames are compressed with
     /// `compression_mode`.
     compression_mode: CompressionMode,
+    /// zstd compression level, zstd's default if None
+    zstd_level: Option<i32>,
+    /// Last frames written, uncompressed. The dictionary of a new shard is
+    /// trained on them in `ZstdTrainedDictionary` mode.
+    recent_frames: VecDeque<bytes::Bytes>,
     /// Serialization format of data frames
     format: Format,
+    /// Time spent compressing the frames of the current shard
+    compress_time: Duration,
 }
 
 // Given path to the store dir, get a Vec<String> of the index file
@@ -212,6 +411,181 @@ fn get_index_files(path: &Path) -> Result<Vec<String>> {
     Ok(entries)
 }
 
+/// Returns the start timestamps of the shards of the store at `path`,
+/// oldest first.
+fn get_shards(path: &Path) -> Result<Vec<u64>> {
+    Ok(get_index_files(path)?
+        .iter()
+        .filter_map(|entry| entry.split('_').nth(1)?.parse::<u64>().ok())
+        .collect())
+}
+
+/// Returns the shards of the store at `path`, oldest first, along with the
+/// timestamp each one ends at. The newest shard is still open for writes and
+/// has no end.
+fn get_shard_ranges(path: &Path) -> Result<Vec<(u64, Option<u64>)>> {
+    let shards = get_shards(path)?;
+    let ends = shards
+        .iter()
+        .skip(1)
+        .map(|end| Some(*end))
+        .chain(Some(None));
+    Ok(shards.iter().copied().zip(ends).collect())
+}
+
+/// Returns the duration of new shards of the store at `path`.
+pub fn get_shard_duration<P: AsRef<Path>>(path: P) -> Result<Duration> {
+    let duration_path = path.as_ref().join(SHARD_DURATION_FILE);
+    let content = match fs::read_to_string(&duration_path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Duration::from_secs(SHARD_TIME)),
+        Err(e) => {
+            return Err(e).context(format!("Failed to read {}", duration_path.display()));
+        }
+    };
+    let secs = content
+        .trim()
+        .parse::<u64>()
+        .with_context(|| format!("Invalid shard duration in {}", duration_path.display()))?;
+    Ok(Duration::from_secs(secs))
+}
+
+/// Records `duration` as the duration of new shards of the store at `path`.
+/// Existing shards keep their length. The duration must evenly divide a day
+/// so that shards start at the same times every day.
+pub fn set_shard_duration<P: AsRef<Path>>(path: P, duration: Duration) -> Result<()> {
+    let path = path.as_ref();
+    let secs = duration.as_secs();
+    if duration.subsec_nanos() != 0 || secs == 0 || secs % 60 != 0 || SHARD_TIME % secs != 0 {
+        bail!(
+            "Shard duration must be a whole number of minutes dividing a day, got {}",
+            humantime::format_duration(duration)
+        );
+    }
+    if get_shard_duration(path)? == duration {
+        return Ok(());
+    }
+    write_setting(path, SHARD_DURATION_FILE, secs)
+}
+
+/// Returns the zstd level new frames of the store at `path` are compressed
+/// at, or None for zstd's default.
+pub fn get_zstd_level<P: AsRef<Path>>(path: P) -> Result<Option<i32>> {
+    let level_path = path.as_ref().join(ZSTD_LEVEL_FILE);
+    let content = match fs::read_to_string(&level_path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => {
+            return Err(e).context(format!("Failed to read {}", level_path.display()));
+        }
+    };
+    let level = content
+        .trim()
+        .parse::<i32>()
+        .with_context(|| format!("Invalid zstd level in {}", level_path.display()))?;
+    Ok(Some(level))
+}
+
+/// Records `level` as the zstd level new frames of the store at `path` are
+/// compressed at, see `compression::zstd_levels`. Existing frames are not
+/// recompressed.
+pub fn set_zstd_level<P: AsRef<Path>>(path: P, level: i32) -> Result<()> {
+    let path = path.as_ref();
+    Compressor::new().set_level(level)?;
+    if get_zstd_level(path)? == Some(level) {
+        return Ok(());
+    }
+    write_setting(path, ZSTD_LEVEL_FILE, level)
+}
+
+/// Records the settings of the store at `from` that aren't part of its
+/// frames, i.e. the shard duration and zstd level, in the store at `to` so
+/// that a store derived from it is written the same way. Settings `to`
+/// already records are kept.
+pub fn copy_store_settings<P: AsRef<Path>, Q: AsRef<Path>>(from: P, to: Q) -> Result<()> {
+    let (from, to) = (from.as_ref(), to.as_ref());
+    if from.join(SHARD_DURATION_FILE).exists() && !to.join(SHARD_DURATION_FILE).exists() {
+        set_shard_duration(to, get_shard_duration(from)?)?;
+    }
+    if let (Some(level), None) = (get_zstd_level(&from)?, get_zstd_level(&to)?) {
+        set_zstd_level(&to, level)?;
+    }
+    Ok(())
+}
+
+/// Writes `value` to the settings file `name` of the store at `path`,
+/// creating the store directory if needed.
+fn write_setting<T: std::fmt::Display>(path: &Path, name: &str, value: T) -> Result<()> {
+    if !path.is_dir() {
+        fs::create_dir_all(path)
+            .with_context(|| format!("Failed to create store path: {}", path.display()))?;
+    }
+    // Readers never see a partially written file
+    let tmp_path = path.join(format!(".{}", name));
+    fs::write(&tmp_path, format!("{}\n", value))
+        .with_context(|| format!("Failed to write {}", tmp_path.display()))?;
+    fs::rename(&tmp_path, path.join(name))
+        .with_context(|| format!("Failed to record {} in {}", name, path.display()))?;
+    Ok(())
+}
+
+/// Locks the shards of the store at `path` against removal and replacement
+/// until the returned file is dropped. Cleanup and rollup run on different
+/// threads, and without it rollup could move a shard back in after cleanup
+/// discarded it, or cleanup could remove a shard halfway through rollup
+/// replacing its files.
+fn lock_shards(path: &Path) -> Result<File> {
+    let lock_path = path.join(SHARD_LOCK_FILE);
+    let lock = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .open(&lock_path)
+        .with_context(|| format!("Failed to open {}", lock_path.display()))?;
+    nix::fcntl::flock(lock.as_raw_fd(), nix::fcntl::FlockArg::LockExclusive)
+        .with_context(|| format!("Failed to lock {}", lock_path.display()))?;
+    Ok(lock)
+}
+
+/// Returns the timestamp of the last valid index entry of `shard` in the
+/// store at `path`, if any.
+fn get_last_timestamp(path: &Path, shard: u64) -> Result<Option<u64>> {
+    let index_path = path.join(format!("index_{:011}", shard));
+    let index = match fs::read(&index_path) {
+        Ok(index) => index,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => {
+            return Err(e).context(format!("Failed to read {}", index_path.display()));
+        }
+    };
+    Ok(parse_index_entries(&index)
+        .flatten()
+        .last()
+        .map(|entry| entry.timestamp))
+}
+
+/// Returns the shard to write `timestamp` to in the store at `path` with
+/// shards of `shard_duration` seconds. This is the aligned shard, unless
+/// starting it would split the existing shard `timestamp` falls in. That
+/// happens after the shard duration changed, in which case writing continues
+/// in the existing shard until the next aligned boundary.
+fn get_shard_for_write(path: &Path, timestamp: SystemTime, shard_duration: u64) -> Result<u64> {
+    let shard = calculate_shard(timestamp, shard_duration);
+    if !path.is_dir() {
+        return Ok(shard);
+    }
+    let secs = get_unix_timestamp(timestamp);
+    let existing = match get_shards(path)?.into_iter().rev().find(|s| *s <= secs) {
+        Some(existing) => existing,
+        None => return Ok(shard),
+    };
+    if existing >= shard || get_last_timestamp(path, existing)?.map_or(false, |last| last >= shard)
+    {
+        Ok(existing)
+    } else {
+        Ok(shard)
+    }
+}
+
 enum SerializedFrame<'a> {
     Owned(bytes::Bytes),
     Borrowed(&'a [u8]),
@@ -235,10 +609,41 @@ impl SerializedFrame<'_> {
     }
 }
 
-/// Serialization format. Currently only Cbor is supported.
-#[derive(Copy, Clone, Debug)]
+/// Serialization format.
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum Format {
     Cbor,
+    /// Keeps field names like Cbor, so frames are upgraded across schema
+    /// versions the same way. Encoding fields by index instead would save
+    /// the names, but serde can't fill in fields missing from older frames
+    /// by position, so every new model field would need a schema upgrade.
+    /// Repeated names mostly compress away, see `benches/format.rs` for
+    /// how the formats compare.
+    MessagePack,
+}
+
+impl Format {
+    fn from_flags(flags: IndexEntryFlags) -> Option<Self> {
+        if flags.contains(IndexEntryFlags::MSGPACK) {
+            Some(Format::MessagePack)
+        } else if flags.contains(IndexEntryFlags::CBOR) {
+            Some(Format::Cbor)
+        } else {
+            None
+        }
+    }
+}
+
+impl std::str::FromStr for Format {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "cbor" => Ok(Format::Cbor),
+            "msgpack" => Ok(Format::MessagePack),
+            _ => bail!("Unknown store format {}, expected cbor or msgpack", s),
+        }
+    }
 }
 
 /// Serialize a single data frame with `format` format.
@@ -248,16 +653,20 @@ fn serialize_frame(data: &DataFrame, format: Format) -> Result<bytes::Bytes> {
             let bytes = serde_cbor::to_vec(data)?;
             Ok(bytes::Bytes::from(bytes))
         }
+        Format::MessagePack => {
+            // Named fields keep frames readable when the model changes
+            let bytes = rmp_serde::to_vec_named(data)?;
+            Ok(bytes::Bytes::from(bytes))
+        }
     }
 }
 
-/// Deserialize a single data frame with `format` format.
-fn deserialize_frame(bytes: &[u8], format: Format) -> Result<DataFrame> {
+/// Deserialize a single data frame with `format` format, upgrading it
+/// from `schema_version` as necessary.
+fn deserialize_frame(bytes: &[u8], format: Format, schema_version: u32) -> Result<DataFrame> {
     match format {
-        Format::Cbor => {
-            let data_frame = serde_cbor::from_slice(bytes)?;
-            Ok(data_frame)
-        }
+        Format::Cbor => schema::deserialize_cbor_frame(bytes, schema_version),
+        Format::MessagePack => schema::deserialize_msgpack_frame(bytes, schema_version),
     }
 }
 
@@ -283,7 +692,8 @@ impl StoreWriter {
         compression_mode: CompressionMode,
         format: Format,
     ) -> Result<Self> {
-        let shard = calculate_shard(timestamp);
+        let shard_duration = get_shard_duration(&path)?.as_secs();
+        let shard = get_shard_for_write(path.as_ref(), timestamp, shard_duration)?;
         Self::new_with_shard(logger, path, shard, compression_mode, format)
     }
 
@@ -349,6 +759,8 @@ impl StoreWriter {
                 )
             })?
             .len();
+        let shard_duration = get_shard_duration(&path)?.as_secs();
+        let zstd_level = get_zstd_level(&path)?;
 
         Ok(StoreWriter {
             logger,
@@ -357,13 +769,77 @@ impl StoreWriter {
             data,
             data_len,
             shard,
+            shard_duration,
             // First compressed write initializes the compressor
             compressor: None,
             compression_mode,
+            zstd_level,
+            recent_frames: VecDeque::new(),
             format,
+            compress_time: Duration::ZERO,
         })
     }
 
+    /// Sets the zstd compression level of frames written from now on and
+    /// records it in the store, see `set_zstd_level`. Ignored unless zstd is
+    /// used.
+    pub fn set_zstd_level(&mut self, level: i32) -> Result<()> {
+        set_zstd_level(&self.dir, level)?;
+        self.zstd_level = Some(level);
+        // Picked up by the next write
+        self.compressor = None;
+        Ok(())
+    }
+
+    fn new_compressor(&self) -> Result<Compressor> {
+        let codec = self.compression_mode.codec().unwrap_or(Codec::Zstd);
+        let mut compressor = Compressor::with_codec(codec);
+        if let (Codec::Zstd, Some(level)) = (codec, self.zstd_level) {
+            compressor.set_level(level)?;
+        }
+        Ok(compressor)
+    }
+
+    /// Returns the trained dictionary of the current shard. If the shard has
+    /// none yet, one is trained on the recent frames and saved. None if there
+    /// are not enough recent frames to train on.
+    fn get_or_train_dict(&mut self) -> Result<Option<bytes::Bytes>> {
+        let dict_path = self.dir.join(format!("dict_{:011}", self.shard));
+        match fs::read(&dict_path) {
+            Ok(dict) => return Ok(Some(dict.into())),
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => {
+                return Err(e).context(format!("Failed to read {}", dict_path.display()));
+            }
+        }
+        if self.recent_frames.len() < DICT_TRAINING_FRAMES {
+            return Ok(None);
+        }
+        let samples = self.recent_frames.make_contiguous();
+        let dict = match compression::train_dict(samples, MAX_DICT_SIZE) {
+            Ok(dict) => dict,
+            Err(e) => {
+                // Retry once there are enough new frames
+                warn!(self.logger, "{:#}", e);
+                self.recent_frames.clear();
+                return Ok(None);
+            }
+        };
+        // Frames are only written with the dictionary once it is complete
+        let tmp_path = self.dir.join(format!(".dict_{:011}", self.shard));
+        fs::write(&tmp_path, &dict)
+            .with_context(|| format!("Failed to write {}", tmp_path.display()))?;
+        fs::rename(&tmp_path, &dict_path)
+            .with_context(|| format!("Failed to save {}", dict_path.display()))?;
+        info!(
+            self.logger,
+            "Trained {} byte dictionary for shard {}",
+            dict.len(),
+            self.shard
+        );
+        Ok(Some(dict))
+    }
+
     /// The index file is padded to the next (1 << alignment_po2) aligned
     /// boundary. Both the original and aligned lengths are then returned.
     /// Mostly used to align index file with INDEX_ENTRY_SIZE or chunk size if
@@ -387,55 +863,43 @@ impl StoreWriter {
         Ok((index_len, aligned_len))
     }
 
-    /// For the given `DataFrame` and an optional Compressor mut ref, returns a
-    /// tuple consisting of:
+    /// For the given serialized frame and an optional Compressor mut ref,
+    /// returns a tuple consisting of:
     ///   1) Raw bytes to write to the data file
     ///   2) Flags to write to the index entry
     /// For compressed write, the Compressor will be initialized if None, and
     /// potentially updated. is_key_frame is used to indicate the start of a new
     /// chunk if dictionary compression is enabled.
     fn get_bytes_and_flags_for_frame(
-        &self,
-        data_frame: &DataFrame,
+        &mut self,
+        frame_bytes: &bytes::Bytes,
         compressor: &mut Option<Compressor>,
         is_key_frame: bool,
     ) -> Result<(bytes::Bytes, IndexEntryFlags)> {
         let mut flags = match self.format {
             Format::Cbor => IndexEntryFlags::CBOR,
+            Format::MessagePack => IndexEntryFlags::MSGPACK,
         };
-        // Get serialized data frame
-        let frame_bytes =
-            serialize_frame(data_frame, self.format).context("Failed to serialize data frame")?;
-        let serialized = match self.compression_mode {
-            CompressionMode::None => frame_bytes,
-            CompressionMode::Zstd => {
-                flags |= IndexEntryFlags::COMPRESSED;
-                compressor
-                    .get_or_insert_with(Compressor::new)
-                    .compress_with_dict_reset(&frame_bytes)
-                    .context("Failed to compress data")?
-            }
-            CompressionMode::ZstdDictionary(ChunkSizePo2(chunk_size_po2)) => {
-                flags |= IndexEntryFlags::COMPRESSED;
-                flags
-                    .set_chunk_compress_size_po2(chunk_size_po2)
-                    .expect("bug: invalid chunk compress size");
-                let compressor = compressor.get_or_insert_with(Compressor::new);
-                if is_key_frame {
-                    let serialized = compressor
-                        .compress_with_dict_reset(&frame_bytes)
-                        .context("Failed to compress key frame")?;
-                    compressor
-                        .load_dict(&frame_bytes)
-                        .context("Failed to set key frame as dict")?;
-                    serialized
-                } else {
-                    compressor
-                        .compress_with_loaded_dict(&frame_bytes)
-                        .context("Failed to compress data frame")?
-                }
-            }
-        };
+        flags
+            .set_schema_version(schema::CURRENT_SCHEMA_VERSION)
+            .expect("bug: invalid schema version");
+        if self.compression_mode.codec().is_none() {
+            return Ok((frame_bytes.clone(), flags));
+        }
+        if compressor.is_none() {
+            *compressor = Some(self.new_compressor()?);
+        }
+        let compressor = compressor.as_mut().expect("compressor should be set");
+        let start = Instant::now();
+        let serialized = compress_frame(
+            self.compression_mode,
+            compressor,
+            frame_bytes,
+            is_key_frame,
+            &mut flags,
+            || self.get_or_train_dict(),
+        )?;
+        self.compress_time += start.elapsed();
         Ok((serialized, flags))
     }
 
@@ -443,8 +907,7 @@ impl StoreWriter {
     /// Fails if data does not belong to current shard. Errors may be
     /// returned if file operations fail.
     fn put_in_current_shard(&mut self, timestamp: SystemTime, data: &DataFrame) -> Result<()> {
-        let shard = calculate_shard(timestamp);
-        if shard != self.shard {
+        if get_unix_timestamp(timestamp) < self.shard {
             panic!("Can't write data to shard as it belongs to different shard")
         }
 
@@ -504,8 +967,10 @@ impl StoreWriter {
         // current frame is the key frame.
         let is_key_frame =
             chunk_alignment_po2 != 0 && aligned_len.trailing_zeros() >= chunk_alignment_po2;
+        let frame_bytes =
+            serialize_frame(data, self.format).context("Failed to serialize data frame")?;
         let (serialized, flags) = self
-            .get_bytes_and_flags_for_frame(data, &mut compressor, is_key_frame)
+            .get_bytes_and_flags_for_frame(&frame_bytes, &mut compressor, is_key_frame)
             .context("Failed to get serialized frame and flags")?;
 
         // Appends to data file are large and cannot be atomic. We
@@ -571,6 +1036,12 @@ impl StoreWriter {
         // Set compressor only after successful writes. No-op if not in
         // compression mode
         self.compressor = compressor;
+        if let CompressionMode::ZstdTrainedDictionary = self.compression_mode {
+            if self.recent_frames.len() >= DICT_TRAINING_FRAMES {
+                self.recent_frames.pop_front();
+            }
+            self.recent_frames.push_back(frame_bytes);
+        }
         Ok(())
     }
 
@@ -578,7 +1049,14 @@ impl StoreWriter {
     /// is created and data is written successfully. Errors may be returned if
     /// file operations fail.
     pub fn put(&mut self, timestamp: SystemTime, data: &DataFrame) -> Result<bool> {
-        let shard = calculate_shard(timestamp);
+        let shard = calculate_shard(timestamp, self.shard_duration);
+        // The current shard may have started after the aligned one, see
+        // get_shard_for_write
+        let shard = if shard <= self.shard && get_unix_timestamp(timestamp) >= self.shard {
+            self.shard
+        } else {
+            get_shard_for_write(&self.dir, timestamp, self.shard_duration)?
+        };
         if shard != self.shard {
             // We just recreate the StoreWriter since this is a new shard
             let mut writer = Self::new_with_shard(
@@ -588,10 +1066,22 @@ impl StoreWriter {
                 self.compression_mode,
                 self.format,
             )?;
+            writer.zstd_level = self.zstd_level;
+            writer.recent_frames = std::mem::take(&mut self.recent_frames);
             // Set self to new shard only if we succeed in writing the first
             // frame. If we don't do this, we may "forget" returning a true
             // for a new shard where the first write fails.
-            writer.put_in_current_shard(timestamp, data)?;
+            if let Err(e) = writer.put_in_current_shard(timestamp, data) {
+                // A failed write leaves the recent frames as they were
+                self.recent_frames = std::mem::take(&mut writer.recent_frames);
+                return Err(e);
+            }
+            if self.compression_mode.codec().is_some() {
+                info!(
+                    self.logger,
+                    "Spent {:?} compressing shard {}", self.compress_time, self.shard
+                );
+            }
             *self = writer;
             Ok(true)
         } else {
@@ -601,31 +1091,16 @@ impl StoreWriter {
     }
 
     /// Discard shards from the oldest first until f(shard_timestamp) is true
-    /// or we've reached the current shard. Returns true if f(shard_timestamp)
-    /// is true for the last shard visited or false otherwise.
+    /// or we've reached the current shard. Returns true if f is true for the
+    /// last shard visited or false otherwise.
     fn discard_until<F>(&self, f: F) -> Result<bool>
     where
-        F: Fn(u64) -> bool,
+        F: Fn(u64) -> Result<bool>,
     {
-        let entries = get_index_files(self.dir.as_path())?;
-
-        // Entries are sorted with increasing timestamp
-        for entry in entries {
-            let v: Vec<&str> = entry.split('_').collect();
-            if v.len() != 2 {
-                warn!(self.logger, "Invalid index file name: {}", entry);
-                continue;
-            }
-
-            let entry_shard = match v[1].parse::<u64>() {
-                Ok(val) => val,
-                _ => {
-                    warn!(self.logger, "Cannot parse index shard: {}", entry);
-                    continue;
-                }
-            };
-
-            if f(entry_shard) {
+        let _lock = lock_shards(&self.dir)?;
+        // Shards are sorted with increasing timestamp
+        for entry_shard in get_shards(self.dir.as_path())? {
+            if f(entry_shard)? {
                 return Ok(true);
             }
             if entry_shard >= self.shard {
@@ -635,7 +1110,7 @@ impl StoreWriter {
             // Removal order doesn't matter at all, it's the
             // responsibility of the read side to handle missing files
             let mut index_path = self.dir.clone();
-            index_path.push(&entry);
+            index_path.push(format!("index_{:011}", entry_shard));
 
             match std::fs::remove_file(&index_path) {
                 Err(e) if e.kind() != ErrorKind::NotFound => {
@@ -659,6 +1134,20 @@ impl StoreWriter {
                 }
                 _ => {}
             };
+
+            // Only shards written with a trained dictionary have one
+            let mut dict_path = self.dir.clone();
+            dict_path.push(format!("dict_{:011}", entry_shard));
+
+            match std::fs::remove_file(&dict_path) {
+                Err(e) if e.kind() != ErrorKind::NotFound => {
+                    return Err(e).context(format!(
+                        "Failed to remove dict file: {}",
+                        dict_path.display()
+                    ));
+                }
+                _ => {}
+            };
         }
         Ok(false)
     }
@@ -668,8 +1157,14 @@ impl StoreWriter {
     /// We do not modify index and data files. We just look for files
     /// which can only contain earlier data and remove them.
     pub fn discard_earlier(&self, timestamp: SystemTime) -> Result<()> {
-        let shard = calculate_shard(timestamp);
-        self.discard_until(|shard_timestamp| shard_timestamp >= shard)?;
+        let timestamp = get_unix_timestamp(timestamp);
+        // Keep the first shard with data at or after timestamp. Shards can
+        // have different durations and gaps between them, so the start of
+        // the next shard says nothing about where this one ends.
+        self.discard_until(|shard| {
+            Ok(shard >= timestamp
+                || get_last_timestamp(&self.dir, shard)?.map_or(false, |last| last >= timestamp))
+        })?;
         Ok(())
     }
 
@@ -680,13 +1175,13 @@ impl StoreWriter {
         let dir = self.dir.clone();
         self.discard_until(|_| {
             let size = get_dir_size(dir.clone());
-            size <= store_size_limit
+            Ok(size <= store_size_limit)
         })
     }
 }
 
 /// Direction to scan for next sample
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum Direction {
     Forward,
     Reverse,
@@ -721,6 +1216,217 @@ pub fn read_next_sample<P: AsRef<Path>>(
     cursor.get_next(&get_unix_timestamp(timestamp), direction)
 }
 
+/// Summary of a single shard of a store, see `get_store_info`
+#[derive(Default, Debug, PartialEq)]
+pub struct ShardInfo {
+    pub shard: u64,
+    /// Number of valid index entries
+    pub entries: u64,
+    /// Number of index entries that failed their CRC check
+    pub corrupt_entries: u64,
+    /// Number of valid index entries per schema version
+    pub schema_versions: BTreeMap<u32, u64>,
+    /// Number of valid index entries per compression, see
+    /// `CompressionMode::name`
+    pub compression: BTreeMap<&'static str, u64>,
+    pub index_bytes: u64,
+    pub data_bytes: u64,
+    /// Size of the trained dictionary of the shard, if any
+    pub dict_bytes: u64,
+}
+
+/// Decodes the entries of the index file content `index`, skipping zero
+/// padding. Entries failing their crc are returned as None.
+fn parse_index_entries(index: &[u8]) -> impl Iterator<Item = Option<IndexEntry>> + '_ {
+    // A partially written trailing entry is ignored, just like readers do
+    index
+        .chunks_exact(INDEX_ENTRY_SIZE)
+        // Zero padding from dictionary compression
+        .filter(|slice| *slice != [0; INDEX_ENTRY_SIZE])
+        .map(|slice| Some(read_index_entry(slice)).filter(|entry| entry.crc32() == entry.index_crc))
+}
+
+/// Reads the index entry at the start of `slice`, which must be at least
+/// `INDEX_ENTRY_SIZE` long. The entry still needs its crc checked.
+fn read_index_entry(slice: &[u8]) -> IndexEntry {
+    assert!(slice.len() >= INDEX_ENTRY_SIZE);
+    // The read is unaligned because Vec<u8> only guarantees byte alignment.
+    // Any bit pattern is validated by the crc.
+    unsafe { std::ptr::read_unaligned(slice.as_ptr() as *const IndexEntry) }
+}
+
+/// Scans the index files of the store at `path` without decoding any
+/// frames. Shards are returned oldest first.
+pub fn get_store_info<P: AsRef<Path>>(path: P) -> Result<Vec<ShardInfo>> {
+    let path = path.as_ref();
+    let mut shards = Vec::new();
+    for entry in get_index_files(path)? {
+        let shard = match entry.split('_').nth(1).map(str::parse::<u64>) {
+            Some(Ok(shard)) => shard,
+            _ => continue,
+        };
+        let index_path = path.join(&entry);
+        let index = fs::read(&index_path)
+            .with_context(|| format!("Failed to read {}", index_path.display()))?;
+        let file_bytes = |prefix: &str| {
+            let file_path = path.join(format!("{}_{:011}", prefix, shard));
+            match fs::metadata(file_path) {
+                Ok(metadata) => Ok(metadata.len()),
+                Err(e) if e.kind() == ErrorKind::NotFound => Ok(0),
+                Err(e) => Err(e),
+            }
+        };
+        let mut info = ShardInfo {
+            shard,
+            index_bytes: index.len() as u64,
+            data_bytes: file_bytes("data")?,
+            dict_bytes: file_bytes("dict")?,
+            ..Default::default()
+        };
+        for index_entry in parse_index_entries(&index) {
+            let index_entry = match index_entry {
+                Some(index_entry) => index_entry,
+                None => {
+                    info.corrupt_entries += 1;
+                    continue;
+                }
+            };
+            info.entries += 1;
+            *info
+                .schema_versions
+                .entry(index_entry.flags.get_schema_version())
+                .or_default() += 1;
+            *info
+                .compression
+                .entry(CompressionMode::from_flags(index_entry.flags).name())
+                .or_default() += 1;
+        }
+        shards.push(info);
+    }
+    Ok(shards)
+}
+
+/// Size and decompression cost of the frames of one compression, see
+/// `get_compression_stats`
+#[derive(Default, Debug, PartialEq)]
+pub struct CompressionStats {
+    pub frames: u64,
+    /// Bytes in the data files
+    pub stored_bytes: u64,
+    /// Bytes after decompression
+    pub raw_bytes: u64,
+    /// Time spent reading and decompressing, including dictionaries
+    pub decompress_time: Duration,
+    /// Time spent compressing the frames again the way `below record` does,
+    /// zstd frames at the level recorded in the store
+    pub compress_time: Duration,
+}
+
+/// Decompresses every readable frame of the store at `path`, compresses it
+/// again and returns the stats per compression, see `CompressionMode::name`.
+/// Frames are not deserialized. Reads the whole store, unlike
+/// `get_store_info`.
+pub fn get_compression_stats<P: AsRef<Path>>(
+    logger: slog::Logger,
+    path: P,
+) -> Result<BTreeMap<&'static str, CompressionStats>> {
+    let path = path.as_ref();
+    let mut cursor = StoreCursor::new(logger, path.to_path_buf());
+    let zstd_level = get_zstd_level(path)?;
+    let mut stats = BTreeMap::new();
+    for shard in get_shards(path)? {
+        let index_path = path.join(format!("index_{:011}", shard));
+        let index = fs::read(&index_path)
+            .with_context(|| format!("Failed to read {}", index_path.display()))?;
+        // Loads the mmaps of the shard
+        cursor.set_offset(StoreOffset::new(Some(shard), None));
+        // Like the StoreWriter, keep one compressor while the compression
+        // doesn't change so that dictionaries carry over between frames
+        let mut compressor: Option<(&'static str, Compressor)> = None;
+        for (i, slice) in index.chunks_exact(INDEX_ENTRY_SIZE).enumerate() {
+            // Skip padding and corrupt entries
+            if slice == [0; INDEX_ENTRY_SIZE] {
+                continue;
+            }
+            let entry = read_index_entry(slice);
+            if entry.crc32() != entry.index_crc {
+                continue;
+            }
+            let start = Instant::now();
+            // Damaged frames are for fsck to report
+            let (index_entry, frame) =
+                match cursor.get_index_and_serialized_frame_at(i * INDEX_ENTRY_SIZE) {
+                    Ok(item) => item,
+                    Err(_) => continue,
+                };
+            let decompress_time = start.elapsed();
+
+            let compression_mode = CompressionMode::from_flags(index_entry.flags);
+            let name = compression_mode.name();
+            let mut compress_time = Duration::ZERO;
+            if let Some(codec) = compression_mode.codec() {
+                if compressor.as_ref().map(|(n, _)| *n) != Some(name) {
+                    let mut new_compressor = Compressor::with_codec(codec);
+                    if let (Codec::Zstd, Some(level)) = (codec, zstd_level) {
+                        new_compressor.set_level(level)?;
+                    }
+                    compressor = Some((name, new_compressor));
+                }
+                let (_, compressor) = compressor.as_mut().expect("compressor should be set");
+                let frame = bytes::Bytes::copy_from_slice(frame.as_ref());
+                // Same as the chunk alignment check of the StoreWriter
+                let is_key_frame = match compression_mode {
+                    CompressionMode::ZstdDictionary(ChunkSizePo2(chunk_size_po2)) => {
+                        (i * INDEX_ENTRY_SIZE).trailing_zeros()
+                            >= chunk_size_po2 + INDEX_ENTRY_SIZE_PO2
+                    }
+                    _ => false,
+                };
+                let dict_path = path.join(format!("dict_{:011}", shard));
+                let start = Instant::now();
+                compress_frame(
+                    compression_mode,
+                    compressor,
+                    &frame,
+                    is_key_frame,
+                    &mut IndexEntryFlags::empty(),
+                    || Ok(fs::read(&dict_path).ok().map(Into::into)),
+                )
+                .with_context(|| format!("Failed to compress frame of shard {}", shard))?;
+                compress_time = start.elapsed();
+            }
+
+            let compression_stats: &mut CompressionStats = stats.entry(name).or_default();
+            compression_stats.frames += 1;
+            compression_stats.stored_bytes += index_entry.len as u64;
+            compression_stats.raw_bytes += frame.as_ref().len() as u64;
+            compression_stats.decompress_time += decompress_time;
+            compression_stats.compress_time += compress_time;
+        }
+    }
+    Ok(stats)
+}
+
+/// Returns the compression mode and format the newest valid frame of the
+/// store at `path` was written with, or None if there is no such frame.
+/// Used to write derived stores with the same settings.
+pub fn get_store_settings<P: AsRef<Path>>(path: P) -> Result<Option<(CompressionMode, Format)>> {
+    let path = path.as_ref();
+    for entry in get_index_files(path)?.iter().rev() {
+        let index_path = path.join(entry);
+        let index = fs::read(&index_path)
+            .with_context(|| format!("Failed to read {}", index_path.display()))?;
+        if let Some(index_entry) = parse_index_entries(&index).flatten().last() {
+            return Ok(Some((
+                CompressionMode::from_flags(index_entry.flags),
+                // Frames without a format flag are CBOR
+                Format::from_flags(index_entry.flags).unwrap_or(Format::Cbor),
+            )));
+        }
+    }
+    Ok(None)
+}
+
 pub trait Store {
     // We intentionally make this trait generic which not tied to the DataFrame and Model
     // type for ease of testing.
@@ -860,10 +1566,10 @@ impl Crc32 for IndexEntry {
 }
 
 // This is the timestamp rounded down to the nearest
-// multiple of SHARD_TIME
-fn calculate_shard(timestamp: SystemTime) -> u64 {
+// multiple of shard_duration
+fn calculate_shard(timestamp: SystemTime, shard_duration: u64) -> u64 {
     let timestamp_secs = get_unix_timestamp(timestamp);
-    let shard_rem = timestamp_secs % SHARD_TIME;
+    let shard_rem = timestamp_secs % shard_duration;
     timestamp_secs - shard_rem
 }
 
@@ -915,6 +1621,44 @@ mod tests {
                     $func(CompressionMode::ZstdDictionary(ChunkSizePo2(2)), Format::Cbor);
                 }
             }
+
+            paste! {
+                #[test]
+                fn [<$name _uncompressed_msgpack>]() {
+                    $func(CompressionMode::None, Format::MessagePack);
+                }
+            }
+
+            paste! {
+                #[test]
+                fn [<$name _compressed_msgpack>]() {
+                    $func(CompressionMode::Zstd, Format::MessagePack);
+                }
+            }
+
+            paste! {
+                #[test]
+                fn [<$name _dict_compressed_msgpack>]() {
+                    $func(
+                        CompressionMode::ZstdDictionary(ChunkSizePo2(2)),
+                        Format::MessagePack,
+                    );
+                }
+            }
+
+            paste! {
+                #[test]
+                fn [<$name _trained_dict_compressed_cbor>]() {
+                    $func(CompressionMode::ZstdTrainedDictionary, Format::Cbor);
+                }
+            }
+
+            paste! {
+                #[test]
+                fn [<$name _lz4_compressed_cbor>]() {
+                    $func(CompressionMode::Lz4, Format::Cbor);
+                }
+            }
         };
     }
 
@@ -946,6 +1690,11 @@ mod tests {
                 CompressionMode::ZstdDictionary(ChunkSizePo2(3)),
                 Format::Cbor,
             ),
+            (CompressionMode::None, Format::MessagePack),
+            (
+                CompressionMode::ZstdDictionary(ChunkSizePo2(2)),
+                Format::MessagePack,
+            ),
         ];
         // State sequence that contains all possible transitions
         let state_sequence = states
@@ -1164,7 +1913,9 @@ mod tests {
         let dir = TempDir::new("below_store_test").expect("tempdir failed");
         let now = SystemTime::now();
         // Ensure that the follow writes (within 60s) are to the same shard
-        let ts = if calculate_shard(now) == calculate_shard(now + Duration::from_secs(60)) {
+        let ts = if calculate_shard(now, SHARD_TIME)
+            == calculate_shard(now + Duration::from_secs(60), SHARD_TIME)
+        {
             now
         } else {
             now + Duration::from_secs(60)
@@ -1490,6 +2241,270 @@ mod tests {
         assert_eq!(frame.1.sample.cgroup.memory_current, Some(777));
     }
 
+    #[test]
+    fn trained_dict() {
+        let dir = TempDir::new("below_store_test").expect("tempdir failed");
+        let ts = std::time::UNIX_EPOCH + Duration::from_secs(SHARD_TIME);
+        let nr_frames = DICT_TRAINING_FRAMES as u64 + 8;
+        // Similar frames, like consecutive samples
+        let frame = |i: u64| {
+            let mut frame = DataFrame::default();
+            let children = (0..8)
+                .map(|j| {
+                    let child = model::CgroupSample {
+                        memory_current: Some((i * (j + 1)) as i64),
+                        inode_number: Some(j as i64),
+                        ..Default::default()
+                    };
+                    (format!("child_{}.slice", j), child)
+                })
+                .collect::<BTreeMap<_, _>>();
+            frame.sample.cgroup.memory_current = Some(i as i64);
+            frame.sample.cgroup.children = Some(children);
+            frame
+        };
+        {
+            let mut writer = StoreWriter::new_with_timestamp(
+                get_logger(),
+                &dir,
+                ts,
+                CompressionMode::ZstdTrainedDictionary,
+                Format::Cbor,
+            )
+            .expect("Failed to create store");
+            writer.set_zstd_level(19).expect("Failed to set level");
+            assert!(writer.set_zstd_level(1000).is_err());
+            for i in 0..nr_frames {
+                writer
+                    .put(ts + Duration::from_secs(i), &frame(i))
+                    .expect("Failed to store data");
+            }
+            // The next shard is trained on frames of the first one
+            for i in 0..nr_frames {
+                writer
+                    .put(ts + Duration::from_secs(SHARD_TIME + i), &frame(i))
+                    .expect("Failed to store data");
+            }
+        }
+        let shard = get_unix_timestamp(ts);
+        let dict_path = |shard: u64| dir.path().join(format!("dict_{:011}", shard));
+        assert!(dict_path(shard).exists());
+        assert!(dict_path(shard + SHARD_TIME).exists());
+
+        let shards = get_store_info(&dir).expect("Failed to get store info");
+        // Frames before the first training are compressed without dict
+        assert_eq!(
+            shards[0].compression,
+            maplit::btreemap! {
+                "zstd" => DICT_TRAINING_FRAMES as u64,
+                "zstd-trained-dict" => nr_frames - DICT_TRAINING_FRAMES as u64,
+            }
+        );
+        assert_eq!(
+            shards[1].compression,
+            maplit::btreemap! { "zstd-trained-dict" => nr_frames }
+        );
+        assert!(shards[1].dict_bytes > 0);
+        // The level is recorded for later writers and stats
+        assert_eq!(get_zstd_level(&dir).expect("Failed to read"), Some(19));
+
+        let stats = get_compression_stats(get_logger(), &dir).expect("Failed to get stats");
+        assert_eq!(
+            stats["zstd-trained-dict"].frames,
+            2 * nr_frames - DICT_TRAINING_FRAMES as u64
+        );
+        assert!(stats["zstd-trained-dict"].raw_bytes > stats["zstd-trained-dict"].stored_bytes);
+        assert!(stats["zstd-trained-dict"].compress_time > Duration::ZERO);
+
+        // Restarting in the same shard uses the saved dictionary
+        {
+            let mut writer = StoreWriter::new_with_timestamp(
+                get_logger(),
+                &dir,
+                ts + Duration::from_secs(SHARD_TIME + nr_frames),
+                CompressionMode::ZstdTrainedDictionary,
+                Format::Cbor,
+            )
+            .expect("Failed to create store");
+            assert_eq!(writer.zstd_level, Some(19));
+            writer
+                .put(
+                    ts + Duration::from_secs(SHARD_TIME + nr_frames),
+                    &frame(nr_frames),
+                )
+                .expect("Failed to store data");
+            writer
+                .discard_earlier(ts + Duration::from_secs(SHARD_TIME))
+                .expect("Failed to discard data");
+        }
+        assert!(!dict_path(shard).exists());
+
+        let mut store_cursor = StoreCursor::new(get_logger(), dir.path().to_path_buf());
+        let mut item = store_cursor
+            .get_next(&0, Direction::Forward)
+            .expect("Failed to read sample");
+        for i in 0..=nr_frames {
+            let (frame_ts, read_frame) = item.expect("Did not find stored sample");
+            assert_ts!(frame_ts, ts + Duration::from_secs(SHARD_TIME + i));
+            assert_eq!(read_frame, frame(i));
+            item = store_cursor
+                .next(Direction::Forward)
+                .expect("Failed to read sample");
+        }
+        assert!(item.is_none());
+    }
+
+    store_test!(mixed_shard_durations, _mixed_shard_durations);
+    fn _mixed_shard_durations(compression_mode: CompressionMode, format: Format) {
+        let dir = TempDir::new("below_store_test").expect("tempdir failed");
+        let ts = std::time::UNIX_EPOCH + Duration::from_secs(SHARD_TIME);
+        let hour = Duration::from_secs(3600);
+        assert!(set_shard_duration(&dir, Duration::from_secs(7 * 60)).is_err());
+        assert!(set_shard_duration(&dir, Duration::from_secs(90)).is_err());
+        {
+            let mut writer =
+                StoreWriter::new_with_timestamp(get_logger(), &dir, ts, compression_mode, format)
+                    .expect("Failed to create store");
+            let mut frame = DataFrame::default();
+            frame.sample.cgroup.memory_current = Some(333);
+            writer.put(ts, &frame).expect("Failed to store data");
+        }
+        set_shard_duration(&dir, hour).expect("Failed to set shard duration");
+        assert_eq!(get_shard_duration(&dir).expect("Failed to read"), hour);
+        {
+            let mut writer = StoreWriter::new_with_timestamp(
+                get_logger(),
+                &dir,
+                ts + Duration::from_secs(1),
+                compression_mode,
+                format,
+            )
+            .expect("Failed to create store");
+            let mut frame = DataFrame::default();
+            // Continues the day long shard until the next hour
+            frame.sample.cgroup.memory_current = Some(444);
+            assert!(
+                !writer
+                    .put(ts + Duration::from_secs(1), &frame)
+                    .expect("Failed to store data")
+            );
+            frame.sample.cgroup.memory_current = Some(555);
+            assert!(writer.put(ts + hour, &frame).expect("Failed to store data"));
+            frame.sample.cgroup.memory_current = Some(666);
+            assert!(
+                writer
+                    .put(ts + 2 * hour, &frame)
+                    .expect("Failed to store data")
+            );
+        }
+        set_shard_duration(&dir, Duration::from_secs(SHARD_TIME))
+            .expect("Failed to set shard duration");
+        {
+            let mut writer = StoreWriter::new_with_timestamp(
+                get_logger(),
+                &dir,
+                ts + 2 * hour,
+                compression_mode,
+                format,
+            )
+            .expect("Failed to create store");
+            let mut frame = DataFrame::default();
+            // Stays in the hour long shard until the next day
+            frame.sample.cgroup.memory_current = Some(777);
+            assert!(
+                !writer
+                    .put(ts + 3 * hour, &frame)
+                    .expect("Failed to store data")
+            );
+        }
+        let shard = get_unix_timestamp(ts);
+        assert_eq!(
+            get_shards(dir.path()).expect("Failed to list shards"),
+            vec![shard, shard + 3600, shard + 7200]
+        );
+
+        let read_all = |direction| {
+            let mut store_cursor = StoreCursor::new(get_logger(), dir.path().to_path_buf());
+            let start = match direction {
+                Direction::Forward => 0,
+                Direction::Reverse => get_unix_timestamp(ts + 4 * hour),
+            };
+            let mut values = Vec::new();
+            let mut item = store_cursor
+                .get_next(&start, direction)
+                .expect("Failed to read sample");
+            while let Some((_, frame)) = item {
+                values.push(frame.sample.cgroup.memory_current.unwrap());
+                item = store_cursor.next(direction).expect("Failed to read sample");
+            }
+            values
+        };
+        assert_eq!(read_all(Direction::Forward), vec![333, 444, 555, 666, 777]);
+        assert_eq!(read_all(Direction::Reverse), vec![777, 666, 555, 444, 333]);
+
+        let mut store_cursor = StoreCursor::new(get_logger(), dir.path().to_path_buf());
+        let frame = store_cursor
+            .get_next(
+                &get_unix_timestamp(ts + hour - Duration::from_secs(1)),
+                Direction::Reverse,
+            )
+            .expect("Failed to read sample")
+            .expect("Did not find stored sample");
+        assert_eq!(frame.1.sample.cgroup.memory_current, Some(444));
+
+        let writer = StoreWriter::new_with_timestamp(
+            get_logger(),
+            &dir,
+            ts + 3 * hour,
+            compression_mode,
+            format,
+        )
+        .expect("Failed to create store");
+        writer
+            .discard_earlier(ts + hour)
+            .expect("Failed to discard data");
+        assert_eq!(read_all(Direction::Forward), vec![555, 666, 777]);
+    }
+
+    store_test!(discard_earlier_with_gap, _discard_earlier_with_gap);
+    fn _discard_earlier_with_gap(compression_mode: CompressionMode, format: Format) {
+        let dir = TempDir::new("below_store_test").expect("tempdir failed");
+        let ts = std::time::UNIX_EPOCH + Duration::from_secs(SHARD_TIME);
+        let hour = Duration::from_secs(3600);
+        set_shard_duration(&dir, hour).expect("Failed to set shard duration");
+        {
+            let mut writer =
+                StoreWriter::new_with_timestamp(get_logger(), &dir, ts, compression_mode, format)
+                    .expect("Failed to create store");
+            let mut frame = DataFrame::default();
+            frame.sample.cgroup.memory_current = Some(333);
+            writer.put(ts, &frame).expect("Failed to store data");
+            // Nothing recorded for hours, e.g. while the host was down
+            frame.sample.cgroup.memory_current = Some(444);
+            writer
+                .put(ts + 5 * hour, &frame)
+                .expect("Failed to store data");
+
+            // The first shard only has data from before the gap
+            writer
+                .discard_earlier(ts + 3 * hour)
+                .expect("Failed to discard data");
+        }
+        let shard = get_unix_timestamp(ts);
+        assert_eq!(
+            get_shards(dir.path()).expect("Failed to list shards"),
+            vec![shard + 5 * 3600]
+        );
+
+        let mut store_cursor = StoreCursor::new(get_logger(), dir.path().to_path_buf());
+        let frame = store_cursor
+            .get_next(&0, Direction::Forward)
+            .expect("Failed to read sample")
+            .expect("Did not find stored sample");
+        assert_ts!(frame.0, ts + 5 * hour);
+        assert_eq!(frame.1.sample.cgroup.memory_current, Some(444));
+    }
+
     store_test!(try_discard_until_size, _try_discard_until_size);
     fn _try_discard_until_size(compression_mode: CompressionMode, format: Format) {
         let dir = TempDir::new("below_store_test").expect("tempdir failed");
@@ -1617,7 +2632,7 @@ mod tests {
     fn _flock_protects(compression_mode: CompressionMode, format: Format) {
         let dir = TempDir::new("below_store_test").expect("tempdir failed");
         let ts = SystemTime::now();
-        let shard = calculate_shard(ts);
+        let shard = calculate_shard(ts, SHARD_TIME);
         let mut index_path = dir.path().to_path_buf();
         index_path.push(format!("index_{:011}", shard));
         let index = OpenOptions::new()
@@ -1698,7 +2713,7 @@ mod tests {
         }
         // Append garbage to the index entry
         {
-            let shard = calculate_shard(ts);
+            let shard = calculate_shard(ts, SHARD_TIME);
             let mut index_path = dir.path().to_path_buf();
             index_path.push(format!("index_{:011}", shard));
             let mut index = OpenOptions::new()
@@ -0,0 +1,434 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Combining stores, e.g. collected from several hosts, into one store and
+//! splitting a store apart again.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+
+use common::util::get_unix_timestamp;
+
+use crate::cursor::{Cursor, KeyedCursor, StoreCursor};
+use crate::{
+    copy_store_settings, get_index_files, CompressionMode, DataFrame, Direction, Format,
+    StoreWriter,
+};
+
+/// A store to merge frames from.
+pub struct MergeSource {
+    pub path: PathBuf,
+    /// Host to record as the source of the frames of this store. Frames are
+    /// attributed to the hostname they were collected with if None.
+    pub hostname: Option<String>,
+}
+
+#[derive(Default, Debug, PartialEq)]
+pub struct MergeStats {
+    /// Number of frames written
+    pub frames: u64,
+    /// Frames dropped because their host already had a frame at or after
+    /// their timestamp, e.g. from overlapping snapshots
+    pub duplicates: u64,
+    /// Frames dropped per host because a frame of another host was already
+    /// written at the same second
+    pub collisions: BTreeMap<String, u64>,
+}
+
+fn ensure_no_store(path: &Path) -> Result<()> {
+    if path.is_dir() && !get_index_files(path)?.is_empty() {
+        bail!(
+            "{} already contains a store, need an empty directory",
+            path.display()
+        );
+    }
+    Ok(())
+}
+
+/// Host a frame was collected on: the host it was merged from if any,
+/// otherwise the hostname it was collected with.
+fn source_host(frame: &DataFrame) -> &str {
+    frame
+        .sample
+        .source_host
+        .as_deref()
+        .unwrap_or(&frame.sample.system.hostname)
+}
+
+/// Merges the frames of `sources` into one new store at `to_path`, oldest
+/// first, recording the host of every frame in `Sample::source_host`.
+/// Repeated frames of a host are dropped. The store holds at most one
+/// frame per second, so when frames of several hosts share a second the
+/// frame of the source given first is kept and the others are dropped and
+/// counted as collisions. Timestamps are never changed.
+pub fn merge_stores<P: AsRef<Path>>(
+    logger: slog::Logger,
+    sources: &[MergeSource],
+    to_path: P,
+    compression_mode: CompressionMode,
+    format: Format,
+) -> Result<MergeStats> {
+    let to_path = to_path.as_ref();
+    let mut heads = Vec::new();
+    for source in sources {
+        let mut cursor = StoreCursor::new(logger.clone(), source.path.clone());
+        let head = cursor
+            .get_next(&0, Direction::Forward)
+            .with_context(|| format!("Failed to read {}", source.path.display()))?;
+        heads.push((cursor, head));
+    }
+
+    ensure_no_store(to_path)?;
+    fs::create_dir_all(to_path)
+        .with_context(|| format!("Failed to create {}", to_path.display()))?;
+    // Opened on the first frame, to start the store at its timestamp
+    let mut out: Option<(StoreWriter, u64)> = None;
+    // Last timestamp written of every host
+    let mut host_last: HashMap<String, u64> = HashMap::new();
+    let mut stats = MergeStats::default();
+    loop {
+        // Oldest frame first, ties go to the source given first
+        let next = heads
+            .iter()
+            .enumerate()
+            .filter_map(|(i, (_, head))| head.as_ref().map(|(ts, _)| (*ts, i)))
+            .min();
+        let i = match next {
+            Some((_, i)) => i,
+            None => break,
+        };
+        let (cursor, head) = &mut heads[i];
+        let (ts, mut frame) = head.take().expect("head should be set");
+        *head = cursor
+            .next(Direction::Forward)
+            .with_context(|| format!("Failed to read {}", sources[i].path.display()))?;
+
+        let hostname = match &sources[i].hostname {
+            Some(hostname) => hostname.clone(),
+            None => source_host(&frame).to_owned(),
+        };
+        let timestamp = get_unix_timestamp(ts);
+        if host_last
+            .get(&hostname)
+            .map_or(false, |last| timestamp <= *last)
+        {
+            stats.duplicates += 1;
+            continue;
+        }
+        // Frames come oldest first, so only the last frame written can
+        // share the second
+        if out.as_ref().map_or(false, |(_, last)| timestamp == *last) {
+            *stats.collisions.entry(hostname).or_default() += 1;
+            continue;
+        }
+        if out.is_none() {
+            let store_writer = StoreWriter::new_with_timestamp(
+                logger.clone(),
+                to_path,
+                ts,
+                compression_mode,
+                format,
+            )?;
+            out = Some((store_writer, timestamp));
+        }
+        let (writer, last) = out.as_mut().expect("writer should be open");
+        frame.sample.source_host = Some(hostname.clone());
+        writer.put(ts, &frame)?;
+        *last = timestamp;
+        host_last.insert(hostname, timestamp);
+        stats.frames += 1;
+    }
+    Ok(stats)
+}
+
+/// How to partition a store. Partitions are directories under the target
+/// directory, nested in the order of the fields.
+#[derive(Default, Debug)]
+pub struct SplitBy {
+    /// One store per hostname the frames were collected on
+    pub hostname: bool,
+    /// One store per time window of this length, named after the unix
+    /// timestamp the window starts at
+    pub interval: Option<Duration>,
+}
+
+/// Directory name for the frames of a hostname. Hostnames come from the
+/// stored samples, so names that would leave the target directory are
+/// rejected.
+fn hostname_dir(hostname: &str) -> Result<String> {
+    match hostname {
+        "" => Ok("unknown_host".to_owned()),
+        "." | ".." => bail!("Invalid hostname {:?}", hostname),
+        _ => Ok(hostname.replace('/', "_")),
+    }
+}
+
+/// Splits the store at `path` into stores under `to_path` as given by
+/// `split_by`, keeping its settings, see `copy_store_settings`. Returns the path and number of frames of every store
+/// written.
+pub fn split_store<P: AsRef<Path>, Q: AsRef<Path>>(
+    logger: slog::Logger,
+    path: P,
+    to_path: Q,
+    split_by: &SplitBy,
+    compression_mode: CompressionMode,
+    format: Format,
+) -> Result<BTreeMap<PathBuf, u64>> {
+    let path = path.as_ref();
+    let to_path = to_path.as_ref();
+    if !split_by.hostname && split_by.interval.is_none() {
+        bail!("Nothing to split by");
+    }
+    if split_by.interval == Some(Duration::from_secs(0)) {
+        bail!("Split interval must be at least a second");
+    }
+    let mut cursor = StoreCursor::new(logger.clone(), path.to_path_buf());
+    let mut item = cursor.get_next(&0, Direction::Forward)?;
+    // Frames are read in time order, so each hostname only ever needs the
+    // writer of its current window
+    let mut writers: HashMap<String, (PathBuf, StoreWriter)> = HashMap::new();
+    // Hostname written to each host directory
+    let mut dir_hosts: HashMap<String, String> = HashMap::new();
+    let mut nr_frames = BTreeMap::new();
+    while let Some((ts, frame)) = item {
+        let mut partition = to_path.to_path_buf();
+        let writer_key = if split_by.hostname {
+            let hostname = source_host(&frame);
+            let dir = hostname_dir(hostname)?;
+            // Distinct hostnames can map to the same directory
+            let other = dir_hosts
+                .entry(dir.clone())
+                .or_insert_with(|| hostname.to_owned());
+            if other != hostname {
+                bail!(
+                    "Hosts {:?} and {:?} would both be split into {}",
+                    other,
+                    hostname,
+                    to_path.join(&dir).display()
+                );
+            }
+            partition.push(dir);
+            hostname.to_owned()
+        } else {
+            String::new()
+        };
+        if let Some(interval) = split_by.interval {
+            let timestamp = get_unix_timestamp(ts);
+            let interval = interval.as_secs();
+            partition.push(format!("{:011}", timestamp - timestamp % interval));
+        }
+
+        let is_open = writers
+            .get(&writer_key)
+            .map_or(false, |(writer_path, _)| *writer_path == partition);
+        if !is_open {
+            ensure_no_store(&partition)?;
+            fs::create_dir_all(&partition)
+                .with_context(|| format!("Failed to create {}", partition.display()))?;
+            copy_store_settings(path, &partition)?;
+            let writer = StoreWriter::new_with_timestamp(
+                logger.clone(),
+                &partition,
+                ts,
+                compression_mode,
+                format,
+            )?;
+            // Replaces the writer of the previous window
+            writers.insert(writer_key.clone(), (partition.clone(), writer));
+        }
+        let (_, writer) = writers.get_mut(&writer_key).expect("writer should be open");
+        writer.put(ts, &frame)?;
+        *nr_frames.entry(partition).or_default() += 1;
+        item = cursor.next(Direction::Forward)?;
+    }
+    Ok(nr_frames)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SHARD_TIME;
+    use common::util::get_system_time;
+    use slog::Drain;
+    use tempdir::TempDir;
+
+    fn get_logger() -> slog::Logger {
+        let plain = slog_term::PlainSyncDecorator::new(std::io::stderr());
+        slog::Logger::root(slog_term::FullFormat::new(plain).build().fuse(), slog::o!())
+    }
+
+    /// Timestamp, source host and collected hostname of all frames of the
+    /// store at `path`
+    fn read_hosts(path: &Path) -> Vec<(u64, Option<String>, String)> {
+        let mut cursor = StoreCursor::new(get_logger(), path.to_path_buf());
+        let mut frames = Vec::new();
+        let mut item = cursor
+            .get_next(&0, Direction::Forward)
+            .expect("Failed to read");
+        while let Some((ts, frame)) = item {
+            frames.push((
+                get_unix_timestamp(ts),
+                frame.sample.source_host,
+                frame.sample.system.hostname,
+            ));
+            item = cursor.next(Direction::Forward).expect("Failed to read");
+        }
+        frames
+    }
+
+    fn write_store(path: &Path, hostname: &str, timestamps: &[u64]) {
+        let mut writer = StoreWriter::new_with_timestamp(
+            get_logger(),
+            path,
+            get_system_time(timestamps[0]),
+            CompressionMode::Zstd,
+            Format::Cbor,
+        )
+        .expect("Failed to create store");
+        let mut frame = DataFrame::default();
+        frame.sample.system.hostname = hostname.to_owned();
+        for ts in timestamps {
+            writer
+                .put(get_system_time(*ts), &frame)
+                .expect("Failed to store data");
+        }
+    }
+
+    #[test]
+    fn merge_and_split() {
+        let dir = TempDir::new("below_merge_test").expect("tempdir failed");
+        let ts = SHARD_TIME;
+        let sources = ["a1", "a2", "b"]
+            .iter()
+            .map(|name| MergeSource {
+                path: dir.path().join(name),
+                hostname: None,
+            })
+            .collect::<Vec<_>>();
+        // Two overlapping snapshots of host a and host b sampling partly at
+        // the same seconds
+        write_store(&sources[0].path, "a", &[ts, ts + 5, ts + 10]);
+        write_store(&sources[1].path, "a", &[ts + 5, ts + 10, ts + 15]);
+        write_store(&sources[2].path, "b", &[ts + 5, ts + 6, ts + SHARD_TIME]);
+
+        let merged = dir.path().join("merged");
+        let stats = merge_stores(
+            get_logger(),
+            &sources,
+            &merged,
+            CompressionMode::ZstdDictionary(crate::ChunkSizePo2(2)),
+            Format::Cbor,
+        )
+        .expect("Failed to merge");
+        assert_eq!(
+            stats,
+            MergeStats {
+                frames: 6,
+                duplicates: 2,
+                collisions: vec![("b".to_owned(), 1)].into_iter().collect(),
+            }
+        );
+        let frame = |ts: u64, h: &str| (ts, Some(h.to_owned()), h.to_owned());
+        // The frame of b at ts + 5 collides with the one of a given first
+        assert_eq!(
+            read_hosts(&merged),
+            vec![
+                frame(ts, "a"),
+                frame(ts + 5, "a"),
+                frame(ts + 6, "b"),
+                frame(ts + 10, "a"),
+                frame(ts + 15, "a"),
+                frame(ts + SHARD_TIME, "b"),
+            ]
+        );
+        // Refuses to mix into an existing store
+        assert!(merge_stores(
+            get_logger(),
+            &sources,
+            &merged,
+            CompressionMode::None,
+            Format::Cbor,
+        )
+        .is_err());
+
+        // Tags set the source host and keep the collected hostname
+        let tag = |i: usize, hostname: &str| MergeSource {
+            path: sources[i].path.clone(),
+            hostname: Some(hostname.to_owned()),
+        };
+        let merge_tagged = |name: &str, sources: &[MergeSource]| {
+            let path = dir.path().join(name);
+            merge_stores(
+                get_logger(),
+                sources,
+                &path,
+                CompressionMode::None,
+                Format::Cbor,
+            )
+            .expect("Failed to merge");
+            path
+        };
+        let tagged = merge_tagged("tagged", &[tag(2, "c")]);
+        assert!(read_hosts(&tagged)
+            .iter()
+            .all(|(_, source, h)| source.as_deref() == Some("c") && h == "b"));
+
+        let split = dir.path().join("split");
+        let split_by = SplitBy {
+            hostname: true,
+            interval: Some(Duration::from_secs(SHARD_TIME)),
+        };
+        let split_into = |path: &Path, to_path: &Path| {
+            split_store(
+                get_logger(),
+                path,
+                to_path,
+                &split_by,
+                CompressionMode::Zstd,
+                Format::MessagePack,
+            )
+        };
+        crate::set_zstd_level(&merged, 7).expect("Failed to set level");
+        let nr_frames = split_into(&merged, &split).expect("Failed to split");
+        let day = |d: u64| format!("{:011}", ts + d * SHARD_TIME);
+        assert_eq!(
+            nr_frames,
+            vec![
+                (split.join("a").join(day(0)), 4),
+                (split.join("b").join(day(0)), 1),
+                (split.join("b").join(day(1)), 1),
+            ]
+            .into_iter()
+            .collect::<BTreeMap<_, _>>()
+        );
+        assert_eq!(
+            read_hosts(&split.join("b").join(day(0))),
+            vec![frame(ts + 6, "b")]
+        );
+        assert_eq!(
+            crate::get_zstd_level(split.join("b").join(day(1))).expect("Failed to read"),
+            Some(7)
+        );
+        // Refuses to mix hosts that end up in the same directory
+        let clash = merge_tagged("clash", &[tag(0, "x/y"), tag(2, "x_y")]);
+        assert!(split_into(&clash, &dir.path().join("split_clash")).is_err());
+        // Refuses hostnames that would leave the target directory
+        let parent = merge_tagged("parent", &[tag(2, "..")]);
+        assert!(split_into(&parent, &dir.path().join("split_parent")).is_err());
+    }
+}
@@ -12,24 +12,371 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::time::SystemTime;
+//! A small request/response protocol to read frames from the store of a
+//! remote `below record`. Each message is a big endian u32 length followed
+//! by that many bytes of CBOR. The client sends a `Request` and the server
+//! answers with exactly one `Response`, so a connection can be reused for
+//! any number of lookups.
 
-use anyhow::{bail, Result};
+use std::io::{Read, Write};
+use std::net::{IpAddr, SocketAddr, TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, SystemTime};
 
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use slog::{error, info, warn};
+
+use crate::cursor::{KeyedCursor, StoreCursor};
 use crate::{DataFrame, Direction};
 
-pub struct RemoteStore {}
+/// Bumped whenever `Request` or `Response` change incompatibly
+const PROTOCOL_VERSION: u32 = 1;
+
+/// Upper bound on a single message so that a bogus length can't make us
+/// allocate arbitrary amounts of memory
+const MAX_MESSAGE_SIZE: u32 = 256 << 20;
+
+/// How long the client waits for the server before giving up on a request
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long the server keeps an idle connection open. Clients reconnect
+/// transparently, so this only bounds how long a silent peer holds a thread.
+const SERVER_IDLE_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// Connections served at once, each of which costs a thread and a cursor
+const MAX_CONNECTIONS: usize = 64;
+
+#[derive(Serialize, Deserialize)]
+struct Request {
+    version: u32,
+    timestamp: u64,
+    direction: Direction,
+}
+
+#[derive(Serialize, Deserialize)]
+enum Response {
+    /// Timestamp in seconds since epoch and the frame stored for it
+    Frame(Option<(u64, DataFrame)>),
+    Error(String),
+}
+
+fn write_message<T: Serialize>(stream: &mut TcpStream, message: &T) -> Result<()> {
+    let bytes = serde_cbor::to_vec(message).context("Failed to serialize message")?;
+    let len = u32::try_from(bytes.len())
+        .ok()
+        .filter(|len| *len <= MAX_MESSAGE_SIZE)
+        .ok_or_else(|| anyhow!("Message of {} bytes is too large", bytes.len()))?;
+    stream.write_all(&len.to_be_bytes())?;
+    stream.write_all(&bytes)?;
+    stream.flush()?;
+    Ok(())
+}
+
+/// Returns None if the peer closed the connection before sending anything
+fn read_message<T: DeserializeOwned>(stream: &mut TcpStream) -> Result<Option<T>> {
+    let mut len = [0; 4];
+    match stream.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    }
+    let len = u32::from_be_bytes(len);
+    if len > MAX_MESSAGE_SIZE {
+        bail!("Message of {} bytes is too large", len);
+    }
+    let mut bytes = vec![0; len as usize];
+    stream.read_exact(&mut bytes)?;
+    Ok(Some(
+        serde_cbor::from_slice(&bytes).context("Failed to deserialize message")?,
+    ))
+}
+
+pub struct RemoteStore {
+    addr: (String, u16),
+    /// Dropped after any failure so that a half read response can't be
+    /// mistaken for the answer to the next request
+    stream: Option<TcpStream>,
+}
 
 impl RemoteStore {
-    pub fn new(_host: String, _port: Option<u16>) -> Result<RemoteStore> {
-        bail!("Remote client not supported")
+    pub fn new(host: String, port: Option<u16>) -> Result<RemoteStore> {
+        let port = port.ok_or_else(|| anyhow!("--port is required to connect to {}", host))?;
+        let mut store = RemoteStore {
+            addr: (host, port),
+            stream: None,
+        };
+        // Fail early on unreachable hosts rather than on the first frame
+        store.connect()?;
+        Ok(store)
+    }
+
+    fn connect(&mut self) -> Result<&mut TcpStream> {
+        if self.stream.is_none() {
+            let stream = TcpStream::connect((self.addr.0.as_str(), self.addr.1))
+                .with_context(|| format!("Failed to connect to {}:{}", self.addr.0, self.addr.1))?;
+            stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+            stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+            stream.set_nodelay(true)?;
+            self.stream = Some(stream);
+        }
+        Ok(self.stream.as_mut().unwrap())
+    }
+
+    fn request(&mut self, request: &Request) -> Result<Response> {
+        let stream = self.connect()?;
+        write_message(stream, request)?;
+        read_message(stream)?.ok_or_else(|| anyhow!("Connection closed by remote"))
+    }
+
+    /// Like `request`, but retries once on a fresh connection if a reused
+    /// one fails, e.g. after the server closed it for being idle
+    fn request_with_retry(&mut self, request: &Request) -> Result<Response> {
+        let reused = self.stream.is_some();
+        match self.request(request) {
+            Err(_) if reused => {
+                self.stream = None;
+                self.request(request)
+            }
+            res => res,
+        }
     }
 
     pub fn get_frame(
         &mut self,
-        _timestamp: u64,
-        _direction: Direction,
+        timestamp: u64,
+        direction: Direction,
     ) -> Result<Option<(SystemTime, DataFrame)>> {
-        bail!("Remote client not supported")
+        let request = Request {
+            version: PROTOCOL_VERSION,
+            timestamp,
+            direction,
+        };
+        let response = self.request_with_retry(&request).map_err(|e| {
+            self.stream = None;
+            e.context(format!(
+                "Failed to get frame from {}:{}",
+                self.addr.0, self.addr.1
+            ))
+        })?;
+        match response {
+            Response::Frame(frame) => {
+                Ok(frame
+                    .map(|(ts, frame)| (SystemTime::UNIX_EPOCH + Duration::from_secs(ts), frame)))
+            }
+            Response::Error(e) => bail!("Remote error: {}", e),
+        }
+    }
+}
+
+/// Serves frames from a local store to `RemoteStore` clients
+pub struct RemoteStoreServer {
+    logger: slog::Logger,
+    store_dir: PathBuf,
+    listener: TcpListener,
+    connections: Arc<AtomicUsize>,
+}
+
+impl RemoteStoreServer {
+    /// Listens on `port` of `address`. Port 0 picks a free port, see
+    /// `local_addr()`.
+    pub fn bind(
+        logger: slog::Logger,
+        store_dir: PathBuf,
+        address: IpAddr,
+        port: u16,
+    ) -> Result<Self> {
+        let listener = TcpListener::bind((address, port))
+            .with_context(|| format!("Failed to listen on {}", SocketAddr::new(address, port)))?;
+        Ok(Self {
+            logger,
+            store_dir,
+            listener,
+            connections: Arc::new(AtomicUsize::new(0)),
+        })
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    /// Accepts connections forever, serving each one from its own thread.
+    /// Connections beyond `MAX_CONNECTIONS` are answered with an error and
+    /// closed.
+    pub fn run(self) -> Result<()> {
+        info!(
+            self.logger,
+            "Serving remote store on {}",
+            self.local_addr()?
+        );
+        for stream in self.listener.incoming() {
+            let mut stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    warn!(self.logger, "Failed to accept connection: {}", e);
+                    continue;
+                }
+            };
+            let peer = stream
+                .peer_addr()
+                .map_or("unknown peer".to_owned(), |addr| addr.to_string());
+            if self.connections.fetch_add(1, Ordering::SeqCst) >= MAX_CONNECTIONS {
+                self.connections.fetch_sub(1, Ordering::SeqCst);
+                warn!(
+                    self.logger,
+                    "Rejecting remote store connection from {}: {} connections already open",
+                    peer,
+                    MAX_CONNECTIONS
+                );
+                // Best effort, the client reads this as the answer to its
+                // first request
+                let _ = stream
+                    .set_write_timeout(Some(CLIENT_TIMEOUT))
+                    .map_err(anyhow::Error::from)
+                    .and_then(|_| {
+                        write_message(
+                            &mut stream,
+                            &Response::Error("Too many connections".to_owned()),
+                        )
+                    });
+                continue;
+            }
+            let logger = self.logger.clone();
+            let store_dir = self.store_dir.clone();
+            let connections = self.connections.clone();
+            let spawned = thread::Builder::new()
+                .name("remote_store_conn".to_owned())
+                .spawn(move || {
+                    if let Err(e) = serve_connection(logger.clone(), store_dir, stream) {
+                        error!(logger, "Remote store connection from {}: {:#}", peer, e);
+                    }
+                    connections.fetch_sub(1, Ordering::SeqCst);
+                });
+            if let Err(e) = spawned {
+                self.connections.fetch_sub(1, Ordering::SeqCst);
+                return Err(e).context("Failed to spawn remote store connection thread");
+            }
+        }
+        Ok(())
+    }
+}
+
+fn is_timeout(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<std::io::Error>().map_or(false, |e| {
+        matches!(
+            e.kind(),
+            std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+        )
+    })
+}
+
+fn serve_connection(logger: slog::Logger, store_dir: PathBuf, mut stream: TcpStream) -> Result<()> {
+    stream.set_nodelay(true)?;
+    stream.set_read_timeout(Some(SERVER_IDLE_TIMEOUT))?;
+    stream.set_write_timeout(Some(CLIENT_TIMEOUT))?;
+    // Each client gets its own cursor so concurrent replays don't move each
+    // other around
+    let mut cursor = StoreCursor::new(logger.clone(), store_dir);
+    loop {
+        let request = match read_message::<Request>(&mut stream) {
+            Ok(Some(request)) => request,
+            Ok(None) => break,
+            Err(e) if is_timeout(&e) => {
+                info!(logger, "Closing idle remote store connection");
+                break;
+            }
+            Err(e) => return Err(e),
+        };
+        let response = if request.version != PROTOCOL_VERSION {
+            Response::Error(format!(
+                "Unsupported protocol version {}, expected {}",
+                request.version, PROTOCOL_VERSION
+            ))
+        } else {
+            match cursor.get_next(&request.timestamp, request.direction) {
+                Ok(frame) => Response::Frame(
+                    frame.map(|(ts, frame)| (common::util::get_unix_timestamp(ts), frame)),
+                ),
+                Err(e) => Response::Error(format!("{:#}", e)),
+            }
+        };
+        write_message(&mut stream, &response)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CompressionMode, Format, StoreWriter};
+    use slog::Drain;
+    use std::net::Ipv4Addr;
+    use tempdir::TempDir;
+
+    fn get_logger() -> slog::Logger {
+        let plain = slog_term::PlainSyncDecorator::new(std::io::stderr());
+        slog::Logger::root(slog_term::FullFormat::new(plain).build().fuse(), slog::o!())
+    }
+
+    #[test]
+    fn get_frame_over_loopback() {
+        let dir = TempDir::new("below_remote_store_test").expect("tempdir failed");
+        let ts = SystemTime::UNIX_EPOCH + Duration::from_secs(24 * 60 * 60);
+        {
+            let mut writer =
+                StoreWriter::new(get_logger(), &dir, CompressionMode::Zstd, Format::Cbor)
+                    .expect("Failed to create store");
+            let mut frame = DataFrame::default();
+            for i in 0..3 {
+                frame.sample.cgroup.memory_current = Some(i);
+                writer
+                    .put(ts + Duration::from_secs(i as u64 * 5), &frame)
+                    .expect("Failed to store data");
+            }
+        }
+
+        let server = RemoteStoreServer::bind(
+            get_logger(),
+            dir.path().to_path_buf(),
+            Ipv4Addr::LOCALHOST.into(),
+            0,
+        )
+        .expect("Failed to bind");
+        let port = server.local_addr().expect("No local address").port();
+        thread::spawn(move || server.run());
+
+        let mut store =
+            RemoteStore::new("127.0.0.1".to_owned(), Some(port)).expect("Failed to connect");
+        let base = common::util::get_unix_timestamp(ts);
+
+        let (frame_ts, frame) = store
+            .get_frame(base + 1, Direction::Forward)
+            .expect("Failed to get frame")
+            .expect("No frame found");
+        assert_eq!(frame_ts, ts + Duration::from_secs(5));
+        assert_eq!(frame.sample.cgroup.memory_current, Some(1));
+
+        // The same connection serves any number of requests
+        let (frame_ts, frame) = store
+            .get_frame(base + 9, Direction::Reverse)
+            .expect("Failed to get frame")
+            .expect("No frame found");
+        assert_eq!(frame_ts, ts + Duration::from_secs(5));
+        assert_eq!(frame.sample.cgroup.memory_current, Some(1));
+
+        assert!(store
+            .get_frame(base + 11, Direction::Forward)
+            .expect("Failed to get frame")
+            .is_none());
+
+        // Nobody listens on the port of a closed socket
+        let closed_port = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .and_then(|listener| listener.local_addr())
+            .expect("Failed to bind")
+            .port();
+        assert!(RemoteStore::new("127.0.0.1".to_owned(), Some(closed_port)).is_err());
     }
 }
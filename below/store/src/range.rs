@@ -0,0 +1,235 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::{Duration, SystemTime};
+
+use anyhow::Result;
+
+use crate::{Direction, Store};
+
+/// Iterator over the samples of a store within a time range, see
+/// `iter_range`.
+pub struct RangeIter<'a, S: Store + ?Sized> {
+    store: &'a mut S,
+    begin: SystemTime,
+    end: SystemTime,
+    step: usize,
+    direction: Direction,
+    /// Where to look for the next sample. None once the iterator is done.
+    next_timestamp: Option<SystemTime>,
+}
+
+/// Iterates over the samples of `store` with timestamps in `[begin, end]`,
+/// oldest first, yielding every `step`th of them. A `step` of 0 is treated
+/// as 1. Shard boundaries and corrupt entries are skipped over by the store.
+/// The iterator ends after the first error.
+pub fn iter_range<S: Store + ?Sized>(
+    store: &mut S,
+    begin: SystemTime,
+    end: SystemTime,
+    step: usize,
+) -> RangeIter<'_, S> {
+    RangeIter {
+        store,
+        begin,
+        end,
+        step: step.max(1),
+        direction: Direction::Forward,
+        next_timestamp: Some(begin),
+    }
+}
+
+impl<S: Store + ?Sized> RangeIter<'_, S> {
+    /// Iterates newest first instead, starting from `end`. Thinning then
+    /// keeps every `step`th sample counting from the newest one.
+    pub fn reversed(mut self) -> Self {
+        self.direction = Direction::Reverse;
+        self.next_timestamp = Some(self.end);
+        self
+    }
+
+    fn in_range(&self, timestamp: SystemTime) -> bool {
+        self.begin <= timestamp && timestamp <= self.end
+    }
+
+    /// Returns the next sample in range, skipping none
+    fn next_sample(&mut self) -> Result<Option<(SystemTime, S::SampleType)>> {
+        let timestamp = match self.next_timestamp.take() {
+            Some(timestamp) => timestamp,
+            None => return Ok(None),
+        };
+        let (sample_timestamp, sample) = match self
+            .store
+            .get_sample_at_timestamp(timestamp, self.direction)?
+        {
+            Some(found) if self.in_range(found.0) => found,
+            _ => return Ok(None),
+        };
+        // Timestamps have a resolution of one second
+        self.next_timestamp = match self.direction {
+            Direction::Forward => sample_timestamp.checked_add(Duration::from_secs(1)),
+            Direction::Reverse => sample_timestamp.checked_sub(Duration::from_secs(1)),
+        };
+        Ok(Some((sample_timestamp, sample)))
+    }
+}
+
+impl<S: Store + ?Sized> Iterator for RangeIter<'_, S> {
+    type Item = Result<(SystemTime, S::SampleType)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let sample = match self.next_sample() {
+            Ok(sample) => sample,
+            Err(e) => {
+                self.next_timestamp = None;
+                return Some(Err(e));
+            }
+        };
+        // Skip the samples in between
+        for _ in 1..self.step {
+            match self.next_sample() {
+                Ok(Some(_)) => {}
+                Ok(None) => break,
+                Err(e) => {
+                    self.next_timestamp = None;
+                    return Some(Err(e));
+                }
+            }
+        }
+        sample.map(Ok)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CompressionMode, DataFrame, Format, LocalStore, StoreWriter, SHARD_TIME};
+    use anyhow::bail;
+    use common::util::{get_system_time, get_unix_timestamp};
+    use slog::Drain;
+    use tempdir::TempDir;
+
+    /// Returns the sample closest to the timestamp in the given direction,
+    /// with the sample being its own timestamp
+    struct FakeStore {
+        samples: Vec<u64>,
+        fail_at: Option<u64>,
+    }
+
+    impl Store for FakeStore {
+        type SampleType = u64;
+
+        fn get_sample_at_timestamp(
+            &mut self,
+            timestamp: SystemTime,
+            direction: Direction,
+        ) -> Result<Option<(SystemTime, u64)>> {
+            let timestamp = get_unix_timestamp(timestamp);
+            if self.fail_at == Some(timestamp) {
+                bail!("error");
+            }
+            let sample = match direction {
+                Direction::Forward => self.samples.iter().find(|s| **s >= timestamp),
+                Direction::Reverse => self.samples.iter().rev().find(|s| **s <= timestamp),
+            };
+            Ok(sample.map(|s| (get_system_time(*s), *s)))
+        }
+    }
+
+    fn collect<S: Store<SampleType = u64>>(iter: RangeIter<S>) -> Vec<u64> {
+        iter.map(|item| item.expect("Failed to iterate").1)
+            .collect()
+    }
+
+    #[test]
+    fn iterate_range() {
+        let mut store = FakeStore {
+            samples: vec![3, 10, 20, 21, 50, 60],
+            fail_at: None,
+        };
+        let (begin, end) = (get_system_time(5), get_system_time(50));
+        assert_eq!(
+            collect(iter_range(&mut store, begin, end, 1)),
+            vec![10, 20, 21, 50]
+        );
+        assert_eq!(collect(iter_range(&mut store, begin, end, 2)), vec![10, 21]);
+        assert_eq!(
+            collect(iter_range(&mut store, begin, end, 1).reversed()),
+            vec![50, 21, 20, 10]
+        );
+        assert_eq!(
+            collect(iter_range(&mut store, begin, end, 3).reversed()),
+            vec![50, 10]
+        );
+        // Nothing in range
+        assert!(collect(iter_range(
+            &mut store,
+            get_system_time(61),
+            get_system_time(70),
+            1
+        ))
+        .is_empty());
+
+        // Errors end the iteration
+        store.fail_at = Some(21);
+        let items: Vec<_> = iter_range(&mut store, begin, end, 1).collect();
+        assert_eq!(items.len(), 3);
+        assert!(items[2].is_err());
+    }
+
+    #[test]
+    fn iterate_local_store_across_shards() {
+        let plain = slog_term::PlainSyncDecorator::new(std::io::stderr());
+        let logger =
+            slog::Logger::root(slog_term::FullFormat::new(plain).build().fuse(), slog::o!());
+        let dir = TempDir::new("below_range_test").expect("tempdir failed");
+        let first_ts = SHARD_TIME - 10;
+        let timestamps: Vec<u64> = (0..6).map(|i| first_ts + i * 5).collect();
+        {
+            let mut writer = StoreWriter::new_with_timestamp(
+                logger.clone(),
+                &dir,
+                get_system_time(first_ts),
+                CompressionMode::ZstdDictionary(crate::ChunkSizePo2(1)),
+                Format::Cbor,
+            )
+            .expect("Failed to create store");
+            let mut frame = DataFrame::default();
+            for ts in &timestamps {
+                frame.sample.cgroup.memory_current = Some(*ts as i64);
+                writer
+                    .put(get_system_time(*ts), &frame)
+                    .expect("Failed to store data");
+            }
+        }
+
+        let mut store = LocalStore::new(logger, dir.path().to_path_buf());
+        let read: Vec<_> = iter_range(
+            &mut store,
+            get_system_time(0),
+            get_system_time(SHARD_TIME * 2),
+            1,
+        )
+        .map(|item| {
+            let (ts, frame) = item.expect("Failed to iterate");
+            assert_eq!(
+                frame.sample.cgroup.memory_current,
+                Some(get_unix_timestamp(ts) as i64)
+            );
+            get_unix_timestamp(ts)
+        })
+        .collect();
+        assert_eq!(read, timestamps);
+    }
+}
@@ -0,0 +1,529 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Rewrites old shards with fewer, coarser samples.
+//!
+//! The samples of a shard are grouped into windows of `resolution` and
+//! each window is replaced by a single sample stamped with the time of the
+//! last sample in it. How each field is combined is defined by
+//! `model::rollup`. A rolled up shard is just a shard with fewer samples,
+//! so readers don't need to know about it.
+//!
+//! The new shard is written to a temporary directory next to the store and
+//! moved over the original once complete. Data files are moved before
+//! index files. A reader opening the shard in between sees entries whose
+//! data crc doesn't match and skips them, like any other corruption.
+//! Temporary directories left behind by a crash are either finished or
+//! removed by the next pass. Installing holds the store's shard lock, and a
+//! shard that cleanup discarded in the meantime is not brought back.
+
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use anyhow::{anyhow, Context, Result};
+use serde_cbor::Value;
+use slog::{info, warn};
+
+use common::util::get_unix_timestamp;
+use model::rollup::{get_rollup, has_rollup_below, Rollup};
+
+use crate::cursor::{Cursor, KeyedCursor, StoreCursor};
+use crate::{
+    get_shard_ranges, lock_shards, parse_index_entries, CompressionMode, DataFrame, Direction,
+    Format, StoreWriter,
+};
+
+const TMP_DIR_PREFIX: &str = ".rollup_";
+/// Created in the temporary directory once the new shard is fully written
+const COMPLETE_MARKER: &str = "complete";
+
+fn get_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Integer(i) => Some(*i as f64),
+        Value::Float(f) => Some(*f),
+        _ => None,
+    }
+}
+
+/// Stores `number` in `acc`, keeping integers integers
+fn set_number(acc: &mut Value, number: f64) {
+    match acc {
+        Value::Integer(i) => *i = number.round() as i128,
+        Value::Float(f) => *f = number,
+        _ => {}
+    }
+}
+
+fn get_path_element(key: &Value) -> String {
+    match key {
+        Value::Text(s) => s.clone(),
+        Value::Integer(i) => i.to_string(),
+        _ => "?".to_owned(),
+    }
+}
+
+/// Merges the values of the field at `path` into `acc`, which starts out
+/// as the value of the last sample. `others` has the value of every sample
+/// of the window, oldest first, and None for samples without the field.
+fn merge_value(acc: &mut Value, others: &[Option<&Value>], path: &mut Vec<String>) {
+    match get_rollup(path) {
+        Rollup::Avg => {
+            if get_number(acc).is_some() {
+                let numbers: Vec<f64> = others
+                    .iter()
+                    .flatten()
+                    .filter_map(|v| get_number(v))
+                    .collect();
+                set_number(acc, numbers.iter().sum::<f64>() / numbers.len() as f64);
+            }
+            return;
+        }
+        Rollup::Max => {
+            if get_number(acc).is_some() {
+                let max = others
+                    .iter()
+                    .flatten()
+                    .filter_map(|v| get_number(v))
+                    .fold(f64::MIN, f64::max);
+                set_number(acc, max);
+            }
+            return;
+        }
+        Rollup::Concat => {
+            let mut items = None;
+            for other in others.iter().flatten() {
+                if let Value::Array(array) = other {
+                    items
+                        .get_or_insert_with(Vec::new)
+                        .extend(array.iter().cloned());
+                }
+            }
+            if let Some(items) = items {
+                *acc = Value::Array(items);
+            }
+            return;
+        }
+        Rollup::Union => {
+            if let Value::Map(map) = acc {
+                // Newest first so that the latest version of an entry wins
+                for other in others.iter().rev().flatten() {
+                    if let Value::Map(other) = other {
+                        for (key, value) in other {
+                            if !map.contains_key(key) {
+                                map.insert(key.clone(), value.clone());
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Rollup::Last => {}
+    }
+
+    if !has_rollup_below(path) {
+        return;
+    }
+    match acc {
+        Value::Map(map) => {
+            for (key, value) in map.iter_mut() {
+                let children: Vec<_> = others
+                    .iter()
+                    .map(|other| match other {
+                        Some(Value::Map(other)) => other.get(key),
+                        _ => None,
+                    })
+                    .collect();
+                path.push(get_path_element(key));
+                merge_value(value, &children, path);
+                path.pop();
+            }
+        }
+        Value::Array(array) => {
+            for (i, value) in array.iter_mut().enumerate() {
+                let children: Vec<_> = others
+                    .iter()
+                    .map(|other| match other {
+                        Some(Value::Array(other)) => other.get(i),
+                        _ => None,
+                    })
+                    .collect();
+                path.push(i.to_string());
+                merge_value(value, &children, path);
+                path.pop();
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Combines consecutive `frames`, oldest first, into one
+pub fn rollup_frames(frames: &[DataFrame]) -> Result<DataFrame> {
+    let values = frames
+        .iter()
+        .map(|frame| serde_cbor::value::to_value(&frame.sample))
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to convert sample")?;
+    let mut acc = values
+        .last()
+        .ok_or_else(|| anyhow!("No frames to roll up"))?
+        .clone();
+    let others: Vec<_> = values.iter().map(Some).collect();
+    merge_value(&mut acc, &others, &mut Vec::new());
+    Ok(DataFrame {
+        sample: serde_cbor::value::from_value(acc).context("Failed to convert rolled up sample")?,
+    })
+}
+
+fn get_tmp_dir(dir: &Path, shard: u64) -> PathBuf {
+    dir.join(format!("{}{:011}", TMP_DIR_PREFIX, shard))
+}
+
+/// Moves the files of a completely written rollup of `shard` into the store
+/// and removes its temporary directory. The rollup is dropped if the shard
+/// was discarded since.
+fn install_shard(dir: &Path, tmp_dir: &Path, shard: u64) -> Result<()> {
+    let _lock = lock_shards(dir)?;
+    // The original index is only replaced by the last move, so it's there
+    // until the install is done, even across a crash
+    if dir.join(format!("index_{:011}", shard)).exists() {
+        // The index goes last as it refers to the others
+        for prefix in &["dict", "data", "index"] {
+            let name = format!("{}_{:011}", prefix, shard);
+            match fs::rename(tmp_dir.join(&name), dir.join(&name)) {
+                // Already moved before a crash
+                Err(e) if e.kind() == ErrorKind::NotFound => {}
+                res => res.with_context(|| format!("Failed to move {}", name))?,
+            }
+        }
+    }
+    fs::remove_dir_all(tmp_dir).with_context(|| format!("Failed to remove {}", tmp_dir.display()))
+}
+
+/// Finishes or discards rollups interrupted by a crash
+fn recover(logger: &slog::Logger, dir: &Path) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let shard = match name
+            .to_str()
+            .and_then(|name| name.strip_prefix(TMP_DIR_PREFIX))
+            .map(str::parse::<u64>)
+        {
+            Some(Ok(shard)) => shard,
+            _ => continue,
+        };
+        let tmp_dir = entry.path();
+        if tmp_dir.join(COMPLETE_MARKER).exists() {
+            info!(logger, "Finishing interrupted rollup of shard {}", shard);
+            install_shard(dir, &tmp_dir, shard)?;
+        } else {
+            warn!(logger, "Discarding incomplete rollup of shard {}", shard);
+            fs::remove_dir_all(&tmp_dir)
+                .with_context(|| format!("Failed to remove {}", tmp_dir.display()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns whether any two samples of `shard` fall in the same window.
+/// Only reads the index, so shards that are already rolled up are cheap to
+/// skip.
+fn needs_rollup(dir: &Path, shard: u64, resolution_secs: u64) -> Result<bool> {
+    let index_path = dir.join(format!("index_{:011}", shard));
+    let index = match fs::read(&index_path) {
+        Ok(index) => index,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(false),
+        Err(e) => {
+            return Err(e).context(format!("Failed to read {}", index_path.display()));
+        }
+    };
+    let mut windows: Vec<u64> = parse_index_entries(&index)
+        .flatten()
+        .map(|entry| entry.timestamp / resolution_secs)
+        .collect();
+    let len = windows.len();
+    windows.sort_unstable();
+    windows.dedup();
+    Ok(windows.len() != len)
+}
+
+/// Rolls up `shard` of the store in `dir`, which ends at `shard_end`, into
+/// samples at most one per `resolution_secs`. Returns whether the shard was
+/// rewritten.
+fn rollup_shard(
+    logger: &slog::Logger,
+    dir: &Path,
+    shard: u64,
+    shard_end: u64,
+    resolution_secs: u64,
+    compression_mode: CompressionMode,
+    format: Format,
+) -> Result<bool> {
+    if !needs_rollup(dir, shard, resolution_secs)? {
+        return Ok(false);
+    }
+
+    let tmp_dir = get_tmp_dir(dir, shard);
+    let mut writer =
+        StoreWriter::new_with_shard(logger.clone(), &tmp_dir, shard, compression_mode, format)?;
+    let mut cursor = StoreCursor::new(logger.clone(), dir.to_path_buf());
+    let mut window: Vec<DataFrame> = Vec::new();
+    let mut window_ts = None;
+    let mut nr_frames = 0;
+    let mut item = cursor.get_next(&shard, Direction::Forward)?;
+    loop {
+        let (ts, frame) = match item {
+            Some((ts, frame)) if get_unix_timestamp(ts) < shard_end => (ts, frame),
+            _ => break,
+        };
+        let secs = get_unix_timestamp(ts);
+        if let Some(last_ts) = window_ts {
+            if get_unix_timestamp(last_ts) / resolution_secs != secs / resolution_secs {
+                writer.put(last_ts, &rollup_frames(&window)?)?;
+                window.clear();
+            }
+        }
+        window.push(frame);
+        window_ts = Some(ts);
+        nr_frames += 1;
+        item = cursor.next(Direction::Forward)?;
+    }
+    if let Some(last_ts) = window_ts {
+        writer.put(last_ts, &rollup_frames(&window)?)?;
+    }
+    writer.data.sync_all()?;
+    writer.index.sync_all()?;
+    drop(writer);
+
+    fs::File::create(tmp_dir.join(COMPLETE_MARKER))?.sync_all()?;
+    install_shard(dir, &tmp_dir, shard)?;
+    info!(
+        logger,
+        "Rolled up {} samples of shard {} to {}s resolution", nr_frames, shard, resolution_secs
+    );
+    Ok(true)
+}
+
+/// Rolls up all shards of the store in `dir` that only contain data earlier
+/// than `timestamp` to samples at most one per `resolution`. The shard being
+/// written to is never touched. Shards that are already rolled up are
+/// skipped, so this is cheap to call repeatedly.
+pub fn rollup_earlier(
+    logger: &slog::Logger,
+    dir: &Path,
+    timestamp: SystemTime,
+    resolution: Duration,
+    compression_mode: CompressionMode,
+    format: Format,
+) -> Result<()> {
+    let resolution_secs = resolution.as_secs();
+    if resolution_secs == 0 {
+        return Ok(());
+    }
+    recover(logger, dir)?;
+    let until = get_unix_timestamp(std::cmp::min(timestamp, SystemTime::now()));
+    for (shard, shard_end) in get_shard_ranges(dir)? {
+        // Shards are sorted, and the newest one is still being written to
+        let shard_end = match shard_end {
+            Some(shard_end) if shard_end <= until => shard_end,
+            _ => break,
+        };
+        if let Err(e) = rollup_shard(
+            logger,
+            dir,
+            shard,
+            shard_end,
+            resolution_secs,
+            compression_mode,
+            format,
+        ) {
+            // Leave the original shard in place and move on
+            warn!(logger, "Failed to roll up shard {}: {:#}", shard, e);
+            let tmp_dir = get_tmp_dir(dir, shard);
+            if tmp_dir.exists() && !tmp_dir.join(COMPLETE_MARKER).exists() {
+                fs::remove_dir_all(&tmp_dir)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{get_store_info, SHARD_TIME};
+    use slog::Drain;
+    use tempdir::TempDir;
+
+    fn get_logger() -> slog::Logger {
+        let plain = slog_term::PlainSyncDecorator::new(std::io::stderr());
+        slog::Logger::root(slog_term::FullFormat::new(plain).build().fuse(), slog::o!())
+    }
+
+    fn get_frame(i: u64) -> DataFrame {
+        let mut frame = DataFrame::default();
+        frame.sample.cgroup.memory_current = Some(i as i64 * 10);
+        frame.sample.cgroup.cpu_stat = Some(cgroupfs::CpuStat {
+            usage_usec: Some(i as i64 * 1000),
+            ..Default::default()
+        });
+        frame.sample.execs = Some(vec![procfs::ExecEvent {
+            pid: Some(i as i32),
+            ..Default::default()
+        }]);
+        frame
+    }
+
+    fn read_all(dir: &Path, from: u64) -> Vec<(u64, DataFrame)> {
+        let mut cursor = StoreCursor::new(get_logger(), dir.to_path_buf());
+        let mut frames = Vec::new();
+        let mut item = cursor
+            .get_next(&from, Direction::Forward)
+            .expect("Failed to read");
+        while let Some((ts, frame)) = item {
+            frames.push((get_unix_timestamp(ts), frame));
+            item = cursor.next(Direction::Forward).expect("Failed to read");
+        }
+        frames
+    }
+
+    #[test]
+    fn rollup_old_shards() {
+        let dir = TempDir::new("below_rollup_test").expect("tempdir failed");
+        let shard = SHARD_TIME;
+        let ts = SystemTime::UNIX_EPOCH + Duration::from_secs(shard);
+        {
+            let mut writer = StoreWriter::new_with_timestamp(
+                get_logger(),
+                &dir,
+                ts,
+                CompressionMode::ZstdDictionary(crate::ChunkSizePo2(2)),
+                Format::Cbor,
+            )
+            .expect("Failed to create store");
+            // 2 minutes of 5s samples in an old shard and one in the next
+            for i in 0..24 {
+                writer
+                    .put(ts + Duration::from_secs(i * 5), &get_frame(i))
+                    .expect("Failed to store data");
+            }
+            writer
+                .put(ts + Duration::from_secs(SHARD_TIME), &get_frame(100))
+                .expect("Failed to store data");
+        }
+
+        let rollup = || {
+            rollup_earlier(
+                &get_logger(),
+                dir.path(),
+                ts + Duration::from_secs(SHARD_TIME),
+                Duration::from_secs(60),
+                CompressionMode::Zstd,
+                Format::Cbor,
+            )
+            .expect("Failed to roll up");
+        };
+        rollup();
+
+        let frames = read_all(dir.path(), shard);
+        // Both windows of the old shard and the untouched newer shard
+        let timestamps: Vec<_> = frames.iter().map(|(ts, _)| *ts - shard).collect();
+        assert_eq!(timestamps, vec![55, 115, SHARD_TIME]);
+        let (_, first) = &frames[0];
+        // Gauges are averaged over samples 0 to 11
+        assert_eq!(first.sample.cgroup.memory_current, Some(55));
+        // Counters keep the last value
+        assert_eq!(
+            first.sample.cgroup.cpu_stat.as_ref().unwrap().usage_usec,
+            Some(11000)
+        );
+        // No event is lost
+        let pids: Vec<_> = first
+            .sample
+            .execs
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|exec| exec.pid.unwrap())
+            .collect();
+        assert_eq!(pids, (0..12).collect::<Vec<_>>());
+        assert_eq!(frames[2].1, get_frame(100));
+
+        // Rolled up shards are left alone
+        let info = get_store_info(dir.path()).expect("Failed to get store info");
+        rollup();
+        assert_eq!(
+            get_store_info(dir.path()).expect("Failed to get store info"),
+            info
+        );
+        assert!(!get_tmp_dir(dir.path(), shard).exists());
+    }
+
+    /// Writes a shard holding just `frame` to the store in `dir`
+    fn write_shard(dir: &Path, shard: u64, frame: &DataFrame) {
+        let mut writer = StoreWriter::new_with_shard(
+            get_logger(),
+            dir,
+            shard,
+            CompressionMode::None,
+            Format::Cbor,
+        )
+        .expect("Failed to create store");
+        writer
+            .put(SystemTime::UNIX_EPOCH + Duration::from_secs(shard), frame)
+            .expect("Failed to store data");
+    }
+
+    #[test]
+    fn recover_interrupted_rollup() {
+        let dir = TempDir::new("below_rollup_test").expect("tempdir failed");
+        let shard = SHARD_TIME;
+        write_shard(dir.path(), shard, &get_frame(0));
+        for complete in &[false, true] {
+            let tmp_dir = get_tmp_dir(dir.path(), shard);
+            write_shard(&tmp_dir, shard, &get_frame(1 + *complete as u64));
+            if *complete {
+                fs::File::create(tmp_dir.join(COMPLETE_MARKER)).expect("Failed to mark");
+            }
+            recover(&get_logger(), dir.path()).expect("Failed to recover");
+            assert!(!tmp_dir.exists());
+        }
+        // Only the completed rollup replaced the original shard
+        let frames = read_all(dir.path(), shard);
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].1, get_frame(2));
+    }
+
+    #[test]
+    fn discarded_shard_stays_discarded() {
+        let dir = TempDir::new("below_rollup_test").expect("tempdir failed");
+        let shard = SHARD_TIME;
+        // Cleanup removed the shard while it was being rolled up
+        let tmp_dir = get_tmp_dir(dir.path(), shard);
+        write_shard(&tmp_dir, shard, &get_frame(1));
+        fs::File::create(tmp_dir.join(COMPLETE_MARKER)).expect("Failed to mark");
+        install_shard(dir.path(), &tmp_dir, shard).expect("Failed to install");
+
+        assert!(!tmp_dir.exists());
+        // Neither the index nor orphaned data or dict files show up
+        let names: Vec<_> = fs::read_dir(dir.path())
+            .expect("Failed to read dir")
+            .map(|entry| entry.expect("Failed to read entry").file_name())
+            .filter(|name| !name.to_string_lossy().starts_with("shard_"))
+            .collect();
+        assert!(names.is_empty(), "Unexpected files {:?}", names);
+    }
+}
@@ -0,0 +1,197 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Schema versions of stored `DataFrame`s.
+//!
+//! Every index entry records the schema version its frame was written
+//! with. Adding an `Option` field to the model doesn't need a new version
+//! since serde fills it with `None` for older frames and ignores unknown
+//! fields in newer ones. Renaming, moving or retyping a field does: bump
+//! `CURRENT_SCHEMA_VERSION` and append an upgrade to `UPGRADES` that
+//! rewrites a frame of the previous version into the new shape.
+//!
+//! MessagePack frames are written with field names as well, and go through
+//! the same upgrades.
+
+use anyhow::{Context, Result};
+use serde_cbor::Value;
+use static_assertions::const_assert;
+
+use crate::DataFrame;
+
+/// Schema version written with new frames. Frames written before versioning
+/// was introduced have version 0.
+pub const CURRENT_SCHEMA_VERSION: u32 = 1;
+
+/// Largest version that fits in `IndexEntryFlags::SCHEMA_VERSION`
+pub const MAX_SCHEMA_VERSION: u32 = 0xFF;
+const_assert!(CURRENT_SCHEMA_VERSION <= MAX_SCHEMA_VERSION);
+
+/// Rewrites a frame of version `n` in place so it deserializes as version
+/// `n + 1`
+type Upgrade = fn(&mut Value) -> Result<()>;
+
+/// `UPGRADES[n]` upgrades a frame from version `n` to `n + 1`. None if the
+/// shape of frames didn't change between the two.
+const UPGRADES: &[Option<Upgrade>] = &[
+    // 0 -> 1: Versioning was introduced
+    None,
+];
+const_assert!(UPGRADES.len() == CURRENT_SCHEMA_VERSION as usize);
+
+/// Deserializes a CBOR frame written with schema `version` into the current
+/// `DataFrame`.
+pub(crate) fn deserialize_cbor_frame(bytes: &[u8], version: u32) -> Result<DataFrame> {
+    deserialize_cbor_frame_with(bytes, version, UPGRADES)
+}
+
+/// Deserializes a MessagePack frame written with schema `version` into the
+/// current `DataFrame`.
+pub(crate) fn deserialize_msgpack_frame(bytes: &[u8], version: u32) -> Result<DataFrame> {
+    deserialize_msgpack_frame_with(bytes, version, UPGRADES)
+}
+
+/// Upgrades still to apply to a frame of `version`. Frames from newer
+/// versions are read on a best effort basis, e.g. when a store is copied to
+/// a host running an older below.
+fn get_pending_upgrades(version: u32, upgrades: &[Option<Upgrade>]) -> &[Option<Upgrade>] {
+    upgrades.get(version as usize..).unwrap_or_default()
+}
+
+fn upgrade_value(mut value: Value, version: u32, pending: &[Option<Upgrade>]) -> Result<DataFrame> {
+    for (from, upgrade) in pending.iter().enumerate() {
+        if let Some(upgrade) = upgrade {
+            upgrade(&mut value).with_context(|| {
+                format!(
+                    "Failed to upgrade frame from schema version {}",
+                    version as usize + from
+                )
+            })?;
+        }
+    }
+    Ok(serde_cbor::value::from_value(value)?)
+}
+
+fn deserialize_cbor_frame_with(
+    bytes: &[u8],
+    version: u32,
+    upgrades: &[Option<Upgrade>],
+) -> Result<DataFrame> {
+    let pending = get_pending_upgrades(version, upgrades);
+    // Skip the intermediate representation if nothing would change it
+    if pending.iter().all(Option::is_none) {
+        return Ok(serde_cbor::from_slice(bytes)?);
+    }
+    upgrade_value(serde_cbor::from_slice(bytes)?, version, pending)
+}
+
+fn deserialize_msgpack_frame_with(
+    bytes: &[u8],
+    version: u32,
+    upgrades: &[Option<Upgrade>],
+) -> Result<DataFrame> {
+    let pending = get_pending_upgrades(version, upgrades);
+    if pending.iter().all(Option::is_none) {
+        return Ok(rmp_serde::from_slice(bytes)?);
+    }
+    // Upgrades work on CBOR values, which MessagePack maps onto directly
+    upgrade_value(rmp_serde::from_slice(bytes)?, version, pending)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+    use std::collections::BTreeMap;
+
+    fn get_cgroup_map(value: &mut Value) -> Result<&mut BTreeMap<Value, Value>> {
+        let mut curr = value;
+        for key in &["sample", "cgroup"] {
+            curr = match curr {
+                Value::Map(map) => map
+                    .get_mut(&Value::Text(key.to_string()))
+                    .ok_or_else(|| anyhow!("Missing {}", key))?,
+                _ => return Err(anyhow!("Expected a map")),
+            };
+        }
+        match curr {
+            Value::Map(map) => Ok(map),
+            _ => Err(anyhow!("Expected a map")),
+        }
+    }
+
+    fn rename(value: &mut Value, from: &str, to: &str) -> Result<()> {
+        let cgroup = get_cgroup_map(value)?;
+        if let Some(field) = cgroup.remove(&Value::Text(from.to_owned())) {
+            cgroup.insert(Value::Text(to.to_owned()), field);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn upgrade_chain() {
+        let mut frame = DataFrame::default();
+        frame.sample.cgroup.memory_current = Some(42);
+        let current_bytes = serde_cbor::to_vec(&frame).unwrap();
+        // Pretend that memory_current used to be called memory_usage
+        let mut value = serde_cbor::value::to_value(&frame).unwrap();
+        rename(&mut value, "memory_current", "memory_usage").unwrap();
+        let old_bytes = serde_cbor::to_vec(&value).unwrap();
+
+        let upgrades: &[Option<Upgrade>] = &[
+            None,
+            Some(|value| rename(value, "memory_usage", "memory_current")),
+        ];
+
+        // Frames from before the rename are upgraded
+        for version in 0..2 {
+            let upgraded = deserialize_cbor_frame_with(&old_bytes, version, upgrades)
+                .expect("Failed to upgrade frame");
+            assert_eq!(upgraded, frame);
+        }
+        // Current frames are read as is
+        let current = deserialize_cbor_frame_with(&current_bytes, 2, upgrades)
+            .expect("Failed to read current frame");
+        assert_eq!(current, frame);
+        // Frames from the future are read best effort
+        let future = deserialize_cbor_frame_with(&current_bytes, 3, upgrades)
+            .expect("Failed to read future frame");
+        assert_eq!(future, frame);
+
+        // MessagePack frames go through the same upgrades
+        let old_bytes = rmp_serde::to_vec_named(&value).unwrap();
+        let upgraded = deserialize_msgpack_frame_with(&old_bytes, 1, upgrades)
+            .expect("Failed to upgrade frame");
+        assert_eq!(upgraded, frame);
+    }
+
+    #[test]
+    fn msgpack_model_changes() {
+        let mut frame = DataFrame::default();
+        frame.sample.cgroup.memory_current = Some(42);
+        frame.sample.cgroup.memory_high = Some(7);
+        // Pretend that the frame was written by a below whose model had one
+        // field more and one field less
+        let mut value = serde_cbor::value::to_value(&frame).unwrap();
+        let cgroup = get_cgroup_map(&mut value).unwrap();
+        cgroup.insert(Value::Text("memory_future".to_owned()), Value::Integer(1));
+        cgroup.remove(&Value::Text("memory_high".to_owned()));
+        let bytes = rmp_serde::to_vec_named(&value).unwrap();
+
+        let read = deserialize_msgpack_frame(&bytes, CURRENT_SCHEMA_VERSION)
+            .expect("Failed to read frame");
+        frame.sample.cgroup.memory_high = None;
+        assert_eq!(read, frame);
+    }
+}
@@ -200,7 +200,8 @@ pub mod default_tabs {
         NrPeriodsPerSec, NrThrottledPerSec, SystemPct, ThrottledPct, UsagePct, UserPct,
     };
     use model::CgroupIoModelFieldId::{
-        DbytesPerSec, DiosPerSec, RbytesPerSec, RiosPerSec, RwbytesPerSec, WbytesPerSec, WiosPerSec,
+        DbytesPerSec, DiosPerSec, RbytesPerSec, ReadLatencyP50Us, ReadLatencyP99Us, RiosPerSec,
+        RwbytesPerSec, WbytesPerSec, WiosPerSec, WriteLatencyP50Us, WriteLatencyP99Us,
     };
     use model::CgroupMemoryModelFieldId::{
         ActiveAnon, ActiveFile, Anon, AnonThp, EventsHigh, EventsLow, EventsMax, EventsOom,
@@ -245,6 +246,9 @@ pub mod default_tabs {
             ViewItem::from_default(Cpu(NrPeriodsPerSec)),
             ViewItem::from_default(Cpu(NrThrottledPerSec)),
             ViewItem::from_default(Cpu(ThrottledPct)),
+            ViewItem::from_default(Cpu(RunDelayP50Us)),
+            ViewItem::from_default(Cpu(RunDelayP90Us)),
+            ViewItem::from_default(Cpu(RunDelayP99Us)),
         ])
     });
 
@@ -300,6 +304,10 @@ pub mod default_tabs {
             ViewItem::from_default(Io(WiosPerSec)),
             ViewItem::from_default(Io(DiosPerSec)),
             ViewItem::from_default(Io(RwbytesPerSec)),
+            ViewItem::from_default(Io(ReadLatencyP50Us)),
+            ViewItem::from_default(Io(ReadLatencyP99Us)),
+            ViewItem::from_default(Io(WriteLatencyP50Us)),
+            ViewItem::from_default(Io(WriteLatencyP99Us)),
         ])
     });
 
@@ -27,6 +27,7 @@ use crate::cgroup_tabs::{
     },
     CgroupTab,
 };
+use crate::run_delay_popup;
 use crate::stats_view::{StateCommon, StatsView, ViewBridge};
 use crate::ViewState;
 use model::{
@@ -272,6 +273,15 @@ impl CgroupView {
             view.state.borrow_mut().set_reverse(true);
             view.refresh(c)
         })
+        .on_event('R', |c| {
+            let mut view = Self::get_cgroup_view(c);
+            let selection = view
+                .get_detail_view()
+                .selection()
+                .map_or(String::new(), |selection| selection.to_string());
+            let popup = run_delay_popup::new(&view.state.borrow().get_model(), &selection);
+            c.add_layer(popup);
+        })
         .with_name(Self::get_view_name())
     }
 
@@ -18,14 +18,18 @@ use crate::stats_view::StateCommon;
 use base_render::{get_fixed_width, RenderConfigBuilder as Rc};
 use common::util::get_prefix;
 use model::system::{
-    MemoryModelFieldId, SingleCpuModelFieldId, SingleDiskModelFieldId, VmModelFieldId,
+    MemoryModelFieldId, SingleCifsModelFieldId, SingleCpuModelFieldId, SingleDiskModelFieldId,
+    SingleFsModelFieldId, SingleNfsModelFieldId, SingleSwapDeviceModelFieldId, SwapModel,
+    SwapModelFieldId, VmCounterModelFieldId, VmModelFieldId, ZramModelFieldId, ZswapModelFieldId,
 };
-use model::EnumIter;
+use model::{BTreeMapFieldId, EnumIter, VecFieldId};
 
 use cursive::utils::markup::StyledString;
 
 const FIELD_NAME_WIDTH: usize = 20;
 const FIELD_WIDTH: usize = 20;
+/// Fits the longest /proc/vmstat counter names
+const VM_FIELD_NAME_WIDTH: usize = 32;
 
 pub trait CoreTab {
     fn get_title_vec(&self) -> Vec<String> {
@@ -90,10 +94,55 @@ impl CoreTab for CoreCpu {
 #[derive(Default, Clone)]
 pub struct CoreMem;
 
+impl CoreMem {
+    /// Swap totals followed by the fields of each swap device, zram device
+    /// and zswap if it's in use.
+    fn swap_field_ids(swap: &SwapModel) -> Vec<SwapModelFieldId> {
+        let mut field_ids: Vec<_> = SwapModelFieldId::unit_variant_iter().collect();
+        for idx in 0..swap.devices.len() {
+            field_ids.extend(SingleSwapDeviceModelFieldId::unit_variant_iter().map(
+                |subquery_id| {
+                    SwapModelFieldId::Devices(VecFieldId {
+                        idx: Some(idx),
+                        subquery_id,
+                    })
+                },
+            ));
+        }
+        for key in swap.zram.keys() {
+            field_ids.extend(ZramModelFieldId::unit_variant_iter().map(|subquery_id| {
+                SwapModelFieldId::Zram(BTreeMapFieldId {
+                    key: Some(key.clone()),
+                    subquery_id,
+                })
+            }));
+        }
+        if swap.zswap != Default::default() {
+            field_ids.extend(ZswapModelFieldId::unit_variant_iter().map(SwapModelFieldId::Zswap));
+        }
+        field_ids
+    }
+}
+
 impl CoreTab for CoreMem {
     fn get_rows(&self, state: &CoreState, _offset: Option<usize>) -> Vec<(StyledString, String)> {
         let model = state.get_model();
 
+        let swap_rows = Self::swap_field_ids(&model.swap)
+            .into_iter()
+            .map(|field_id| {
+                let mut line = StyledString::new();
+                let item =
+                    ViewItem::from_default(field_id).update(Rc::new().width(FIELD_NAME_WIDTH));
+                line.append_plain(item.config.render_title());
+                line.append_plain(" ");
+                line.append(
+                    item.update(Rc::new().width(FIELD_WIDTH))
+                        .render(&model.swap),
+                );
+                line
+            });
+
         MemoryModelFieldId::unit_variant_iter()
             .map(|field_id| {
                 let mut line = StyledString::new();
@@ -104,6 +153,7 @@ impl CoreTab for CoreMem {
                 line.append(item.update(Rc::new().width(FIELD_WIDTH)).render(&model.mem));
                 line
             })
+            .chain(swap_rows)
             .filter(|s| {
                 if let Some(f) = &state.filter {
                     s.source().contains(f)
@@ -120,19 +170,48 @@ impl CoreTab for CoreMem {
 pub struct CoreVm;
 
 impl CoreTab for CoreVm {
+    fn get_title_vec(&self) -> Vec<String> {
+        vec![
+            get_fixed_width("Field", VM_FIELD_NAME_WIDTH),
+            get_fixed_width("Value", FIELD_WIDTH),
+            get_fixed_width("Rate", FIELD_WIDTH),
+        ]
+    }
+
     fn get_rows(&self, state: &CoreState, _offset: Option<usize>) -> Vec<(StyledString, String)> {
         let model = state.get_model();
 
+        // All /proc/vmstat entries, titled by their name so any of them can
+        // be found with the filter. Gauges such as nr_free_pages only make
+        // sense as a value and event counters as a rate, so show both.
+        let counter_rows = model.vm.counters.keys().map(|name| {
+            let mut line = StyledString::new();
+            line.append_plain(get_fixed_width(name, VM_FIELD_NAME_WIDTH));
+            for subquery_id in [VmCounterModelFieldId::Value, VmCounterModelFieldId::PerSec] {
+                let item = ViewItem::from_default(VmModelFieldId::Counters(BTreeMapFieldId {
+                    key: Some(name.clone()),
+                    subquery_id,
+                }));
+                line.append_plain(" ");
+                line.append(item.update(Rc::new().width(FIELD_WIDTH)).render(&model.vm));
+            }
+            line
+        });
+
+        // The fixed fields are all rates
         VmModelFieldId::unit_variant_iter()
             .map(|field_id| {
                 let mut line = StyledString::new();
                 let item =
-                    ViewItem::from_default(field_id).update(Rc::new().width(FIELD_NAME_WIDTH));
+                    ViewItem::from_default(field_id).update(Rc::new().width(VM_FIELD_NAME_WIDTH));
                 line.append_plain(item.config.render_title());
                 line.append_plain(" ");
+                line.append_plain(get_fixed_width("", FIELD_WIDTH));
+                line.append_plain(" ");
                 line.append(item.update(Rc::new().width(FIELD_WIDTH)).render(&model.vm));
                 line
             })
+            .chain(counter_rows)
             .filter(|s| {
                 if let Some(f) = &state.filter {
                     s.source().contains(f)
@@ -156,20 +235,19 @@ impl CoreTab for CoreDisk {
     }
 
     fn get_rows(&self, state: &CoreState, offset: Option<usize>) -> Vec<(StyledString, String)> {
-        state
-            .get_model()
-            .disks
-            .iter()
-            .filter_map(|(dn, sdm)| {
-                // We use the partition parent id to check if it exists in collapsed_disk set.
-                let idx_major = format!("{}.0", sdm.major.unwrap_or(0));
-                let idx = format!("{}.{}", sdm.major.unwrap_or(0), sdm.minor.unwrap_or(0));
-                let collapse = state.collapsed_disk.contains(&idx_major) && sdm.minor != Some(0);
-                if state
-                    .filter
+        let model = state.get_model();
+        model
+            .disks_tree()
+            .into_iter()
+            .filter_map(|sdm| {
+                let dn = sdm.name.clone().unwrap_or_default();
+                // Partitions are hidden when their whole disk is in the collapsed_disk set.
+                let hidden = sdm
+                    .parent
                     .as_ref()
-                    .map_or(!collapse, |f| dn.starts_with(f))
-                {
+                    .map_or(false, |parent| state.collapsed_disk.contains(parent));
+                let collapse = sdm.parent.is_none() && state.collapsed_disk.contains(&dn);
+                if state.filter.as_ref().map_or(!hidden, |f| dn.starts_with(f)) {
                     Some((
                         std::iter::once(SingleDiskModelFieldId::Name)
                             .chain(
@@ -189,7 +267,7 @@ impl CoreTab for CoreDisk {
                                 line.append_plain(" ");
                                 line
                             }),
-                        idx,
+                        dn,
                     ))
                 } else {
                     None
@@ -198,3 +276,117 @@ impl CoreTab for CoreDisk {
             .collect()
     }
 }
+
+#[derive(Default, Clone)]
+pub struct CoreFs;
+
+impl CoreTab for CoreFs {
+    fn get_title_vec(&self) -> Vec<String> {
+        SingleFsModelFieldId::unit_variant_iter()
+            .map(|field_id| ViewItem::from_default(field_id).config.render_title())
+            .collect()
+    }
+
+    fn get_rows(&self, state: &CoreState, offset: Option<usize>) -> Vec<(StyledString, String)> {
+        state
+            .get_model()
+            .filesystems
+            .iter()
+            .filter(|(mount_point, _)| {
+                state
+                    .filter
+                    .as_ref()
+                    .map_or(true, |f| mount_point.starts_with(f))
+            })
+            .map(|(mount_point, sfm)| {
+                (
+                    std::iter::once(SingleFsModelFieldId::MountPoint)
+                        .chain(
+                            SingleFsModelFieldId::unit_variant_iter().skip(offset.unwrap_or(0) + 1),
+                        )
+                        .fold(StyledString::new(), |mut line, field_id| {
+                            line.append(ViewItem::from_default(field_id).render(sfm));
+                            line.append_plain(" ");
+                            line
+                        }),
+                    mount_point.clone(),
+                )
+            })
+            .collect()
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct CoreNfs;
+
+impl CoreTab for CoreNfs {
+    fn get_title_vec(&self) -> Vec<String> {
+        // Include the per-op sub-models
+        SingleNfsModelFieldId::all_variant_iter()
+            .map(|field_id| ViewItem::from_default(field_id).config.render_title())
+            .collect()
+    }
+
+    fn get_rows(&self, state: &CoreState, offset: Option<usize>) -> Vec<(StyledString, String)> {
+        state
+            .get_model()
+            .nfs
+            .iter()
+            .filter(|(mount_point, _)| {
+                state
+                    .filter
+                    .as_ref()
+                    .map_or(true, |f| mount_point.starts_with(f))
+            })
+            .map(|(mount_point, snm)| {
+                (
+                    std::iter::once(SingleNfsModelFieldId::MountPoint)
+                        .chain(
+                            SingleNfsModelFieldId::all_variant_iter().skip(offset.unwrap_or(0) + 1),
+                        )
+                        .fold(StyledString::new(), |mut line, field_id| {
+                            line.append(ViewItem::from_default(field_id).render(snm));
+                            line.append_plain(" ");
+                            line
+                        }),
+                    mount_point.clone(),
+                )
+            })
+            .collect()
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct CoreCifs;
+
+impl CoreTab for CoreCifs {
+    fn get_title_vec(&self) -> Vec<String> {
+        SingleCifsModelFieldId::unit_variant_iter()
+            .map(|field_id| ViewItem::from_default(field_id).config.render_title())
+            .collect()
+    }
+
+    fn get_rows(&self, state: &CoreState, offset: Option<usize>) -> Vec<(StyledString, String)> {
+        state
+            .get_model()
+            .cifs
+            .iter()
+            .filter(|(share, _)| state.filter.as_ref().map_or(true, |f| share.starts_with(f)))
+            .map(|(share, scm)| {
+                (
+                    std::iter::once(SingleCifsModelFieldId::Share)
+                        .chain(
+                            SingleCifsModelFieldId::unit_variant_iter()
+                                .skip(offset.unwrap_or(0) + 1),
+                        )
+                        .fold(StyledString::new(), |mut line, field_id| {
+                            line.append(ViewItem::from_default(field_id).render(scm));
+                            line.append_plain(" ");
+                            line
+                        }),
+                    share.clone(),
+                )
+            })
+            .collect()
+    }
+}
@@ -67,6 +67,9 @@ pub enum CoreView {
     Mem(CoreMem),
     Vm(CoreVm),
     Disk(CoreDisk),
+    Fs(CoreFs),
+    Nfs(CoreNfs),
+    Cifs(CoreCifs),
 }
 
 impl CoreView {
@@ -75,7 +78,14 @@ impl CoreView {
         list.set_on_submit(|c, idx: &String| {
             let mut view = CoreView::get_core_view(c);
             // We only care about disk not partition
-            if view.get_tab_view().get_cur_selected() == "Disk" && idx.ends_with(".0") {
+            let is_disk = view
+                .state
+                .borrow()
+                .get_model()
+                .disks
+                .get(idx)
+                .map_or(false, |sdm| sdm.parent.is_none());
+            if view.get_tab_view().get_cur_selected() == "Disk" && is_disk {
                 if view.state.borrow_mut().collapsed_disk.contains(idx) {
                     view.state.borrow_mut().collapsed_disk.remove(idx);
                 } else {
@@ -89,12 +99,23 @@ impl CoreView {
             }
         });
 
-        let tabs = vec!["CPU".into(), "Mem".into(), "Vm".into(), "Disk".into()];
+        let tabs = vec![
+            "CPU".into(),
+            "Mem".into(),
+            "Vm".into(),
+            "Disk".into(),
+            "Filesystem".into(),
+            "NFS".into(),
+            "CIFS".into(),
+        ];
         let mut tabs_map: HashMap<String, CoreView> = HashMap::new();
         tabs_map.insert("CPU".into(), CoreView::Cpu(Default::default()));
         tabs_map.insert("Mem".into(), CoreView::Mem(Default::default()));
         tabs_map.insert("Vm".into(), CoreView::Vm(Default::default()));
         tabs_map.insert("Disk".into(), CoreView::Disk(Default::default()));
+        tabs_map.insert("Filesystem".into(), CoreView::Fs(Default::default()));
+        tabs_map.insert("NFS".into(), CoreView::Nfs(Default::default()));
+        tabs_map.insert("CIFS".into(), CoreView::Cifs(Default::default()));
         let user_data = c
             .user_data::<ViewState>()
             .expect("No data stored in Cursive Object!");
@@ -125,6 +146,9 @@ impl CoreView {
             Self::Mem(inner) => Box::new(inner.clone()),
             Self::Vm(inner) => Box::new(inner.clone()),
             Self::Disk(inner) => Box::new(inner.clone()),
+            Self::Fs(inner) => Box::new(inner.clone()),
+            Self::Nfs(inner) => Box::new(inner.clone()),
+            Self::Cifs(inner) => Box::new(inner.clone()),
         }
     }
 }
@@ -53,6 +53,8 @@ impl HasViewStyle for model::SingleProcessModel {
     }
 }
 
+impl HasViewStyle for model::SingleExecModel {}
+
 impl HasViewStyle for model::ProcessCpuModel {
     fn get_view_style(field_id: &Self::FieldId) -> Option<ViewStyle> {
         use model::ProcessCpuModelFieldId::{SystemPct, UsagePct, UserPct};
@@ -87,4 +89,12 @@ impl HasViewStyle for model::SingleCpuModel {}
 
 impl HasViewStyle for model::VmModel {}
 
+impl HasViewStyle for model::SwapModel {}
+
 impl HasViewStyle for model::SingleDiskModel {}
+
+impl HasViewStyle for model::SingleFsModel {}
+
+impl HasViewStyle for model::SingleNfsModel {}
+
+impl HasViewStyle for model::SingleCifsModel {}
@@ -72,7 +72,7 @@ use common::open_source_shim;
 use common::util::{
     get_belowrc_cmd_section_key, get_belowrc_filename, get_belowrc_view_section_key,
 };
-use model::{CgroupModel, Model, NetworkModel, ProcessModel, SystemModel};
+use model::{CgroupModel, Model, NetworkModel, OomModel, ProcessModel, SystemModel};
 use store::Advance;
 extern crate render as base_render;
 
@@ -89,6 +89,7 @@ mod help_menu;
 mod process_tabs;
 mod process_view;
 mod render;
+mod run_delay_popup;
 pub mod stats_view;
 mod status_bar;
 mod system_view;
@@ -189,6 +190,7 @@ pub struct ViewState {
     pub cgroup: Rc<RefCell<CgroupModel>>,
     pub process: Rc<RefCell<ProcessModel>>,
     pub network: Rc<RefCell<NetworkModel>>,
+    pub oom: Rc<RefCell<OomModel>>,
     pub main_view_state: MainViewState,
     pub mode: ViewMode,
     pub event_controllers: Rc<RefCell<HashMap<Event, controllers::Controllers>>>,
@@ -206,6 +208,7 @@ impl ViewState {
         self.cgroup.replace(model.cgroup);
         self.process.replace(model.process);
         self.network.replace(model.network);
+        self.oom.replace(model.oom);
     }
 
     pub fn new_with_advance(main_view_state: MainViewState, model: Model, mode: ViewMode) -> Self {
@@ -217,6 +220,7 @@ impl ViewState {
             cgroup: Rc::new(RefCell::new(model.cgroup)),
             process: Rc::new(RefCell::new(model.process)),
             network: Rc::new(RefCell::new(model.network)),
+            oom: Rc::new(RefCell::new(model.oom)),
             main_view_state,
             mode,
             event_controllers: Rc::new(RefCell::new(HashMap::new())),
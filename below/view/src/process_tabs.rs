@@ -15,7 +15,7 @@
 use crate::process_view::ProcessState;
 use crate::render::ViewItem;
 use crate::stats_view::StateCommon;
-use model::SingleProcessModel;
+use model::{Queriable, SingleExecModel, SingleProcessModel, SingleProcessModelFieldId};
 
 use cursive::utils::markup::StyledString;
 use itertools::Itertools;
@@ -48,7 +48,14 @@ impl ProcessTab {
             .chain(self.view_items.iter())
             .skip(offset.unwrap_or(0))
         {
-            line.append(item.render(model));
+            match (&item.field_id, model.exit_status.as_ref()) {
+                // Show how exited processes died rather than just DEAD
+                (SingleProcessModelFieldId::State, Some(_)) => line.append(
+                    item.config
+                        .render(model.query(&SingleProcessModelFieldId::ExitStatus)),
+                ),
+                _ => line.append(item.render(model)),
+            }
             line.append_plain(" ");
         }
 
@@ -121,15 +128,81 @@ impl ProcessTab {
     }
 }
 
+/// Renders corresponding Fields From SingleExecModel.
+type ExecViewItem = ViewItem<model::SingleExecModelFieldId>;
+
+/// Lists the programs executed during the interval, including ones that
+/// exited before they could be sampled.
+#[derive(Clone)]
+pub struct ProcessExecTab {
+    pub view_items: Vec<ExecViewItem>,
+}
+
+impl ProcessExecTab {
+    fn new(view_items: Vec<ExecViewItem>) -> Self {
+        Self { view_items }
+    }
+
+    pub fn get_title_vec(&self) -> Vec<String> {
+        self.view_items
+            .iter()
+            .map(|item| item.config.render_title())
+            .collect()
+    }
+
+    pub fn get_rows(
+        &self,
+        state: &ProcessState,
+        offset: Option<usize>,
+    ) -> Vec<(StyledString, String)> {
+        let unknown = "?".to_string();
+        state
+            .get_model()
+            .execs
+            .iter()
+            .filter(|sem| {
+                if let Some(f) = &state.cgroup_filter {
+                    sem.cgroup.as_ref().unwrap_or(&unknown).starts_with(f)
+                } else {
+                    true
+                }
+            })
+            .filter(|sem| {
+                if let Some(f) = &state.filter {
+                    sem.comm.as_ref().unwrap_or(&unknown).contains(f)
+                } else {
+                    true
+                }
+            })
+            .map(|sem: &SingleExecModel| {
+                // The first column (Comm) stays in place when scrolling
+                let line = self
+                    .view_items
+                    .iter()
+                    .take(1)
+                    .chain(self.view_items.iter().skip(1 + offset.unwrap_or(0)))
+                    .fold(StyledString::new(), |mut line, item| {
+                        line.append(item.render(sem));
+                        line.append_plain(" ");
+                        line
+                    });
+                (line, sem.pid.unwrap_or(0).to_string())
+            })
+            .collect()
+    }
+}
+
 pub mod default_tabs {
     use super::*;
 
+    use base_render::RenderConfigBuilder as Rc;
     use model::ProcessCpuModelFieldId::{NumThreads, SystemPct, UsagePct, UserPct};
     use model::ProcessIoModelFieldId::{RbytesPerSec, RwbytesPerSec, WbytesPerSec};
     use model::ProcessMemoryModelFieldId::{
         Anon, File, HugeTlb, Lock, MajorfaultsPerSec, MinorfaultsPerSec, Pin, Pte, RssBytes, Shmem,
         Swap, VmSize,
     };
+    use model::SingleExecModelFieldId;
     use model::SingleProcessModelFieldId::{
         Cgroup, Cmdline, Comm, Cpu, Io, Mem, Pid, Ppid, State, UptimeSecs,
     };
@@ -144,7 +217,8 @@ pub mod default_tabs {
         ProcessTab::new(vec![
             ViewItem::from_default(Pid),
             ViewItem::from_default(Ppid),
-            ViewItem::from_default(State),
+            // Wide enough for exit reasons such as "exited (SIGKILL)"
+            ViewItem::from_default(State).update(Rc::new().width(20)),
             ViewItem::from_default(Cpu(UsagePct)),
             ViewItem::from_default(Cpu(UserPct)),
             ViewItem::from_default(Cpu(SystemPct)),
@@ -193,4 +267,15 @@ pub mod default_tabs {
             ViewItem::from_default(Io(RwbytesPerSec)),
         ])
     });
+
+    pub static PROCESS_EXEC_TAB: Lazy<ProcessExecTab> = Lazy::new(|| {
+        ProcessExecTab::new(vec![
+            ViewItem::from_default(SingleExecModelFieldId::Comm),
+            ViewItem::from_default(SingleExecModelFieldId::Cgroup),
+            ViewItem::from_default(SingleExecModelFieldId::Pid),
+            ViewItem::from_default(SingleExecModelFieldId::Ppid),
+            ViewItem::from_default(SingleExecModelFieldId::Filename),
+            ViewItem::from_default(SingleExecModelFieldId::Cmdline),
+        ])
+    });
 }
@@ -27,8 +27,10 @@ use model::{
 };
 
 use crate::process_tabs::{
-    default_tabs::{PROCESS_CPU_TAB, PROCESS_GENERAL_TAB, PROCESS_IO_TAB, PROCESS_MEM_TAB},
-    ProcessTab,
+    default_tabs::{
+        PROCESS_CPU_TAB, PROCESS_EXEC_TAB, PROCESS_GENERAL_TAB, PROCESS_IO_TAB, PROCESS_MEM_TAB,
+    },
+    ProcessExecTab, ProcessTab,
 };
 use crate::stats_view::{StateCommon, StatsView, ViewBridge};
 use crate::ViewState;
@@ -69,13 +71,15 @@ impl StateCommon for ProcessState {
     }
 
     fn set_sort_tag_from_tab_idx(&mut self, tab: &str, idx: usize, reverse: &mut bool) -> bool {
+        let tab = match self.sort_tags.get(tab) {
+            Some(tab) => tab,
+            // Execs are listed in the order they happened
+            None => return false,
+        };
         let sort_order = match idx {
             0 => Self::TagType::Comm,
             1 => Self::TagType::Cgroup,
-            _ => self
-                .sort_tags
-                .get(tab)
-                .unwrap_or_else(|| panic!("Fail to find tab: {}", tab))
+            _ => tab
                 .view_items
                 .get(idx - 2)
                 .expect("Out of title scope")
@@ -147,8 +151,9 @@ impl ProcessState {
     }
 }
 
-pub struct ProcessView {
-    tab: &'static ProcessTab,
+pub enum ProcessView {
+    Process(&'static ProcessTab),
+    Exec(&'static ProcessExecTab),
 }
 
 impl ProcessView {
@@ -156,45 +161,24 @@ impl ProcessView {
         let mut list = SelectView::<String>::new();
         list.set_on_select(|c, pid: &String| {
             c.call_on_name(Self::get_view_name(), |view: &mut ViewType| {
-                let cmdline = view
-                    .state
-                    .borrow()
-                    .get_model()
-                    .processes
-                    .get(&pid.parse::<i32>().unwrap_or(0))
-                    .map_or("?".to_string(), |spm| {
-                        spm.cmdline.clone().unwrap_or_else(|| "?".to_string())
-                    });
+                let cmdline = Self::get_cmdline(&view.state.borrow().get_model(), pid);
                 view.get_cmd_palette().set_info(cmdline);
             });
         });
 
-        let tabs = vec!["General".into(), "CPU".into(), "Mem".into(), "I/O".into()];
-        let mut tabs_map: HashMap<String, ProcessView> = HashMap::new();
-        tabs_map.insert(
+        let tabs = vec![
             "General".into(),
-            Self {
-                tab: &*PROCESS_GENERAL_TAB,
-            },
-        );
-        tabs_map.insert(
             "CPU".into(),
-            Self {
-                tab: &*PROCESS_CPU_TAB,
-            },
-        );
-        tabs_map.insert(
             "Mem".into(),
-            Self {
-                tab: &*PROCESS_MEM_TAB,
-            },
-        );
-        tabs_map.insert(
             "I/O".into(),
-            Self {
-                tab: &*PROCESS_IO_TAB,
-            },
-        );
+            "Exec".into(),
+        ];
+        let mut tabs_map: HashMap<String, ProcessView> = HashMap::new();
+        tabs_map.insert("General".into(), Self::Process(&*PROCESS_GENERAL_TAB));
+        tabs_map.insert("CPU".into(), Self::Process(&*PROCESS_CPU_TAB));
+        tabs_map.insert("Mem".into(), Self::Process(&*PROCESS_MEM_TAB));
+        tabs_map.insert("I/O".into(), Self::Process(&*PROCESS_IO_TAB));
+        tabs_map.insert("Exec".into(), Self::Exec(&*PROCESS_EXEC_TAB));
         let user_data = c
             .user_data::<ViewState>()
             .expect("No data stored in Cursive Object!");
@@ -257,6 +241,25 @@ impl ProcessView {
         .with_name(Self::get_view_name())
     }
 
+    /// Command line of a running process, or of a program that was executed
+    /// during the interval if it's no longer around.
+    fn get_cmdline(model: &ProcessModel, pid: &str) -> String {
+        let pid = pid.parse::<i32>().unwrap_or(0);
+        model
+            .processes
+            .get(&pid)
+            .and_then(|spm| spm.cmdline.clone())
+            .or_else(|| {
+                model
+                    .execs
+                    .iter()
+                    .rev()
+                    .find(|sem| sem.pid == Some(pid))
+                    .and_then(|sem| sem.cmdline.clone())
+            })
+            .unwrap_or_else(|| "?".to_string())
+    }
+
     pub fn get_process_view(c: &mut Cursive) -> ViewRef<ViewType> {
         ViewType::get_view(c)
     }
@@ -271,15 +274,7 @@ impl ProcessView {
             view.get_detail_view().selection(),
         ) {
             (false, Some(selection)) => {
-                let cmdline = view
-                    .state
-                    .borrow()
-                    .get_model()
-                    .processes
-                    .get(&selection.parse::<i32>().unwrap_or(0))
-                    .map_or("?".to_string(), |spm| {
-                        spm.cmdline.clone().unwrap_or_else(|| "?".to_string())
-                    });
+                let cmdline = Self::get_cmdline(&view.state.borrow().get_model(), &selection);
                 cmd_palette.set_info(cmdline)
             }
             _ => {}
@@ -293,7 +288,10 @@ impl ViewBridge for ProcessView {
         "process_view"
     }
     fn get_title_vec(&self) -> Vec<String> {
-        self.tab.get_title_vec()
+        match self {
+            Self::Process(tab) => tab.get_title_vec(),
+            Self::Exec(tab) => tab.get_title_vec(),
+        }
     }
 
     fn get_rows(
@@ -301,6 +299,9 @@ impl ViewBridge for ProcessView {
         state: &Self::StateType,
         offset: Option<usize>,
     ) -> Vec<(StyledString, String)> {
-        self.tab.get_rows(state, offset)
+        match self {
+            Self::Process(tab) => tab.get_rows(state, offset),
+            Self::Exec(tab) => tab.get_rows(state, offset),
+        }
     }
 }
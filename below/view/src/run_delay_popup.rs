@@ -0,0 +1,103 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use cursive::event::Key;
+use cursive::view::View;
+use cursive::views::{Dialog, OnEventView, TextView};
+
+use model::{CgroupCpuModel, CgroupModel};
+
+const BAR_WIDTH: u64 = 40;
+
+/// Renders a log2 histogram the same way bcc's runqlat does
+fn render_hist(hist: &[u64]) -> String {
+    let max = hist.iter().copied().max().unwrap_or(0);
+    // Skip empty leading and trailing buckets
+    let first = hist.iter().position(|count| *count != 0).unwrap_or(0);
+    let last = hist.iter().rposition(|count| *count != 0).unwrap_or(0);
+
+    let mut lines = vec![format!(
+        "{:>25} : {:<10} {}",
+        "usecs", "count", "distribution"
+    )];
+    for (idx, count) in hist.iter().enumerate().take(last + 1).skip(first) {
+        let low: u64 = if idx == 0 { 0 } else { 1 << idx };
+        let range = if idx == hist.len() - 1 {
+            format!("{} -> ...", low)
+        } else {
+            format!("{} -> {}", low, (1u64 << (idx + 1)) - 1)
+        };
+        let bar_len = if max == 0 { 0 } else { count * BAR_WIDTH / max };
+        lines.push(format!(
+            "{:>25} : {:<10} |{:<width$}|",
+            range,
+            count,
+            "*".repeat(bar_len as usize),
+            width = BAR_WIDTH as usize
+        ));
+    }
+    lines.join("\n")
+}
+
+fn get_content(cpu: Option<&CgroupCpuModel>) -> String {
+    let cpu = match cpu {
+        Some(cpu) => cpu,
+        None => return "No CPU data for this cgroup".to_owned(),
+    };
+    match cpu.run_delay_hist.as_ref() {
+        Some(hist) if hist.iter().any(|count| *count != 0) => format!(
+            "p50: {} us  p90: {} us  p99: {} us\n\n{}",
+            cpu.run_delay_p50_us
+                .map_or("?".to_owned(), |v| v.to_string()),
+            cpu.run_delay_p90_us
+                .map_or("?".to_owned(), |v| v.to_string()),
+            cpu.run_delay_p99_us
+                .map_or("?".to_owned(), |v| v.to_string()),
+            render_hist(hist)
+        ),
+        Some(_) => "No tasks waited for a CPU during this interval".to_owned(),
+        None => {
+            "Run queue latency was not recorded. Try below record --enable-runq-latency".to_owned()
+        }
+    }
+}
+
+/// Shows the run queue latency histogram of the cgroup at `full_path`
+pub fn new(model: &CgroupModel, full_path: &str) -> impl View {
+    let mut cgroup = Some(model);
+    for part in full_path.split('/').filter(|part| !part.is_empty()) {
+        cgroup = cgroup.and_then(|cgroup| cgroup.children.get(&part.to_owned()));
+    }
+    let (title, content) = match cgroup {
+        Some(cgroup) => (
+            format!("Run delay of {}", cgroup.data.name),
+            get_content(cgroup.data.cpu.as_ref()),
+        ),
+        None => (
+            "Run delay".to_owned(),
+            format!("Cgroup {} no longer exists", full_path),
+        ),
+    };
+
+    OnEventView::new(
+        Dialog::new()
+            .title(title)
+            .padding_lrtb(1, 1, 1, 0)
+            .content(TextView::new(content))
+            .dismiss_button("Close"),
+    )
+    .on_event(Key::Esc, |s| {
+        s.pop_layer();
+    })
+}
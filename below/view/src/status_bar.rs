@@ -60,6 +60,35 @@ fn get_content(c: &mut Cursive) -> impl Into<StyledString> {
     header_str.append_plain(get_spacing());
     header_str.append_plain(view_state.view_mode_str());
 
+    // Mark samples with OOM kills so they stand out while stepping through
+    // a replay
+    let oom = view_state.oom.borrow();
+    if let Some(last) = oom.kills.last() {
+        let mut marker = format!(
+            "OOM kill: {}({})",
+            last.comm.as_deref().unwrap_or("?"),
+            last.pid.map_or("?".to_owned(), |pid| pid.to_string()),
+        );
+        if oom.kills.len() > 1 {
+            marker.push_str(&format!(" +{} more", oom.kills.len() - 1));
+        }
+        header_str.append_plain(get_spacing());
+        header_str.append_styled(
+            marker,
+            cursive::theme::Color::Light(cursive::theme::BaseColor::Red),
+        );
+    }
+
+    // Exited processes may be missing or incomplete when exitstat is
+    // struggling, so say so rather than silently showing less
+    if view_state.system.borrow().exitstat.health.as_deref() == Some("degraded") {
+        header_str.append_plain(get_spacing());
+        header_str.append_styled(
+            "exitstat degraded",
+            cursive::theme::Color::Light(cursive::theme::BaseColor::Yellow),
+        );
+    }
+
     header_str
 }
 